@@ -0,0 +1,109 @@
+use crate::models::{AppSettings, WeekStart, WorkingDays};
+use chrono::Weekday;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 配置文件名，位于数据目录中
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 数据目录中的配置文件，未填写的字段不覆盖已保存的设置
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub autosave_interval_secs: Option<u64>,
+    pub week_start: Option<WeekStart>,
+    pub utc_offset_minutes: Option<i32>,
+    pub working_days: Option<Vec<Weekday>>, // 如 ["Mon", "Tue", "Wed", "Thu", "Fri"]
+}
+
+impl Config {
+    /// 读取数据目录中的配置文件，文件不存在时返回默认配置
+    pub fn load(dir: &str) -> io::Result<Self> {
+        let path = Path::new(dir).join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// 解析配置文件内容
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        toml::from_str(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("配置文件格式错误: {}", e),
+            )
+        })
+    }
+
+    /// 用配置文件中填写的字段覆盖设置
+    pub fn apply(&self, settings: &mut AppSettings) {
+        if let Some(interval) = self.autosave_interval_secs {
+            settings.autosave_interval_secs = interval;
+        }
+        if let Some(week_start) = self.week_start {
+            settings.week_start = week_start;
+        }
+        if let Some(offset) = self.utc_offset_minutes {
+            settings.utc_offset_minutes = offset;
+        }
+        if let Some(days) = &self.working_days {
+            settings.working_days = WorkingDays::from_weekdays(days);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_config_uses_defaults() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = Config::load(&temp_dir.path().to_string_lossy()).unwrap();
+        assert_eq!(config, Config::default());
+
+        let mut settings = AppSettings::default();
+        config.apply(&mut settings);
+        assert_eq!(settings, AppSettings::default());
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "week_start = \"Sunday\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&temp_dir.path().to_string_lossy()).unwrap();
+        assert_eq!(config.week_start, Some(WeekStart::Sunday));
+        assert_eq!(config.autosave_interval_secs, None);
+
+        let mut settings = AppSettings {
+            autosave_interval_secs: 30,
+            ..AppSettings::default()
+        };
+        config.apply(&mut settings);
+        assert_eq!(settings.week_start, WeekStart::Sunday);
+        assert_eq!(settings.autosave_interval_secs, 30);
+
+        let config = Config::parse("autosave_interval_secs = 0").unwrap();
+        assert_eq!(config.autosave_interval_secs, Some(0));
+
+        let config = Config::parse("utc_offset_minutes = 480").unwrap();
+        config.apply(&mut settings);
+        assert_eq!(settings.timezone().local_minus_utc(), 8 * 3600);
+        let config =
+            Config::parse("working_days = [\"Mon\", \"Tue\", \"Wed\", \"Thu\", \"Fri\"]").unwrap();
+        config.apply(&mut settings);
+        assert_eq!(settings.working_days, WorkingDays::monday_to_friday());
+        assert!(Config::parse("working_days = [\"Someday\"]").is_err());
+        assert!(Config::parse("week_start = \"Friday\"").is_err());
+        assert!(Config::parse("autosave_interval_secs = ").is_err());
+    }
+}
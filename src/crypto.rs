@@ -0,0 +1,98 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::io;
+
+/// 加密文件的文件头，用于区分加密文件和明文JSON
+const MAGIC: &[u8; 8] = b"PMENC\x00\x00\x01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 判断数据是否为加密格式
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// 用口令加密数据，格式为：文件头 + 盐 + 随机数 + 密文
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    fill_random(&mut salt)?;
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| io::Error::other("加密数据失败"))?;
+
+    let mut output = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(MAGIC);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// 解密由 encrypt 生成的数据，口令错误或数据被篡改时返回错误
+pub fn decrypt(passphrase: &str, data: &[u8]) -> io::Result<Vec<u8>> {
+    let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if !is_encrypted(data) || data.len() < header_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "不是有效的加密数据文件",
+        ));
+    }
+
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = Nonce::from_slice(&data[MAGIC.len() + SALT_LEN..header_len]);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(nonce, &data[header_len..])
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "密码错误或数据文件已损坏"))
+}
+
+/// 使用Argon2从口令派生256位密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> io::Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| io::Error::other(format!("派生密钥失败: {}", e)))?;
+    Ok(key)
+}
+
+fn fill_random(buf: &mut [u8]) -> io::Result<()> {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    OsRng
+        .try_fill_bytes(buf)
+        .map_err(|e| io::Error::other(format!("生成随机数失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let encrypted = encrypt("口令", "客户A的数据".as_bytes()).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(3).any(|w| w == "客".as_bytes()));
+
+        let decrypted = decrypt("口令", &encrypted).unwrap();
+        assert_eq!(decrypted, "客户A的数据".as_bytes());
+
+        // 每次加密使用新的盐和随机数
+        assert_ne!(
+            encrypt("口令", b"data").unwrap(),
+            encrypt("口令", b"data").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_wrong_passphrase_and_tampering() {
+        let mut encrypted = encrypt("正确", b"secret").unwrap();
+        assert!(decrypt("错误", &encrypted).is_err());
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        assert!(decrypt("正确", &encrypted).is_err());
+        assert!(decrypt("正确", b"{}").is_err());
+    }
+}
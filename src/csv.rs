@@ -0,0 +1,89 @@
+//! 报表、数据导出和导入共用的CSV工具
+
+/// 按RFC 4180转义CSV字段：包含逗号、引号或换行时加引号，字段内的引号写成两个引号
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 把转义后的字段拼成一行CSV
+pub fn csv_row(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+    format!("{}\n", escaped.join(","))
+}
+
+/// 解析CSV内容，支持带逗号、引号（用两个引号转义）和换行的引号字段
+pub fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                // 跳过空行
+                if row.len() > 1 || !row[0].is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("普通名称"), "普通名称");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("说\"你好\""), "\"说\"\"你好\"\"\"");
+        assert_eq!(csv_escape("第一行\n第二行"), "\"第一行\n第二行\"");
+        assert_eq!(csv_escape(""), "");
+    }
+
+    #[test]
+    fn test_csv_row_round_trip() {
+        let line = csv_row(&["项目", "a,b", "说\"你好\"", "第一行\n第二行"]);
+        assert_eq!(line, "项目,\"a,b\",\"说\"\"你好\"\"\",\"第一行\n第二行\"\n");
+
+        let rows = parse_csv(&format!("{}\r\n\n{}", line, csv_row(&["", "最后"])));
+        assert_eq!(
+            rows,
+            vec![
+                vec!["项目", "a,b", "说\"你好\"", "第一行\n第二行"],
+                vec!["", "最后"],
+            ]
+        );
+    }
+}
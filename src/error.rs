@@ -0,0 +1,233 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// 项目操作的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectError {
+    NotFound,
+    SourceNotFound,
+    TargetNotFound,
+    MergeIntoSelf,
+    Archived,
+    Closed,        // 切换到已完成或已取消的项目
+    AlreadyClosed, // 已结束的项目直接改为另一种结束状态
+    NoCurrentProject,
+    Invalid(String), // 输入校验失败，附带具体原因
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::NotFound => write!(f, "项目不存在"),
+            ProjectError::SourceNotFound => write!(f, "源项目不存在"),
+            ProjectError::TargetNotFound => write!(f, "目标项目不存在"),
+            ProjectError::MergeIntoSelf => write!(f, "不能把项目合并到自身"),
+            ProjectError::Archived => write!(f, "项目已归档"),
+            ProjectError::Closed => write!(f, "项目已完成或已取消"),
+            ProjectError::AlreadyClosed => write!(f, "项目已结束，请先重新打开"),
+            ProjectError::NoCurrentProject => write!(f, "没有当前活动项目"),
+            ProjectError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+/// 事件和时间记录操作的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventError {
+    NotFound,
+    NewEventNotFound, // 拆分记录时指定的新事件不存在
+    TemplateNotFound,
+    RecordNotFound,
+    SubtaskNotFound,
+    NotInTrash,
+    InTrash,
+    AlreadyEnded,
+    NotEnded,
+    InvalidEndTime,
+    StartAfterEnd(DateTime<Utc>), // 新的开始时间不早于事件的结束时间
+    StartAfterRecordEnd(DateTime<Utc>), // 新的开始时间不早于某条时间记录的结束时间
+    SplitOutOfRange,
+    RecordSplitOutOfRange,
+    NoPomodoro,
+    PomodoroRunning,
+    PomodoroTooShort,
+    SelfDependency,
+    CyclicDependency,
+    DependencyNotFound,
+    TagNotFound,
+    Invalid(String), // 输入校验失败，附带具体原因
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::NotFound => write!(f, "事件不存在"),
+            EventError::NewEventNotFound => write!(f, "新事件不存在"),
+            EventError::TemplateNotFound => write!(f, "模板不存在"),
+            EventError::RecordNotFound => write!(f, "时间记录不存在"),
+            EventError::SubtaskNotFound => write!(f, "检查项不存在"),
+            EventError::NotInTrash => write!(f, "回收站中没有该事件"),
+            EventError::InTrash => write!(f, "事件在回收站中，请先恢复"),
+            EventError::AlreadyEnded => write!(f, "事件已经结束"),
+            EventError::NotEnded => write!(f, "事件尚未完成"),
+            EventError::InvalidEndTime => write!(f, "结束时间必须晚于开始时间"),
+            EventError::StartAfterEnd(end_time) => write!(
+                f,
+                "开始时间必须早于结束时间 {}",
+                end_time.format("%Y-%m-%d %H:%M")
+            ),
+            EventError::StartAfterRecordEnd(end_time) => write!(
+                f,
+                "开始时间必须早于时间记录的结束时间 {}",
+                end_time.format("%Y-%m-%d %H:%M")
+            ),
+            EventError::SplitOutOfRange => write!(f, "拆分时间必须位于事件的开始和结束时间之间"),
+            EventError::RecordSplitOutOfRange => {
+                write!(f, "拆分时间必须位于时间记录的开始和结束时间之间")
+            }
+            EventError::NoPomodoro => write!(f, "该事件没有进行中的番茄钟"),
+            EventError::PomodoroRunning => write!(f, "该事件的番茄钟已在进行中"),
+            EventError::PomodoroTooShort => write!(f, "番茄钟的工作时间不足1分钟"),
+            EventError::SelfDependency => write!(f, "事件不能依赖自己"),
+            EventError::CyclicDependency => write!(f, "添加后会形成循环依赖"),
+            EventError::DependencyNotFound => write!(f, "事件没有该前置事件"),
+            EventError::TagNotFound => write!(f, "事件没有该标签"),
+            EventError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
+/// 界面操作的错误，可能来自项目或事件操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    Project(ProjectError),
+    Event(EventError),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Project(e) => write!(f, "{}", e),
+            AppError::Event(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Project(e) => Some(e),
+            AppError::Event(e) => Some(e),
+        }
+    }
+}
+
+impl From<ProjectError> for AppError {
+    fn from(e: ProjectError) -> Self {
+        AppError::Project(e)
+    }
+}
+
+impl From<EventError> for AppError {
+    fn from(e: EventError) -> Self {
+        AppError::Event(e)
+    }
+}
+
+/// 生成报表的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportError {
+    InvalidQuarter(u32),       // 季度不在1到4之间
+    InvalidThresholds(String), // 效率阈值无效，附带具体原因
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::InvalidQuarter(quarter) => {
+                write!(f, "季度必须在1到4之间，当前为{}", quarter)
+            }
+            ReportError::InvalidThresholds(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+/// 导入报表JSON的错误
+#[derive(Debug)]
+pub enum ReportImportError {
+    Json(serde_json::Error),
+    InvalidRange,                                     // 开始日期晚于结束日期
+    TotalMismatch { total: i64, breakdown_sum: i64 }, // 项目时间分解之和与项目内总时间不一致
+}
+
+impl fmt::Display for ReportImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportImportError::Json(e) => write!(f, "报表JSON格式错误: {}", e),
+            ReportImportError::InvalidRange => write!(f, "报表的开始时间晚于结束时间"),
+            ReportImportError::TotalMismatch {
+                total,
+                breakdown_sum,
+            } => write!(
+                f,
+                "项目时间分解之和（{}分钟）与项目内时间（{}分钟）不一致",
+                breakdown_sum, total
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReportImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReportImportError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ReportImportError {
+    fn from(e: serde_json::Error) -> Self {
+        ReportImportError::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(ProjectError::NotFound.to_string(), "项目不存在");
+        assert_eq!(
+            ProjectError::Invalid("名称不能为空".to_string()).to_string(),
+            "名称不能为空"
+        );
+        assert_eq!(EventError::AlreadyEnded.to_string(), "事件已经结束");
+
+        let end_time = chrono::NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(10, 30, 0)
+            .unwrap()
+            .and_utc();
+        assert_eq!(
+            EventError::StartAfterEnd(end_time).to_string(),
+            "开始时间必须早于结束时间 2024-03-04 10:30"
+        );
+
+        assert_eq!(
+            AppError::from(ProjectError::NoCurrentProject).to_string(),
+            "没有当前活动项目"
+        );
+        assert_eq!(
+            ReportError::InvalidQuarter(5).to_string(),
+            "季度必须在1到4之间，当前为5"
+        );
+    }
+}
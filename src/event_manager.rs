@@ -1,11 +1,21 @@
-use crate::models::{Event, EventType, TimeRecord};
-use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use crate::error::EventError;
+use crate::models::{
+    validate_name, validate_project_shares, Event, EventTemplate, EventType, PomodoroSession,
+    Priority, ProjectActivity, Subtask, TimeRecord,
+};
+use crate::time_calculator::{utc_offset, TimeCalculator};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// 填补空档时创建的事件标题
+pub const UNTRACKED_TITLE: &str = "未记录时间";
+
 pub struct EventManager {
     events: HashMap<Uuid, Event>,
     time_records: HashMap<Uuid, TimeRecord>,
+    templates: HashMap<Uuid, EventTemplate>,
+    pomodoros: HashMap<Uuid, PomodoroSession>, // 按事件ID索引，事件结束或番茄钟放弃后删除
 }
 
 impl EventManager {
@@ -13,6 +23,8 @@ impl EventManager {
         Self {
             events: HashMap::new(),
             time_records: HashMap::new(),
+            templates: HashMap::new(),
+            pomodoros: HashMap::new(),
         }
     }
 
@@ -23,17 +35,17 @@ impl EventManager {
         description: Option<String>,
         project_id: Uuid,
         start_time: Option<DateTime<Utc>>,
-    ) -> Uuid {
+    ) -> Result<Uuid, EventError> {
         let start_time = start_time.unwrap_or_else(Utc::now);
         let event = Event::new(
-            title,
+            validate_name(&title).map_err(EventError::Invalid)?,
             description,
             EventType::ProjectRelated(project_id),
             start_time,
         );
         let event_id = event.id;
         self.events.insert(event_id, event);
-        event_id
+        Ok(event_id)
     }
 
     /// 添加项目外事件
@@ -42,78 +54,591 @@ impl EventManager {
         title: String,
         description: Option<String>,
         start_time: Option<DateTime<Utc>>,
-    ) -> Uuid {
+    ) -> Result<Uuid, EventError> {
         let start_time = start_time.unwrap_or_else(Utc::now);
-        let event = Event::new(title, description, EventType::NonProject, start_time);
+        let event = Event::new(
+            validate_name(&title).map_err(EventError::Invalid)?,
+            description,
+            EventType::NonProject,
+            start_time,
+        );
         let event_id = event.id;
         self.events.insert(event_id, event);
-        event_id
+        Ok(event_id)
+    }
+
+    /// 添加一个已经结束的事件（用于补录过去的工作），同时生成时间记录
+    pub fn add_completed_event(
+        &mut self,
+        title: String,
+        description: Option<String>,
+        event_type: EventType,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Uuid, EventError> {
+        if end_time <= start_time {
+            return Err(EventError::InvalidEndTime);
+        }
+        let event = Event::new(
+            validate_name(&title).map_err(EventError::Invalid)?,
+            description,
+            event_type,
+            start_time,
+        );
+        let event_id = event.id;
+        self.events.insert(event_id, event);
+        self.set_event_end_time(event_id, Some(end_time))?;
+        Ok(event_id)
+    }
+
+    /// 复制事件的标题、描述和类型创建一个新的进行中事件，start_time 为空时从现在开始
+    pub fn duplicate_event(
+        &mut self,
+        event_id: Uuid,
+        start_time: Option<DateTime<Utc>>,
+    ) -> Result<Uuid, EventError> {
+        let source = self.get_event(event_id).ok_or(EventError::NotFound)?;
+        let event = Event::new(
+            source.title.clone(),
+            source.description.clone(),
+            source.event_type.clone(),
+            start_time.unwrap_or_else(Utc::now),
+        );
+        let new_id = event.id;
+        self.events.insert(new_id, event);
+        Ok(new_id)
+    }
+
+    /// 插入已有事件（保留原ID和结束时间，用于从保存的数据恢复）
+    pub fn insert_event(&mut self, event: Event) {
+        self.events.insert(event.id, event);
     }
 
-    /// 设置事件结束时间
+    /// 插入已有时间记录（保留原ID，用于从保存的数据恢复）
+    pub fn insert_time_record(&mut self, record: TimeRecord) {
+        self.time_records.insert(record.id, record);
+    }
+
+    /// 恢复进行中的番茄钟（用于从保存的数据恢复或撤销），事件不存在或已结束时忽略
+    pub fn insert_pomodoro(&mut self, session: PomodoroSession) {
+        let active = self
+            .events
+            .get(&session.event_id)
+            .is_some_and(|event| !event.is_completed());
+        if active {
+            self.pomodoros.insert(session.event_id, session);
+        }
+    }
+
+    /// 获取所有进行中的番茄钟
+    pub fn get_all_pomodoros(&self) -> Vec<&PomodoroSession> {
+        self.pomodoros.values().collect()
+    }
+
+    /// 添加重复事件模板（保留模板ID，也用于从保存的数据恢复）
+    pub fn add_template(&mut self, template: EventTemplate) -> Uuid {
+        let template_id = template.id;
+        self.templates.insert(template_id, template);
+        template_id
+    }
+
+    /// 删除重复事件模板，已生成的事件保留
+    pub fn remove_template(&mut self, template_id: Uuid) -> Result<(), EventError> {
+        self.templates
+            .remove(&template_id)
+            .map(|_| ())
+            .ok_or(EventError::TemplateNotFound)
+    }
+
+    /// 获取所有重复事件模板（按标题排序）
+    pub fn get_all_templates(&self) -> Vec<&EventTemplate> {
+        let mut templates: Vec<&EventTemplate> = self.templates.values().collect();
+        templates.sort_by(|a, b| a.title.cmp(&b.title).then(a.id.cmp(&b.id)));
+        templates
+    }
+
+    /// 为日期范围内（含首尾）的每个匹配日期生成模板事件，已有实例的日期会跳过，返回新事件的ID
+    pub fn materialize_templates(&mut self, from: NaiveDate, to: NaiveDate) -> Vec<Uuid> {
+        let existing: HashSet<(Uuid, NaiveDate)> = self
+            .events
+            .values()
+            .filter_map(|event| Some((event.template_id?, event.start_time.date_naive())))
+            .collect();
+
+        let mut created = Vec::new();
+        for template in self.get_all_templates() {
+            for date in from.iter_days().take_while(|date| *date <= to) {
+                if !template.recurrence.occurs_on(date) || existing.contains(&(template.id, date)) {
+                    continue;
+                }
+
+                let mut event = Event::new(
+                    template.title.clone(),
+                    template.description.clone(),
+                    template.event_type.clone(),
+                    date.and_time(template.start_time).and_utc(),
+                );
+                event.template_id = Some(template.id);
+                created.push(event);
+            }
+        }
+
+        created
+            .into_iter()
+            .map(|event| {
+                let event_id = event.id;
+                self.events.insert(event_id, event);
+                event_id
+            })
+            .collect()
+    }
+
+    /// 设置事件结束时间，有进行中的番茄钟时按番茄钟结束，时间记录只包含工作时间
     pub fn set_event_end_time(
         &mut self,
         event_id: Uuid,
         end_time: Option<DateTime<Utc>>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EventError> {
         let end_time = end_time.unwrap_or_else(Utc::now);
 
+        if self.pomodoros.contains_key(&event_id) {
+            match self.finish_pomodoro(event_id, Some(end_time)) {
+                // 番茄钟刚开始，放弃它并按普通事件结束
+                Err(EventError::PomodoroTooShort) => {
+                    self.pomodoros.remove(&event_id);
+                }
+                result => return result.map(|_| ()),
+            }
+        }
+
         if let Some(event) = self.events.get_mut(&event_id) {
             if event.end_time.is_some() {
-                return Err("事件已经结束".to_string());
+                return Err(EventError::AlreadyEnded);
             }
 
             if end_time <= event.start_time {
-                return Err("结束时间必须晚于开始时间".to_string());
+                return Err(EventError::InvalidEndTime);
             }
 
             event.set_end_time(end_time);
 
-            // 创建时间记录
-            let project_id = match event.event_type {
-                EventType::ProjectRelated(id) => Some(id),
-                EventType::NonProject => None,
-            };
+            // 创建时间记录，多项目分摊的事件为每个项目各创建一条
+            let total_minutes = end_time
+                .signed_duration_since(event.start_time)
+                .num_minutes();
+            for (project_id, minutes) in event.split_minutes(total_minutes) {
+                let mut time_record =
+                    TimeRecord::new(event_id, project_id, event.start_time, end_time);
+                time_record.duration_minutes = minutes;
+                self.time_records.insert(time_record.id, time_record);
+            }
+            Ok(())
+        } else {
+            Err(EventError::NotFound)
+        }
+    }
+
+    /// 为进行中的事件开始番茄钟
+    pub fn start_pomodoro(
+        &mut self,
+        event_id: Uuid,
+        work_min: i64,
+        break_min: i64,
+    ) -> Result<(), EventError> {
+        self.start_pomodoro_at(event_id, work_min, break_min, Utc::now())
+    }
+
+    /// 从指定时间开始番茄钟
+    pub fn start_pomodoro_at(
+        &mut self,
+        event_id: Uuid,
+        work_min: i64,
+        break_min: i64,
+        started_at: DateTime<Utc>,
+    ) -> Result<(), EventError> {
+        let event = self.events.get(&event_id).ok_or(EventError::NotFound)?;
+        if event.is_completed() {
+            return Err(EventError::AlreadyEnded);
+        }
+        if work_min <= 0 || break_min < 0 {
+            return Err(EventError::Invalid(
+                "工作时间必须大于0，休息时间不能为负数".to_string(),
+            ));
+        }
+        if self.pomodoros.contains_key(&event_id) {
+            return Err(EventError::PomodoroRunning);
+        }
+
+        self.pomodoros.insert(
+            event_id,
+            PomodoroSession {
+                event_id,
+                work_minutes: work_min,
+                break_minutes: break_min,
+                started_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// 获取事件进行中的番茄钟
+    pub fn get_pomodoro(&self, event_id: Uuid) -> Option<&PomodoroSession> {
+        self.pomodoros.get(&event_id)
+    }
+
+    /// 结束番茄钟并完成事件，时间记录只包含工作时间，多项目分摊的事件按比例拆分，返回完成的循环数
+    pub fn finish_pomodoro(
+        &mut self,
+        event_id: Uuid,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<u32, EventError> {
+        let end_time = end_time.unwrap_or_else(Utc::now);
+        let session = self
+            .pomodoros
+            .get(&event_id)
+            .ok_or(EventError::NoPomodoro)?;
+        let work_seconds = session.work_seconds(end_time);
+        if work_seconds < 60 {
+            return Err(EventError::PomodoroTooShort);
+        }
+        let cycles = session.completed_cycles(end_time);
+        let started_at = session.started_at;
 
-            let time_record = TimeRecord::new(event_id, project_id, event.start_time, end_time);
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if event.is_completed() {
+            return Err(EventError::AlreadyEnded);
+        }
+        event.set_end_time(end_time);
 
+        // 记录从番茄钟开始计时，长度只等于累计的工作时间
+        let record_end = started_at + chrono::Duration::seconds(work_seconds);
+        for (project_id, minutes) in event.split_minutes(work_seconds / 60) {
+            let mut time_record = TimeRecord::new(event_id, project_id, started_at, record_end);
+            time_record.duration_minutes = minutes;
             self.time_records.insert(time_record.id, time_record);
-            Ok(())
-        } else {
-            Err("事件不存在".to_string())
         }
+        self.pomodoros.remove(&event_id);
+        Ok(cycles)
+    }
+
+    /// 放弃番茄钟，不产生时间记录
+    pub fn cancel_pomodoro(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        self.pomodoros
+            .remove(&event_id)
+            .map(|_| ())
+            .ok_or(EventError::NoPomodoro)
+    }
+
+    /// 结束所有进行中的事件，时间无效的事件（如开始时间晚于结束时间）会被跳过，返回已完成的事件ID
+    pub fn complete_all_active(&mut self, end_time: Option<DateTime<Utc>>) -> Vec<Uuid> {
+        let end_time = end_time.unwrap_or_else(Utc::now);
+        let active: Vec<Uuid> = self
+            .get_active_events()
+            .iter()
+            .map(|event| event.id)
+            .collect();
+
+        active
+            .into_iter()
+            .filter(|event_id| self.set_event_end_time(*event_id, Some(end_time)).is_ok())
+            .collect()
+    }
+
+    /// 用项目外的“未记录时间”事件填满某天工作时间（UTC小时）内没有记录的空档，返回新建的事件ID
+    ///
+    /// 已填补的空档会被新记录覆盖，重复调用不会重复创建
+    pub fn fill_day_gaps(
+        &mut self,
+        date: NaiveDate,
+        work_start_hour: u32,
+        work_end_hour: u32,
+    ) -> Result<Vec<Uuid>, EventError> {
+        self.fill_day_gaps_tz(date, work_start_hour, work_end_hour, utc_offset())
+    }
+
+    /// 用“未记录时间”事件填满某天工作时间内的空档，日期和工作时间都按tz时区的当地时间计算
+    pub fn fill_day_gaps_tz(
+        &mut self,
+        date: NaiveDate,
+        work_start_hour: u32,
+        work_end_hour: u32,
+        tz: FixedOffset,
+    ) -> Result<Vec<Uuid>, EventError> {
+        if work_start_hour >= work_end_hour || work_end_hour > 24 {
+            return Err(EventError::Invalid("工作时间范围无效".to_string()));
+        }
+
+        let day_start = tz
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        let window_start = day_start + chrono::Duration::hours(work_start_hour as i64);
+        let window_end = day_start + chrono::Duration::hours(work_end_hour as i64);
+        let gaps =
+            TimeCalculator::idle_gaps(&self.get_all_time_records(), window_start, window_end);
+
+        let mut created = Vec::new();
+        // 不足1分钟的空档不计入
+        for (start, end) in gaps
+            .into_iter()
+            .filter(|(start, end)| (*end - *start).num_minutes() >= 1)
+        {
+            let event_id =
+                self.add_non_project_event(UNTRACKED_TITLE.to_string(), None, Some(start))?;
+            self.set_event_end_time(event_id, Some(end))?;
+            created.push(event_id);
+        }
+        Ok(created)
+    }
+
+    /// 重新打开已完成的事件，清除结束时间并删除对应的时间记录
+    pub fn reopen_event(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if event.end_time.is_none() {
+            return Err(EventError::NotEnded);
+        }
+
+        event.end_time = None;
+        self.time_records
+            .retain(|_, record| record.event_id != event_id);
+        Ok(())
+    }
+
+    /// 在指定时间点把事件拆分为两个事件，返回新事件的ID
+    pub fn split_event(
+        &mut self,
+        event_id: Uuid,
+        split_time: DateTime<Utc>,
+        second_title: String,
+    ) -> Result<Uuid, EventError> {
+        let second_title = validate_name(&second_title).map_err(EventError::Invalid)?;
+        let event = self.events.get(&event_id).ok_or(EventError::NotFound)?;
+        let span_end = event.end_time.unwrap_or_else(Utc::now);
+
+        if split_time <= event.start_time || split_time >= span_end {
+            return Err(EventError::SplitOutOfRange);
+        }
+
+        let original_end = event.end_time;
+        // 后半段沿用原事件的描述、标签、优先级和项目分摊
+        let mut second = Event::new(
+            second_title,
+            event.description.clone(),
+            event.event_type.clone(),
+            split_time,
+        );
+        second.tags = event.tags.clone();
+        second.priority = event.priority;
+        second.project_shares = event.project_shares.clone();
+        let second_id = second.id;
+
+        // 截断原事件并重新生成时间记录
+        self.time_records
+            .retain(|_, record| record.event_id != event_id);
+        if let Some(event) = self.events.get_mut(&event_id) {
+            event.end_time = None;
+        }
+        self.set_event_end_time(event_id, Some(split_time))?;
+
+        // 新事件继承原事件的结束状态，进行中的事件拆分后新事件继续进行
+        self.events.insert(second_id, second);
+        if let Some(end_time) = original_end {
+            self.set_event_end_time(second_id, Some(end_time))?;
+        }
+
+        Ok(second_id)
+    }
+
+    /// 在指定时间点把时间记录拆成两段，后一段归到另一个事件（及其项目），返回新记录的ID。
+    /// 原事件在拆分点结束；新事件必须尚未结束，拆分后从拆分点开始、到原记录结束时结束
+    pub fn split_time_record(
+        &mut self,
+        record_id: Uuid,
+        at: DateTime<Utc>,
+        new_event_id: Uuid,
+    ) -> Result<Uuid, EventError> {
+        let new_event = self
+            .events
+            .get(&new_event_id)
+            .ok_or(EventError::NewEventNotFound)?;
+        let record = self
+            .time_records
+            .get(&record_id)
+            .ok_or(EventError::RecordNotFound)?;
+
+        if at <= record.start_time || at >= record.end_time {
+            return Err(EventError::RecordSplitOutOfRange);
+        }
+        if new_event_id == record.event_id {
+            return Err(EventError::Invalid(
+                "新事件不能是记录所属的事件".to_string(),
+            ));
+        }
+        if new_event.end_time.is_some() {
+            return Err(EventError::AlreadyEnded);
+        }
+        if self.pomodoros.contains_key(&new_event_id) {
+            return Err(EventError::PomodoroRunning);
+        }
+        // 事件和记录一一对应时才能保持拆分后两边一致
+        let event_record_count = self
+            .time_records
+            .values()
+            .filter(|other| other.event_id == record.event_id)
+            .count();
+        if event_record_count > 1 || !new_event.project_shares.is_empty() {
+            return Err(EventError::Invalid(
+                "多项目分摊的事件不能拆分时间记录".to_string(),
+            ));
+        }
+
+        let project_id = match new_event.event_type {
+            EventType::ProjectRelated(id) => Some(id),
+            EventType::NonProject => None,
+        };
+        let original_event_id = record.event_id;
+        let end_time = record.end_time;
+        let mut second = TimeRecord::new(new_event_id, project_id, at, end_time);
+        let first_minutes = at.signed_duration_since(record.start_time).num_minutes();
+        // 按整分钟截断时，误差全部留给后一段，保证两段之和等于原时长
+        second.duration_minutes = record.duration_minutes - first_minutes;
+        let second_id = second.id;
+
+        if let Some(record) = self.time_records.get_mut(&record_id) {
+            record.end_time = at;
+            record.duration_minutes = first_minutes;
+        }
+        if let Some(event) = self.events.get_mut(&original_event_id) {
+            event.set_end_time(at);
+        }
+        if let Some(event) = self.events.get_mut(&new_event_id) {
+            event.start_time = at;
+            event.set_end_time(end_time);
+        }
+        self.time_records.insert(second_id, second);
+
+        Ok(second_id)
     }
 
     /// 获取事件
     pub fn get_event(&self, event_id: Uuid) -> Option<&Event> {
-        self.events.get(&event_id)
+        self.events
+            .get(&event_id)
+            .filter(|event| event.deleted_at.is_none())
+    }
+
+    /// 不在回收站中的事件
+    fn visible_events(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .values()
+            .filter(|event| event.deleted_at.is_none())
+    }
+
+    /// 不属于回收站中事件的时间记录
+    fn visible_time_records(&self) -> impl Iterator<Item = &TimeRecord> {
+        self.time_records.values().filter(|record| {
+            self.events
+                .get(&record.event_id)
+                .is_none_or(|event| event.deleted_at.is_none())
+        })
     }
 
-    /// 获取所有事件
+    /// 获取回收站中的事件（按删除时间排序）
+    pub fn get_deleted_events(&self) -> Vec<&Event> {
+        let mut events: Vec<&Event> = self
+            .events
+            .values()
+            .filter(|event| event.deleted_at.is_some())
+            .collect();
+        events.sort_by_key(|event| (event.deleted_at, event.id));
+        events
+    }
+
+    /// 获取回收站中事件的时间记录（按开始时间排序）
+    pub fn get_deleted_time_records(&self) -> Vec<&TimeRecord> {
+        let mut records: Vec<&TimeRecord> = self
+            .time_records
+            .values()
+            .filter(|record| {
+                self.events
+                    .get(&record.event_id)
+                    .is_some_and(|event| event.deleted_at.is_some())
+            })
+            .collect();
+        records.sort_by_key(|r| (r.start_time, r.id));
+        records
+    }
+
+    /// 获取所有事件（按创建时间排序）
     pub fn get_all_events(&self) -> Vec<&Event> {
-        self.events.values().collect()
+        Self::sorted(self.visible_events().collect())
+    }
+
+    /// 按创建时间排序，创建时间相同时按ID排序，保证顺序稳定
+    fn sorted(mut events: Vec<&Event>) -> Vec<&Event> {
+        events.sort_by_key(|e| (e.created_at, e.id));
+        events
+    }
+
+    /// 获取所有事件，优先级高的在前，同一优先级内按创建时间排序
+    pub fn get_events_sorted_by_priority(&self) -> Vec<&Event> {
+        let mut events = self.get_all_events();
+        events.sort_by_key(|event| std::cmp::Reverse(event.priority));
+        events
     }
 
     /// 获取进行中的事件
     pub fn get_active_events(&self) -> Vec<&Event> {
-        self.events
-            .values()
-            .filter(|event| event.end_time.is_none())
-            .collect()
+        Self::sorted(
+            self.visible_events()
+                .filter(|event| event.end_time.is_none())
+                .collect(),
+        )
     }
 
     /// 获取已完成的事件
     pub fn get_completed_events(&self) -> Vec<&Event> {
-        self.events
-            .values()
-            .filter(|event| event.end_time.is_some())
-            .collect()
+        Self::sorted(
+            self.visible_events()
+                .filter(|event| event.end_time.is_some())
+                .collect(),
+        )
+    }
+
+    /// 获取最近完成的事件（按结束时间倒序，最多n个）
+    pub fn recent_completions(&self, n: usize) -> Vec<&Event> {
+        let mut completed = self.get_completed_events();
+        completed.sort_by_key(|event| std::cmp::Reverse(event.end_time));
+        completed.truncate(n);
+        completed
+    }
+
+    /// 查找时间区间相互重叠的事件对（进行中的事件视为到现在结束，首尾相接不算重叠）
+    pub fn find_overlapping_events(&self) -> Vec<(Uuid, Uuid)> {
+        let now = Utc::now();
+        let mut events: Vec<&Event> = self.visible_events().collect();
+        events.sort_by_key(|event| (event.start_time, event.id));
+
+        let mut overlaps = Vec::new();
+        for (i, first) in events.iter().enumerate() {
+            let first_end = first.end_time.unwrap_or(now);
+            // 按开始时间排序后，后面的事件一旦从first结束之后开始，就不会再重叠
+            for second in events[i + 1..]
+                .iter()
+                .take_while(|second| second.start_time < first_end)
+            {
+                let second_end = second.end_time.unwrap_or(now);
+                if first.start_time < second_end {
+                    overlaps.push((first.id, second.id));
+                }
+            }
+        }
+        overlaps
     }
 
     /// 获取项目相关事件
     pub fn get_project_events(&self, project_id: Uuid) -> Vec<&Event> {
-        self.events
-            .values()
+        self.visible_events()
             .filter(|event| match event.event_type {
                 EventType::ProjectRelated(id) => id == project_id,
                 EventType::NonProject => false,
@@ -121,34 +646,193 @@ impl EventManager {
             .collect()
     }
 
+    /// 获取项目在时间范围内的事件和时间记录，跨越范围边界的也包括在内
+    pub fn get_project_activity(
+        &self,
+        project_id: Uuid,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> ProjectActivity {
+        let mut events: Vec<Event> = self
+            .get_project_events(project_id)
+            .into_iter()
+            .filter(|event| {
+                event.start_time <= end_time && event.end_time.is_none_or(|end| end >= start_time)
+            })
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| event.start_time);
+
+        let records: Vec<&TimeRecord> = self
+            .get_project_time_records(project_id)
+            .into_iter()
+            .filter(|record| {
+                TimeCalculator::clipped_minutes(record, start_time, end_time).is_some()
+            })
+            .collect();
+        let total_minutes = TimeCalculator::calculate_project_total_time(
+            &records,
+            project_id,
+            Some(start_time),
+            Some(end_time),
+        );
+        let mut time_records: Vec<TimeRecord> = records.into_iter().cloned().collect();
+        time_records.sort_by_key(|record| record.start_time);
+
+        ProjectActivity {
+            project_id,
+            events,
+            time_records,
+            total_minutes,
+        }
+    }
+
     /// 获取项目外事件
     pub fn get_non_project_events(&self) -> Vec<&Event> {
-        self.events
-            .values()
+        self.visible_events()
             .filter(|event| matches!(event.event_type, EventType::NonProject))
             .collect()
     }
 
-    /// 删除事件
-    pub fn delete_event(&mut self, event_id: Uuid) -> Result<(), String> {
-        if self.events.remove(&event_id).is_none() {
-            return Err("事件不存在".to_string());
+    /// 把源项目的事件和时间记录迁移到目标项目，返回迁移的事件数量
+    pub fn reassign_project(&mut self, source_id: Uuid, target_id: Uuid) -> usize {
+        let mut moved = 0;
+        for event in self.events.values_mut() {
+            if matches!(event.event_type, EventType::ProjectRelated(id) if id == source_id) {
+                event.event_type = EventType::ProjectRelated(target_id);
+                moved += 1;
+            }
+            event.merge_project_share(source_id, target_id);
         }
 
-        // 同时删除相关的时间记录
-        self.time_records
-            .retain(|_, record| record.event_id != event_id);
+        // 同一事件分摊给两个项目时，合并后两条记录归到同一项目，合并为一条
+        let mut merged: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut duplicates = Vec::new();
+        for record in self.time_records.values() {
+            if record.project_id != Some(source_id) && record.project_id != Some(target_id) {
+                continue;
+            }
+            match merged.get(&record.event_id) {
+                Some(kept_id) => duplicates.push((*kept_id, record.id)),
+                None => {
+                    merged.insert(record.event_id, record.id);
+                }
+            }
+        }
+        for (kept_id, duplicate_id) in duplicates {
+            if let Some(duplicate) = self.time_records.remove(&duplicate_id) {
+                if let Some(kept) = self.time_records.get_mut(&kept_id) {
+                    kept.duration_minutes += duplicate.duration_minutes;
+                }
+            }
+        }
+        for record in self.time_records.values_mut() {
+            if record.project_id == Some(source_id) {
+                record.project_id = Some(target_id);
+            }
+        }
+        moved
+    }
+
+    /// 删除项目的所有事件、时间记录和模板，返回删除的事件数量。
+    /// 其他事件分摊给该项目的部分一并删除，其余项目按原比例重新分摊
+    pub fn delete_project_events(&mut self, project_id: Uuid) -> usize {
+        let belongs = |event_type: &EventType| matches!(event_type, EventType::ProjectRelated(id) if *id == project_id);
+
+        let before = self.events.len();
+        self.events.retain(|_, event| !belongs(&event.event_type));
+        for event in self.events.values_mut() {
+            event.remove_project_share(project_id);
+        }
+        let events = &self.events;
+        self.pomodoros
+            .retain(|event_id, _| events.contains_key(event_id));
+        self.time_records.retain(|_, record| {
+            events.contains_key(&record.event_id) && record.project_id != Some(project_id)
+        });
+        self.templates
+            .retain(|_, template| !belongs(&template.event_type));
+        before - self.events.len()
+    }
+
+    /// 把项目的事件、时间记录和模板改为项目外，返回受影响的事件数量。
+    /// 其他事件分摊给该项目的部分从分摊中去掉，已有的记录改为项目外
+    pub fn detach_project(&mut self, project_id: Uuid) -> usize {
+        let mut detached = 0;
+        for event in self.events.values_mut() {
+            if matches!(event.event_type, EventType::ProjectRelated(id) if id == project_id) {
+                event.event_type = EventType::NonProject;
+                detached += 1;
+            }
+            event.remove_project_share(project_id);
+        }
+        for record in self.time_records.values_mut() {
+            if record.project_id == Some(project_id) {
+                record.project_id = None;
+            }
+        }
+        for template in self.templates.values_mut() {
+            if matches!(template.event_type, EventType::ProjectRelated(id) if id == project_id) {
+                template.event_type = EventType::NonProject;
+            }
+        }
+        detached
+    }
+
+    /// 把事件移入回收站，时间记录随事件一起隐藏，可以用 restore_event 恢复
+    pub fn delete_event(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        let event = self
+            .events
+            .get_mut(&event_id)
+            .filter(|event| event.deleted_at.is_none())
+            .ok_or(EventError::NotFound)?;
+        event.deleted_at = Some(Utc::now());
+        self.pomodoros.remove(&event_id);
+
+        Ok(())
+    }
 
+    /// 从回收站恢复事件
+    pub fn restore_event(&mut self, event_id: Uuid) -> Result<(), EventError> {
+        let event = self
+            .events
+            .get_mut(&event_id)
+            .filter(|event| event.deleted_at.is_some())
+            .ok_or(EventError::NotInTrash)?;
+        event.deleted_at = None;
         Ok(())
     }
 
+    /// 永久删除在指定时间之前移入回收站的事件及其时间记录，返回删除的事件数
+    pub fn purge_deleted(&mut self, before: DateTime<Utc>) -> usize {
+        let purged: HashSet<Uuid> = self
+            .events
+            .values()
+            .filter(|event| {
+                event
+                    .deleted_at
+                    .is_some_and(|deleted_at| deleted_at < before)
+            })
+            .map(|event| event.id)
+            .collect();
+
+        self.events.retain(|id, _| !purged.contains(id));
+        self.time_records
+            .retain(|_, record| !purged.contains(&record.event_id));
+        purged.len()
+    }
+
     /// 更新事件信息
     pub fn update_event(
         &mut self,
         event_id: Uuid,
         title: Option<String>,
         description: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EventError> {
+        let title = title
+            .map(|title| validate_name(&title))
+            .transpose()
+            .map_err(EventError::Invalid)?;
         if let Some(event) = self.events.get_mut(&event_id) {
             if let Some(title) = title {
                 event.title = title;
@@ -158,51 +842,347 @@ impl EventManager {
             }
             Ok(())
         } else {
-            Err("事件不存在".to_string())
+            Err(EventError::NotFound)
         }
     }
 
-    /// 获取时间记录
-    pub fn get_time_record(&self, record_id: Uuid) -> Option<&TimeRecord> {
-        self.time_records.get(&record_id)
-    }
+    /// 修改事件开始时间，已完成的事件会同步更新时间记录：从原开始时间起算或早于新开始时间的
+    /// 记录移到新开始时间，其余记录不变；记录中不计入的时间（如番茄钟的休息时间）保持不变
+    pub fn set_event_start_time(
+        &mut self,
+        event_id: Uuid,
+        new_start: DateTime<Utc>,
+    ) -> Result<(), EventError> {
+        let event = self.events.get(&event_id).ok_or(EventError::NotFound)?;
+        if event.deleted_at.is_some() {
+            return Err(EventError::InTrash);
+        }
+        if let Some(end_time) = event.end_time {
+            if new_start >= end_time {
+                return Err(EventError::StartAfterEnd(end_time));
+            }
+        }
+        let old_start = event.start_time;
+        let moved = |record: &TimeRecord| {
+            record.event_id == event_id
+                && (record.start_time == old_start || record.start_time < new_start)
+        };
+        if let Some(record) = self
+            .time_records
+            .values()
+            .filter(|record| moved(record))
+            .find(|record| new_start >= record.end_time)
+        {
+            return Err(EventError::StartAfterRecordEnd(record.end_time));
+        }
 
-    /// 获取所有时间记录
-    pub fn get_all_time_records(&self) -> Vec<&TimeRecord> {
-        self.time_records.values().collect()
+        // 原时长与记录时长之差是不计入的时间，修改后保持不变
+        let span_minutes = |start: DateTime<Utc>, end: DateTime<Utc>| {
+            end.signed_duration_since(start).num_minutes()
+        };
+        let excluded_minutes = |record: &TimeRecord| {
+            span_minutes(record.start_time, record.end_time) - record.duration_minutes
+        };
+        // 多项目分摊的记录按新的总时长重新分摊
+        let shared_parts = if event.project_shares.is_empty() {
+            None
+        } else {
+            let records: Vec<&TimeRecord> = self
+                .time_records
+                .values()
+                .filter(|record| moved(record))
+                .collect();
+            records.first().map(|first| {
+                let total: i64 = records.iter().map(|record| record.duration_minutes).sum();
+                let excluded = span_minutes(first.start_time, first.end_time) - total;
+                let new_total = (span_minutes(new_start, first.end_time) - excluded).max(0);
+                event.split_minutes(new_total)
+            })
+        };
+
+        for record in self.time_records.values_mut() {
+            if !moved(record) {
+                continue;
+            }
+            let shared_minutes = shared_parts
+                .iter()
+                .flatten()
+                .find_map(|(project_id, minutes)| {
+                    (*project_id == record.project_id).then_some(*minutes)
+                });
+            record.duration_minutes = shared_minutes.unwrap_or_else(|| {
+                (span_minutes(new_start, record.end_time) - excluded_minutes(record)).max(0)
+            });
+            record.start_time = new_start;
+        }
+        if let Some(event) = self.events.get_mut(&event_id) {
+            event.start_time = new_start;
+        }
+
+        Ok(())
     }
 
-    /// 获取事件的时间记录
-    pub fn get_event_time_record(&self, event_id: Uuid) -> Option<&TimeRecord> {
-        self.time_records
-            .values()
-            .find(|record| record.event_id == event_id)
+    /// 设置时间记录的笔记，空白内容会清除笔记
+    pub fn set_record_note(
+        &mut self,
+        record_id: Uuid,
+        note: Option<String>,
+    ) -> Result<(), EventError> {
+        let record = self
+            .time_records
+            .get_mut(&record_id)
+            .ok_or(EventError::RecordNotFound)?;
+        record.note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        Ok(())
     }
 
-    /// 获取项目的时间记录
+    /// 直接设置时间记录的时长（分钟），结束时间随之调整为开始时间加上时长；
+    /// 所属事件的结束时间与原记录一致时也一并更新
+    pub fn set_record_duration(&mut self, record_id: Uuid, minutes: i64) -> Result<(), EventError> {
+        if minutes < 0 {
+            return Err(EventError::Invalid("时长不能为负数".to_string()));
+        }
+        let record = self
+            .time_records
+            .get_mut(&record_id)
+            .ok_or(EventError::RecordNotFound)?;
+        let old_end = record.end_time;
+        record.end_time = record.start_time + chrono::Duration::minutes(minutes);
+        record.duration_minutes = minutes;
+        let (event_id, new_end) = (record.event_id, record.end_time);
+
+        if let Some(event) = self.events.get_mut(&event_id) {
+            if event.end_time == Some(old_end) {
+                event.set_end_time(new_end);
+            }
+        }
+        Ok(())
+    }
+
+    /// 设置进行中事件的多项目分摊比例（比例之和须为100），空列表表示取消分摊
+    pub fn set_project_shares(
+        &mut self,
+        event_id: Uuid,
+        shares: Vec<(Uuid, u8)>,
+    ) -> Result<(), EventError> {
+        if !shares.is_empty() {
+            validate_project_shares(&shares).map_err(EventError::Invalid)?;
+        }
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if event.end_time.is_some() {
+            return Err(EventError::AlreadyEnded);
+        }
+        event.project_shares = shares;
+        Ok(())
+    }
+
+    /// 设置事件的工作量点数
+    pub fn set_event_effort_points(
+        &mut self,
+        event_id: Uuid,
+        effort_points: Option<u32>,
+    ) -> Result<(), EventError> {
+        if let Some(event) = self.events.get_mut(&event_id) {
+            event.set_effort_points(effort_points);
+            Ok(())
+        } else {
+            Err(EventError::NotFound)
+        }
+    }
+
+    /// 设置事件优先级
+    pub fn set_priority(&mut self, event_id: Uuid, priority: Priority) -> Result<(), EventError> {
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        event.priority = priority;
+        Ok(())
+    }
+
+    /// 添加前置事件：event_id 要等 dependency_id 完成后才能开始，会形成循环依赖时拒绝
+    pub fn add_dependency(
+        &mut self,
+        event_id: Uuid,
+        dependency_id: Uuid,
+    ) -> Result<(), EventError> {
+        if event_id == dependency_id {
+            return Err(EventError::SelfDependency);
+        }
+        if !self.event_exists(event_id) || !self.event_exists(dependency_id) {
+            return Err(EventError::NotFound);
+        }
+        if self.depends_on_transitively(dependency_id, event_id) {
+            return Err(EventError::CyclicDependency);
+        }
+
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if !event.depends_on.contains(&dependency_id) {
+            event.depends_on.push(dependency_id);
+        }
+        Ok(())
+    }
+
+    /// 移除前置事件
+    pub fn remove_dependency(
+        &mut self,
+        event_id: Uuid,
+        dependency_id: Uuid,
+    ) -> Result<(), EventError> {
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if !event.depends_on.contains(&dependency_id) {
+            return Err(EventError::DependencyNotFound);
+        }
+        event.depends_on.retain(|id| *id != dependency_id);
+        Ok(())
+    }
+
+    /// event_id 是否直接或间接依赖 target_id
+    fn depends_on_transitively(&self, event_id: Uuid, target_id: Uuid) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![event_id];
+        while let Some(id) = stack.pop() {
+            if id == target_id {
+                return true;
+            }
+            if visited.insert(id) {
+                if let Some(event) = self.events.get(&id) {
+                    stack.extend(event.depends_on.iter().copied());
+                }
+            }
+        }
+        false
+    }
+
+    /// 获取还没完成、且有前置事件在now之前没有完成的事件；已删除的前置事件不再阻塞
+    pub fn get_blocked_events(&self, now: DateTime<Utc>) -> Vec<&Event> {
+        let is_done = |event: &Event| event.end_time.is_some_and(|end| end <= now);
+        Self::sorted(
+            self.visible_events()
+                .filter(|event| !is_done(event))
+                .filter(|event| {
+                    event.depends_on.iter().any(|id| {
+                        self.get_event(*id)
+                            .is_some_and(|dependency| !is_done(dependency))
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// 为事件添加标签（重复的标签会被忽略）
+    pub fn add_tag(&mut self, event_id: Uuid, tag: &str) -> Result<(), EventError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(EventError::Invalid("标签不能为空".to_string()));
+        }
+
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        if !event.tags.iter().any(|t| t == tag) {
+            event.tags.push(tag.to_string());
+        }
+        Ok(())
+    }
+
+    /// 给事件添加一个未完成的检查项，返回检查项的序号
+    pub fn add_subtask(&mut self, event_id: Uuid, title: &str) -> Result<usize, EventError> {
+        let title = validate_name(title).map_err(EventError::Invalid)?;
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        event.subtasks.push(Subtask { title, done: false });
+        Ok(event.subtasks.len() - 1)
+    }
+
+    /// 切换检查项的完成状态，返回切换后的状态
+    pub fn toggle_subtask(&mut self, event_id: Uuid, index: usize) -> Result<bool, EventError> {
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        let subtask = event
+            .subtasks
+            .get_mut(index)
+            .ok_or(EventError::SubtaskNotFound)?;
+        subtask.done = !subtask.done;
+        Ok(subtask.done)
+    }
+
+    /// 给项目的所有事件加上标签，已有该标签的事件跳过，返回新加上标签的事件数量
+    pub fn tag_project_events(&mut self, project_id: Uuid, tag: &str) -> Result<usize, EventError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(EventError::Invalid("标签不能为空".to_string()));
+        }
+
+        let mut tagged = 0;
+        for event in self.events.values_mut() {
+            if event.deleted_at.is_none()
+                && matches!(event.event_type, EventType::ProjectRelated(id) if id == project_id)
+                && !event.tags.iter().any(|t| t == tag)
+            {
+                event.tags.push(tag.to_string());
+                tagged += 1;
+            }
+        }
+        Ok(tagged)
+    }
+
+    /// 移除事件的标签
+    pub fn remove_tag(&mut self, event_id: Uuid, tag: &str) -> Result<(), EventError> {
+        let event = self.events.get_mut(&event_id).ok_or(EventError::NotFound)?;
+        let tag = tag.trim();
+        if !event.tags.iter().any(|t| t == tag) {
+            return Err(EventError::TagNotFound);
+        }
+        event.tags.retain(|t| t != tag);
+        Ok(())
+    }
+
+    /// 获取带有指定标签的事件
+    pub fn get_events_by_tag(&self, tag: &str) -> Vec<&Event> {
+        let tag = tag.trim();
+        Self::sorted(
+            self.visible_events()
+                .filter(|event| event.tags.iter().any(|t| t == tag))
+                .collect(),
+        )
+    }
+
+    /// 获取时间记录
+    pub fn get_time_record(&self, record_id: Uuid) -> Option<&TimeRecord> {
+        self.time_records.get(&record_id)
+    }
+
+    /// 获取所有时间记录（按开始时间排序）
+    pub fn get_all_time_records(&self) -> Vec<&TimeRecord> {
+        let mut records: Vec<&TimeRecord> = self.visible_time_records().collect();
+        records.sort_by_key(|r| (r.start_time, r.id));
+        records
+    }
+
+    /// 获取事件的时间记录
+    pub fn get_event_time_record(&self, event_id: Uuid) -> Option<&TimeRecord> {
+        self.visible_time_records()
+            .find(|record| record.event_id == event_id)
+    }
+
+    /// 获取项目的时间记录
     pub fn get_project_time_records(&self, project_id: Uuid) -> Vec<&TimeRecord> {
-        self.time_records
-            .values()
+        self.visible_time_records()
             .filter(|record| record.project_id == Some(project_id))
             .collect()
     }
 
     /// 获取项目外的时间记录
     pub fn get_non_project_time_records(&self) -> Vec<&TimeRecord> {
-        self.time_records
-            .values()
+        self.visible_time_records()
             .filter(|record| record.project_id.is_none())
             .collect()
     }
 
     /// 获取事件数量
     pub fn get_event_count(&self) -> usize {
-        self.events.len()
+        self.visible_events().count()
     }
 
     /// 检查事件是否存在
     pub fn event_exists(&self, event_id: Uuid) -> bool {
-        self.events.contains_key(&event_id)
+        self.get_event(event_id).is_some()
     }
 
     /// 获取指定时间范围内的事件
@@ -211,8 +1191,7 @@ impl EventManager {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Vec<&Event> {
-        self.events
-            .values()
+        self.visible_events()
             .filter(|event| event.start_time >= start_time && event.start_time <= end_time)
             .collect()
     }
@@ -223,8 +1202,7 @@ impl EventManager {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Vec<&TimeRecord> {
-        self.time_records
-            .values()
+        self.visible_time_records()
             .filter(|record| record.start_time >= start_time && record.start_time <= end_time)
             .collect()
     }
@@ -239,6 +1217,7 @@ impl Default for EventManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{PomodoroPhase, Recurrence, RoundingMode, MAX_NAME_LENGTH};
     use chrono::Duration;
 
     #[test]
@@ -246,12 +1225,14 @@ mod tests {
         let mut manager = EventManager::new();
         let project_id = Uuid::new_v4();
 
-        let event_id = manager.add_project_event(
-            "测试事件".to_string(),
-            Some("测试描述".to_string()),
-            project_id,
-            None,
-        );
+        let event_id = manager
+            .add_project_event(
+                "测试事件".to_string(),
+                Some("测试描述".to_string()),
+                project_id,
+                None,
+            )
+            .unwrap();
 
         assert_eq!(manager.get_event_count(), 1);
         assert!(manager.event_exists(event_id));
@@ -266,7 +1247,9 @@ mod tests {
     fn test_add_non_project_event() {
         let mut manager = EventManager::new();
 
-        let event_id = manager.add_non_project_event("非项目事件".to_string(), None, None);
+        let event_id = manager
+            .add_non_project_event("非项目事件".to_string(), None, None)
+            .unwrap();
 
         assert_eq!(manager.get_event_count(), 1);
 
@@ -280,7 +1263,9 @@ mod tests {
         let mut manager = EventManager::new();
         let project_id = Uuid::new_v4();
 
-        let event_id = manager.add_project_event("测试事件".to_string(), None, project_id, None);
+        let event_id = manager
+            .add_project_event("测试事件".to_string(), None, project_id, None)
+            .unwrap();
 
         let end_time = Utc::now() + Duration::hours(1);
         manager
@@ -298,25 +1283,1691 @@ mod tests {
     }
 
     #[test]
-    fn test_get_project_events() {
+    fn test_reopen_event() {
         let mut manager = EventManager::new();
-        let project_id1 = Uuid::new_v4();
-        let project_id2 = Uuid::new_v4();
+        let start = Utc::now() - Duration::hours(1);
+        let event_id = manager
+            .add_non_project_event("误点完成".to_string(), None, Some(start))
+            .unwrap();
+        assert!(manager.reopen_event(event_id).is_err());
 
-        manager.add_project_event("项目1事件".to_string(), None, project_id1, None);
-        manager.add_project_event("项目2事件".to_string(), None, project_id2, None);
-        manager.add_non_project_event("非项目事件".to_string(), None, None);
+        manager.set_event_end_time(event_id, None).unwrap();
+        assert!(manager.get_event_time_record(event_id).is_some());
 
-        let project1_events = manager.get_project_events(project_id1);
-        assert_eq!(project1_events.len(), 1);
-        assert_eq!(project1_events[0].title, "项目1事件");
+        manager.reopen_event(event_id).unwrap();
+        assert!(manager.get_event(event_id).unwrap().end_time.is_none());
+        assert!(manager.get_event_time_record(event_id).is_none());
+        assert!(manager.get_all_time_records().is_empty());
+        assert_eq!(manager.get_active_events()[0].id, event_id);
 
-        let project2_events = manager.get_project_events(project_id2);
-        assert_eq!(project2_events.len(), 1);
-        assert_eq!(project2_events[0].title, "项目2事件");
+        assert!(manager.reopen_event(Uuid::new_v4()).is_err());
+    }
 
-        let non_project_events = manager.get_non_project_events();
-        assert_eq!(non_project_events.len(), 1);
-        assert_eq!(non_project_events[0].title, "非项目事件");
+    #[test]
+    fn test_set_start_time_of_active_event() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(1);
+        let event_id = manager
+            .add_non_project_event("补录".to_string(), None, Some(start))
+            .unwrap();
+
+        let earlier = start - Duration::hours(2);
+        manager.set_event_start_time(event_id, earlier).unwrap();
+
+        assert_eq!(manager.get_event(event_id).unwrap().start_time, earlier);
+        assert!(manager.get_event_time_record(event_id).is_none());
+    }
+
+    #[test]
+    fn test_set_start_time_of_completed_event() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(3);
+        let end = start + Duration::hours(1);
+        let event_id = manager
+            .add_non_project_event("补录".to_string(), None, Some(start))
+            .unwrap();
+        manager.set_event_end_time(event_id, Some(end)).unwrap();
+
+        // 提前半小时开始，时长随之变为90分钟
+        let earlier = start - Duration::minutes(30);
+        manager.set_event_start_time(event_id, earlier).unwrap();
+        let record = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(record.start_time, earlier);
+        assert_eq!(record.duration_minutes, 90);
+
+        // 开始时间不能晚于或等于结束时间
+        assert!(manager.set_event_start_time(event_id, end).is_err());
+        assert!(manager
+            .set_event_start_time(event_id, end + Duration::minutes(1))
+            .is_err());
+        assert_eq!(manager.get_event(event_id).unwrap().start_time, earlier);
+        assert_eq!(
+            manager
+                .get_event_time_record(event_id)
+                .unwrap()
+                .duration_minutes,
+            90
+        );
+    }
+
+    #[test]
+    fn test_insert_time_record_keeps_fields() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let start = Utc::now();
+        let mut event = Event::new(
+            "已保存事件".to_string(),
+            None,
+            EventType::ProjectRelated(project_id),
+            start,
+        );
+        event.set_end_time(start + Duration::minutes(45));
+        let record = TimeRecord::new(
+            event.id,
+            Some(project_id),
+            start,
+            start + Duration::minutes(45),
+        );
+        let (event_id, record_id) = (event.id, record.id);
+
+        manager.insert_event(event);
+        manager.insert_time_record(record);
+
+        assert!(manager.get_event(event_id).unwrap().is_completed());
+        let restored = manager.get_time_record(record_id).unwrap();
+        assert_eq!(restored.event_id, event_id);
+        assert_eq!(restored.project_id, Some(project_id));
+        assert_eq!(restored.duration_minutes, 45);
+    }
+
+    #[test]
+    fn test_recent_completions() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let first = manager
+            .add_project_event("第一".to_string(), None, project_id, Some(base_time))
+            .unwrap();
+        let second = manager
+            .add_non_project_event("第二".to_string(), None, Some(base_time))
+            .unwrap();
+        let third = manager
+            .add_project_event("第三".to_string(), None, project_id, Some(base_time))
+            .unwrap();
+        manager
+            .add_non_project_event("进行中".to_string(), None, Some(base_time))
+            .unwrap();
+
+        manager
+            .set_event_end_time(first, Some(base_time + Duration::hours(1)))
+            .unwrap();
+        manager
+            .set_event_end_time(second, Some(base_time + Duration::hours(3)))
+            .unwrap();
+        manager
+            .set_event_end_time(third, Some(base_time + Duration::hours(2)))
+            .unwrap();
+
+        let recent = manager.recent_completions(10);
+        let titles: Vec<&str> = recent.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["第二", "第三", "第一"]);
+
+        let limited = manager.recent_completions(2);
+        assert_eq!(limited.len(), 2);
+        assert_eq!(limited[0].id, second);
+    }
+
+    #[test]
+    fn test_event_elapsed() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(3);
+        let done = manager
+            .add_non_project_event("会议".to_string(), None, Some(start))
+            .unwrap();
+        manager
+            .set_event_end_time(done, Some(start + Duration::minutes(90)))
+            .unwrap();
+        let active = manager
+            .add_non_project_event("写代码".to_string(), None, Some(start))
+            .unwrap();
+
+        let now = start + Duration::minutes(125);
+        let done = manager.get_event(done).unwrap();
+        assert_eq!(Some(done.elapsed(now)), done.duration());
+        assert_eq!(done.elapsed(now), Duration::minutes(90));
+
+        let active = manager.get_event(active).unwrap();
+        assert_eq!(active.duration(), None);
+        assert_eq!(active.elapsed(now), Duration::minutes(125));
+        assert_eq!(
+            TimeCalculator::format_duration(active.elapsed(now).num_minutes()),
+            "2小时5分钟"
+        );
+    }
+
+    #[test]
+    fn test_split_event_at_midpoint() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let start = Utc::now() - Duration::hours(4);
+        let end = start + Duration::hours(2);
+
+        let event_id = manager
+            .add_project_event("写报告".to_string(), None, project_id, Some(start))
+            .unwrap();
+        manager.set_event_end_time(event_id, Some(end)).unwrap();
+
+        let split_time = start + Duration::hours(1);
+        let second_id = manager
+            .split_event(event_id, split_time, "回复邮件".to_string())
+            .unwrap();
+
+        let original = manager.get_event(event_id).unwrap();
+        assert_eq!(original.end_time, Some(split_time));
+        let second = manager.get_event(second_id).unwrap();
+        assert_eq!(second.title, "回复邮件");
+        assert_eq!(second.start_time, split_time);
+        assert_eq!(second.end_time, Some(end));
+        assert!(matches!(second.event_type, EventType::ProjectRelated(id) if id == project_id));
+
+        assert_eq!(manager.get_all_time_records().len(), 2);
+        assert_eq!(
+            manager
+                .get_event_time_record(event_id)
+                .unwrap()
+                .duration_minutes,
+            60
+        );
+        assert_eq!(
+            manager
+                .get_event_time_record(second_id)
+                .unwrap()
+                .duration_minutes,
+            60
+        );
+    }
+
+    #[test]
+    fn test_split_event_rejects_boundaries() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(4);
+        let end = start + Duration::hours(2);
+
+        let event_id = manager
+            .add_non_project_event("会议".to_string(), None, Some(start))
+            .unwrap();
+        manager.set_event_end_time(event_id, Some(end)).unwrap();
+
+        assert!(manager
+            .split_event(event_id, start, "拆分".to_string())
+            .is_err());
+        assert!(manager
+            .split_event(event_id, end, "拆分".to_string())
+            .is_err());
+        assert!(manager
+            .split_event(event_id, end + Duration::minutes(1), "拆分".to_string())
+            .is_err());
+        // 后半段的标题与新建事件一样需要校验
+        let middle = start + Duration::hours(1);
+        assert!(matches!(
+            manager.split_event(event_id, middle, "  ".to_string()),
+            Err(EventError::Invalid(_))
+        ));
+        assert!(matches!(
+            manager.split_event(event_id, middle, "长".repeat(MAX_NAME_LENGTH + 1)),
+            Err(EventError::Invalid(_))
+        ));
+
+        // 失败的拆分不应改变原事件
+        assert_eq!(manager.get_event_count(), 1);
+        assert_eq!(manager.get_event(event_id).unwrap().end_time, Some(end));
+        assert_eq!(manager.get_all_time_records().len(), 1);
+    }
+
+    #[test]
+    fn test_event_order_is_stable() {
+        let mut manager = EventManager::new();
+        for title in ["事件C", "事件A", "事件B"] {
+            manager
+                .add_non_project_event(title.to_string(), None, None)
+                .unwrap();
+        }
+
+        let first: Vec<Uuid> = manager.get_all_events().iter().map(|e| e.id).collect();
+        for _ in 0..10 {
+            let again: Vec<Uuid> = manager.get_all_events().iter().map(|e| e.id).collect();
+            assert_eq!(first, again);
+        }
+        assert_eq!(
+            manager
+                .get_active_events()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>(),
+            first
+        );
+    }
+
+    #[test]
+    fn test_find_overlapping_events() {
+        let mut manager = EventManager::new();
+        let base = Utc::now() - Duration::hours(10);
+
+        // 首尾相接：09:00-10:00 与 10:00-11:00 不算重叠
+        let first = manager
+            .add_non_project_event("A".to_string(), None, Some(base))
+            .unwrap();
+        manager
+            .set_event_end_time(first, Some(base + Duration::hours(1)))
+            .unwrap();
+        let second = manager
+            .add_non_project_event("B".to_string(), None, Some(base + Duration::hours(1)))
+            .unwrap();
+        manager
+            .set_event_end_time(second, Some(base + Duration::hours(2)))
+            .unwrap();
+        assert!(manager.find_overlapping_events().is_empty());
+
+        // 与B重叠的已完成事件
+        let third = manager
+            .add_non_project_event("C".to_string(), None, Some(base + Duration::minutes(90)))
+            .unwrap();
+        manager
+            .set_event_end_time(third, Some(base + Duration::hours(3)))
+            .unwrap();
+        assert_eq!(manager.find_overlapping_events(), vec![(second, third)]);
+
+        // 进行中的事件视为持续到现在，与之后开始的事件重叠
+        let active = manager
+            .add_non_project_event("进行中".to_string(), None, Some(base + Duration::hours(5)))
+            .unwrap();
+        let later = manager
+            .add_non_project_event("D".to_string(), None, Some(base + Duration::hours(6)))
+            .unwrap();
+        manager
+            .set_event_end_time(later, Some(base + Duration::hours(7)))
+            .unwrap();
+        let overlaps = manager.find_overlapping_events();
+        assert_eq!(overlaps.len(), 2);
+        assert!(overlaps.contains(&(active, later)));
+    }
+
+    #[test]
+    fn test_weekday_template_skips_weekends() {
+        let mut manager = EventManager::new();
+        let nine = chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        manager.add_template(EventTemplate::new(
+            "站会".to_string(),
+            None,
+            EventType::NonProject,
+            Recurrence::Weekdays,
+            nine,
+        ));
+
+        // 2024-03-01 是周五，到下周一共4天，其中周六周日跳过
+        let friday = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let created = manager.materialize_templates(friday, monday);
+        assert_eq!(created.len(), 2);
+
+        let dates: Vec<NaiveDate> = manager
+            .get_all_events()
+            .iter()
+            .map(|event| event.start_time.date_naive())
+            .collect();
+        assert!(dates.contains(&friday));
+        assert!(dates.contains(&monday));
+        assert!(manager
+            .get_all_events()
+            .iter()
+            .all(|event| event.start_time.time() == nine && event.title == "站会"));
+    }
+
+    #[test]
+    fn test_materialize_templates_is_idempotent() {
+        let mut manager = EventManager::new();
+        let nine = chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        manager.add_template(EventTemplate::new(
+            "站会".to_string(),
+            None,
+            EventType::NonProject,
+            Recurrence::Daily,
+            nine,
+        ));
+        manager.add_template(EventTemplate::new(
+            "计划会".to_string(),
+            None,
+            EventType::NonProject,
+            Recurrence::Weekly(chrono::Weekday::Mon),
+            nine,
+        ));
+
+        let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(manager.materialize_templates(from, to).len(), 11);
+        assert!(manager.materialize_templates(from, to).is_empty());
+
+        // 扩大范围时只补上新的日期
+        let later = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        assert_eq!(manager.materialize_templates(from, later).len(), 2);
+        assert_eq!(manager.get_event_count(), 13);
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut manager = EventManager::new();
+        let meeting = manager
+            .add_non_project_event("周会".to_string(), None, None)
+            .unwrap();
+        let fix = manager
+            .add_non_project_event("修复登录".to_string(), None, None)
+            .unwrap();
+
+        manager.add_tag(meeting, "meeting").unwrap();
+        manager.add_tag(meeting, " meeting ").unwrap();
+        manager.add_tag(fix, "bugfix").unwrap();
+        manager.add_tag(fix, "meeting").unwrap();
+        assert!(manager.add_tag(fix, "  ").is_err());
+
+        // 重复的标签只保留一个
+        assert_eq!(manager.get_event(meeting).unwrap().tags, vec!["meeting"]);
+
+        let meetings = manager.get_events_by_tag("meeting");
+        assert_eq!(meetings.len(), 2);
+        let bugfixes = manager.get_events_by_tag("bugfix");
+        assert_eq!(bugfixes.len(), 1);
+        assert_eq!(bugfixes[0].id, fix);
+
+        manager.remove_tag(fix, "meeting").unwrap();
+        assert_eq!(manager.get_events_by_tag("meeting").len(), 1);
+        assert!(manager.remove_tag(fix, "meeting").is_err());
+    }
+
+    #[test]
+    fn test_subtasks() {
+        let mut manager = EventManager::new();
+        let event_id = manager
+            .add_non_project_event("发布新版本".to_string(), None, None)
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_event(event_id)
+                .unwrap()
+                .subtask_completion_percentage(),
+            None
+        );
+
+        for (expected, title) in ["更新日志", "打标签", "上传安装包", "发公告"]
+            .iter()
+            .enumerate()
+        {
+            assert_eq!(manager.add_subtask(event_id, title).unwrap(), expected);
+        }
+        assert!(manager.add_subtask(event_id, "  ").is_err());
+        assert!(manager.add_subtask(Uuid::new_v4(), "检查项").is_err());
+
+        assert!(manager.toggle_subtask(event_id, 0).unwrap());
+        assert!(manager.toggle_subtask(event_id, 2).unwrap());
+        assert!(manager.toggle_subtask(event_id, 3).unwrap());
+        // 再次切换恢复为未完成
+        assert!(!manager.toggle_subtask(event_id, 3).unwrap());
+        assert!(manager.toggle_subtask(event_id, 4).is_err());
+
+        let event = manager.get_event(event_id).unwrap();
+        assert_eq!(event.subtask_progress(), (2, 4));
+        assert_eq!(event.subtask_completion_percentage(), Some(50.0));
+        assert_eq!(event.subtasks[1].title, "打标签");
+        assert!(!event.subtasks[1].done);
+
+        // 旧数据没有检查项字段时为空
+        let mut value = serde_json::to_value(event).unwrap();
+        value.as_object_mut().unwrap().remove("subtasks");
+        let legacy: Event = serde_json::from_value(value).unwrap();
+        assert!(legacy.subtasks.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_event() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let start = Utc::now() - Duration::hours(3);
+        let original = manager
+            .add_project_event(
+                "每日站会".to_string(),
+                Some("同步进度".to_string()),
+                project_id,
+                Some(start),
+            )
+            .unwrap();
+        manager
+            .set_event_end_time(original, Some(start + Duration::minutes(15)))
+            .unwrap();
+
+        let restart = start + Duration::days(1);
+        let copy_id = manager.duplicate_event(original, Some(restart)).unwrap();
+        assert_ne!(copy_id, original);
+        let copy = manager.get_event(copy_id).unwrap();
+        assert_eq!(copy.title, "每日站会");
+        assert_eq!(copy.description.as_deref(), Some("同步进度"));
+        assert!(matches!(copy.event_type, EventType::ProjectRelated(id) if id == project_id));
+        assert_eq!(copy.start_time, restart);
+        assert_eq!(copy.end_time, None);
+        assert_eq!(manager.get_project_events(project_id).len(), 2);
+        // 原事件保持不变
+        assert!(manager.get_event(original).unwrap().is_completed());
+
+        assert!(manager.duplicate_event(Uuid::new_v4(), None).is_err());
+    }
+
+    #[test]
+    fn test_tag_project_events() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let other_project = Uuid::new_v4();
+        let mut project_events = Vec::new();
+        for title in ["设计", "开发", "测试"] {
+            project_events.push(
+                manager
+                    .add_project_event(title.to_string(), None, project_id, None)
+                    .unwrap(),
+            );
+        }
+        let meeting = manager
+            .add_non_project_event("周会".to_string(), None, None)
+            .unwrap();
+        let other = manager
+            .add_project_event("别的项目".to_string(), None, other_project, None)
+            .unwrap();
+        manager.add_tag(project_events[0], "q3").unwrap();
+
+        assert_eq!(manager.tag_project_events(project_id, " q3 ").unwrap(), 2);
+        for event_id in &project_events {
+            assert_eq!(manager.get_event(*event_id).unwrap().tags, vec!["q3"]);
+        }
+        assert!(manager.get_event(meeting).unwrap().tags.is_empty());
+        assert!(manager.get_event(other).unwrap().tags.is_empty());
+        assert_eq!(manager.get_events_by_tag("q3").len(), 3);
+
+        // 再次添加时没有新的事件需要加标签
+        assert_eq!(manager.tag_project_events(project_id, "q3").unwrap(), 0);
+        assert!(manager.tag_project_events(project_id, " ").is_err());
+    }
+
+    #[test]
+    fn test_pomodoro_records_only_work_time() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let start = Utc::now() - Duration::hours(2);
+        let event_id = manager
+            .add_project_event("专注写代码".to_string(), None, project_id, Some(start))
+            .unwrap();
+        manager.start_pomodoro_at(event_id, 25, 5, start).unwrap();
+        assert!(manager.start_pomodoro_at(event_id, 25, 5, start).is_err());
+
+        let session = manager.get_pomodoro(event_id).unwrap();
+        assert_eq!(
+            session.phase(start + Duration::minutes(10)),
+            PomodoroPhase::Work
+        );
+        assert_eq!(
+            session.remaining(start + Duration::minutes(10)),
+            Duration::minutes(15)
+        );
+        assert_eq!(
+            session.phase(start + Duration::minutes(27)),
+            PomodoroPhase::Break
+        );
+        assert_eq!(
+            session.remaining(start + Duration::minutes(27)),
+            Duration::minutes(3)
+        );
+
+        // 三个25/5循环：90分钟中只有75分钟计入记录
+        let cycles = manager
+            .finish_pomodoro(event_id, Some(start + Duration::minutes(90)))
+            .unwrap();
+        assert_eq!(cycles, 3);
+        let record = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(record.duration_minutes, 75);
+        assert_eq!(record.project_id, Some(project_id));
+        assert!(manager.get_event(event_id).unwrap().is_completed());
+        assert!(manager.get_pomodoro(event_id).is_none());
+    }
+
+    #[test]
+    fn test_pomodoro_splits_shared_event() {
+        let mut manager = EventManager::new();
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let start = Utc::now() - Duration::hours(2);
+        let event_id = manager
+            .add_project_event("两个项目一起做".to_string(), None, project_a, Some(start))
+            .unwrap();
+        manager
+            .set_project_shares(event_id, vec![(project_a, 60), (project_b, 40)])
+            .unwrap();
+        manager.start_pomodoro_at(event_id, 25, 5, start).unwrap();
+
+        // 两个循环共50分钟工作时间，按60/40分摊
+        manager
+            .finish_pomodoro(event_id, Some(start + Duration::minutes(60)))
+            .unwrap();
+        assert_eq!(manager.get_all_time_records().len(), 2);
+        assert_eq!(
+            manager.get_project_time_records(project_a)[0].duration_minutes,
+            30
+        );
+        assert_eq!(
+            manager.get_project_time_records(project_b)[0].duration_minutes,
+            20
+        );
+    }
+
+    #[test]
+    fn test_insert_pomodoro_only_for_active_events() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(1);
+        let event_id = manager
+            .add_non_project_event("专注".to_string(), None, Some(start))
+            .unwrap();
+        manager.start_pomodoro_at(event_id, 25, 5, start).unwrap();
+        let session = manager.get_pomodoro(event_id).unwrap().clone();
+        manager.cancel_pomodoro(event_id).unwrap();
+
+        manager.insert_pomodoro(session.clone());
+        assert_eq!(manager.get_all_pomodoros().len(), 1);
+        manager.cancel_pomodoro(event_id).unwrap();
+
+        manager.set_event_end_time(event_id, None).unwrap();
+        manager.insert_pomodoro(session);
+        assert!(manager.get_all_pomodoros().is_empty());
+    }
+
+    #[test]
+    fn test_complete_all_finishes_running_pomodoro() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(2);
+        let event_id = manager
+            .add_non_project_event("专注".to_string(), None, Some(start))
+            .unwrap();
+        manager.start_pomodoro_at(event_id, 25, 5, start).unwrap();
+
+        // 60分钟中包含两次5分钟休息，只记录50分钟
+        let completed = manager.complete_all_active(Some(start + Duration::minutes(60)));
+        assert_eq!(completed, vec![event_id]);
+        let record = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(record.duration_minutes, 50);
+        assert!(manager.get_pomodoro(event_id).is_none());
+
+        // 重新打开后不会沿用旧的番茄钟
+        manager.reopen_event(event_id).unwrap();
+        assert!(manager.get_pomodoro(event_id).is_none());
+
+        // 番茄钟不足1分钟时放弃番茄钟，按普通事件结束
+        let other = manager
+            .add_non_project_event("刚开始".to_string(), None, Some(start))
+            .unwrap();
+        manager
+            .start_pomodoro_at(other, 25, 5, start + Duration::minutes(30))
+            .unwrap();
+        manager
+            .set_event_end_time(other, Some(start + Duration::minutes(30)))
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_event_time_record(other)
+                .unwrap()
+                .duration_minutes,
+            30
+        );
+        assert!(manager.get_pomodoro(other).is_none());
+    }
+
+    #[test]
+    fn test_pomodoro_partial_cycle_and_cancel() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(2);
+        let event_id = manager
+            .add_non_project_event("阅读".to_string(), None, Some(start))
+            .unwrap();
+
+        // 第三个循环只工作了20分钟
+        manager.start_pomodoro_at(event_id, 25, 5, start).unwrap();
+        let session = manager.get_pomodoro(event_id).unwrap();
+        assert_eq!(session.completed_cycles(start + Duration::minutes(80)), 2);
+        assert_eq!(session.work_seconds(start + Duration::minutes(80)), 70 * 60);
+
+        manager.cancel_pomodoro(event_id).unwrap();
+        assert!(manager.finish_pomodoro(event_id, None).is_err());
+        assert!(manager.get_event_time_record(event_id).is_none());
+        assert!(manager.start_pomodoro(event_id, 0, 5).is_err());
+    }
+
+    #[test]
+    fn test_complete_all_active() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(2);
+        let active: Vec<Uuid> = (0..3)
+            .map(|i| {
+                manager
+                    .add_non_project_event(format!("进行中{}", i), None, Some(start))
+                    .unwrap()
+            })
+            .collect();
+        let done = manager
+            .add_non_project_event("已完成".to_string(), None, Some(start))
+            .unwrap();
+        manager
+            .set_event_end_time(done, Some(start + Duration::minutes(30)))
+            .unwrap();
+        // 开始时间晚于结束时间的事件会被跳过
+        let future = manager
+            .add_non_project_event(
+                "未来".to_string(),
+                None,
+                Some(Utc::now() + Duration::hours(1)),
+            )
+            .unwrap();
+
+        let completed = manager.complete_all_active(None);
+        assert_eq!(completed.len(), 3);
+        assert!(active.iter().all(|id| completed.contains(id)));
+        assert_eq!(manager.get_all_time_records().len(), 4);
+        assert!(!manager.get_event(future).unwrap().is_completed());
+        assert_eq!(
+            manager
+                .get_event_time_record(done)
+                .unwrap()
+                .duration_minutes,
+            30
+        );
+    }
+
+    #[test]
+    fn test_fill_day_gaps() {
+        let mut manager = EventManager::new();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let at = |hour, minute| date.and_hms_opt(hour, minute, 0).unwrap().and_utc();
+        let project_id = Uuid::new_v4();
+
+        let morning = manager
+            .add_project_event("上午".to_string(), None, project_id, Some(at(9, 0)))
+            .unwrap();
+        manager
+            .set_event_end_time(morning, Some(at(12, 0)))
+            .unwrap();
+        let afternoon = manager
+            .add_project_event("下午".to_string(), None, project_id, Some(at(13, 0)))
+            .unwrap();
+        manager
+            .set_event_end_time(afternoon, Some(at(18, 0)))
+            .unwrap();
+
+        // 只有午饭时间一个空档
+        let created = manager.fill_day_gaps(date, 9, 18).unwrap();
+        assert_eq!(created.len(), 1);
+        let event = manager.get_event(created[0]).unwrap();
+        assert_eq!(event.title, UNTRACKED_TITLE);
+        assert!(matches!(event.event_type, EventType::NonProject));
+        let record = manager.get_event_time_record(created[0]).unwrap();
+        assert_eq!((record.start_time, record.end_time), (at(12, 0), at(13, 0)));
+        assert_eq!(record.project_id, None);
+
+        // 再次调用不会重复填补
+        assert!(manager.fill_day_gaps(date, 9, 18).unwrap().is_empty());
+        assert_eq!(manager.get_all_time_records().len(), 3);
+        assert!(manager.fill_day_gaps(date, 18, 9).is_err());
+    }
+
+    #[test]
+    fn test_fill_day_gaps_in_local_time() {
+        let mut manager = EventManager::new();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        // 当地时间 9:00-18:00 即 UTC 1:00-10:00
+        let local = |hour| {
+            tz.from_local_datetime(&date.and_hms_opt(hour, 0, 0).unwrap())
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let event_id = manager
+            .add_non_project_event("上午".to_string(), None, Some(local(9)))
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(local(12)))
+            .unwrap();
+
+        let created = manager.fill_day_gaps_tz(date, 9, 18, tz).unwrap();
+        assert_eq!(created.len(), 1);
+        let record = manager.get_event_time_record(created[0]).unwrap();
+        assert_eq!((record.start_time, record.end_time), (local(12), local(18)));
+    }
+
+    #[test]
+    fn test_fill_day_gaps_fully_recorded_day() {
+        let mut manager = EventManager::new();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let at = |hour| date.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        // 记录超出工作时间的部分不影响结果
+        let event_id = manager
+            .add_non_project_event("全天".to_string(), None, Some(at(8)))
+            .unwrap();
+        manager.set_event_end_time(event_id, Some(at(19))).unwrap();
+
+        assert!(manager.fill_day_gaps(date, 9, 18).unwrap().is_empty());
+        assert_eq!(manager.get_event_count(), 1);
+    }
+
+    #[test]
+    fn test_set_record_note() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(1);
+        let event_id = manager
+            .add_non_project_event("写文档".to_string(), None, Some(start))
+            .unwrap();
+        manager.set_event_end_time(event_id, None).unwrap();
+        let record_id = manager.get_event_time_record(event_id).unwrap().id;
+        assert!(manager.get_time_record(record_id).unwrap().note.is_none());
+
+        manager
+            .set_record_note(record_id, Some("  完成了安装说明  ".to_string()))
+            .unwrap();
+        assert_eq!(
+            manager.get_time_record(record_id).unwrap().note.as_deref(),
+            Some("完成了安装说明")
+        );
+
+        manager
+            .set_record_note(record_id, Some("   ".to_string()))
+            .unwrap();
+        assert!(manager.get_time_record(record_id).unwrap().note.is_none());
+        assert!(manager.set_record_note(Uuid::new_v4(), None).is_err());
+    }
+
+    #[test]
+    fn test_shared_event_splits_time_records() {
+        let mut manager = EventManager::new();
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = manager
+            .add_project_event("联合评审会".to_string(), None, project_a, Some(start))
+            .unwrap();
+
+        assert!(manager
+            .set_project_shares(event_id, vec![(project_a, 60), (project_b, 30)])
+            .is_err());
+        assert!(manager
+            .set_project_shares(event_id, vec![(project_a, 100), (project_b, 0)])
+            .is_err());
+        assert!(manager
+            .set_project_shares(event_id, vec![(project_a, 50), (project_a, 50)])
+            .is_err());
+        manager
+            .set_project_shares(event_id, vec![(project_a, 60), (project_b, 40)])
+            .unwrap();
+
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::minutes(100)))
+            .unwrap();
+        assert_eq!(
+            manager.get_project_time_records(project_a)[0].duration_minutes,
+            60
+        );
+        assert_eq!(
+            manager.get_project_time_records(project_b)[0].duration_minutes,
+            40
+        );
+        assert_eq!(manager.get_all_time_records().len(), 2);
+
+        // 修改开始时间后按比例重新分摊，截断误差归最后一个项目
+        manager
+            .set_event_start_time(event_id, start + Duration::minutes(1))
+            .unwrap();
+        assert_eq!(
+            manager.get_project_time_records(project_a)[0].duration_minutes,
+            59
+        );
+        assert_eq!(
+            manager.get_project_time_records(project_b)[0].duration_minutes,
+            40
+        );
+        assert!(manager.set_project_shares(event_id, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_set_start_time_keeps_records_valid() {
+        let mut manager = EventManager::new();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = manager
+            .add_non_project_event("番茄钟".to_string(), None, Some(start))
+            .unwrap();
+        // 番茄钟在事件开始10分钟后启动，25分钟工作加5分钟休息，共两轮
+        let pomodoro_start = start + Duration::minutes(10);
+        manager
+            .start_pomodoro_at(event_id, 25, 5, pomodoro_start)
+            .unwrap();
+        let end = pomodoro_start + Duration::minutes(60);
+        manager.set_event_end_time(event_id, Some(end)).unwrap();
+        let record = manager.get_event_time_record(event_id).unwrap().clone();
+        assert_eq!(record.end_time, pomodoro_start + Duration::minutes(50));
+        assert_eq!(record.duration_minutes, 50);
+
+        // 新的开始时间不能晚于记录的结束时间，否则记录的开始时间会晚于结束时间
+        assert_eq!(
+            manager.set_event_start_time(event_id, record.end_time + Duration::minutes(1)),
+            Err(EventError::StartAfterRecordEnd(record.end_time))
+        );
+        assert_eq!(manager.get_event(event_id).unwrap().start_time, start);
+
+        // 提前事件开始时间不影响从番茄钟开始计时的记录
+        manager
+            .set_event_start_time(event_id, start - Duration::minutes(30))
+            .unwrap();
+        let unchanged = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(unchanged.start_time, record.start_time);
+        assert_eq!(unchanged.duration_minutes, 50);
+
+        // 推迟到记录中间时裁掉前面的部分
+        manager
+            .set_event_start_time(event_id, pomodoro_start + Duration::minutes(20))
+            .unwrap();
+        let clipped = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(clipped.start_time, pomodoro_start + Duration::minutes(20));
+        assert_eq!(clipped.end_time, record.end_time);
+        assert_eq!(clipped.duration_minutes, 30);
+
+        // 回收站中的事件不能修改
+        manager.delete_event(event_id).unwrap();
+        assert_eq!(
+            manager.set_event_start_time(event_id, start),
+            Err(EventError::InTrash)
+        );
+    }
+
+    #[test]
+    fn test_set_start_time_keeps_excluded_time() {
+        let mut manager = EventManager::new();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = manager
+            .add_non_project_event("中间休息过".to_string(), None, Some(start))
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::hours(2)))
+            .unwrap();
+        let record_id = manager.get_event_time_record(event_id).unwrap().id;
+        // 两小时中有30分钟不计入
+        manager
+            .time_records
+            .get_mut(&record_id)
+            .unwrap()
+            .duration_minutes = 90;
+
+        // 30分钟不计入的时间在修改开始时间后保持不变
+        manager
+            .set_event_start_time(event_id, start - Duration::minutes(15))
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_event_time_record(event_id)
+                .unwrap()
+                .duration_minutes,
+            105
+        );
+    }
+
+    #[test]
+    fn test_shared_event_across_midnight_is_not_double_counted() {
+        let mut manager = EventManager::new();
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = manager
+            .add_project_event("跨夜发布".to_string(), None, project_a, Some(start))
+            .unwrap();
+        manager
+            .set_project_shares(event_id, vec![(project_a, 50), (project_b, 50)])
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::hours(2)))
+            .unwrap();
+
+        // 两个小时分摊到两个项目，每天每个项目各30分钟
+        let records = manager.get_all_time_records();
+        for day in [start, start + Duration::hours(2)] {
+            assert_eq!(
+                TimeCalculator::calculate_daily_stats(&records, day),
+                (60, 0)
+            );
+        }
+        let record_a = manager.get_project_time_records(project_a)[0];
+        let first_day_end = start + Duration::minutes(59) + Duration::seconds(59);
+        assert_eq!(
+            TimeCalculator::clipped_minutes(record_a, start - Duration::hours(23), first_day_end),
+            Some(30)
+        );
+        // 完全落在范围内时仍按记录时长计算
+        assert_eq!(
+            TimeCalculator::clipped_minutes(record_a, start, start + Duration::hours(2)),
+            Some(60)
+        );
+        // 计费取整同样按分摊后的时长计算
+        assert_eq!(
+            TimeCalculator::rounded_record_minutes(
+                record_a,
+                start,
+                start + Duration::hours(2),
+                RoundingMode::Up15
+            ),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let mut manager = EventManager::new();
+        let missing = Uuid::new_v4();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        assert_eq!(
+            manager.set_event_end_time(missing, None),
+            Err(EventError::NotFound)
+        );
+        assert_eq!(
+            manager.set_record_note(missing, None),
+            Err(EventError::RecordNotFound)
+        );
+        assert_eq!(manager.restore_event(missing), Err(EventError::NotInTrash));
+        assert_eq!(
+            manager.remove_template(missing),
+            Err(EventError::TemplateNotFound)
+        );
+        assert!(matches!(
+            manager.add_non_project_event(String::new(), None, Some(start)),
+            Err(EventError::Invalid(_))
+        ));
+
+        let event_id = manager
+            .add_non_project_event("会议".to_string(), None, Some(start))
+            .unwrap();
+        assert_eq!(manager.reopen_event(event_id), Err(EventError::NotEnded));
+        assert_eq!(
+            manager.set_event_end_time(event_id, Some(start)),
+            Err(EventError::InvalidEndTime)
+        );
+        assert_eq!(
+            manager.cancel_pomodoro(event_id),
+            Err(EventError::NoPomodoro)
+        );
+        assert_eq!(
+            manager.add_dependency(event_id, event_id),
+            Err(EventError::SelfDependency)
+        );
+        assert_eq!(
+            manager.remove_tag(event_id, "不存在"),
+            Err(EventError::TagNotFound)
+        );
+        assert_eq!(
+            manager.toggle_subtask(event_id, 0),
+            Err(EventError::SubtaskNotFound)
+        );
+
+        let end = start + Duration::hours(1);
+        manager.set_event_end_time(event_id, Some(end)).unwrap();
+        assert_eq!(
+            manager.set_event_end_time(event_id, None),
+            Err(EventError::AlreadyEnded)
+        );
+        assert_eq!(
+            manager.set_event_start_time(event_id, end),
+            Err(EventError::StartAfterEnd(end))
+        );
+        assert_eq!(
+            manager
+                .split_event(event_id, end + Duration::hours(1), "后半段".to_string())
+                .unwrap_err(),
+            EventError::SplitOutOfRange
+        );
+    }
+
+    #[test]
+    fn test_add_completed_event() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap()
+            .and_utc();
+        let end = start + Duration::minutes(95);
+
+        let event_id = manager
+            .add_completed_event(
+                "补录的评审".to_string(),
+                None,
+                EventType::ProjectRelated(project_id),
+                start,
+                end,
+            )
+            .unwrap();
+        let event = manager.get_event(event_id).unwrap();
+        assert_eq!(event.start_time, start);
+        assert_eq!(event.end_time, Some(end));
+
+        let record = manager.get_event_time_record(event_id).unwrap();
+        assert_eq!(record.project_id, Some(project_id));
+        assert_eq!(record.start_time, start);
+        assert_eq!(record.end_time, end);
+        assert_eq!(record.duration_minutes, 95);
+
+        assert_eq!(
+            manager.add_completed_event(
+                "无效".to_string(),
+                None,
+                EventType::NonProject,
+                end,
+                start
+            ),
+            Err(EventError::InvalidEndTime)
+        );
+        assert!(matches!(
+            manager.add_completed_event(String::new(), None, EventType::NonProject, start, end),
+            Err(EventError::Invalid(_))
+        ));
+        assert_eq!(manager.get_event_count(), 1);
+    }
+
+    #[test]
+    fn test_set_record_duration() {
+        let mut manager = EventManager::new();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = manager
+            .add_non_project_event("评审".to_string(), None, Some(start))
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::minutes(20)))
+            .unwrap();
+        let record_id = manager.get_event_time_record(event_id).unwrap().id;
+
+        manager.set_record_duration(record_id, 45).unwrap();
+        let record = manager.get_time_record(record_id).unwrap();
+        assert_eq!(record.duration_minutes, 45);
+        assert_eq!(record.end_time, start + Duration::minutes(45));
+        assert_eq!(
+            manager.get_event(event_id).unwrap().end_time,
+            Some(start + Duration::minutes(45))
+        );
+
+        assert!(manager.set_record_duration(record_id, -1).is_err());
+        assert_eq!(
+            manager.get_time_record(record_id).unwrap().duration_minutes,
+            45
+        );
+        assert!(manager.set_record_duration(Uuid::new_v4(), 10).is_err());
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let mut manager = EventManager::new();
+        let event_id = manager
+            .add_non_project_event("紧急修复".to_string(), None, None)
+            .unwrap();
+        assert_eq!(
+            manager.get_event(event_id).unwrap().priority,
+            Priority::Medium
+        );
+
+        manager.set_priority(event_id, Priority::High).unwrap();
+        assert_eq!(
+            manager.get_event(event_id).unwrap().priority,
+            Priority::High
+        );
+        assert!(manager.set_priority(Uuid::new_v4(), Priority::Low).is_err());
+
+        // 旧数据没有优先级字段时默认为中
+        let mut value = serde_json::to_value(manager.get_event(event_id).unwrap()).unwrap();
+        value.as_object_mut().unwrap().remove("priority");
+        let legacy: Event = serde_json::from_value(value).unwrap();
+        assert_eq!(legacy.priority, Priority::Medium);
+    }
+
+    #[test]
+    fn test_priority_sort_is_stable() {
+        let mut manager = EventManager::new();
+        let base = Utc::now();
+        let priorities = [
+            Priority::Low,
+            Priority::High,
+            Priority::Medium,
+            Priority::High,
+            Priority::Low,
+        ];
+        let mut ids = Vec::new();
+        for (i, priority) in priorities.into_iter().enumerate() {
+            let mut event = Event::new(format!("事件{}", i), None, EventType::NonProject, base);
+            event.created_at = base + Duration::minutes(i as i64);
+            event.priority = priority;
+            ids.push(event.id);
+            manager.insert_event(event);
+        }
+
+        let sorted: Vec<Uuid> = manager
+            .get_events_sorted_by_priority()
+            .iter()
+            .map(|event| event.id)
+            .collect();
+        assert_eq!(sorted, vec![ids[1], ids[3], ids[2], ids[0], ids[4]]);
+    }
+
+    #[test]
+    fn test_get_project_events() {
+        let mut manager = EventManager::new();
+        let project_id1 = Uuid::new_v4();
+        let project_id2 = Uuid::new_v4();
+
+        manager
+            .add_project_event("项目1事件".to_string(), None, project_id1, None)
+            .unwrap();
+        manager
+            .add_project_event("项目2事件".to_string(), None, project_id2, None)
+            .unwrap();
+        manager
+            .add_non_project_event("非项目事件".to_string(), None, None)
+            .unwrap();
+
+        let project1_events = manager.get_project_events(project_id1);
+        assert_eq!(project1_events.len(), 1);
+        assert_eq!(project1_events[0].title, "项目1事件");
+
+        let project2_events = manager.get_project_events(project_id2);
+        assert_eq!(project2_events.len(), 1);
+        assert_eq!(project2_events[0].title, "项目2事件");
+
+        let non_project_events = manager.get_non_project_events();
+        assert_eq!(non_project_events.len(), 1);
+        assert_eq!(non_project_events[0].title, "非项目事件");
+    }
+
+    #[test]
+    fn test_get_project_activity() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let other_id = Uuid::new_v4();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let at = |hour: u32| day.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+
+        let add_completed = |manager: &mut EventManager, project: Uuid, start: u32, end: u32| {
+            let id = manager
+                .add_project_event("任务".to_string(), None, project, Some(at(start)))
+                .unwrap();
+            manager.set_event_end_time(id, Some(at(end))).unwrap();
+            id
+        };
+        let before = add_completed(&mut manager, project_id, 6, 8);
+        let inside = add_completed(&mut manager, project_id, 10, 11);
+        let spanning = add_completed(&mut manager, project_id, 8, 10);
+        let other = add_completed(&mut manager, other_id, 10, 12);
+        let after = add_completed(&mut manager, project_id, 13, 14);
+
+        let activity = manager.get_project_activity(project_id, at(9), at(12));
+        assert_eq!(activity.project_id, project_id);
+        let event_ids: Vec<Uuid> = activity.events.iter().map(|event| event.id).collect();
+        assert_eq!(event_ids, vec![spanning, inside]);
+        assert!(!event_ids.contains(&before));
+        assert!(!event_ids.contains(&other));
+        assert!(!event_ids.contains(&after));
+        assert_eq!(activity.time_records.len(), 2);
+        assert!(activity
+            .time_records
+            .iter()
+            .all(|record| record.project_id == Some(project_id)));
+
+        // 跨越开始边界的记录只统计范围内的部分
+        let all_records = manager.get_all_time_records();
+        let expected = TimeCalculator::calculate_project_total_time(
+            &all_records,
+            project_id,
+            Some(at(9)),
+            Some(at(12)),
+        );
+        assert_eq!(activity.total_minutes, expected);
+        assert_eq!(activity.total_minutes, 120);
+
+        let empty = manager.get_project_activity(project_id, at(20), at(22));
+        assert!(empty.events.is_empty());
+        assert!(empty.time_records.is_empty());
+        assert_eq!(empty.total_minutes, 0);
+    }
+
+    #[test]
+    fn test_add_event_validates_title() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+
+        assert!(manager
+            .add_project_event(String::new(), None, project_id, None)
+            .is_err());
+        assert!(manager
+            .add_non_project_event("   ".to_string(), None, None)
+            .is_err());
+        let oversized = "a".repeat(MAX_NAME_LENGTH + 1);
+        assert!(manager
+            .add_project_event(oversized.clone(), None, project_id, None)
+            .is_err());
+        assert!(manager
+            .add_non_project_event(oversized, None, None)
+            .is_err());
+        assert_eq!(manager.get_event_count(), 0);
+
+        let event_id = manager
+            .add_project_event(" 写文档\n".to_string(), None, project_id, None)
+            .unwrap();
+        assert_eq!(manager.get_event(event_id).unwrap().title, "写文档");
+        let event_id = manager
+            .add_non_project_event("\t午饭 ".to_string(), None, None)
+            .unwrap();
+        assert_eq!(manager.get_event(event_id).unwrap().title, "午饭");
+    }
+
+    #[test]
+    fn test_split_time_record() {
+        let mut manager = EventManager::new();
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let event_id = manager
+            .add_project_event("长时间工作".to_string(), None, project_a, Some(start))
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::hours(6)))
+            .unwrap();
+        let record_id = manager.get_event_time_record(event_id).unwrap().id;
+        let other_event = manager
+            .add_project_event("其实是另一个项目".to_string(), None, project_b, Some(start))
+            .unwrap();
+
+        // 拆分点不在记录内部
+        for at in [
+            start,
+            start - Duration::minutes(1),
+            start + Duration::hours(6),
+            start + Duration::hours(7),
+        ] {
+            assert!(manager
+                .split_time_record(record_id, at, other_event)
+                .is_err());
+        }
+        assert!(manager
+            .split_time_record(record_id, start + Duration::hours(1), Uuid::new_v4())
+            .is_err());
+        assert!(manager
+            .split_time_record(Uuid::new_v4(), start + Duration::hours(1), other_event)
+            .is_err());
+        assert_eq!(manager.get_all_time_records().len(), 1);
+
+        let at = start + Duration::minutes(150) + Duration::seconds(30);
+        let second_id = manager
+            .split_time_record(record_id, at, other_event)
+            .unwrap();
+
+        let first = manager.get_time_record(record_id).unwrap();
+        let second = manager.get_time_record(second_id).unwrap();
+        assert_eq!(first.duration_minutes + second.duration_minutes, 360);
+        assert_eq!(first.end_time, at);
+        assert_eq!(second.start_time, at);
+        assert_eq!(second.end_time, start + Duration::hours(6));
+        assert_eq!(first.project_id, Some(project_a));
+        assert_eq!(second.event_id, other_event);
+        assert_eq!(second.project_id, Some(project_b));
+
+        // 事件的起止时间与各自的记录一致，每个事件只有一条记录
+        let original = manager.get_event(event_id).unwrap();
+        assert_eq!(original.end_time, Some(at));
+        let moved = manager.get_event(other_event).unwrap();
+        assert_eq!(moved.start_time, at);
+        assert_eq!(moved.end_time, Some(start + Duration::hours(6)));
+        assert_eq!(
+            manager.get_event_time_record(other_event).unwrap().id,
+            second_id
+        );
+
+        // 已结束的事件不能再接收拆分出的记录，否则会有多条记录
+        assert_eq!(
+            manager.split_time_record(second_id, at + Duration::hours(1), event_id),
+            Err(EventError::AlreadyEnded)
+        );
+        let third_event = manager
+            .add_project_event("第三个项目".to_string(), None, project_a, None)
+            .unwrap();
+        assert!(manager
+            .split_time_record(second_id, at + Duration::hours(1), other_event)
+            .is_err());
+        assert!(manager
+            .split_time_record(second_id, at + Duration::hours(1), third_event)
+            .is_ok());
+        assert_eq!(manager.get_all_time_records().len(), 3);
+    }
+
+    #[test]
+    fn test_delete_project_events_clears_pomodoros() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let deleted = manager
+            .add_project_event("项目事件".to_string(), None, project_id, None)
+            .unwrap();
+        let kept = manager
+            .add_non_project_event("项目外事件".to_string(), None, None)
+            .unwrap();
+        manager.start_pomodoro(deleted, 25, 5).unwrap();
+        manager.start_pomodoro(kept, 25, 5).unwrap();
+
+        assert_eq!(manager.delete_project_events(project_id), 1);
+        assert!(manager.get_pomodoro(deleted).is_none());
+        assert!(manager.get_pomodoro(kept).is_some());
+    }
+
+    #[test]
+    fn test_update_event_validates_title() {
+        let mut manager = EventManager::new();
+        let event_id = manager
+            .add_non_project_event("原标题".to_string(), None, None)
+            .unwrap();
+
+        for title in ["   ".to_string(), "长".repeat(MAX_NAME_LENGTH + 1)] {
+            assert!(matches!(
+                manager.update_event(event_id, Some(title), None),
+                Err(EventError::Invalid(_))
+            ));
+        }
+        assert_eq!(manager.get_event(event_id).unwrap().title, "原标题");
+
+        manager
+            .update_event(event_id, Some(" 新标题 ".to_string()), None)
+            .unwrap();
+        assert_eq!(manager.get_event(event_id).unwrap().title, "新标题");
+    }
+
+    /// 创建一个已完成的多项目分摊事件，时长100分钟
+    fn add_shared_event(
+        manager: &mut EventManager,
+        owner: Uuid,
+        shares: Vec<(Uuid, u8)>,
+        start: DateTime<Utc>,
+    ) -> Uuid {
+        let event_id = manager
+            .add_project_event("分摊".to_string(), None, owner, Some(start))
+            .unwrap();
+        manager.set_project_shares(event_id, shares).unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::minutes(100)))
+            .unwrap();
+        event_id
+    }
+
+    #[test]
+    fn test_delete_project_events_updates_shares() {
+        let mut manager = EventManager::new();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now() - Duration::hours(3);
+        let other = add_shared_event(&mut manager, b, vec![(a, 50), (b, 30), (c, 20)], start);
+        let owned = add_shared_event(&mut manager, a, vec![(a, 60), (b, 40)], start);
+
+        assert_eq!(manager.delete_project_events(a), 1);
+        assert!(manager.get_event(owned).is_none());
+        // 被删除事件分摊给其他项目的记录也一并删除，不留下孤立的记录
+        assert!(manager
+            .get_all_time_records()
+            .iter()
+            .all(|record| record.event_id == other));
+        assert!(manager.get_project_time_records(a).is_empty());
+        assert_eq!(manager.get_project_time_records(b)[0].duration_minutes, 30);
+        assert_eq!(
+            manager.get_event(other).unwrap().project_shares,
+            vec![(b, 60), (c, 40)]
+        );
+    }
+
+    #[test]
+    fn test_detach_project_updates_shares() {
+        let mut manager = EventManager::new();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now() - Duration::hours(3);
+        let event_id = add_shared_event(&mut manager, b, vec![(a, 50), (b, 30), (c, 20)], start);
+
+        assert_eq!(manager.detach_project(a), 0);
+        assert_eq!(
+            manager.get_event(event_id).unwrap().project_shares,
+            vec![(b, 60), (c, 40)]
+        );
+        assert!(manager.get_project_time_records(a).is_empty());
+        assert_eq!(
+            manager.get_non_project_time_records()[0].duration_minutes,
+            50
+        );
+    }
+
+    #[test]
+    fn test_reassign_project_merges_shares() {
+        let mut manager = EventManager::new();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now() - Duration::hours(3);
+        let event_id = add_shared_event(&mut manager, a, vec![(a, 50), (b, 30), (c, 20)], start);
+
+        assert_eq!(manager.reassign_project(a, b), 1);
+        let event = manager.get_event(event_id).unwrap();
+        assert!(matches!(event.event_type, EventType::ProjectRelated(id) if id == b));
+        assert_eq!(event.project_shares, vec![(b, 80), (c, 20)]);
+        // 两条归到同一项目的记录合并为一条
+        assert_eq!(manager.get_all_time_records().len(), 2);
+        let records = manager.get_project_time_records(b);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].duration_minutes, 80);
+        assert!(manager.get_project_time_records(a).is_empty());
+    }
+
+    #[test]
+    fn test_split_event_keeps_details_and_shares() {
+        let mut manager = EventManager::new();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Utc::now() - Duration::hours(3);
+        let event_id = manager
+            .add_project_event(
+                "分摊".to_string(),
+                Some("两个项目的联调".to_string()),
+                a,
+                Some(start),
+            )
+            .unwrap();
+        manager.add_tag(event_id, "联调").unwrap();
+        manager.set_priority(event_id, Priority::High).unwrap();
+        manager
+            .set_project_shares(event_id, vec![(a, 60), (b, 40)])
+            .unwrap();
+        manager
+            .set_event_end_time(event_id, Some(start + Duration::minutes(100)))
+            .unwrap();
+
+        let second_id = manager
+            .split_event(
+                event_id,
+                start + Duration::minutes(50),
+                "后半段".to_string(),
+            )
+            .unwrap();
+        let second = manager.get_event(second_id).unwrap();
+        assert_eq!(second.description.as_deref(), Some("两个项目的联调"));
+        assert_eq!(second.tags, vec!["联调".to_string()]);
+        assert_eq!(second.priority, Priority::High);
+        assert_eq!(second.project_shares, vec![(a, 60), (b, 40)]);
+
+        // 后半段的50分钟按60/40分摊，而不是全部记到主项目
+        let second_minutes = |project_id| {
+            manager
+                .get_project_time_records(project_id)
+                .iter()
+                .filter(|record| record.event_id == second_id)
+                .map(|record| record.duration_minutes)
+                .sum::<i64>()
+        };
+        assert_eq!(second_minutes(a), 30);
+        assert_eq!(second_minutes(b), 20);
+    }
+
+    #[test]
+    fn test_delete_and_restore_event() {
+        let mut manager = EventManager::new();
+        let project_id = Uuid::new_v4();
+        let event_id = manager
+            .add_project_event("误删的事件".to_string(), None, project_id, None)
+            .unwrap();
+        manager.set_event_end_time(event_id, None).unwrap();
+        let kept = manager
+            .add_non_project_event("保留".to_string(), None, None)
+            .unwrap();
+
+        manager.delete_event(event_id).unwrap();
+        assert!(manager.get_event(event_id).is_none());
+        assert!(!manager.event_exists(event_id));
+        assert_eq!(manager.get_event_count(), 1);
+        assert_eq!(manager.get_all_events()[0].id, kept);
+        assert!(manager.get_project_events(project_id).is_empty());
+        assert!(manager.get_all_time_records().is_empty());
+        assert!(manager.get_project_time_records(project_id).is_empty());
+
+        let trash = manager.get_deleted_events();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, event_id);
+        assert!(trash[0].deleted_at.is_some());
+        assert_eq!(manager.get_deleted_time_records().len(), 1);
+        assert!(manager.delete_event(event_id).is_err());
+
+        manager.restore_event(event_id).unwrap();
+        assert!(manager.get_event(event_id).unwrap().deleted_at.is_none());
+        assert_eq!(manager.get_project_time_records(project_id).len(), 1);
+        assert!(manager.get_deleted_events().is_empty());
+        assert!(manager.restore_event(event_id).is_err());
+    }
+
+    #[test]
+    fn test_purge_deleted_cutoff() {
+        let mut manager = EventManager::new();
+        let old = manager
+            .add_non_project_event("很早删除".to_string(), None, None)
+            .unwrap();
+        manager.set_event_end_time(old, None).unwrap();
+        let recent = manager
+            .add_non_project_event("最近删除".to_string(), None, None)
+            .unwrap();
+        let active = manager
+            .add_non_project_event("未删除".to_string(), None, None)
+            .unwrap();
+
+        manager.delete_event(old).unwrap();
+        manager.delete_event(recent).unwrap();
+        let now = Utc::now();
+        manager.events.get_mut(&old).unwrap().deleted_at = Some(now - Duration::days(40));
+        manager.events.get_mut(&recent).unwrap().deleted_at = Some(now - Duration::days(2));
+
+        assert_eq!(manager.purge_deleted(now - Duration::days(30)), 1);
+        assert!(!manager.events.contains_key(&old));
+        assert!(manager.time_records.is_empty());
+        let trash: Vec<Uuid> = manager.get_deleted_events().iter().map(|e| e.id).collect();
+        assert_eq!(trash, vec![recent]);
+        assert!(manager.restore_event(old).is_err());
+
+        assert_eq!(manager.purge_deleted(now), 1);
+        assert!(manager.get_deleted_events().is_empty());
+        assert!(manager.event_exists(active));
+        assert_eq!(manager.purge_deleted(now), 0);
+    }
+
+    #[test]
+    fn test_event_dependencies() {
+        let mut manager = EventManager::new();
+        let start = Utc::now() - Duration::hours(3);
+        let design = manager
+            .add_non_project_event("设计".to_string(), None, Some(start))
+            .unwrap();
+        let build = manager
+            .add_non_project_event("开发".to_string(), None, Some(start))
+            .unwrap();
+        let release = manager
+            .add_non_project_event("发布".to_string(), None, Some(start))
+            .unwrap();
+
+        // 设计 <- 开发 <- 发布
+        manager.add_dependency(build, design).unwrap();
+        manager.add_dependency(release, build).unwrap();
+        manager.add_dependency(release, build).unwrap();
+        assert_eq!(manager.get_event(release).unwrap().depends_on, vec![build]);
+
+        // 循环依赖和自依赖被拒绝
+        assert!(manager.add_dependency(design, release).is_err());
+        assert!(manager.add_dependency(design, build).is_err());
+        assert!(manager.add_dependency(design, design).is_err());
+        assert!(manager.add_dependency(design, Uuid::new_v4()).is_err());
+        assert!(manager.get_event(design).unwrap().depends_on.is_empty());
+
+        let now = Utc::now();
+        let blocked: Vec<Uuid> = manager
+            .get_blocked_events(now)
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(blocked.len(), 2);
+        assert!(blocked.contains(&build) && blocked.contains(&release));
+
+        // 完成前置事件后解除阻塞
+        manager
+            .set_event_end_time(design, Some(start + Duration::hours(1)))
+            .unwrap();
+        let blocked: Vec<Uuid> = manager
+            .get_blocked_events(now)
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(blocked, vec![release]);
+
+        // 在前置事件完成之前的时间点仍然被阻塞
+        assert_eq!(manager.get_blocked_events(start).len(), 2);
+
+        manager
+            .set_event_end_time(build, Some(start + Duration::hours(2)))
+            .unwrap();
+        assert!(manager.get_blocked_events(now).is_empty());
+
+        manager.remove_dependency(release, build).unwrap();
+        assert!(manager.remove_dependency(release, build).is_err());
     }
 }
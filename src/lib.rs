@@ -0,0 +1,22 @@
+//! 项目管理系统的核心库：项目和事件管理、时间统计、报表生成和数据存储
+
+pub mod config;
+pub mod crypto;
+pub mod csv;
+pub mod error;
+pub mod event_manager;
+pub mod models;
+pub mod project_manager;
+pub mod report_generator;
+pub mod sqlite_storage;
+pub mod storage;
+pub mod time_calculator;
+pub mod ui;
+
+pub use error::{EventError, ProjectError, ReportImportError};
+pub use event_manager::EventManager;
+pub use models::{AppSettings, Event, EventType, Project, TimeRecord, WeekStart, WeeklyReport};
+pub use project_manager::ProjectManager;
+pub use report_generator::ReportGenerator;
+pub use storage::{AppData, DataStore, Storage};
+pub use time_calculator::TimeCalculator;
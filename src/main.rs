@@ -1,32 +1,126 @@
-mod event_manager;
-mod models;
-mod project_manager;
-mod report_generator;
-mod storage;
-mod time_calculator;
-mod ui;
-
+use clap::{Parser, ValueEnum};
 use eframe::egui;
-use storage::Storage;
-use ui::App;
+use project_manager::config::Config;
+use project_manager::sqlite_storage::SqliteStorage;
+use project_manager::ui::{App, AppMode};
+use project_manager::{DataStore, ReportGenerator, Storage};
+use std::time::{Duration, Instant};
+
+/// 数据存储后端
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    /// JSON文件（数据目录中的 app_data.json）
+    Json,
+    /// SQLite数据库（数据目录中的 app_data.db）
+    Sqlite,
+}
+
+#[derive(Parser)]
+#[command(name = "project_manager", about = "项目管理系统")]
+struct Cli {
+    /// 输出累计统计后退出，不启动图形界面
+    #[arg(long)]
+    stats: bool,
+
+    /// 数据目录，其中的 config.toml 可以覆盖部分设置
+    #[arg(long, env = "PM_DATA_DIR", default_value = "./data")]
+    data_dir: String,
+
+    /// 数据存储后端
+    #[arg(long, value_enum, default_value = "json")]
+    backend: Backend,
+
+    /// 加密JSON数据文件和备份所用的口令（仅JSON后端，与SQLite后端同时使用时拒绝启动）
+    #[arg(long, env = "PROJECT_MANAGER_PASSPHRASE", hide_env_values = true)]
+    passphrase: Option<String>,
+
+    /// JSON数据文件不缩进，减小文件体积（备份不受影响）
+    #[arg(long)]
+    compact_json: bool,
+}
 
 fn main() -> eframe::Result<()> {
-    println!("启动项目管理系统GUI界面...");
+    let cli = Cli::parse();
+
+    // SQLite数据库不加密，带口令时拒绝启动，以免误以为数据已加密
+    if cli.passphrase.is_some() && matches!(cli.backend, Backend::Sqlite) {
+        eprintln!("SQLite后端不支持加密，请去掉 --passphrase 或改用JSON后端");
+        std::process::exit(1);
+    }
 
     // 初始化存储
-    let storage = Storage::new("./data".to_string());
+    let json_storage = match cli.passphrase {
+        Some(passphrase) => Storage::with_passphrase(cli.data_dir.clone(), passphrase),
+        None => Storage::new(cli.data_dir.clone()),
+    }
+    .with_compact_json(cli.compact_json);
+    let config = Config::load(&cli.data_dir).unwrap_or_else(|e| {
+        eprintln!("无法读取配置文件，使用默认配置: {}", e);
+        Config::default()
+    });
+    let storage: Box<dyn DataStore> = match cli.backend {
+        Backend::Json => Box::new(json_storage),
+        Backend::Sqlite => match SqliteStorage::open(&format!("{}/app_data.db", cli.data_dir)) {
+            Ok(storage) => Box::new(storage),
+            Err(e) => {
+                eprintln!("无法打开SQLite数据库: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    if cli.stats {
+        match storage.load_data() {
+            Ok(data) => println!(
+                "{}",
+                ReportGenerator::generate_all_time_stats(
+                    &data.visible_time_records().collect::<Vec<_>>(),
+                    &data.visible_events().collect::<Vec<_>>(),
+                    &data.projects.iter().collect::<Vec<_>>(),
+                )
+            ),
+            Err(e) => {
+                eprintln!("无法加载数据: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // 持有数据目录锁直到程序退出，避免两个实例互相覆盖数据
+    let _lock = match Storage::new(cli.data_dir.clone()).acquire_lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("无法启动: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("启动项目管理系统GUI界面...");
 
     // 尝试加载保存的数据
-    let app = match storage.load_data() {
-        Ok(data) => {
-            println!("已加载保存的数据");
+    let mut app = match storage.load_data_with_recovery() {
+        Ok((data, recovery)) => {
+            if let Some(recovery) = recovery {
+                eprintln!("数据文件已损坏，已移到 {}", recovery.corrupt_path);
+                match recovery.restored_from {
+                    Some(backup) => eprintln!("已从备份 {} 恢复数据", backup),
+                    None => eprintln!("没有可用的备份，使用新的应用状态"),
+                }
+            } else {
+                println!("已加载保存的数据");
+            }
             App::from_data(data)
         }
+        // 口令错误、版本过高等情况下继续运行会在退出时用空数据覆盖原文件，因此直接退出
         Err(e) => {
-            println!("无法加载数据，使用新的应用状态: {}", e);
-            App::new()
+            eprintln!("无法加载数据，程序退出以免覆盖原有数据: {}", e);
+            std::process::exit(1);
         }
     };
+    config.apply(&mut app.settings);
+    app.data_dir = cli.data_dir.clone();
+    app.mode = AppMode::Dashboard;
 
     // 运行egui应用
     let native_options = eframe::NativeOptions {
@@ -40,26 +134,26 @@ fn main() -> eframe::Result<()> {
         Box::new(|cc| {
             // 设置中文字体
             let mut fonts = egui::FontDefinitions::default();
-            
+
             // 加载Noto Sans SC字体
             fonts.font_data.insert(
                 "noto_sans_sc".to_owned(),
                 egui::FontData::from_static(include_bytes!("../assets/NotoSansSC-Regular.ttf")),
             );
-            
+
             // 将中文字体设置为默认字体
             fonts
                 .families
                 .entry(egui::FontFamily::Proportional)
                 .or_default()
                 .insert(0, "noto_sans_sc".to_owned());
-            
+
             fonts
                 .families
                 .entry(egui::FontFamily::Monospace)
                 .or_default()
                 .push("noto_sans_sc".to_owned());
-            
+
             cc.egui_ctx.set_fonts(fonts);
 
             Box::new(EguiApp::new(app, storage))
@@ -69,26 +163,57 @@ fn main() -> eframe::Result<()> {
 
 struct EguiApp {
     app: App,
-    storage: Storage,
+    storage: Box<dyn DataStore>,
+    last_save: Instant,
 }
 
 impl EguiApp {
-    fn new(app: App, storage: Storage) -> Self {
-        Self { app, storage }
+    fn new(app: App, storage: Box<dyn DataStore>) -> Self {
+        Self {
+            app,
+            storage,
+            last_save: Instant::now(),
+        }
     }
 }
 
 impl eframe::App for EguiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // 还有进行中的事件时先询问，避免这些事件没有时间记录
+        if ctx.input(|i| i.viewport().close_requested()) && !self.app.request_exit() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+
         self.app.update(ctx);
+
+        // 定时自动保存，避免异常退出时丢失整个会话的数据
+        let interval_secs = self.app.settings.autosave_interval_secs;
+        if interval_secs > 0 {
+            let interval = Duration::from_secs(interval_secs);
+            if self.last_save.elapsed() >= interval {
+                if let Err(e) = self.app.save_if_dirty(self.storage.as_ref()) {
+                    eprintln!("自动保存失败: {}", e);
+                }
+                self.last_save = Instant::now();
+            }
+            ctx.request_repaint_after(interval);
+        }
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        // 保存数据
-        if let Err(e) = self.storage.save_data(&self.app.project_manager, &self.app.event_manager) {
-            eprintln!("保存数据失败: {}", e);
-        } else {
-            println!("数据已保存");
+        let unclosed = self.app.unclosed_event_titles();
+        if !unclosed.is_empty() {
+            eprintln!(
+                "警告: 以下事件仍在进行中，没有时间记录: {}",
+                unclosed.join("、")
+            );
+        }
+
+        // 保存数据，内容没有变化时跳过写入
+        match self.app.save_if_changed(self.storage.as_ref()) {
+            Ok(true) => println!("数据已保存"),
+            Ok(false) => println!("数据没有变化，无需保存"),
+            Err(e) => eprintln!("保存数据失败: {}", e),
         }
     }
 }
@@ -1,7 +1,40 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 项目名称和事件标题的最大长度（字符数）
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// 校验项目名称或事件标题，返回去掉首尾空白后的名称
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err("名称不能为空".to_string());
+    }
+    if trimmed.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("名称不能超过{}个字符", MAX_NAME_LENGTH));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// 校验多项目分摊比例：每个项目只出现一次、比例大于0且总和为100
+pub fn validate_project_shares(shares: &[(Uuid, u8)]) -> Result<(), String> {
+    let mut seen = std::collections::HashSet::new();
+    for (project_id, percent) in shares {
+        if *percent == 0 {
+            return Err("分摊比例必须大于0".to_string());
+        }
+        if !seen.insert(project_id) {
+            return Err("同一项目不能重复分摊".to_string());
+        }
+    }
+    let total: u32 = shares.iter().map(|(_, percent)| *percent as u32).sum();
+    if total != 100 {
+        return Err(format!("分摊比例之和必须为100，当前为{}", total));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub id: Uuid,
@@ -9,6 +42,16 @@ pub struct Project {
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub is_active: bool,
+    #[serde(default)]
+    pub archived: bool, // 已归档的项目不出现在项目列表中，但仍可在历史报表中查到
+    #[serde(default)]
+    pub hourly_rate: Option<f64>, // 每小时费率，未设置的项目不计费
+    #[serde(default)]
+    pub color: Option<String>, // 十六进制颜色，如 "#3366cc"
+    #[serde(default)]
+    pub weekly_goal_minutes: Option<i64>, // 每周目标时间
+    #[serde(default)]
+    pub status: ProjectStatus, // 项目生命周期状态，与表示当前项目的 is_active 无关
 }
 
 impl Project {
@@ -19,6 +62,11 @@ impl Project {
             description,
             created_at: Utc::now(),
             is_active: false,
+            archived: false,
+            hourly_rate: None,
+            color: None,
+            weekly_goal_minutes: None,
+            status: ProjectStatus::default(),
         }
     }
 
@@ -27,6 +75,23 @@ impl Project {
     }
 }
 
+/// 项目生命周期状态，旧数据默认为进行中
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ProjectStatus {
+    Planned, // 计划中
+    #[default]
+    Active, // 进行中
+    Done,    // 已完成
+    Cancelled, // 已取消
+}
+
+impl ProjectStatus {
+    /// 已完成或已取消的项目视为已结束
+    pub fn is_closed(self) -> bool {
+        matches!(self, ProjectStatus::Done | ProjectStatus::Cancelled)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventType {
     ProjectRelated(Uuid), // 关联到特定项目
@@ -42,6 +107,29 @@ pub struct Event {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub effort_points: Option<u32>, // 预估工作量点数（类似故事点）
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub template_id: Option<Uuid>, // 由重复事件模板生成时记录模板ID
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>, // 移入回收站的时间，回收站中的事件不出现在普通列表中
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>, // 必须先完成的前置事件
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>, // 检查项，不单独记录时间
+    #[serde(default)]
+    pub project_shares: Vec<(Uuid, u8)>, // 多个项目分摊时间时各项目的百分比，为空时按事件类型记录
+}
+
+/// 事件下的检查项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subtask {
+    pub title: String,
+    pub done: bool,
 }
 
 impl Event {
@@ -59,6 +147,14 @@ impl Event {
             start_time,
             end_time: None,
             created_at: Utc::now(),
+            effort_points: None,
+            tags: Vec::new(),
+            template_id: None,
+            priority: Priority::default(),
+            deleted_at: None,
+            depends_on: Vec::new(),
+            subtasks: Vec::new(),
+            project_shares: Vec::new(),
         }
     }
 
@@ -66,16 +162,174 @@ impl Event {
         self.end_time = Some(end_time);
     }
 
+    pub fn set_effort_points(&mut self, effort_points: Option<u32>) {
+        self.effort_points = effort_points;
+    }
+
     pub fn duration(&self) -> Option<chrono::Duration> {
-        match self.end_time {
-            Some(end) => Some(end.signed_duration_since(self.start_time)),
-            None => None,
-        }
+        self.end_time
+            .map(|end| end.signed_duration_since(self.start_time))
     }
 
     pub fn is_completed(&self) -> bool {
         self.end_time.is_some()
     }
+
+    /// 已完成的检查项数量和检查项总数
+    pub fn subtask_progress(&self) -> (usize, usize) {
+        let done = self.subtasks.iter().filter(|subtask| subtask.done).count();
+        (done, self.subtasks.len())
+    }
+
+    /// 检查项的完成百分比，没有检查项时返回 None
+    pub fn subtask_completion_percentage(&self) -> Option<f64> {
+        let (done, total) = self.subtask_progress();
+        (total > 0).then(|| done as f64 / total as f64 * 100.0)
+    }
+
+    /// 把分摊中的 source 项目并入 target 项目，target 已在分摊中时两者比例相加
+    pub fn merge_project_share(&mut self, source_id: Uuid, target_id: Uuid) {
+        let Some(source_index) = self
+            .project_shares
+            .iter()
+            .position(|(project_id, _)| *project_id == source_id)
+        else {
+            return;
+        };
+        let target_index = self
+            .project_shares
+            .iter()
+            .position(|(project_id, _)| *project_id == target_id);
+        match target_index {
+            Some(target_index) => {
+                let (_, percent) = self.project_shares.remove(source_index);
+                let target_index = if target_index > source_index {
+                    target_index - 1
+                } else {
+                    target_index
+                };
+                self.project_shares[target_index].1 += percent;
+            }
+            None => self.project_shares[source_index].0 = target_id,
+        }
+    }
+
+    /// 从分摊中去掉项目，其余项目按原有比例重新分配到100%，截断误差归最后一个项目
+    pub fn remove_project_share(&mut self, project_id: Uuid) {
+        let before = self.project_shares.len();
+        self.project_shares.retain(|(id, _)| *id != project_id);
+        if self.project_shares.len() == before {
+            return;
+        }
+
+        let total: u32 = self
+            .project_shares
+            .iter()
+            .map(|(_, percent)| *percent as u32)
+            .sum();
+        let mut remaining = 100;
+        for (_, percent) in &mut self.project_shares {
+            *percent = (*percent as u32 * 100 / total) as u8;
+            remaining -= *percent;
+        }
+        if let Some(last) = self.project_shares.last_mut() {
+            last.1 += remaining;
+        }
+    }
+
+    /// 按分摊比例拆分时长，返回每个项目分到的分钟数；整分钟截断的误差归最后一个项目，
+    /// 保证各部分之和等于总时长。没有分摊时整段时间归事件类型对应的项目
+    pub fn split_minutes(&self, total_minutes: i64) -> Vec<(Option<Uuid>, i64)> {
+        if self.project_shares.is_empty() {
+            let project_id = match self.event_type {
+                EventType::ProjectRelated(id) => Some(id),
+                EventType::NonProject => None,
+            };
+            return vec![(project_id, total_minutes)];
+        }
+
+        let mut remaining = total_minutes;
+        let mut parts: Vec<(Option<Uuid>, i64)> = self
+            .project_shares
+            .iter()
+            .map(|&(project_id, percent)| {
+                let minutes = total_minutes * percent as i64 / 100;
+                remaining -= minutes;
+                (Some(project_id), minutes)
+            })
+            .collect();
+        if let Some(last) = parts.last_mut() {
+            last.1 += remaining;
+        }
+        parts
+    }
+
+    /// 已持续的时间，进行中的事件计算到 now，已完成的事件返回最终时长
+    pub fn elapsed(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.end_time
+            .unwrap_or(now)
+            .signed_duration_since(self.start_time)
+    }
+}
+
+/// 事件优先级，旧数据默认为中
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+/// 重复规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Recurrence {
+    Daily,           // 每天
+    Weekdays,        // 周一至周五
+    Weekly(Weekday), // 每周的某一天
+}
+
+impl Recurrence {
+    /// 判断指定日期是否需要生成事件
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Daily => true,
+            Recurrence::Weekdays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            Recurrence::Weekly(weekday) => date.weekday() == *weekday,
+        }
+    }
+}
+
+/// 重复事件模板，按重复规则为每个日期生成具体的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTemplate {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub event_type: EventType,
+    pub recurrence: Recurrence,
+    pub start_time: NaiveTime, // 每次事件的开始时间（UTC）
+}
+
+impl EventTemplate {
+    pub fn new(
+        title: String,
+        description: Option<String>,
+        event_type: EventType,
+        recurrence: Recurrence,
+        start_time: NaiveTime,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            title,
+            description,
+            event_type,
+            recurrence,
+            start_time,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +341,8 @@ pub struct TimeRecord {
     pub end_time: DateTime<Utc>,
     pub duration_minutes: i64,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub note: Option<String>, // 完成后记录实际做了什么
 }
 
 impl TimeRecord {
@@ -105,10 +361,71 @@ impl TimeRecord {
             end_time,
             duration_minutes: duration.num_minutes(),
             created_at: Utc::now(),
+            note: None,
         }
     }
 }
 
+/// 番茄钟当前所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PomodoroPhase {
+    Work,
+    Break,
+}
+
+/// 进行中的番茄钟，按“工作+休息”循环，只有工作时间计入时间记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroSession {
+    pub event_id: Uuid,
+    pub work_minutes: i64,
+    pub break_minutes: i64,
+    pub started_at: DateTime<Utc>,
+}
+
+impl PomodoroSession {
+    fn elapsed_seconds(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.started_at).num_seconds().max(0)
+    }
+
+    fn cycle_seconds(&self) -> i64 {
+        (self.work_minutes + self.break_minutes) * 60
+    }
+
+    /// 已完成工作阶段的循环数
+    pub fn completed_cycles(&self, now: DateTime<Utc>) -> u32 {
+        let elapsed = self.elapsed_seconds(now);
+        let full = elapsed / self.cycle_seconds();
+        let finished_work = elapsed % self.cycle_seconds() >= self.work_minutes * 60;
+        (full + i64::from(finished_work)) as u32
+    }
+
+    /// 到指定时间为止累计的工作秒数（不含休息）
+    pub fn work_seconds(&self, now: DateTime<Utc>) -> i64 {
+        let elapsed = self.elapsed_seconds(now);
+        let work = self.work_minutes * 60;
+        elapsed / self.cycle_seconds() * work + (elapsed % self.cycle_seconds()).min(work)
+    }
+
+    /// 当前阶段
+    pub fn phase(&self, now: DateTime<Utc>) -> PomodoroPhase {
+        if self.elapsed_seconds(now) % self.cycle_seconds() < self.work_minutes * 60 {
+            PomodoroPhase::Work
+        } else {
+            PomodoroPhase::Break
+        }
+    }
+
+    /// 当前阶段的剩余时间，用于界面显示倒计时
+    pub fn remaining(&self, now: DateTime<Utc>) -> chrono::Duration {
+        let position = self.elapsed_seconds(now) % self.cycle_seconds();
+        let phase_end = match self.phase(now) {
+            PomodoroPhase::Work => self.work_minutes * 60,
+            PomodoroPhase::Break => self.cycle_seconds(),
+        };
+        chrono::Duration::seconds(phase_end - position)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeeklyReport {
     pub id: Uuid,
@@ -141,3 +458,324 @@ impl WeeklyReport {
         }
     }
 }
+
+/// 任意时间范围（季度、年度等）的汇总统计
+#[derive(Debug, Clone)]
+pub struct RangeStats {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub project_time_minutes: i64,
+    pub non_project_time_minutes: i64,
+    pub efficiency: f64, // 项目内时间占比（百分比）
+    pub project_breakdown: Vec<ProjectTimeBreakdown>,
+}
+
+/// 项目在一段时间内的事件、时间记录和总时间
+#[derive(Debug, Clone)]
+pub struct ProjectActivity {
+    pub project_id: Uuid,
+    pub events: Vec<Event>, // 与时间范围有重叠的事件，按开始时间排序
+    pub time_records: Vec<TimeRecord>, // 与时间范围有重叠的时间记录，按开始时间排序
+    pub total_minutes: i64, // 截取到时间范围内的总时间
+}
+
+/// 趋势报表中一周的统计
+#[derive(Debug, Clone)]
+pub struct WeekTrendPoint {
+    pub week_start: DateTime<Utc>,
+    pub project_minutes: i64,
+    pub non_project_minutes: i64,
+    pub top_project: Option<String>, // 当周时间最多的项目
+}
+
+/// 最近若干周的时间趋势，按时间先后排列
+#[derive(Debug, Clone)]
+pub struct WeeklyTrend {
+    pub weeks: Vec<WeekTrendPoint>,
+    pub sparkline: String, // 每周项目内时间的迷你折线，每个字符一周
+}
+
+/// 项目每周目标的完成情况
+#[derive(Debug, Clone)]
+pub struct GoalProgress {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub actual_minutes: i64,
+    pub goal_minutes: i64,
+    pub percentage: f64, // 完成百分比，超过目标时大于100
+}
+
+/// 全部数据的累计统计
+#[derive(Debug, Clone, Default)]
+pub struct AllTimeStats {
+    pub total_time_minutes: i64,
+    pub record_count: usize,
+    pub event_count: usize,
+    pub project_count: usize,
+    pub most_tracked_project: Option<(String, i64)>, // 项目名称和总分钟数
+    pub longest_session: Option<(String, i64)>,      // 事件标题和分钟数
+    pub first_activity: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+/// 连续工作时段的判定规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionConfig {
+    pub max_gap_minutes: i64,    // 相邻记录间隔不超过该分钟数时视为同一时段
+    pub same_project_only: bool, // 是否只合并同一项目（或同为项目外）的记录
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            max_gap_minutes: 5,
+            same_project_only: true,
+        }
+    }
+}
+
+/// 时段所属的项目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionProject {
+    Project(Uuid),
+    NonProject,
+    Mixed, // 包含多个项目的记录
+}
+
+/// 由若干相邻时间记录合并而成的连续工作时段
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub active_minutes: i64, // 实际有记录的分钟数，不含间隔，重叠部分只计一次
+    pub project: SessionProject,
+    pub event_ids: Vec<Uuid>,
+}
+
+/// 日时间线中的一个时间块（已裁剪到当天范围内）
+#[derive(Debug, Clone)]
+pub struct TimelineBlock {
+    pub record_id: Uuid,
+    pub event_id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub start_minute: i64, // 距当天零点的分钟数
+    pub end_minute: i64,
+    pub column: usize,       // 与其他时间块重叠时所在的列
+    pub column_count: usize, // 所在重叠组的总列数
+}
+
+/// 效率分析给出建议时使用的项目内时间占比阈值（百分比）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EfficiencyThresholds {
+    /// 低于此值时建议减少项目外活动
+    pub low: f64,
+    /// 高于此值时提醒注意工作生活平衡
+    pub high: f64,
+}
+
+impl EfficiencyThresholds {
+    /// 创建阈值，要求两者都在 0 到 100 之间且 low 小于 high
+    pub fn new(low: f64, high: f64) -> Result<Self, String> {
+        let thresholds = Self { low, high };
+        thresholds.validate()?;
+        Ok(thresholds)
+    }
+
+    /// 检查阈值是否有效
+    pub fn validate(&self) -> Result<(), String> {
+        let in_range = |value: f64| (0.0..=100.0).contains(&value);
+        if !in_range(self.low) || !in_range(self.high) {
+            return Err("效率阈值必须在0到100之间".to_string());
+        }
+        if self.low >= self.high {
+            return Err("效率下限必须小于上限".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for EfficiencyThresholds {
+    fn default() -> Self {
+        Self {
+            low: 50.0,
+            high: 90.0,
+        }
+    }
+}
+
+/// 报表保存到文件时使用的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ReportFormat {
+    Json,
+    #[default]
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl ReportFormat {
+    /// 该格式的文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// 报表中项目时间分解的排序方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum BreakdownOrder {
+    /// 按时间从多到少
+    #[default]
+    TimeDesc,
+    /// 按项目名称
+    Name,
+    /// 按用户指定的顺序，未指定的项目按时间排在后面
+    Pinned(Vec<Uuid>),
+}
+
+/// 每周的第一天
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// 指定星期几距离一周第一天的天数
+    pub fn days_from_start(self, weekday: Weekday) -> u32 {
+        match self {
+            WeekStart::Monday => weekday.num_days_from_monday(),
+            WeekStart::Sunday => weekday.num_days_from_sunday(),
+        }
+    }
+}
+
+/// 计入效率统计的工作日，默认一周七天都计入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkingDays {
+    days: [bool; 7], // 按周一到周日排列
+}
+
+impl WorkingDays {
+    /// 一周七天都是工作日
+    pub fn all() -> Self {
+        Self { days: [true; 7] }
+    }
+
+    /// 周一到周五为工作日
+    pub fn monday_to_friday() -> Self {
+        Self::from_weekdays(&[
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ])
+    }
+
+    /// 只有列出的星期几是工作日
+    pub fn from_weekdays(weekdays: &[Weekday]) -> Self {
+        let mut days = [false; 7];
+        for weekday in weekdays {
+            days[weekday.num_days_from_monday() as usize] = true;
+        }
+        Self { days }
+    }
+
+    pub fn contains(&self, weekday: Weekday) -> bool {
+        self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// 设置某个星期几是否为工作日
+    pub fn set(&mut self, weekday: Weekday, working: bool) {
+        self.days[weekday.num_days_from_monday() as usize] = working;
+    }
+
+    /// 是否七天都是工作日
+    pub fn is_all(&self) -> bool {
+        self.days.iter().all(|day| *day)
+    }
+}
+
+impl Default for WorkingDays {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// 计费时的时间取整方式，按每条记录分别取整后再求和
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum RoundingMode {
+    /// 不取整，使用记录的分钟数
+    #[default]
+    None,
+    /// 四舍五入到分钟
+    NearestMinute,
+    /// 向上取整到15分钟
+    Up15,
+    /// 四舍五入到15分钟
+    Nearest15,
+}
+
+/// 需要持久化的用户偏好设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub breakdown_order: BreakdownOrder,
+    #[serde(default = "default_true")]
+    pub confirm_discard_input: bool, // 取消有未保存输入的表单前先确认
+    #[serde(default)]
+    pub week_start: WeekStart,
+    #[serde(default = "default_autosave_interval")]
+    pub autosave_interval_secs: u64, // 自动保存间隔，0 表示只在退出时保存
+    #[serde(default)]
+    pub billing_rounding: RoundingMode,
+    #[serde(default)]
+    pub utc_offset_minutes: i32, // 报表按此时区划分日期，默认 UTC
+    #[serde(default)]
+    pub min_report_duration_minutes: i64, // 周报忽略短于此时长的记录，0 表示不忽略
+    #[serde(default)]
+    pub currency_symbol: String, // 计费报表金额前的货币符号，如 "¥"
+    #[serde(default)]
+    pub working_days: WorkingDays, // 详细周报的每日统计和效率分析只计入这些日期
+    #[serde(default)]
+    pub efficiency_thresholds: EfficiencyThresholds, // 效率分析给出建议时使用的阈值
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            breakdown_order: BreakdownOrder::default(),
+            confirm_discard_input: true,
+            week_start: WeekStart::default(),
+            autosave_interval_secs: default_autosave_interval(),
+            billing_rounding: RoundingMode::default(),
+            utc_offset_minutes: 0,
+            min_report_duration_minutes: 0,
+            currency_symbol: String::new(),
+            working_days: WorkingDays::default(),
+            efficiency_thresholds: EfficiencyThresholds::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// 报表使用的时区，偏移超出范围时回退到 UTC
+    pub fn timezone(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_autosave_interval() -> u64 {
+    60
+}
@@ -1,4 +1,5 @@
-use crate::models::{Event, EventType, Project};
+use crate::error::ProjectError;
+use crate::models::{validate_name, Event, EventType, Project, ProjectStatus};
 use chrono::Utc;
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -17,8 +18,15 @@ impl ProjectManager {
     }
 
     /// 添加新项目
-    pub fn add_project(&mut self, name: String, description: Option<String>) -> Uuid {
-        let mut project = Project::new(name, description);
+    pub fn add_project(
+        &mut self,
+        name: String,
+        description: Option<String>,
+    ) -> Result<Uuid, ProjectError> {
+        let mut project = Project::new(
+            validate_name(&name).map_err(ProjectError::Invalid)?,
+            description,
+        );
         let project_id = project.id;
 
         // 如果这是第一个项目，自动设置为当前项目
@@ -28,13 +36,24 @@ impl ProjectManager {
         }
 
         self.projects.insert(project_id, project);
-        project_id
+        Ok(project_id)
+    }
+
+    /// 插入已有项目（保留原ID，用于从保存的数据恢复）
+    pub fn insert_project(&mut self, project: Project) {
+        let project_id = project.id;
+        let is_active = project.is_active;
+        self.projects.insert(project_id, project);
+
+        if is_active {
+            self.current_project_id = Some(project_id);
+        }
     }
 
     /// 删除项目
-    pub fn delete_project(&mut self, project_id: Uuid) -> Result<(), String> {
+    pub fn delete_project(&mut self, project_id: Uuid) -> Result<(), ProjectError> {
         if !self.projects.contains_key(&project_id) {
-            return Err("项目不存在".to_string());
+            return Err(ProjectError::NotFound);
         }
 
         // 如果删除的是当前项目，清除当前项目ID
@@ -46,10 +65,65 @@ impl ProjectManager {
         Ok(())
     }
 
+    /// 把源项目合并到目标项目并删除源项目（事件和时间记录需由 EventManager::reassign_project 迁移）
+    pub fn merge_projects(&mut self, source_id: Uuid, target_id: Uuid) -> Result<(), ProjectError> {
+        if source_id == target_id {
+            return Err(ProjectError::MergeIntoSelf);
+        }
+        if !self.projects.contains_key(&source_id) {
+            return Err(ProjectError::SourceNotFound);
+        }
+        let target = self
+            .projects
+            .get(&target_id)
+            .ok_or(ProjectError::TargetNotFound)?;
+        let target_selectable = !target.archived && !target.status.is_closed();
+
+        let was_current = self.current_project_id == Some(source_id);
+        self.delete_project(source_id)?;
+
+        // 源项目是当前项目时切换到目标项目（已归档或已结束的项目不能作为当前项目）
+        if was_current && target_selectable {
+            self.switch_to_project(target_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// 归档项目（保留数据，但不再出现在项目列表中）
+    pub fn archive_project(&mut self, project_id: Uuid) -> Result<(), ProjectError> {
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        project.archived = true;
+        project.set_active(false);
+
+        // 归档的项目不能作为当前项目
+        if self.current_project_id == Some(project_id) {
+            self.current_project_id = None;
+        }
+
+        Ok(())
+    }
+
+    /// 取消归档项目
+    pub fn unarchive_project(&mut self, project_id: Uuid) -> Result<(), ProjectError> {
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        project.archived = false;
+        Ok(())
+    }
+
     /// 切换当前项目
-    pub fn switch_to_project(&mut self, project_id: Uuid) -> Result<(), String> {
-        if !self.projects.contains_key(&project_id) {
-            return Err("项目不存在".to_string());
+    pub fn switch_to_project(&mut self, project_id: Uuid) -> Result<(), ProjectError> {
+        match self.projects.get(&project_id) {
+            None => return Err(ProjectError::NotFound),
+            Some(project) if project.archived => return Err(ProjectError::Archived),
+            Some(project) if project.status.is_closed() => return Err(ProjectError::Closed),
+            Some(_) => {}
         }
 
         // 取消所有项目的激活状态
@@ -66,15 +140,68 @@ impl ProjectManager {
         Ok(())
     }
 
+    /// 取消当前项目，所有项目都不再处于激活状态
+    pub fn clear_current_project(&mut self) {
+        for project in self.projects.values_mut() {
+            project.set_active(false);
+        }
+        self.current_project_id = None;
+    }
+
+    /// 设置项目状态；已结束的项目需先重新打开才能改为另一种结束状态，结束的当前项目会被取消选择
+    pub fn set_status(
+        &mut self,
+        project_id: Uuid,
+        status: ProjectStatus,
+    ) -> Result<(), ProjectError> {
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        if project.status.is_closed() && status.is_closed() && project.status != status {
+            return Err(ProjectError::AlreadyClosed);
+        }
+
+        project.status = status;
+        if status.is_closed() {
+            project.set_active(false);
+            if self.current_project_id == Some(project_id) {
+                self.current_project_id = None;
+            }
+        }
+        Ok(())
+    }
+
     /// 获取当前项目
     pub fn get_current_project(&self) -> Option<&Project> {
         self.current_project_id
             .and_then(|id| self.projects.get(&id))
     }
 
-    /// 获取所有项目
+    /// 获取所有项目（按创建时间排序）
     pub fn get_all_projects(&self) -> Vec<&Project> {
-        self.projects.values().collect()
+        Self::sorted(self.projects.values().collect())
+    }
+
+    /// 获取未归档的项目（按创建时间排序）
+    pub fn get_unarchived_projects(&self) -> Vec<&Project> {
+        Self::sorted(self.projects.values().filter(|p| !p.archived).collect())
+    }
+
+    /// 获取默认显示的项目：未归档且未完成、未取消（按创建时间排序）
+    pub fn get_open_projects(&self) -> Vec<&Project> {
+        Self::sorted(
+            self.projects
+                .values()
+                .filter(|p| !p.archived && !p.status.is_closed())
+                .collect(),
+        )
+    }
+
+    /// 按创建时间排序，创建时间相同时按ID排序，保证顺序稳定
+    fn sorted(mut projects: Vec<&Project>) -> Vec<&Project> {
+        projects.sort_by_key(|p| (p.created_at, p.id));
+        projects
     }
 
     /// 根据ID获取项目
@@ -82,24 +209,92 @@ impl ProjectManager {
         self.projects.get(&project_id)
     }
 
-    /// 更新项目信息
+    /// 按名称查找项目，忽略大小写、首尾空白以及连续空白的差异
+    pub fn find_project_by_name_fuzzy(&self, name: &str) -> Option<Uuid> {
+        let target = normalize_project_name(name);
+        Self::sorted(self.projects.values().collect())
+            .into_iter()
+            .find(|p| normalize_project_name(&p.name) == target)
+            .map(|p| p.id)
+    }
+
+    /// 修改项目名称和描述，None 表示不修改，描述为空白时清除描述
     pub fn update_project(
         &mut self,
         project_id: Uuid,
         name: Option<String>,
         description: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), ProjectError> {
+        let name = name
+            .map(|name| validate_name(&name))
+            .transpose()
+            .map_err(ProjectError::Invalid)?;
         if let Some(project) = self.projects.get_mut(&project_id) {
             if let Some(name) = name {
                 project.name = name;
             }
-            if description.is_some() {
-                project.description = description;
+            if let Some(description) = description {
+                let description = description.trim();
+                project.description = (!description.is_empty()).then(|| description.to_string());
             }
             Ok(())
         } else {
-            Err("项目不存在".to_string())
+            Err(ProjectError::NotFound)
+        }
+    }
+
+    /// 设置项目的每小时费率，None 表示不计费
+    pub fn set_project_hourly_rate(
+        &mut self,
+        project_id: Uuid,
+        hourly_rate: Option<f64>,
+    ) -> Result<(), ProjectError> {
+        if hourly_rate.is_some_and(|rate| !rate.is_finite() || rate < 0.0) {
+            return Err(ProjectError::Invalid("费率必须是非负数".to_string()));
+        }
+
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        project.hourly_rate = hourly_rate;
+        Ok(())
+    }
+
+    /// 设置项目颜色（"#abc" 或 "#aabbcc" 格式），None 表示使用默认颜色
+    pub fn set_color(&mut self, project_id: Uuid, color: Option<&str>) -> Result<(), ProjectError> {
+        let color = match color {
+            Some(hex) => {
+                let [r, g, b] = parse_hex_color(hex).map_err(ProjectError::Invalid)?;
+                Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+            }
+            None => None,
+        };
+
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        project.color = color;
+        Ok(())
+    }
+
+    /// 设置项目每周目标分钟数，None 表示没有目标
+    pub fn set_weekly_goal(
+        &mut self,
+        project_id: Uuid,
+        goal_minutes: Option<i64>,
+    ) -> Result<(), ProjectError> {
+        if goal_minutes.is_some_and(|minutes| minutes <= 0) {
+            return Err(ProjectError::Invalid("每周目标必须大于0".to_string()));
         }
+
+        let project = self
+            .projects
+            .get_mut(&project_id)
+            .ok_or(ProjectError::NotFound)?;
+        project.weekly_goal_minutes = goal_minutes;
+        Ok(())
     }
 
     /// 获取项目数量
@@ -122,23 +317,32 @@ impl ProjectManager {
         &self,
         title: String,
         description: Option<String>,
-    ) -> Result<Event, String> {
+    ) -> Result<Event, ProjectError> {
         if let Some(current_project_id) = self.current_project_id {
             let event = Event::new(
-                title,
+                validate_name(&title).map_err(ProjectError::Invalid)?,
                 description,
                 EventType::ProjectRelated(current_project_id),
                 Utc::now(),
             );
             Ok(event)
         } else {
-            Err("没有当前活动项目".to_string())
+            Err(ProjectError::NoCurrentProject)
         }
     }
 
     /// 创建项目外事件
-    pub fn create_non_project_event(&self, title: String, description: Option<String>) -> Event {
-        Event::new(title, description, EventType::NonProject, Utc::now())
+    pub fn create_non_project_event(
+        &self,
+        title: String,
+        description: Option<String>,
+    ) -> Result<Event, ProjectError> {
+        Ok(Event::new(
+            validate_name(&title).map_err(ProjectError::Invalid)?,
+            description,
+            EventType::NonProject,
+            Utc::now(),
+        ))
     }
 }
 
@@ -148,14 +352,51 @@ impl Default for ProjectManager {
     }
 }
 
+/// 规范化项目名称：去掉首尾空白、合并连续空白并转为小写，用于按名称匹配项目
+pub fn normalize_project_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// 解析十六进制颜色，支持 "#abc" 和 "#aabbcc" 两种格式
+pub fn parse_hex_color(hex: &str) -> Result<[u8; 3], String> {
+    let invalid = || format!("无效的颜色: {}（应为 #abc 或 #aabbcc 格式）", hex);
+    let digits = hex.trim().strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |s: &str| u8::from_str_radix(s, 16).map_err(|_| invalid());
+    match digits.len() {
+        3 => {
+            let mut rgb = [0; 3];
+            for (i, c) in digits.chars().enumerate() {
+                rgb[i] = channel(&c.to_string())? * 17;
+            }
+            Ok(rgb)
+        }
+        6 => Ok([
+            channel(&digits[0..2])?,
+            channel(&digits[2..4])?,
+            channel(&digits[4..6])?,
+        ]),
+        _ => Err(invalid()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::MAX_NAME_LENGTH;
 
     #[test]
     fn test_add_project() {
         let mut manager = ProjectManager::new();
-        let project_id = manager.add_project("测试项目".to_string(), Some("测试描述".to_string()));
+        let project_id = manager
+            .add_project("测试项目".to_string(), Some("测试描述".to_string()))
+            .unwrap();
 
         assert_eq!(manager.get_project_count(), 1);
         assert!(manager.project_exists(project_id));
@@ -166,11 +407,146 @@ mod tests {
         assert!(project.is_active);
     }
 
+    #[test]
+    fn test_find_project_by_name_fuzzy() {
+        let mut manager = ProjectManager::new();
+        let project_x = manager.add_project("Project X".to_string(), None).unwrap();
+        let project_a = manager.add_project("Project A".to_string(), None).unwrap();
+
+        assert_eq!(
+            manager.find_project_by_name_fuzzy("project x "),
+            Some(project_x)
+        );
+        assert_eq!(
+            manager.find_project_by_name_fuzzy("  PROJECT \t X"),
+            Some(project_x)
+        );
+        assert_eq!(
+            manager.find_project_by_name_fuzzy("Project A"),
+            Some(project_a)
+        );
+        assert_eq!(manager.find_project_by_name_fuzzy("Project B"), None);
+        assert_eq!(manager.find_project_by_name_fuzzy("ProjectX"), None);
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let mut manager = ProjectManager::new();
+        let missing = Uuid::new_v4();
+        assert_eq!(
+            manager
+                .create_project_event("事件".to_string(), None)
+                .unwrap_err(),
+            ProjectError::NoCurrentProject
+        );
+        assert!(matches!(
+            manager.create_non_project_event(" \t".to_string(), None),
+            Err(ProjectError::Invalid(_))
+        ));
+        assert_eq!(
+            manager
+                .create_non_project_event(" 午饭 ".to_string(), None)
+                .unwrap()
+                .title,
+            "午饭"
+        );
+        let project_id = manager.add_project("项目".to_string(), None).unwrap();
+        manager.switch_to_project(project_id).unwrap();
+        assert!(matches!(
+            manager.create_project_event("长".repeat(MAX_NAME_LENGTH + 1), None),
+            Err(ProjectError::Invalid(_))
+        ));
+        assert_eq!(
+            manager.switch_to_project(missing),
+            Err(ProjectError::NotFound)
+        );
+        assert_eq!(manager.delete_project(missing), Err(ProjectError::NotFound));
+        assert!(matches!(
+            manager.add_project("  ".to_string(), None),
+            Err(ProjectError::Invalid(_))
+        ));
+
+        let project_id = manager.add_project("项目".to_string(), None).unwrap();
+        let other_id = manager.add_project("另一个项目".to_string(), None).unwrap();
+        assert_eq!(
+            manager.merge_projects(project_id, project_id),
+            Err(ProjectError::MergeIntoSelf)
+        );
+        assert_eq!(
+            manager.merge_projects(missing, project_id),
+            Err(ProjectError::SourceNotFound)
+        );
+        assert_eq!(
+            manager.merge_projects(project_id, missing),
+            Err(ProjectError::TargetNotFound)
+        );
+        assert!(matches!(
+            manager.set_weekly_goal(project_id, Some(0)),
+            Err(ProjectError::Invalid(_))
+        ));
+
+        manager.set_status(other_id, ProjectStatus::Done).unwrap();
+        assert_eq!(
+            manager.switch_to_project(other_id),
+            Err(ProjectError::Closed)
+        );
+        assert_eq!(
+            manager.set_status(other_id, ProjectStatus::Cancelled),
+            Err(ProjectError::AlreadyClosed)
+        );
+        manager.archive_project(project_id).unwrap();
+        assert_eq!(
+            manager.switch_to_project(project_id),
+            Err(ProjectError::Archived)
+        );
+    }
+
+    #[test]
+    fn test_clear_current_project() {
+        let mut manager = ProjectManager::new();
+        let project_id = manager.add_project("项目".to_string(), None).unwrap();
+        assert!(manager.get_current_project().is_some());
+
+        manager.clear_current_project();
+        assert!(manager.get_current_project().is_none());
+        assert!(!manager.get_project(project_id).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_update_project() {
+        let mut manager = ProjectManager::new();
+        let project_id = manager
+            .add_project("旧名称".to_string(), Some("旧描述".to_string()))
+            .unwrap();
+
+        manager
+            .update_project(project_id, None, Some(" 新描述 ".to_string()))
+            .unwrap();
+        let project = manager.get_project(project_id).unwrap();
+        assert_eq!(project.name, "旧名称");
+        assert_eq!(project.description.as_deref(), Some("新描述"));
+
+        manager
+            .update_project(project_id, Some("新名称".to_string()), Some(String::new()))
+            .unwrap();
+        let project = manager.get_project(project_id).unwrap();
+        assert_eq!(project.name, "新名称");
+        assert_eq!(project.description, None);
+
+        assert!(manager
+            .update_project(project_id, Some("  ".to_string()), None)
+            .is_err());
+        assert_eq!(manager.get_project(project_id).unwrap().name, "新名称");
+        assert!(manager
+            .update_project(Uuid::new_v4(), None, Some("描述".to_string()))
+            .is_err());
+    }
+
     #[test]
     fn test_switch_project() {
         let mut manager = ProjectManager::new();
-        let id1 = manager.add_project("项目1".to_string(), None);
-        let id2 = manager.add_project("项目2".to_string(), None);
+        let id1 = manager.add_project("项目1".to_string(), None).unwrap();
+        let id2 = manager.add_project("项目2".to_string(), None).unwrap();
 
         // 第一个项目应该是当前项目
         assert_eq!(manager.get_current_project().unwrap().id, id1);
@@ -184,11 +560,119 @@ mod tests {
         assert!(manager.get_project(id2).unwrap().is_active);
     }
 
+    #[test]
+    fn test_insert_project_keeps_id() {
+        let mut manager = ProjectManager::new();
+        let mut project = Project::new("已保存项目".to_string(), None);
+        project.set_active(true);
+        let project_id = project.id;
+
+        manager.insert_project(project);
+
+        assert!(manager.project_exists(project_id));
+        assert_eq!(manager.get_current_project().unwrap().id, project_id);
+    }
+
+    #[test]
+    fn test_merge_projects() {
+        let mut manager = ProjectManager::new();
+        let source = manager.add_project("重复项目".to_string(), None).unwrap();
+        let target = manager.add_project("项目".to_string(), None).unwrap();
+        assert_eq!(manager.get_current_project().unwrap().id, source);
+
+        assert!(manager.merge_projects(source, source).is_err());
+        assert!(manager.merge_projects(source, Uuid::new_v4()).is_err());
+        assert!(manager.merge_projects(Uuid::new_v4(), target).is_err());
+
+        manager.merge_projects(source, target).unwrap();
+        assert!(!manager.project_exists(source));
+        assert_eq!(manager.get_current_project().unwrap().id, target);
+        assert_eq!(manager.get_project_count(), 1);
+    }
+
+    #[test]
+    fn test_set_project_hourly_rate() {
+        let mut manager = ProjectManager::new();
+        let project_id = manager.add_project("客户项目".to_string(), None).unwrap();
+
+        manager
+            .set_project_hourly_rate(project_id, Some(120.5))
+            .unwrap();
+        assert_eq!(
+            manager.get_project(project_id).unwrap().hourly_rate,
+            Some(120.5)
+        );
+
+        assert!(manager
+            .set_project_hourly_rate(project_id, Some(-1.0))
+            .is_err());
+        assert!(manager
+            .set_project_hourly_rate(project_id, Some(f64::NAN))
+            .is_err());
+        assert_eq!(
+            manager.get_project(project_id).unwrap().hourly_rate,
+            Some(120.5)
+        );
+
+        manager.set_project_hourly_rate(project_id, None).unwrap();
+        assert_eq!(manager.get_project(project_id).unwrap().hourly_rate, None);
+        assert!(manager
+            .set_project_hourly_rate(Uuid::new_v4(), None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_archive_project() {
+        let mut manager = ProjectManager::new();
+        let id1 = manager.add_project("项目1".to_string(), None).unwrap();
+        let id2 = manager.add_project("项目2".to_string(), None).unwrap();
+
+        manager.archive_project(id1).unwrap();
+
+        // 归档当前项目后不再有当前项目
+        assert!(manager.get_current_project().is_none());
+        assert!(!manager.get_project(id1).unwrap().is_active);
+
+        // 列表中不包含已归档项目，但仍可按ID查到
+        let unarchived = manager.get_unarchived_projects();
+        assert_eq!(unarchived.len(), 1);
+        assert_eq!(unarchived[0].id, id2);
+        assert_eq!(manager.get_all_projects().len(), 2);
+        assert_eq!(manager.get_project(id1).unwrap().name, "项目1");
+
+        // 已归档项目不能成为当前项目
+        assert!(manager.switch_to_project(id1).is_err());
+
+        manager.unarchive_project(id1).unwrap();
+        assert_eq!(manager.get_unarchived_projects().len(), 2);
+        manager.switch_to_project(id1).unwrap();
+        assert_eq!(manager.get_current_project().unwrap().id, id1);
+    }
+
+    #[test]
+    fn test_project_order_is_stable() {
+        let mut manager = ProjectManager::new();
+        manager.add_project("项目C".to_string(), None).unwrap();
+        manager.add_project("项目A".to_string(), None).unwrap();
+        manager.add_project("项目B".to_string(), None).unwrap();
+
+        let first: Vec<Uuid> = manager.get_all_projects().iter().map(|p| p.id).collect();
+        for _ in 0..10 {
+            let again: Vec<Uuid> = manager.get_all_projects().iter().map(|p| p.id).collect();
+            assert_eq!(first, again);
+        }
+
+        let projects = manager.get_all_projects();
+        assert!(projects
+            .windows(2)
+            .all(|pair| (pair[0].created_at, pair[0].id) <= (pair[1].created_at, pair[1].id)));
+    }
+
     #[test]
     fn test_delete_project() {
         let mut manager = ProjectManager::new();
-        let id1 = manager.add_project("项目1".to_string(), None);
-        let id2 = manager.add_project("项目2".to_string(), None);
+        let id1 = manager.add_project("项目1".to_string(), None).unwrap();
+        let id2 = manager.add_project("项目2".to_string(), None).unwrap();
 
         manager.switch_to_project(id2).unwrap();
         manager.delete_project(id1).unwrap();
@@ -197,4 +681,112 @@ mod tests {
         assert!(!manager.project_exists(id1));
         assert!(manager.project_exists(id2));
     }
+
+    #[test]
+    fn test_project_status_transitions() {
+        let mut manager = ProjectManager::new();
+        let current = manager.add_project("当前项目".to_string(), None).unwrap();
+        let other = manager.add_project("计划中".to_string(), None).unwrap();
+        assert_eq!(
+            manager.get_project(current).unwrap().status,
+            ProjectStatus::Active
+        );
+
+        manager.set_status(other, ProjectStatus::Planned).unwrap();
+        assert_eq!(manager.get_open_projects().len(), 2);
+
+        // 完成当前项目会取消选择，但不影响其他项目
+        manager.set_status(current, ProjectStatus::Done).unwrap();
+        assert!(manager.get_current_project().is_none());
+        assert!(!manager.get_project(current).unwrap().is_active);
+        assert_eq!(manager.get_open_projects().len(), 1);
+
+        // 已结束的项目不能直接改为另一种结束状态
+        assert!(manager
+            .set_status(current, ProjectStatus::Cancelled)
+            .is_err());
+        manager.set_status(current, ProjectStatus::Active).unwrap();
+        manager
+            .set_status(current, ProjectStatus::Cancelled)
+            .unwrap();
+        assert!(manager
+            .set_status(Uuid::new_v4(), ProjectStatus::Done)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cannot_switch_to_closed_project() {
+        let mut manager = ProjectManager::new();
+        let first = manager.add_project("项目1".to_string(), None).unwrap();
+        let cancelled = manager.add_project("已取消".to_string(), None).unwrap();
+        manager
+            .set_status(cancelled, ProjectStatus::Cancelled)
+            .unwrap();
+
+        assert!(manager.switch_to_project(cancelled).is_err());
+        assert_eq!(manager.get_current_project().unwrap().id, first);
+
+        // 合并到已结束的项目时不会把它设为当前项目
+        let source = manager.add_project("源项目".to_string(), None).unwrap();
+        manager.switch_to_project(source).unwrap();
+        manager.merge_projects(source, cancelled).unwrap();
+        assert!(manager.get_current_project().is_none());
+
+        // 旧数据没有状态字段时视为进行中
+        let mut value = serde_json::to_value(manager.get_project(first).unwrap()).unwrap();
+        value.as_object_mut().unwrap().remove("status");
+        let legacy: Project = serde_json::from_value(value).unwrap();
+        assert_eq!(legacy.status, ProjectStatus::Active);
+    }
+
+    #[test]
+    fn test_hex_color_validation() {
+        assert_eq!(parse_hex_color("#abc"), Ok([0xaa, 0xbb, 0xcc]));
+        assert_eq!(parse_hex_color("#aabbcc"), Ok([0xaa, 0xbb, 0xcc]));
+        assert_eq!(parse_hex_color("#3366CC"), Ok([0x33, 0x66, 0xcc]));
+        assert!(parse_hex_color("blue").is_err());
+        assert!(parse_hex_color("#12").is_err());
+        assert!(parse_hex_color("#12345g").is_err());
+        assert!(parse_hex_color("#ａｂｃ").is_err());
+    }
+
+    #[test]
+    fn test_set_color() {
+        let mut manager = ProjectManager::new();
+        let project_id = manager.add_project("彩色项目".to_string(), None).unwrap();
+
+        manager.set_color(project_id, Some("#ABC")).unwrap();
+        assert_eq!(
+            manager.get_project(project_id).unwrap().color.as_deref(),
+            Some("#aabbcc")
+        );
+        assert!(manager.set_color(project_id, Some("blue")).is_err());
+        assert_eq!(
+            manager.get_project(project_id).unwrap().color.as_deref(),
+            Some("#aabbcc")
+        );
+
+        manager.set_color(project_id, None).unwrap();
+        assert!(manager.get_project(project_id).unwrap().color.is_none());
+        assert!(manager.set_color(Uuid::new_v4(), Some("#abc")).is_err());
+    }
+
+    #[test]
+    fn test_add_project_validates_name() {
+        let mut manager = ProjectManager::new();
+
+        assert!(manager.add_project(String::new(), None).is_err());
+        assert!(manager.add_project("   \t ".to_string(), None).is_err());
+        let oversized = "项".repeat(MAX_NAME_LENGTH + 1);
+        assert!(manager.add_project(oversized, None).is_err());
+        assert!(manager.get_all_projects().is_empty());
+
+        let longest = "项".repeat(MAX_NAME_LENGTH);
+        assert!(manager.add_project(longest, None).is_ok());
+
+        let project_id = manager
+            .add_project("  网站改版 ".to_string(), None)
+            .unwrap();
+        assert_eq!(manager.get_project(project_id).unwrap().name, "网站改版");
+    }
 }
@@ -1,7 +1,17 @@
-use crate::models::{TimeRecord, WeeklyReport};
-use crate::time_calculator::TimeCalculator;
-use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use crate::csv::csv_escape;
+use crate::error::{ReportError, ReportImportError};
+use crate::models::{
+    AllTimeStats, EfficiencyThresholds, Event, EventType, GoalProgress, Project, RangeStats,
+    ReportFormat, RoundingMode, SessionConfig, TimeRecord, WeekStart, WeekTrendPoint, WeeklyReport,
+    WeeklyTrend, WorkingDays,
+};
+use crate::time_calculator::{utc_offset, TimeCalculator};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 pub struct ReportGenerator;
@@ -12,9 +22,33 @@ impl ReportGenerator {
         time_records: &[&TimeRecord],
         project_names: &HashMap<Uuid, String>,
         report_date: DateTime<Utc>,
+        first_day: WeekStart,
+        min_duration_minutes: i64,
     ) -> WeeklyReport {
-        let week_start = TimeCalculator::get_week_start(report_date);
-        let week_end = TimeCalculator::get_week_end(report_date);
+        Self::generate_weekly_report_tz(
+            time_records,
+            project_names,
+            report_date,
+            first_day,
+            min_duration_minutes,
+            utc_offset(),
+        )
+    }
+
+    /// 按tz时区的日历生成每周报表
+    pub fn generate_weekly_report_tz(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        report_date: DateTime<Utc>,
+        first_day: WeekStart,
+        min_duration_minutes: i64,
+        tz: FixedOffset,
+    ) -> WeeklyReport {
+        let week_start = TimeCalculator::get_week_start_tz(report_date, first_day, tz);
+        let week_end = TimeCalculator::get_week_end_tz(report_date, first_day, tz);
+        // 过短的记录既不计入总时间，也不计入各项目的事件数
+        let filtered = TimeCalculator::filter_min_duration(time_records, min_duration_minutes);
+        let time_records = filtered.as_slice();
 
         let total_project_time =
             TimeCalculator::calculate_project_time(time_records, week_start, week_end);
@@ -38,13 +72,18 @@ impl ReportGenerator {
 
     /// 生成报表文本摘要
     pub fn generate_report_summary(report: &WeeklyReport) -> String {
+        Self::generate_report_summary_tz(report, utc_offset())
+    }
+
+    /// 生成报表文本摘要，时间范围按tz时区显示
+    pub fn generate_report_summary_tz(report: &WeeklyReport, tz: FixedOffset) -> String {
         let mut summary = String::new();
 
-        summary.push_str(&format!("=== 每周报表 ===\n"));
+        summary.push_str("=== 每周报表 ===\n");
         summary.push_str(&format!(
             "时间范围: {} 至 {}\n\n",
-            report.week_start.format("%Y-%m-%d"),
-            report.week_end.format("%Y-%m-%d")
+            report.week_start.with_timezone(&tz).format("%Y-%m-%d"),
+            report.week_end.with_timezone(&tz).format("%Y-%m-%d")
         ));
 
         summary.push_str(&format!(
@@ -81,56 +120,158 @@ impl ReportGenerator {
 
         summary.push_str(&format!(
             "\n报表生成时间: {}\n",
-            report.generated_at.format("%Y-%m-%d %H:%M:%S")
+            report
+                .generated_at
+                .with_timezone(&tz)
+                .format("%Y-%m-%d %H:%M:%S")
         ));
 
         summary
     }
 
-    /// 生成详细报表（包含每日统计）
+    /// 计算指定日期所在周各项目的目标完成情况，没有设置目标的项目不包含在内
+    pub fn generate_goal_progress(
+        time_records: &[&TimeRecord],
+        projects: &[&Project],
+        week_date: DateTime<Utc>,
+        first_day: WeekStart,
+    ) -> Vec<GoalProgress> {
+        Self::generate_goal_progress_tz(time_records, projects, week_date, first_day, utc_offset())
+    }
+
+    /// 按tz时区的日历计算指定日期所在周各项目的目标完成情况
+    pub fn generate_goal_progress_tz(
+        time_records: &[&TimeRecord],
+        projects: &[&Project],
+        week_date: DateTime<Utc>,
+        first_day: WeekStart,
+        tz: FixedOffset,
+    ) -> Vec<GoalProgress> {
+        let week_start = TimeCalculator::get_week_start_tz(week_date, first_day, tz);
+        let week_end = TimeCalculator::get_week_end_tz(week_date, first_day, tz);
+
+        projects
+            .iter()
+            .filter_map(|project| {
+                let goal_minutes = project.weekly_goal_minutes.filter(|goal| *goal > 0)?;
+                let actual_minutes = TimeCalculator::calculate_project_total_time(
+                    time_records,
+                    project.id,
+                    Some(week_start),
+                    Some(week_end),
+                );
+                Some(GoalProgress {
+                    project_id: project.id,
+                    project_name: project.name.clone(),
+                    actual_minutes,
+                    goal_minutes,
+                    percentage: actual_minutes as f64 / goal_minutes as f64 * 100.0,
+                })
+            })
+            .collect()
+    }
+
+    /// 指定日期所在周每天结束时距离项目每周目标还剩的分钟数（目标减去累计记录时间），
+    /// 达成目标后为0；项目没有设置目标时返回空列表
+    pub fn burndown(
+        project: &Project,
+        time_records: &[&TimeRecord],
+        week_date: DateTime<Utc>,
+        first_day: WeekStart,
+    ) -> Vec<(NaiveDate, i64)> {
+        let Some(goal_minutes) = project.weekly_goal_minutes.filter(|goal| *goal > 0) else {
+            return Vec::new();
+        };
+        let week_start = TimeCalculator::get_week_start(week_date, first_day);
+
+        (0..7)
+            .map(|day| {
+                let day_end =
+                    week_start + chrono::Duration::days(day + 1) - chrono::Duration::seconds(1);
+                let logged = TimeCalculator::calculate_project_total_time(
+                    time_records,
+                    project.id,
+                    Some(week_start),
+                    Some(day_end),
+                );
+                (
+                    (week_start + chrono::Duration::days(day)).date_naive(),
+                    (goal_minutes - logged).max(0),
+                )
+            })
+            .collect()
+    }
+
+    /// 生成目标完成情况文本，每个项目一行进度条
+    pub fn format_goal_progress(progress: &[GoalProgress]) -> String {
+        const BAR_WIDTH: usize = 10;
+
+        let mut summary = String::new();
+        summary.push_str("本周目标:\n");
+        for goal in progress {
+            let filled =
+                ((goal.percentage / 100.0 * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+            summary.push_str(&format!(
+                "  - {}: {} / {} [{}{}] {:.1}%{}\n",
+                goal.project_name,
+                TimeCalculator::format_duration(goal.actual_minutes),
+                TimeCalculator::format_duration(goal.goal_minutes),
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                goal.percentage,
+                if goal.actual_minutes >= goal.goal_minutes {
+                    "（已达成）"
+                } else {
+                    ""
+                }
+            ));
+        }
+        summary
+    }
+
+    /// 生成详细报表（包含每日统计），按tz时区的日历划分每一天，
+    /// 每日统计和工作效率只包含 working_days 中的日期
     pub fn generate_detailed_weekly_report(
         time_records: &[&TimeRecord],
         project_names: &HashMap<Uuid, String>,
         report_date: DateTime<Utc>,
+        first_day: WeekStart,
+        tz: FixedOffset,
+        working_days: WorkingDays,
     ) -> String {
         let mut detailed_report = String::new();
 
-        let week_start = TimeCalculator::get_week_start(report_date);
-        let week_end = TimeCalculator::get_week_end(report_date);
+        let week_start = TimeCalculator::get_week_start_tz(report_date, first_day, tz);
+        let week_end = TimeCalculator::get_week_end_tz(report_date, first_day, tz);
 
-        detailed_report.push_str(&format!("=== 详细每周报表 ===\n"));
+        detailed_report.push_str("=== 详细每周报表 ===\n");
         detailed_report.push_str(&format!(
             "时间范围: {} 至 {}\n\n",
-            week_start.format("%Y-%m-%d"),
-            week_end.format("%Y-%m-%d")
+            week_start.with_timezone(&tz).format("%Y-%m-%d"),
+            week_end.with_timezone(&tz).format("%Y-%m-%d")
         ));
 
-        // 每日统计
+        // 每日统计，跨天的记录按当天的重叠部分统计，所有记录只遍历一次
         detailed_report.push_str("每日统计:\n");
-        let mut current_day = week_start;
-
-        while current_day <= week_end {
-            let daily_records: Vec<&TimeRecord> = time_records
-                .iter()
-                .filter(|record| {
-                    let record_date = record.start_time.date_naive();
-                    let current_date = current_day.date_naive();
-                    record_date == current_date
-                })
-                .copied()
-                .collect();
-
+        let daily_stats = TimeCalculator::daily_stats_tz(time_records, week_start, week_end, tz);
+        let working_dates: Vec<NaiveDate> = week_start
+            .with_timezone(&tz)
+            .date_naive()
+            .iter_days()
+            .take(7)
+            .filter(|date| working_days.contains(date.weekday()))
+            .collect();
+
+        for date in &working_dates {
             let (project_time, non_project_time) =
-                TimeCalculator::calculate_daily_stats(&daily_records, current_day);
+                daily_stats.get(date).copied().unwrap_or_default();
 
             detailed_report.push_str(&format!(
                 "  {}: 项目内={}, 项目外={}\n",
-                current_day.format("%Y-%m-%d (%a)"),
+                date.format("%Y-%m-%d (%a)"),
                 TimeCalculator::format_duration(project_time),
                 TimeCalculator::format_duration(non_project_time)
             ));
-
-            current_day = current_day + chrono::Duration::days(1);
         }
 
         // 总体统计
@@ -149,13 +290,31 @@ impl ReportGenerator {
             TimeCalculator::format_duration(total_non_project_time)
         ));
 
-        let total_time = total_project_time + total_non_project_time;
-        let efficiency = if total_time > 0 {
-            (total_project_time as f64 / total_time as f64) * 100.0
+        let (working_project_time, working_non_project_time) = if working_days.is_all() {
+            (total_project_time, total_non_project_time)
+        } else {
+            working_dates
+                .iter()
+                .filter_map(|date| daily_stats.get(date))
+                .fold((0, 0), |(project, non_project), (p, n)| {
+                    (project + p, non_project + n)
+                })
+        };
+        let working_time = working_project_time + working_non_project_time;
+        let efficiency = if working_time > 0 {
+            (working_project_time as f64 / working_time as f64) * 100.0
         } else {
             0.0
         };
-        detailed_report.push_str(&format!("  工作效率: {:.2}%\n", efficiency));
+        detailed_report.push_str(&format!(
+            "  工作效率: {:.2}%{}\n",
+            efficiency,
+            if working_days.is_all() {
+                ""
+            } else {
+                "（仅工作日）"
+            }
+        ));
 
         // 项目分解
         let project_breakdown = TimeCalculator::generate_project_breakdown(
@@ -177,6 +336,30 @@ impl ReportGenerator {
             }
         }
 
+        // 本周时间记录的笔记
+        let mut noted: Vec<&&TimeRecord> = time_records
+            .iter()
+            .filter(|record| record.note.is_some())
+            .filter(|record| record.start_time >= week_start && record.start_time <= week_end)
+            .collect();
+        noted.sort_by_key(|record| record.start_time);
+        if !noted.is_empty() {
+            detailed_report.push_str("\n工作笔记:\n");
+            for record in noted {
+                let project_name = record
+                    .project_id
+                    .and_then(|id| project_names.get(&id))
+                    .map(|name| name.as_str())
+                    .unwrap_or("项目外");
+                detailed_report.push_str(&format!(
+                    "  {} [{}] {}\n",
+                    record.start_time.with_timezone(&tz).format("%m-%d %H:%M"),
+                    project_name,
+                    record.note.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
         // 项目排名
         let project_ranking =
             TimeCalculator::get_project_ranking(time_records, project_names, week_start, week_end);
@@ -195,7 +378,7 @@ impl ReportGenerator {
 
         detailed_report.push_str(&format!(
             "\n报表生成时间: {}\n",
-            Utc::now().format("%Y-%m-%d %H:%M:%S")
+            Utc::now().with_timezone(&tz).format("%Y-%m-%d %H:%M:%S")
         ));
 
         detailed_report
@@ -208,62 +391,92 @@ impl ReportGenerator {
         year: i32,
         month: u32,
     ) -> String {
-        let mut summary = String::new();
-
-        let (project_time, non_project_time) =
-            TimeCalculator::calculate_monthly_stats(time_records, year, month);
-
-        summary.push_str(&format!("=== 月度报表 ===\n"));
-        summary.push_str(&format!("时间范围: {}年{}月\n\n", year, month));
-
-        summary.push_str(&format!(
-            "项目内时间: {}\n",
-            TimeCalculator::format_duration(project_time)
-        ));
-        summary.push_str(&format!(
-            "项目外时间: {}\n",
-            TimeCalculator::format_duration(non_project_time)
-        ));
+        Self::generate_monthly_summary_tz(time_records, project_names, year, month, utc_offset())
+    }
 
-        let total_time = project_time + non_project_time;
-        let efficiency = if total_time > 0 {
-            (project_time as f64 / total_time as f64) * 100.0
-        } else {
-            0.0
-        };
+    /// 按tz时区的日历生成月度报表摘要
+    pub fn generate_monthly_summary_tz(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        year: i32,
+        month: u32,
+        tz: FixedOffset,
+    ) -> String {
+        let (month_start, month_end) = TimeCalculator::get_month_range_tz(year, month, 1, tz);
+        let stats = TimeCalculator::calculate_range_stats(
+            time_records,
+            project_names,
+            month_start,
+            month_end,
+        );
 
-        summary.push_str(&format!("工作效率: {:.2}%\n", efficiency));
+        Self::format_range_summary("月度报表", &format!("{}年{}月", year, month), &stats)
+    }
 
-        // 计算月度开始和结束时间
-        let month_start = chrono::NaiveDate::from_ymd_opt(year, month, 1)
-            .unwrap()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_utc();
+    /// 生成季度汇总报表，quarter 取值 1-4
+    pub fn generate_quarterly_summary(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        year: i32,
+        quarter: u32,
+    ) -> Result<String, ReportError> {
+        if !(1..=4).contains(&quarter) {
+            return Err(ReportError::InvalidQuarter(quarter));
+        }
 
-        let next_month = if month == 12 {
-            (year + 1, 1)
-        } else {
-            (year, month + 1)
-        };
+        let (quarter_start, quarter_end) =
+            TimeCalculator::get_month_range(year, (quarter - 1) * 3 + 1, 3);
+        let stats = TimeCalculator::calculate_range_stats(
+            time_records,
+            project_names,
+            quarter_start,
+            quarter_end,
+        );
 
-        let month_end = chrono::NaiveDate::from_ymd_opt(next_month.0, next_month.1, 1)
-            .unwrap()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_utc()
-            - chrono::Duration::seconds(1);
+        Ok(Self::format_range_summary(
+            "季度报表",
+            &format!("{}年第{}季度", year, quarter),
+            &stats,
+        ))
+    }
 
-        let project_breakdown = TimeCalculator::generate_project_breakdown(
+    /// 生成年度汇总报表
+    pub fn generate_yearly_summary(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        year: i32,
+    ) -> String {
+        let (year_start, year_end) = TimeCalculator::get_month_range(year, 1, 12);
+        let stats = TimeCalculator::calculate_range_stats(
             time_records,
             project_names,
-            month_start,
-            month_end,
+            year_start,
+            year_end,
         );
 
-        if !project_breakdown.is_empty() {
+        Self::format_range_summary("年度报表", &format!("{}年", year), &stats)
+    }
+
+    /// 月度、季度和年度报表共用的格式
+    fn format_range_summary(title: &str, range_label: &str, stats: &RangeStats) -> String {
+        let mut summary = String::new();
+
+        summary.push_str(&format!("=== {} ===\n", title));
+        summary.push_str(&format!("时间范围: {}\n\n", range_label));
+
+        summary.push_str(&format!(
+            "项目内时间: {}\n",
+            TimeCalculator::format_duration(stats.project_time_minutes)
+        ));
+        summary.push_str(&format!(
+            "项目外时间: {}\n",
+            TimeCalculator::format_duration(stats.non_project_time_minutes)
+        ));
+        summary.push_str(&format!("工作效率: {:.2}%\n", stats.efficiency));
+
+        if !stats.project_breakdown.is_empty() {
             summary.push_str("\n项目时间分解:\n");
-            for breakdown in project_breakdown {
+            for breakdown in &stats.project_breakdown {
                 summary.push_str(&format!(
                     "  - {}: {} ({}个事件)\n",
                     breakdown.project_name,
@@ -276,137 +489,990 @@ impl ReportGenerator {
         summary
     }
 
-    /// 导出报表为JSON格式
-    pub fn export_report_to_json(report: &WeeklyReport) -> Result<String, serde_json::Error> {
-        serde_json::to_string_pretty(report)
+    /// 统计截止到end_week所在周（含）的最近num_weeks周，每周的项目内外时间
+    pub fn generate_trend(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        end_week: DateTime<Utc>,
+        num_weeks: usize,
+        first_day: WeekStart,
+    ) -> WeeklyTrend {
+        Self::generate_trend_tz(
+            time_records,
+            project_names,
+            end_week,
+            num_weeks,
+            first_day,
+            utc_offset(),
+        )
+    }
+
+    /// 按tz时区的日历统计最近num_weeks周的项目内外时间
+    pub fn generate_trend_tz(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        end_week: DateTime<Utc>,
+        num_weeks: usize,
+        first_day: WeekStart,
+        tz: FixedOffset,
+    ) -> WeeklyTrend {
+        let last_week_start = TimeCalculator::get_week_start_tz(end_week, first_day, tz);
+        let weeks: Vec<WeekTrendPoint> = (0..num_weeks)
+            .rev()
+            .map(|weeks_ago| {
+                let week_start = last_week_start - chrono::Duration::weeks(weeks_ago as i64);
+                let week_end = TimeCalculator::get_week_end_tz(week_start, first_day, tz);
+                let top_project = TimeCalculator::generate_project_breakdown(
+                    time_records,
+                    project_names,
+                    week_start,
+                    week_end,
+                )
+                .into_iter()
+                .max_by_key(|breakdown| breakdown.total_time_minutes)
+                .map(|breakdown| breakdown.project_name);
+
+                WeekTrendPoint {
+                    week_start,
+                    project_minutes: TimeCalculator::calculate_project_time(
+                        time_records,
+                        week_start,
+                        week_end,
+                    ),
+                    non_project_minutes: TimeCalculator::calculate_non_project_time(
+                        time_records,
+                        week_start,
+                        week_end,
+                    ),
+                    top_project,
+                }
+            })
+            .collect();
+
+        let project_minutes: Vec<i64> = weeks.iter().map(|week| week.project_minutes).collect();
+        WeeklyTrend {
+            sparkline: sparkline(&project_minutes),
+            weeks,
+        }
     }
 
-    /// 从JSON导入报表
-    pub fn import_report_from_json(json_str: &str) -> Result<WeeklyReport, serde_json::Error> {
-        serde_json::from_str(json_str)
+    /// 生成趋势文本，第一行是项目内时间的迷你折线
+    pub fn format_trend(trend: &WeeklyTrend) -> String {
+        Self::format_trend_tz(trend, utc_offset())
     }
 
-    /// 生成效率分析报告
-    pub fn generate_efficiency_analysis(
-        time_records: &[&TimeRecord],
+    /// 生成趋势文本，每周的开始日期按tz时区显示
+    pub fn format_trend_tz(trend: &WeeklyTrend, tz: FixedOffset) -> String {
+        let mut summary = format!("近{}周趋势: {}\n", trend.weeks.len(), trend.sparkline);
+        for week in &trend.weeks {
+            summary.push_str(&format!(
+                "  {} 当周: 项目内={}, 项目外={}{}\n",
+                week.week_start.with_timezone(&tz).format("%Y-%m-%d"),
+                TimeCalculator::format_duration(week.project_minutes),
+                TimeCalculator::format_duration(week.non_project_minutes),
+                week.top_project
+                    .as_ref()
+                    .map(|name| format!(", 最多: {}", name))
+                    .unwrap_or_default()
+            ));
+        }
+        summary
+    }
+
+    /// 生成速度报告（按周统计已完成事件的工作量点数）
+    pub fn generate_velocity_report(
+        events: &[&Event],
         project_names: &HashMap<Uuid, String>,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
+        first_day: WeekStart,
     ) -> String {
-        let mut analysis = String::new();
+        let mut report = String::new();
 
-        analysis.push_str(&format!("=== 效率分析报告 ===\n"));
-        analysis.push_str(&format!(
-            "分析期间: {} 至 {}\n\n",
+        report.push_str("=== 速度报告 ===\n");
+        report.push_str(&format!(
+            "时间范围: {} 至 {}\n\n",
             start_date.format("%Y-%m-%d"),
             end_date.format("%Y-%m-%d")
         ));
 
-        let project_time =
-            TimeCalculator::calculate_project_time(time_records, start_date, end_date);
-        let non_project_time =
-            TimeCalculator::calculate_non_project_time(time_records, start_date, end_date);
-        let total_time = project_time + non_project_time;
+        // 每周点数，预先填充范围内的所有周，保证没有完成点数的周也会显示
+        let mut weekly_points: BTreeMap<NaiveDate, u32> = BTreeMap::new();
+        let mut week = TimeCalculator::get_week_start(start_date, first_day).date_naive();
+        while week <= end_date.date_naive() {
+            weekly_points.insert(week, 0);
+            week += chrono::Duration::days(7);
+        }
 
-        analysis.push_str("时间分配:\n");
-        analysis.push_str(&format!(
-            "  项目内时间: {} ({:.1}%)\n",
-            TimeCalculator::format_duration(project_time),
-            if total_time > 0 {
-                (project_time as f64 / total_time as f64) * 100.0
-            } else {
-                0.0
+        let mut project_points: HashMap<Option<Uuid>, u32> = HashMap::new();
+
+        // 只统计范围内已完成且有点数的事件
+        for event in events {
+            let (Some(end_time), Some(points)) = (event.end_time, event.effort_points) else {
+                continue;
+            };
+            if end_time < start_date || end_time > end_date {
+                continue;
             }
+
+            let week_start = TimeCalculator::get_week_start(end_time, first_day).date_naive();
+            *weekly_points.entry(week_start).or_insert(0) += points;
+
+            let project_id = match event.event_type {
+                EventType::ProjectRelated(id) => Some(id),
+                EventType::NonProject => None,
+            };
+            *project_points.entry(project_id).or_insert(0) += points;
+        }
+
+        report.push_str("每周完成点数:\n");
+        for (week_start, points) in &weekly_points {
+            report.push_str(&format!(
+                "  {} 当周: {}点\n",
+                week_start.format("%Y-%m-%d"),
+                points
+            ));
+        }
+
+        let total_points: u32 = weekly_points.values().sum();
+        let week_count = weekly_points.len().max(1);
+        report.push_str(&format!(
+            "\n总完成点数: {}点\n平均速度: {:.1}点/周\n",
+            total_points,
+            total_points as f64 / week_count as f64
         ));
-        analysis.push_str(&format!(
-            "  项目外时间: {} ({:.1}%)\n",
-            TimeCalculator::format_duration(non_project_time),
-            if total_time > 0 {
-                (non_project_time as f64 / total_time as f64) * 100.0
+
+        if !project_points.is_empty() {
+            let mut breakdown: Vec<(String, u32)> = project_points
+                .into_iter()
+                .map(|(project_id, points)| {
+                    let name = match project_id {
+                        Some(id) => project_names
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| "未知项目".to_string()),
+                        None => "项目外".to_string(),
+                    };
+                    (name, points)
+                })
+                .collect();
+            breakdown.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+            report.push_str("\n项目完成点数:\n");
+            for (name, points) in breakdown {
+                report.push_str(&format!("  - {}: {}点\n", name, points));
+            }
+        }
+
+        // 速度趋势：比较最近一周与之前各周的平均值
+        let points: Vec<u32> = weekly_points.values().copied().collect();
+        report.push_str("\n速度趋势: ");
+        if let Some((last, previous)) = points.split_last().filter(|(_, prev)| !prev.is_empty()) {
+            let previous_avg = previous.iter().sum::<u32>() as f64 / previous.len() as f64;
+            let last = *last as f64;
+            if last > previous_avg {
+                report.push_str("上升\n");
+            } else if last < previous_avg {
+                report.push_str("下降\n");
             } else {
-                0.0
+                report.push_str("持平\n");
             }
-        ));
+        } else {
+            report.push_str("数据不足\n");
+        }
 
-        // 项目效率分析
-        let project_breakdown = TimeCalculator::generate_project_breakdown(
-            time_records,
-            project_names,
-            start_date,
-            end_date,
-        );
+        report
+    }
 
-        if !project_breakdown.is_empty() {
-            analysis.push_str("\n项目效率分析:\n");
-            for breakdown in project_breakdown {
-                let avg_event_duration = if breakdown.event_count > 0 {
-                    breakdown.total_time_minutes / breakdown.event_count as i64
+    /// 计算全部数据的累计统计（不按日期过滤）
+    pub fn calculate_all_time_stats(
+        time_records: &[&TimeRecord],
+        events: &[&Event],
+        projects: &[&Project],
+    ) -> AllTimeStats {
+        let mut project_totals: HashMap<Uuid, i64> = HashMap::new();
+        for record in time_records {
+            if let Some(project_id) = record.project_id {
+                *project_totals.entry(project_id).or_insert(0) += record.duration_minutes;
+            }
+        }
+
+        let most_tracked_project = project_totals
+            .into_iter()
+            .max_by_key(|(_, minutes)| *minutes)
+            .map(|(project_id, minutes)| {
+                let name = projects
+                    .iter()
+                    .find(|p| p.id == project_id)
+                    .map(|p| p.name.clone())
+                    .unwrap_or_else(|| "未知项目".to_string());
+                (name, minutes)
+            });
+
+        // 最长时段按连续工作时段计算，而非单条时间记录
+        let longest_session = TimeCalculator::sessions(time_records, &SessionConfig::default())
+            .into_iter()
+            .max_by_key(|session| session.active_minutes)
+            .map(|session| {
+                let title = session
+                    .event_ids
+                    .first()
+                    .and_then(|id| events.iter().find(|e| e.id == *id))
+                    .map(|e| e.title.clone())
+                    .unwrap_or_else(|| "未知事件".to_string());
+                let title = if session.event_ids.len() > 1 {
+                    format!("{}等{}个事件", title, session.event_ids.len())
                 } else {
-                    0
+                    title
                 };
-                analysis.push_str(&format!(
-                    "  - {}: 总时间={}, 平均事件时长={}\n",
-                    breakdown.project_name,
-                    TimeCalculator::format_duration(breakdown.total_time_minutes),
-                    TimeCalculator::format_duration(avg_event_duration)
-                ));
-            }
+                (title, session.active_minutes)
+            });
+
+        let first_activity = time_records
+            .iter()
+            .map(|r| r.start_time)
+            .chain(events.iter().map(|e| e.start_time))
+            .min();
+        let last_activity = time_records
+            .iter()
+            .map(|r| r.end_time)
+            .chain(events.iter().map(|e| e.end_time.unwrap_or(e.start_time)))
+            .max();
+
+        AllTimeStats {
+            total_time_minutes: time_records.iter().map(|r| r.duration_minutes).sum(),
+            record_count: time_records.len(),
+            event_count: events.len(),
+            project_count: projects.len(),
+            most_tracked_project,
+            longest_session,
+            first_activity,
+            last_activity,
         }
+    }
 
-        // 建议
-        analysis.push_str("\n改进建议:\n");
-        let efficiency = if total_time > 0 {
-            (project_time as f64 / total_time as f64) * 100.0
-        } else {
-            0.0
-        };
+    /// 生成计费报表：按项目费率计算指定时间范围内的费用，未设置费率的项目单独列出
+    pub fn generate_billing_report(
+        time_records: &[&TimeRecord],
+        projects: &[&Project],
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        rounding: RoundingMode,
+        currency_symbol: &str,
+    ) -> String {
+        let mut report = String::new();
+        report.push_str("=== 计费报表 ===\n");
+        report.push_str(&format!(
+            "时间范围: {} 至 {}\n",
+            start_time.format("%Y-%m-%d"),
+            end_time.format("%Y-%m-%d")
+        ));
+        if rounding != RoundingMode::None {
+            report.push_str(&format!(
+                "时间取整: {}（按每条记录取整）\n",
+                Self::rounding_mode_label(rounding)
+            ));
+        }
+        report.push('\n');
+
+        let mut total_cost = 0.0;
+        let mut unbilled = Vec::new();
+        for project in projects {
+            let minutes = TimeCalculator::calculate_project_total_time_rounded(
+                time_records,
+                project.id,
+                Some(start_time),
+                Some(end_time),
+                rounding,
+            );
+            if minutes == 0 {
+                continue;
+            }
 
-        if efficiency < 50.0 {
-            analysis.push_str("  - 建议减少项目外活动，增加项目内工作时间\n");
-        } else if efficiency > 90.0 {
-            analysis.push_str("  - 工作效率很高，注意保持工作生活平衡\n");
-        } else {
-            analysis.push_str("  - 工作效率良好，继续保持\n");
+            match project.hourly_rate {
+                Some(rate) => {
+                    let cost = Self::round_cents(minutes as f64 / 60.0 * rate);
+                    total_cost += cost;
+                    report.push_str(&format!(
+                        "  - {}: {} × {}/小时 = {}\n",
+                        project.name,
+                        TimeCalculator::format_duration(minutes),
+                        Self::format_money(rate, currency_symbol),
+                        Self::format_money(cost, currency_symbol)
+                    ));
+                }
+                None => unbilled.push((project.name.as_str(), minutes)),
+            }
         }
 
-        if non_project_time > project_time {
-            analysis.push_str("  - 项目外时间过多，建议优化时间分配\n");
+        report.push_str(&format!(
+            "\n合计: {}\n",
+            Self::format_money(total_cost, currency_symbol)
+        ));
+
+        if !unbilled.is_empty() {
+            report.push_str("\n未计费:\n");
+            for (name, minutes) in unbilled {
+                report.push_str(&format!(
+                    "  - {}: {}\n",
+                    name,
+                    TimeCalculator::format_duration(minutes)
+                ));
+            }
         }
 
-        analysis
+        report
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Duration;
+    /// 取整方式的显示名称
+    pub fn rounding_mode_label(rounding: RoundingMode) -> &'static str {
+        match rounding {
+            RoundingMode::None => "不取整",
+            RoundingMode::NearestMinute => "四舍五入到分钟",
+            RoundingMode::Up15 => "向上取整到15分钟",
+            RoundingMode::Nearest15 => "四舍五入到15分钟",
+        }
+    }
 
-    fn create_test_time_record(
-        project_id: Option<Uuid>,
-        start_time: DateTime<Utc>,
-        duration_minutes: i64,
-    ) -> TimeRecord {
-        let end_time = start_time + Duration::minutes(duration_minutes);
-        TimeRecord::new(Uuid::new_v4(), project_id, start_time, end_time)
+    /// 四舍五入到两位小数
+    fn round_cents(amount: f64) -> f64 {
+        (amount * 100.0).round() / 100.0
     }
 
-    #[test]
-    fn test_generate_weekly_report() {
-        let project_id = Uuid::new_v4();
-        let base_time = Utc::now();
+    /// 把金额格式化为带货币符号和千位分隔符的两位小数，例如 "¥1,500.00"
+    pub fn format_money(amount: f64, symbol: &str) -> String {
+        let digits = format!("{:.2}", amount.abs());
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, "00"));
 
-        let record1 = create_test_time_record(Some(project_id), base_time, 120);
-        let record2 = create_test_time_record(Some(project_id), base_time + Duration::hours(3), 90);
-        let record3 = create_test_time_record(None, base_time + Duration::hours(6), 60);
-        let records = vec![&record1, &record2, &record3];
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        // 四舍五入后为0的负数不显示负号
+        let sign = if amount < 0.0 && digits.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            "-"
+        } else {
+            ""
+        };
+        format!("{}{}{}.{}", sign, symbol, grouped, fraction)
+    }
 
-        let mut project_names = HashMap::new();
-        project_names.insert(project_id, "测试项目".to_string());
+    /// 生成累计统计文本
+    pub fn generate_all_time_stats(
+        time_records: &[&TimeRecord],
+        events: &[&Event],
+        projects: &[&Project],
+    ) -> String {
+        let stats = Self::calculate_all_time_stats(time_records, events, projects);
+        let mut summary = String::new();
 
-        let report = ReportGenerator::generate_weekly_report(&records, &project_names, base_time);
+        summary.push_str("=== 累计统计 ===\n");
 
-        assert_eq!(report.total_project_time_minutes, 210); // 120 + 90
+        if stats.record_count == 0 && stats.event_count == 0 && stats.project_count == 0 {
+            summary.push_str("还没有任何数据，添加项目和事件后再来看看吧\n");
+            return summary;
+        }
+
+        summary.push_str(&format!(
+            "累计记录时间: {}\n",
+            TimeCalculator::format_duration(stats.total_time_minutes)
+        ));
+        summary.push_str(&format!("事件数量: {}\n", stats.event_count));
+        summary.push_str(&format!("项目数量: {}\n", stats.project_count));
+
+        if stats.record_count > 0 {
+            summary.push_str(&format!(
+                "平均每段时长: {}\n",
+                TimeCalculator::format_duration(
+                    stats.total_time_minutes / stats.record_count as i64
+                )
+            ));
+        }
+
+        match &stats.most_tracked_project {
+            Some((name, minutes)) => summary.push_str(&format!(
+                "投入最多的项目: {} ({})\n",
+                name,
+                TimeCalculator::format_duration(*minutes)
+            )),
+            None => summary.push_str("投入最多的项目: 无\n"),
+        }
+
+        match &stats.longest_session {
+            Some((title, minutes)) => summary.push_str(&format!(
+                "最长的一段时间: {} ({})\n",
+                title,
+                TimeCalculator::format_duration(*minutes)
+            )),
+            None => summary.push_str("最长的一段时间: 无\n"),
+        }
+
+        if let (Some(first), Some(last)) = (stats.first_activity, stats.last_activity) {
+            summary.push_str(&format!(
+                "首次活动: {}\n最近活动: {}\n",
+                first.format("%Y-%m-%d"),
+                last.format("%Y-%m-%d")
+            ));
+        }
+
+        summary
+    }
+
+    /// 导出报表为JSON格式
+    pub fn export_report_to_json(report: &WeeklyReport) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(report)
+    }
+
+    /// 按指定格式把报表写入文件，路径没有扩展名时补上格式对应的扩展名，返回实际写入的路径
+    pub fn save_report(
+        report: &WeeklyReport,
+        path: &Path,
+        format: ReportFormat,
+    ) -> io::Result<PathBuf> {
+        Self::save_report_tz(report, path, format, utc_offset())
+    }
+
+    /// 按指定格式把报表写入文件，标题中的日期按tz时区显示
+    pub fn save_report_tz(
+        report: &WeeklyReport,
+        path: &Path,
+        format: ReportFormat,
+        tz: FixedOffset,
+    ) -> io::Result<PathBuf> {
+        let contents = match format {
+            ReportFormat::Json => Self::export_report_to_json(report)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            ReportFormat::Markdown => Self::export_report_to_markdown_tz(report, tz),
+            ReportFormat::Html => Self::export_report_to_html_tz(report, tz),
+            ReportFormat::Csv => Self::export_report_to_csv(report),
+        };
+
+        let path = if path.extension().is_none() {
+            path.with_extension(format.extension())
+        } else {
+            path.to_path_buf()
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// 导出报表为Markdown格式，项目时间分解为表格
+    pub fn export_report_to_markdown(report: &WeeklyReport) -> String {
+        Self::export_report_to_markdown_tz(report, utc_offset())
+    }
+
+    /// 导出报表为Markdown格式，标题中的日期按tz时区显示
+    pub fn export_report_to_markdown_tz(report: &WeeklyReport, tz: FixedOffset) -> String {
+        let mut markdown = format!(
+            "# 每周报表 {} 至 {}\n\n",
+            report.week_start.with_timezone(&tz).format("%Y-%m-%d"),
+            report.week_end.with_timezone(&tz).format("%Y-%m-%d")
+        );
+        markdown.push_str(&format!(
+            "- 项目内时间: {}\n- 项目外时间: {}\n\n",
+            TimeCalculator::format_duration(report.total_project_time_minutes),
+            TimeCalculator::format_duration(report.total_non_project_time_minutes)
+        ));
+        markdown.push_str("| 项目 | 时间 | 事件数 |\n| --- | --- | --- |\n");
+        for breakdown in &report.project_breakdown {
+            markdown.push_str(&format!(
+                "| {} | {} | {} |\n",
+                breakdown.project_name.replace('|', "\\|"),
+                TimeCalculator::format_duration(breakdown.total_time_minutes),
+                breakdown.event_count
+            ));
+        }
+        markdown
+    }
+
+    /// 导出报表为独立的HTML页面
+    pub fn export_report_to_html(report: &WeeklyReport) -> String {
+        Self::export_report_to_html_tz(report, utc_offset())
+    }
+
+    /// 导出报表为独立的HTML页面，标题中的日期按tz时区显示
+    pub fn export_report_to_html_tz(report: &WeeklyReport, tz: FixedOffset) -> String {
+        let title = format!(
+            "每周报表 {} 至 {}",
+            report.week_start.with_timezone(&tz).format("%Y-%m-%d"),
+            report.week_end.with_timezone(&tz).format("%Y-%m-%d")
+        );
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n",
+            title
+        );
+        html.push_str(&format!(
+            "<p>项目内时间: {}</p>\n<p>项目外时间: {}</p>\n",
+            TimeCalculator::format_duration(report.total_project_time_minutes),
+            TimeCalculator::format_duration(report.total_non_project_time_minutes)
+        ));
+        html.push_str("<table>\n<tr><th>项目</th><th>时间</th><th>事件数</th></tr>\n");
+        for breakdown in &report.project_breakdown {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                html_escape(&breakdown.project_name),
+                TimeCalculator::format_duration(breakdown.total_time_minutes),
+                breakdown.event_count
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// 导出报表的项目时间分解为CSV，时间以分钟为单位
+    pub fn export_report_to_csv(report: &WeeklyReport) -> String {
+        let mut csv = String::from("项目,分钟,事件数\n");
+        for breakdown in &report.project_breakdown {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                csv_escape(&breakdown.project_name),
+                breakdown.total_time_minutes,
+                breakdown.event_count
+            ));
+        }
+        csv.push_str(&format!(
+            "项目外时间,{},\n",
+            report.total_non_project_time_minutes
+        ));
+        csv
+    }
+
+    /// 把已完成的事件导出为iCalendar（RFC 5545）日历，每个事件一个VEVENT，
+    /// 项目事件的标题前加上项目名称
+    pub fn export_events_to_ics(
+        events: &[&Event],
+        project_names: &HashMap<Uuid, String>,
+    ) -> String {
+        const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//project_manager//项目管理系统//ZH".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+        ];
+        for event in events {
+            let Some(end_time) = event.end_time else {
+                continue;
+            };
+            let summary = match &event.event_type {
+                EventType::ProjectRelated(project_id) => format!(
+                    "[{}] {}",
+                    project_names
+                        .get(project_id)
+                        .map(String::as_str)
+                        .unwrap_or("未知项目"),
+                    event.title
+                ),
+                EventType::NonProject => event.title.clone(),
+            };
+
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}@project_manager", event.id));
+            lines.push(format!("DTSTAMP:{}", event.created_at.format(TIME_FORMAT)));
+            lines.push(format!("DTSTART:{}", event.start_time.format(TIME_FORMAT)));
+            lines.push(format!("DTEND:{}", end_time.format(TIME_FORMAT)));
+            lines.push(format!("SUMMARY:{}", ics_escape(&summary)));
+            if let Some(description) = &event.description {
+                lines.push(format!("DESCRIPTION:{}", ics_escape(description)));
+            }
+            if !event.tags.is_empty() {
+                let tags: Vec<String> = event.tags.iter().map(|tag| ics_escape(tag)).collect();
+                lines.push(format!("CATEGORIES:{}", tags.join(",")));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines
+            .iter()
+            .map(|line| fold_ics_line(line) + "\r\n")
+            .collect()
+    }
+
+    /// 从JSON导入报表，并检查时间范围和项目时间分解与总时间是否一致
+    pub fn import_report_from_json(json_str: &str) -> Result<WeeklyReport, ReportImportError> {
+        let report: WeeklyReport = serde_json::from_str(json_str)?;
+
+        if report.week_start > report.week_end {
+            return Err(ReportImportError::InvalidRange);
+        }
+        let breakdown_sum: i64 = report
+            .project_breakdown
+            .iter()
+            .map(|project| project.total_time_minutes)
+            .sum();
+        if breakdown_sum != report.total_project_time_minutes {
+            return Err(ReportImportError::TotalMismatch {
+                total: report.total_project_time_minutes,
+                breakdown_sum,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// 对比两个时间段的总时间和效率，按项目列出变化，period_a 为基准时间段
+    pub fn generate_comparison(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        period_a: (DateTime<Utc>, DateTime<Utc>),
+        period_b: (DateTime<Utc>, DateTime<Utc>),
+    ) -> String {
+        Self::generate_comparison_tz(
+            time_records,
+            project_names,
+            period_a,
+            period_b,
+            utc_offset(),
+        )
+    }
+
+    /// 对比两个时间段，时段的起止日期按tz时区显示
+    pub fn generate_comparison_tz(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        period_a: (DateTime<Utc>, DateTime<Utc>),
+        period_b: (DateTime<Utc>, DateTime<Utc>),
+        tz: FixedOffset,
+    ) -> String {
+        let mut report = String::new();
+
+        report.push_str("=== 时段对比报表 ===\n");
+        for (label, (start, end)) in [("时段A", period_a), ("时段B", period_b)] {
+            report.push_str(&format!(
+                "{}: {} 至 {}\n",
+                label,
+                start.with_timezone(&tz).format("%Y-%m-%d"),
+                end.with_timezone(&tz).format("%Y-%m-%d")
+            ));
+        }
+
+        let totals = |(start, end): (DateTime<Utc>, DateTime<Utc>)| {
+            (
+                TimeCalculator::calculate_project_time(time_records, start, end),
+                TimeCalculator::calculate_non_project_time(time_records, start, end),
+            )
+        };
+        let (project_a, non_project_a) = totals(period_a);
+        let (project_b, non_project_b) = totals(period_b);
+
+        report.push_str("\n总体:\n");
+        report.push_str(&format!(
+            "  项目内时间: {}\n",
+            format_minutes_change(project_a, project_b)
+        ));
+        report.push_str(&format!(
+            "  项目外时间: {}\n",
+            format_minutes_change(non_project_a, non_project_b)
+        ));
+
+        let efficiency = |project: i64, non_project: i64| {
+            let total = project + non_project;
+            if total > 0 {
+                project as f64 / total as f64 * 100.0
+            } else {
+                0.0
+            }
+        };
+        let efficiency_a = efficiency(project_a, non_project_a);
+        let efficiency_b = efficiency(project_b, non_project_b);
+        let efficiency_delta = efficiency_b - efficiency_a;
+        report.push_str(&format!(
+            "  工作效率: {:.2}% → {:.2}% {} {:+.2}个百分点\n",
+            efficiency_a,
+            efficiency_b,
+            change_arrow(
+                efficiency_delta
+                    .partial_cmp(&0.0)
+                    .unwrap_or(Ordering::Equal)
+            ),
+            efficiency_delta
+        ));
+
+        // 只在一个时间段出现的项目，另一个时间段按0计算
+        let mut by_project: BTreeMap<String, [i64; 2]> = BTreeMap::new();
+        for (index, (start, end)) in [period_a, period_b].into_iter().enumerate() {
+            for breakdown in
+                TimeCalculator::generate_project_breakdown(time_records, project_names, start, end)
+            {
+                by_project.entry(breakdown.project_name).or_default()[index] +=
+                    breakdown.total_time_minutes;
+            }
+        }
+
+        if !by_project.is_empty() {
+            report.push_str("\n项目对比:\n");
+            for (name, [minutes_a, minutes_b]) in by_project {
+                let status = match (minutes_a, minutes_b) {
+                    (0, _) => "（新增）",
+                    (_, 0) => "（已停止）",
+                    _ => "",
+                };
+                report.push_str(&format!(
+                    "  - {}: {}{}\n",
+                    name,
+                    format_minutes_change(minutes_a, minutes_b),
+                    status
+                ));
+            }
+        }
+
+        report
+    }
+
+    /// 生成效率分析报告，阈值无效时返回错误
+    pub fn generate_efficiency_analysis(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        thresholds: EfficiencyThresholds,
+        working_days: WorkingDays,
+    ) -> Result<String, ReportError> {
+        Self::generate_efficiency_analysis_tz(
+            time_records,
+            project_names,
+            start_date,
+            end_date,
+            thresholds,
+            working_days,
+            utc_offset(),
+        )
+    }
+
+    /// 生成效率分析报告，按tz时区的日历划分工作日，阈值无效时返回错误
+    pub fn generate_efficiency_analysis_tz(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        thresholds: EfficiencyThresholds,
+        working_days: WorkingDays,
+        tz: FixedOffset,
+    ) -> Result<String, ReportError> {
+        thresholds
+            .validate()
+            .map_err(ReportError::InvalidThresholds)?;
+
+        let mut analysis = String::new();
+
+        analysis.push_str("=== 效率分析报告 ===\n");
+        analysis.push_str(&format!(
+            "分析期间: {} 至 {}\n\n",
+            start_date.with_timezone(&tz).format("%Y-%m-%d"),
+            end_date.with_timezone(&tz).format("%Y-%m-%d")
+        ));
+
+        // 时间分配和效率只统计工作日
+        let (project_time, non_project_time) = TimeCalculator::calculate_working_day_time_tz(
+            time_records,
+            start_date,
+            end_date,
+            working_days,
+            tz,
+        );
+        let total_time = project_time + non_project_time;
+
+        if working_days.is_all() {
+            analysis.push_str("时间分配:\n");
+        } else {
+            analysis.push_str("时间分配（仅工作日）:\n");
+        }
+        analysis.push_str(&format!(
+            "  项目内时间: {} ({:.1}%)\n",
+            TimeCalculator::format_duration(project_time),
+            if total_time > 0 {
+                (project_time as f64 / total_time as f64) * 100.0
+            } else {
+                0.0
+            }
+        ));
+        analysis.push_str(&format!(
+            "  项目外时间: {} ({:.1}%)\n",
+            TimeCalculator::format_duration(non_project_time),
+            if total_time > 0 {
+                (non_project_time as f64 / total_time as f64) * 100.0
+            } else {
+                0.0
+            }
+        ));
+
+        // 项目效率分析
+        let project_breakdown = TimeCalculator::generate_project_breakdown(
+            time_records,
+            project_names,
+            start_date,
+            end_date,
+        );
+
+        if !project_breakdown.is_empty() {
+            analysis.push_str("\n项目效率分析:\n");
+            for breakdown in project_breakdown {
+                let avg_event_duration = if breakdown.event_count > 0 {
+                    breakdown.total_time_minutes / breakdown.event_count as i64
+                } else {
+                    0
+                };
+                analysis.push_str(&format!(
+                    "  - {}: 总时间={}, 平均事件时长={}\n",
+                    breakdown.project_name,
+                    TimeCalculator::format_duration(breakdown.total_time_minutes),
+                    TimeCalculator::format_duration(avg_event_duration)
+                ));
+            }
+        }
+
+        // 建议
+        analysis.push_str("\n改进建议:\n");
+        let efficiency = if total_time > 0 {
+            (project_time as f64 / total_time as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        if efficiency < thresholds.low {
+            analysis.push_str("  - 建议减少项目外活动，增加项目内工作时间\n");
+        } else if efficiency > thresholds.high {
+            analysis.push_str("  - 工作效率很高，注意保持工作生活平衡\n");
+        } else {
+            analysis.push_str("  - 工作效率良好，继续保持\n");
+        }
+
+        if non_project_time > project_time {
+            analysis.push_str("  - 项目外时间过多，建议优化时间分配\n");
+        }
+
+        Ok(analysis)
+    }
+}
+
+/// 按RFC 5545转义文本值中的反斜杠、分号、逗号和换行
+fn ics_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 按RFC 5545把超过75字节的内容行折行，续行以空格开头，不拆开多字节字符
+fn fold_ics_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+
+    let mut folded = String::with_capacity(line.len());
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded
+}
+
+/// 转义HTML文本中的特殊字符
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 把数值画成由 ▁▂▃▄▅▆▇█ 组成的迷你折线，按最大值缩放，每个数值一个字符
+pub fn sparkline(values: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|value| {
+            let level = (value.max(&0) * (BARS.len() as i64 - 1) + max / 2) / max;
+            BARS[level as usize]
+        })
+        .collect()
+}
+
+/// 变化方向的箭头
+fn change_arrow(direction: Ordering) -> &'static str {
+    match direction {
+        Ordering::Greater => "↑",
+        Ordering::Less => "↓",
+        Ordering::Equal => "→",
+    }
+}
+
+/// 格式化两个时间段的时长变化，如 "1小时 → 2小时 ↑ +1小时 (+100.0%)"
+fn format_minutes_change(before: i64, after: i64) -> String {
+    let delta = after - before;
+    let sign = if delta < 0 { "-" } else { "+" };
+    let percentage = if before > 0 {
+        format!("{:+.1}%", delta as f64 / before as f64 * 100.0)
+    } else {
+        "无基准".to_string()
+    };
+    format!(
+        "{} → {} {} {}{} ({})",
+        TimeCalculator::format_duration(before),
+        TimeCalculator::format_duration(after),
+        change_arrow(after.cmp(&before)),
+        sign,
+        TimeCalculator::format_duration(delta.abs()),
+        percentage
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn create_test_time_record(
+        project_id: Option<Uuid>,
+        start_time: DateTime<Utc>,
+        duration_minutes: i64,
+    ) -> TimeRecord {
+        let end_time = start_time + Duration::minutes(duration_minutes);
+        TimeRecord::new(Uuid::new_v4(), project_id, start_time, end_time)
+    }
+
+    #[test]
+    fn test_generate_weekly_report() {
+        let project_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let record1 = create_test_time_record(Some(project_id), base_time, 120);
+        let record2 = create_test_time_record(Some(project_id), base_time + Duration::hours(3), 90);
+        let record3 = create_test_time_record(None, base_time + Duration::hours(6), 60);
+        let records = vec![&record1, &record2, &record3];
+
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "测试项目".to_string());
+
+        let report = ReportGenerator::generate_weekly_report(
+            &records,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
+
+        assert_eq!(report.total_project_time_minutes, 210); // 120 + 90
         assert_eq!(report.total_non_project_time_minutes, 60);
         assert_eq!(report.project_breakdown.len(), 1);
         assert_eq!(report.project_breakdown[0].project_name, "测试项目");
@@ -414,6 +1480,40 @@ mod tests {
         assert_eq!(report.project_breakdown[0].event_count, 2);
     }
 
+    #[test]
+    fn test_weekly_report_skips_short_records() {
+        let project_id = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let misclick = create_test_time_record(Some(project_id), base_time, 1);
+        let work = create_test_time_record(Some(project_id), base_time + Duration::hours(1), 60);
+        let records = vec![&misclick, &work];
+
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "测试项目".to_string());
+
+        let report = ReportGenerator::generate_weekly_report(
+            &records,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            5,
+        );
+        assert_eq!(report.total_project_time_minutes, 60);
+        assert_eq!(report.project_breakdown[0].total_time_minutes, 60);
+        assert_eq!(report.project_breakdown[0].event_count, 1);
+
+        let report = ReportGenerator::generate_weekly_report(
+            &records,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
+        assert_eq!(report.total_project_time_minutes, 61);
+        assert_eq!(report.project_breakdown[0].event_count, 2);
+    }
+
     #[test]
     fn test_generate_report_summary() {
         let project_id = Uuid::new_v4();
@@ -425,7 +1525,13 @@ mod tests {
         let mut project_names = HashMap::new();
         project_names.insert(project_id, "测试项目".to_string());
 
-        let report = ReportGenerator::generate_weekly_report(&records, &project_names, base_time);
+        let report = ReportGenerator::generate_weekly_report(
+            &records,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
         let summary = ReportGenerator::generate_report_summary(&report);
 
         assert!(summary.contains("每周报表"));
@@ -446,7 +1552,13 @@ mod tests {
         let mut project_names = HashMap::new();
         project_names.insert(project_id, "测试项目".to_string());
 
-        let report = ReportGenerator::generate_weekly_report(&records, &project_names, base_time);
+        let report = ReportGenerator::generate_weekly_report(
+            &records,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
 
         // 导出为JSON
         let json_str = ReportGenerator::export_report_to_json(&report).unwrap();
@@ -468,4 +1580,779 @@ mod tests {
             imported_report.project_breakdown.len()
         );
     }
+
+    #[test]
+    fn test_import_report_validates_consistency() {
+        let project_id = Uuid::new_v4();
+        let base_time = Utc::now();
+        let record = create_test_time_record(Some(project_id), base_time, 120);
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "测试项目".to_string());
+        let report = ReportGenerator::generate_weekly_report(
+            &[&record],
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(ReportGenerator::import_report_from_json(&json.to_string()).is_ok());
+
+        // 手动修改后总时间与分解不一致
+        let mut edited = json.clone();
+        edited["total_project_time_minutes"] = 100.into();
+        let err = ReportGenerator::import_report_from_json(&edited.to_string()).unwrap_err();
+        assert!(matches!(
+            err,
+            ReportImportError::TotalMismatch {
+                total: 100,
+                breakdown_sum: 120
+            }
+        ));
+
+        let mut edited = json.clone();
+        edited["week_start"] = json["week_end"].clone();
+        edited["week_end"] = json["week_start"].clone();
+        let err = ReportGenerator::import_report_from_json(&edited.to_string()).unwrap_err();
+        assert!(matches!(err, ReportImportError::InvalidRange));
+
+        let err = ReportGenerator::import_report_from_json("{").unwrap_err();
+        assert!(matches!(err, ReportImportError::Json(_)));
+    }
+
+    #[test]
+    fn test_efficiency_thresholds() {
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let end = start + Duration::days(1);
+        let project_id = Uuid::new_v4();
+        let names = HashMap::from([(project_id, "项目".to_string())]);
+        let analyze = |project_minutes: i64, thresholds: EfficiencyThresholds| {
+            let project = create_test_time_record(Some(project_id), start, project_minutes);
+            let other = create_test_time_record(None, start, 100 - project_minutes);
+            ReportGenerator::generate_efficiency_analysis(
+                &[&project, &other],
+                &names,
+                start,
+                end,
+                thresholds,
+                WorkingDays::all(),
+            )
+            .unwrap()
+        };
+        const LOW: &str = "建议减少项目外活动";
+        const GOOD: &str = "工作效率良好";
+        const HIGH: &str = "注意保持工作生活平衡";
+
+        let defaults = EfficiencyThresholds::default();
+        assert!(analyze(49, defaults).contains(LOW));
+        // 恰好等于阈值时属于中间档
+        assert!(analyze(50, defaults).contains(GOOD));
+        assert!(analyze(90, defaults).contains(GOOD));
+        assert!(analyze(91, defaults).contains(HIGH));
+
+        let strict = EfficiencyThresholds::new(70.0, 80.0).unwrap();
+        assert!(analyze(60, strict).contains(LOW));
+        assert!(analyze(70, strict).contains(GOOD));
+        assert!(analyze(85, strict).contains(HIGH));
+
+        // 直接构造的无效阈值也会被拒绝
+        let inverted = EfficiencyThresholds {
+            low: 90.0,
+            high: 50.0,
+        };
+        let record = create_test_time_record(Some(project_id), start, 60);
+        let result = ReportGenerator::generate_efficiency_analysis(
+            &[&record],
+            &names,
+            start,
+            end,
+            inverted,
+            WorkingDays::all(),
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            ReportError::InvalidThresholds("效率下限必须小于上限".to_string())
+        );
+
+        assert!(EfficiencyThresholds::new(0.0, 100.0).is_ok());
+        assert!(EfficiencyThresholds::new(90.0, 50.0).is_err());
+        assert!(EfficiencyThresholds::new(50.0, 50.0).is_err());
+        assert!(EfficiencyThresholds::new(-1.0, 50.0).is_err());
+        assert!(EfficiencyThresholds::new(50.0, 100.5).is_err());
+        assert!(EfficiencyThresholds::new(f64::NAN, 50.0).is_err());
+    }
+
+    #[test]
+    fn test_export_events_to_ics() {
+        let start = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap()
+            .and_utc();
+        let project_id = Uuid::new_v4();
+        let project_names = HashMap::from([(project_id, "网站".to_string())]);
+
+        let mut review = Event::new(
+            "评审; 第一轮, 修改".to_string(),
+            Some("检查\\首页\n和登录页".to_string()),
+            EventType::ProjectRelated(project_id),
+            start,
+        );
+        review.set_end_time(start + Duration::minutes(90));
+        let mut lunch = Event::new("午饭".to_string(), None, EventType::NonProject, start);
+        lunch.set_end_time(start + Duration::hours(4));
+        let running = Event::new(
+            "进行中".to_string(),
+            None,
+            EventType::ProjectRelated(project_id),
+            start,
+        );
+        let mut long_title = Event::new("长".repeat(40), None, EventType::NonProject, start);
+        long_title.set_end_time(start + Duration::hours(5));
+
+        let ics = ReportGenerator::export_events_to_ics(
+            &[&review, &lunch, &running, &long_title],
+            &project_names,
+        );
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 3);
+        assert_eq!(ics.matches("END:VEVENT").count(), 3);
+        assert!(!ics.contains("进行中"));
+
+        assert!(ics.contains("\r\nDTSTART:20240506T093000Z\r\nDTEND:20240506T110000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20240506T133000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:[网站] 评审\\; 第一轮\\, 修改\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:检查\\\\首页\\n和登录页\r\n"));
+        assert!(ics.contains(&format!("UID:{}@project_manager", review.id)));
+
+        // 超过75字节的行折行，每个物理行都不超过75字节
+        assert!(ics.contains("\r\n 长"));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_save_report_in_every_format() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let project_id = Uuid::new_v4();
+        let base_time = NaiveDate::from_ymd_opt(2024, 3, 6)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc();
+        let record = create_test_time_record(Some(project_id), base_time, 90);
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "前端 <重构>, 第二期".to_string());
+        let report = ReportGenerator::generate_weekly_report(
+            &[&record],
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            0,
+        );
+
+        for format in [
+            ReportFormat::Json,
+            ReportFormat::Markdown,
+            ReportFormat::Html,
+            ReportFormat::Csv,
+        ] {
+            let path = ReportGenerator::save_report(
+                &report,
+                &temp_dir.path().join("reports").join("weekly"),
+                format,
+            )
+            .unwrap();
+            assert_eq!(path.extension().unwrap().to_str(), Some(format.extension()));
+            let contents = fs::read_to_string(&path).unwrap();
+            assert!(!contents.is_empty());
+            match format {
+                ReportFormat::Json => {
+                    let imported = ReportGenerator::import_report_from_json(&contents).unwrap();
+                    assert_eq!(imported.id, report.id);
+                }
+                ReportFormat::Markdown => {
+                    assert!(contents.contains("| 前端 <重构>, 第二期 | 1小时30分钟 | 1 |"))
+                }
+                ReportFormat::Html => assert!(contents.contains("前端 &lt;重构&gt;, 第二期")),
+                ReportFormat::Csv => assert!(contents.contains("\"前端 <重构>, 第二期\",90,1")),
+            }
+        }
+
+        // 已有扩展名时按原路径写入
+        let path = temp_dir.path().join("周报.txt");
+        let saved = ReportGenerator::save_report(&report, &path, ReportFormat::Markdown).unwrap();
+        assert_eq!(saved, path);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_quarterly_and_yearly_summaries() {
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let mut project_names = HashMap::new();
+        project_names.insert(project_a, "项目A".to_string());
+        project_names.insert(project_b, "项目B".to_string());
+
+        let at = |month: u32, day: u32| {
+            NaiveDate::from_ymd_opt(2024, month, day)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        // 第一季度分布在1、2、3月，另有第二季度和第四季度的记录
+        let records = [
+            create_test_time_record(Some(project_a), at(1, 10), 60),
+            create_test_time_record(Some(project_b), at(2, 15), 120),
+            create_test_time_record(None, at(3, 31), 60),
+            create_test_time_record(Some(project_a), at(4, 1), 30),
+            create_test_time_record(Some(project_b), at(12, 31), 90),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let q1 =
+            ReportGenerator::generate_quarterly_summary(&refs, &project_names, 2024, 1).unwrap();
+        assert!(q1.contains("=== 季度报表 ==="));
+        assert!(q1.contains("2024年第1季度"));
+        assert!(q1.contains("项目内时间: 3小时"));
+        assert!(q1.contains("项目外时间: 1小时"));
+        assert!(q1.contains("工作效率: 75.00%"));
+        assert!(q1.contains("项目B: 2小时 (1个事件)"));
+        assert!(q1.contains("项目A: 1小时 (1个事件)"));
+
+        let q4 =
+            ReportGenerator::generate_quarterly_summary(&refs, &project_names, 2024, 4).unwrap();
+        assert!(q4.contains("项目内时间: 1小时30分钟"));
+        assert!(!q4.contains("项目A"));
+
+        let year = ReportGenerator::generate_yearly_summary(&refs, &project_names, 2024);
+        assert!(year.contains("=== 年度报表 ==="));
+        assert!(year.contains("项目内时间: 5小时"));
+        assert!(year.contains("项目A: 1小时30分钟 (2个事件)"));
+        assert!(year.contains("项目B: 3小时30分钟 (2个事件)"));
+
+        assert_eq!(
+            ReportGenerator::generate_quarterly_summary(&refs, &project_names, 2024, 0),
+            Err(ReportError::InvalidQuarter(0))
+        );
+        assert_eq!(
+            ReportGenerator::generate_quarterly_summary(&refs, &project_names, 2024, 5),
+            Err(ReportError::InvalidQuarter(5))
+        );
+    }
+
+    #[test]
+    fn test_detailed_report_includes_notes() {
+        let base_time = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let project_id = Uuid::new_v4();
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "网站".to_string());
+
+        let mut noted = create_test_time_record(Some(project_id), base_time, 60);
+        noted.note = Some("完成首页改版".to_string());
+        let records = [noted, create_test_time_record(None, base_time, 30)];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let report = ReportGenerator::generate_detailed_weekly_report(
+            &refs,
+            &project_names,
+            base_time,
+            WeekStart::Monday,
+            utc_offset(),
+            WorkingDays::all(),
+        );
+        assert!(report.contains("工作笔记:\n  05-06 09:00 [网站] 完成首页改版\n"));
+    }
+
+    #[test]
+    fn test_detailed_report_groups_by_local_day() {
+        // UTC 5月6日（周一）20:00，即 UTC+8 的 5月7日 04:00
+        let evening = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(20, 0, 0)
+            .unwrap()
+            .and_utc();
+        let project_id = Uuid::new_v4();
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "网站".to_string());
+        let records = [create_test_time_record(Some(project_id), evening, 60)];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let utc_report = ReportGenerator::generate_detailed_weekly_report(
+            &refs,
+            &project_names,
+            evening,
+            WeekStart::Monday,
+            utc_offset(),
+            WorkingDays::all(),
+        );
+        assert!(utc_report.contains("2024-05-06 (Mon): 项目内=1小时,"));
+        assert!(utc_report.contains("2024-05-07 (Tue): 项目内=0分钟"));
+
+        let utc8 = FixedOffset::east_opt(8 * 3600).unwrap();
+        let local_report = ReportGenerator::generate_detailed_weekly_report(
+            &refs,
+            &project_names,
+            evening,
+            WeekStart::Monday,
+            utc8,
+            WorkingDays::all(),
+        );
+        assert!(local_report.contains("2024-05-06 (Mon): 项目内=0分钟"));
+        assert!(local_report.contains("2024-05-07 (Tue): 项目内=1小时,"));
+    }
+
+    #[test]
+    fn test_efficiency_excludes_non_working_days() {
+        // 2024-05-06 是周一，周六只有项目外活动
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let project_id = Uuid::new_v4();
+        let project_names = HashMap::from([(project_id, "网站".to_string())]);
+        let records = [
+            create_test_time_record(Some(project_id), monday, 300),
+            create_test_time_record(None, monday + Duration::days(1), 60),
+            create_test_time_record(None, monday + Duration::days(5), 240),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let detailed = |working_days| {
+            ReportGenerator::generate_detailed_weekly_report(
+                &refs,
+                &project_names,
+                monday,
+                WeekStart::Monday,
+                utc_offset(),
+                working_days,
+            )
+        };
+        let all_days = detailed(WorkingDays::all());
+        assert!(all_days.contains("工作效率: 50.00%\n"));
+        assert!(all_days.contains("2024-05-11 (Sat)"));
+
+        let weekdays = detailed(WorkingDays::monday_to_friday());
+        assert!(weekdays.contains("工作效率: 83.33%（仅工作日）"));
+        assert!(!weekdays.contains("2024-05-11 (Sat)"));
+        assert!(weekdays.contains("2024-05-10 (Fri)"));
+        // 总时间仍然包含周末
+        assert!(weekdays.contains("项目外总时间: 5小时"));
+
+        let analysis = |working_days| {
+            ReportGenerator::generate_efficiency_analysis(
+                &refs,
+                &project_names,
+                monday - Duration::hours(9),
+                monday + Duration::days(7),
+                EfficiencyThresholds::default(),
+                working_days,
+            )
+            .unwrap()
+        };
+        let all_days = analysis(WorkingDays::all());
+        assert!(all_days.contains("项目内时间: 5小时 (50.0%)"));
+        assert!(all_days.contains("工作效率良好"));
+        let weekdays = analysis(WorkingDays::monday_to_friday());
+        assert!(weekdays.contains("时间分配（仅工作日）"));
+        assert!(weekdays.contains("项目内时间: 5小时 (83.3%)"));
+        assert!(weekdays.contains("工作效率良好"));
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        // 2024年5月6日是周一
+        let base_time = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let mut under = Project::new("未达标".to_string(), None);
+        under.weekly_goal_minutes = Some(120);
+        let mut exact = Project::new("刚好达标".to_string(), None);
+        exact.weekly_goal_minutes = Some(90);
+        let mut over = Project::new("超额".to_string(), None);
+        over.weekly_goal_minutes = Some(60);
+        let no_goal = Project::new("没有目标".to_string(), None);
+
+        let records = [
+            create_test_time_record(Some(under.id), base_time, 30),
+            create_test_time_record(Some(exact.id), base_time, 90),
+            create_test_time_record(Some(over.id), base_time, 90),
+            create_test_time_record(Some(no_goal.id), base_time, 45),
+            // 上周的记录不计入
+            create_test_time_record(Some(under.id), base_time - Duration::days(7), 60),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let progress = ReportGenerator::generate_goal_progress(
+            &refs,
+            &[&under, &exact, &over, &no_goal],
+            base_time + Duration::days(2),
+            WeekStart::Monday,
+        );
+        assert_eq!(progress.len(), 3);
+        assert!(progress.iter().all(|goal| goal.project_id != no_goal.id));
+
+        assert_eq!(progress[0].actual_minutes, 30);
+        assert_eq!(progress[0].goal_minutes, 120);
+        assert!((progress[0].percentage - 25.0).abs() < 1e-9);
+        assert!((progress[1].percentage - 100.0).abs() < 1e-9);
+        assert!((progress[2].percentage - 150.0).abs() < 1e-9);
+
+        let text = ReportGenerator::format_goal_progress(&progress);
+        assert!(text.contains("未达标: 30分钟 / 2小时 [###-------] 25.0%\n"));
+        assert!(text.contains("刚好达标: 1小时30分钟 / 1小时30分钟 [##########] 100.0%（已达成）"));
+        assert!(text.contains("超额: 1小时30分钟 / 1小时 [##########] 150.0%（已达成）"));
+        assert!(!text.contains("没有目标"));
+    }
+
+    #[test]
+    fn test_burndown() {
+        // 2024年5月6日是周一
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let mut project = Project::new("冲刺".to_string(), None);
+        project.weekly_goal_minutes = Some(300);
+
+        // 周三达成目标，之后剩余时间保持为0
+        let records = [
+            create_test_time_record(Some(project.id), monday, 120),
+            create_test_time_record(Some(project.id), monday + Duration::days(1), 120),
+            create_test_time_record(Some(project.id), monday + Duration::days(2), 120),
+            create_test_time_record(Some(project.id), monday + Duration::days(4), 60),
+            create_test_time_record(None, monday + Duration::days(3), 60),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+        let burndown = ReportGenerator::burndown(
+            &project,
+            &refs,
+            monday + Duration::days(3),
+            WeekStart::Monday,
+        );
+        assert_eq!(burndown.len(), 7);
+        assert_eq!(burndown[0].0, monday.date_naive());
+        assert_eq!(burndown[6].0, NaiveDate::from_ymd_opt(2024, 5, 12).unwrap());
+        let remaining: Vec<i64> = burndown.iter().map(|(_, minutes)| *minutes).collect();
+        assert_eq!(remaining, vec![180, 60, 0, 0, 0, 0, 0]);
+
+        // 整周都没有达到目标
+        let refs: Vec<&TimeRecord> = records[..1].iter().collect();
+        let remaining: Vec<i64> =
+            ReportGenerator::burndown(&project, &refs, monday, WeekStart::Monday)
+                .into_iter()
+                .map(|(_, minutes)| minutes)
+                .collect();
+        assert_eq!(remaining, vec![180; 7]);
+
+        // 没有设置目标的项目没有燃尽数据
+        let no_goal = Project::new("没有目标".to_string(), None);
+        assert!(ReportGenerator::burndown(&no_goal, &refs, monday, WeekStart::Monday).is_empty());
+    }
+
+    #[test]
+    fn test_generate_billing_report() {
+        let base_time = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let mut client = Project::new("客户项目".to_string(), None);
+        client.hourly_rate = Some(100.0);
+        let mut small = Project::new("小项目".to_string(), None);
+        small.hourly_rate = Some(10.0);
+        let internal = Project::new("内部项目".to_string(), None);
+
+        let records = [
+            create_test_time_record(Some(client.id), base_time, 90),
+            create_test_time_record(Some(small.id), base_time, 20),
+            create_test_time_record(Some(internal.id), base_time, 45),
+            create_test_time_record(None, base_time, 30),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let report = ReportGenerator::generate_billing_report(
+            &refs,
+            &[&client, &small, &internal],
+            base_time - Duration::hours(1),
+            base_time + Duration::hours(8),
+            RoundingMode::None,
+            "",
+        );
+
+        // 90分钟 × 100/小时 = 150.00；20分钟 × 10/小时 = 3.333... 四舍五入为 3.33
+        assert!(report.contains("客户项目: 1小时30分钟 × 100.00/小时 = 150.00"));
+        assert!(report.contains("小项目: 20分钟 × 10.00/小时 = 3.33"));
+        assert!(report.contains("合计: 153.33"));
+        assert!(report.contains("未计费:\n  - 内部项目: 45分钟"));
+        assert!(!report.contains("时间取整"));
+
+        // 20分钟向上取整为30分钟，45分钟保持不变
+        let rounded = ReportGenerator::generate_billing_report(
+            &refs,
+            &[&client, &small, &internal],
+            base_time - Duration::hours(1),
+            base_time + Duration::hours(8),
+            RoundingMode::Up15,
+            "",
+        );
+        assert!(rounded.contains("时间取整: 向上取整到15分钟"));
+        assert!(rounded.contains("小项目: 30分钟 × 10.00/小时 = 5.00"));
+        assert!(rounded.contains("合计: 155.00"));
+
+        // 金额带货币符号和千位分隔符
+        let long_records = [create_test_time_record(Some(client.id), base_time, 900)];
+        let refs: Vec<&TimeRecord> = long_records.iter().collect();
+        let report = ReportGenerator::generate_billing_report(
+            &refs,
+            &[&client],
+            base_time - Duration::hours(1),
+            base_time + Duration::hours(16),
+            RoundingMode::None,
+            "¥",
+        );
+        assert!(report.contains("客户项目: 15小时 × ¥100.00/小时 = ¥1,500.00"));
+        assert!(report.contains("合计: ¥1,500.00"));
+    }
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(ReportGenerator::format_money(0.0, "¥"), "¥0.00");
+        assert_eq!(ReportGenerator::format_money(999.999, "$"), "$1,000.00");
+        assert_eq!(ReportGenerator::format_money(999.99, "$"), "$999.99");
+        assert_eq!(ReportGenerator::format_money(1500.0, "¥"), "¥1,500.00");
+        assert_eq!(
+            ReportGenerator::format_money(1234567.891, ""),
+            "1,234,567.89"
+        );
+        assert_eq!(ReportGenerator::format_money(100000.0, "$"), "$100,000.00");
+        assert_eq!(ReportGenerator::format_money(-2500.5, "¥"), "-¥2,500.50");
+        assert_eq!(ReportGenerator::format_money(-0.001, "¥"), "¥0.00");
+    }
+
+    #[test]
+    fn test_generate_velocity_report() {
+        let project_id = Uuid::new_v4();
+        // 2024年1月8日是周一
+        let week1 = chrono::NaiveDate::from_ymd_opt(2024, 1, 8)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let week2 = week1 + Duration::days(7);
+
+        let mut done1 = Event::new(
+            "任务1".to_string(),
+            None,
+            EventType::ProjectRelated(project_id),
+            week1,
+        );
+        done1.set_end_time(week1 + Duration::hours(1));
+        done1.set_effort_points(Some(3));
+
+        let mut done2 = Event::new("任务2".to_string(), None, EventType::NonProject, week2);
+        done2.set_end_time(week2 + Duration::hours(1));
+        done2.set_effort_points(Some(5));
+
+        // 未完成事件的点数不计入
+        let mut open = Event::new(
+            "任务3".to_string(),
+            None,
+            EventType::ProjectRelated(project_id),
+            week2,
+        );
+        open.set_effort_points(Some(8));
+
+        let events = vec![&done1, &done2, &open];
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "测试项目".to_string());
+
+        let report = ReportGenerator::generate_velocity_report(
+            &events,
+            &project_names,
+            week1,
+            week2 + Duration::days(6),
+            WeekStart::Monday,
+        );
+
+        assert!(report.contains("2024-01-08 当周: 3点"));
+        assert!(report.contains("2024-01-15 当周: 5点"));
+        assert!(report.contains("总完成点数: 8点"));
+        assert!(report.contains("  - 测试项目: 3点"));
+        assert!(report.contains("  - 项目外: 5点"));
+        assert!(report.contains("速度趋势: 上升"));
+    }
+
+    #[test]
+    fn test_all_time_stats() {
+        let project = Project::new("测试项目".to_string(), None);
+        let base_time = Utc::now() - Duration::days(30);
+
+        let mut long_event = Event::new(
+            "长会议".to_string(),
+            None,
+            EventType::ProjectRelated(project.id),
+            base_time,
+        );
+        long_event.set_end_time(base_time + Duration::minutes(180));
+        let mut short_event = Event::new(
+            "喝咖啡".to_string(),
+            None,
+            EventType::NonProject,
+            base_time + Duration::days(10),
+        );
+        short_event.set_end_time(base_time + Duration::days(10) + Duration::minutes(20));
+
+        let record1 = TimeRecord::new(
+            long_event.id,
+            Some(project.id),
+            long_event.start_time,
+            long_event.end_time.unwrap(),
+        );
+        let record2 = TimeRecord::new(
+            short_event.id,
+            None,
+            short_event.start_time,
+            short_event.end_time.unwrap(),
+        );
+
+        let stats = ReportGenerator::calculate_all_time_stats(
+            &[&record1, &record2],
+            &[&long_event, &short_event],
+            &[&project],
+        );
+        assert_eq!(stats.total_time_minutes, 200);
+        assert_eq!(stats.event_count, 2);
+        assert_eq!(stats.project_count, 1);
+        assert_eq!(
+            stats.most_tracked_project,
+            Some(("测试项目".to_string(), 180))
+        );
+        assert_eq!(stats.longest_session, Some(("长会议".to_string(), 180)));
+        assert_eq!(stats.first_activity, Some(base_time));
+        assert_eq!(stats.last_activity, short_event.end_time);
+    }
+
+    #[test]
+    fn test_all_time_stats_empty() {
+        let summary = ReportGenerator::generate_all_time_stats(&[], &[], &[]);
+        assert!(summary.contains("还没有任何数据"));
+    }
+
+    #[test]
+    fn test_generate_comparison() {
+        // 2024年5月6日和13日都是周一
+        let week_a = NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let week_b = week_a + Duration::days(7);
+        let period =
+            |start: DateTime<Utc>| (start, start + Duration::days(7) - Duration::seconds(1));
+
+        let grown = Uuid::new_v4();
+        let appeared = Uuid::new_v4();
+        let disappeared = Uuid::new_v4();
+        let mut project_names = HashMap::new();
+        project_names.insert(grown, "增长项目".to_string());
+        project_names.insert(appeared, "新项目".to_string());
+        project_names.insert(disappeared, "旧项目".to_string());
+
+        let records = [
+            create_test_time_record(Some(grown), week_a, 60),
+            create_test_time_record(Some(disappeared), week_a + Duration::hours(2), 60),
+            create_test_time_record(None, week_a + Duration::hours(4), 120),
+            create_test_time_record(Some(grown), week_b, 90),
+            create_test_time_record(Some(appeared), week_b + Duration::hours(2), 30),
+            create_test_time_record(None, week_b + Duration::hours(4), 60),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let report = ReportGenerator::generate_comparison(
+            &refs,
+            &project_names,
+            period(week_a),
+            period(week_b),
+        );
+
+        assert!(report.contains("时段A: 2024-05-06 至 2024-05-13"));
+        assert!(report.contains("时段B: 2024-05-13 至 2024-05-20"));
+        assert!(report.contains("项目内时间: 2小时 → 2小时 → +0分钟 (+0.0%)"));
+        assert!(report.contains("项目外时间: 2小时 → 1小时 ↓ -1小时 (-50.0%)"));
+        assert!(report.contains("工作效率: 50.00% → 66.67% ↑ +16.67个百分点"));
+        assert!(report.contains("增长项目: 1小时 → 1小时30分钟 ↑ +30分钟 (+50.0%)\n"));
+        assert!(report.contains("新项目: 0分钟 → 30分钟 ↑ +30分钟 (无基准)（新增）"));
+        assert!(report.contains("旧项目: 1小时 → 0分钟 ↓ -1小时 (-100.0%)（已停止）"));
+    }
+
+    #[test]
+    fn test_generate_trend() {
+        // 2024年5月6日是周一
+        let end_week = NaiveDate::from_ymd_opt(2024, 5, 8)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+        let this_monday = end_week - Duration::days(2) - Duration::hours(3);
+        let project_id = Uuid::new_v4();
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "网站".to_string());
+
+        let records = [
+            create_test_time_record(Some(project_id), this_monday, 240),
+            create_test_time_record(Some(project_id), this_monday - Duration::weeks(1), 120),
+            create_test_time_record(None, this_monday - Duration::weeks(1), 30),
+            // 超出统计范围
+            create_test_time_record(Some(project_id), this_monday - Duration::weeks(6), 600),
+            create_test_time_record(Some(project_id), this_monday + Duration::weeks(1), 600),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let trend =
+            ReportGenerator::generate_trend(&refs, &project_names, end_week, 4, WeekStart::Monday);
+        assert_eq!(trend.weeks.len(), 4);
+        assert_eq!(trend.sparkline.chars().count(), 4);
+        assert_eq!(trend.sparkline, "▁▁▅█");
+        assert_eq!(
+            trend.weeks[0].week_start.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 4, 15).unwrap()
+        );
+        assert_eq!(
+            trend.weeks[3].week_start.date_naive(),
+            this_monday.date_naive()
+        );
+        let minutes: Vec<i64> = trend.weeks.iter().map(|w| w.project_minutes).collect();
+        assert_eq!(minutes, vec![0, 0, 120, 240]);
+        assert_eq!(trend.weeks[2].non_project_minutes, 30);
+        assert_eq!(trend.weeks[3].top_project.as_deref(), Some("网站"));
+        assert_eq!(trend.weeks[0].top_project, None);
+
+        let summary = ReportGenerator::format_trend(&trend);
+        assert!(summary.starts_with("近4周趋势: ▁▁▅█\n"));
+        assert_eq!(summary.lines().count(), 5);
+
+        let empty =
+            ReportGenerator::generate_trend(&[], &project_names, end_week, 0, WeekStart::Monday);
+        assert!(empty.weeks.is_empty());
+        assert!(empty.sparkline.is_empty());
+        assert_eq!(sparkline(&[0, 0, 0]), "▁▁▁");
+    }
 }
@@ -0,0 +1,458 @@
+use crate::event_manager::EventManager;
+use crate::models::{Event, Project, TimeRecord};
+use crate::project_manager::ProjectManager;
+use crate::storage::{AppData, DataStore};
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use uuid::Uuid;
+
+/// 以UUID为主键的数据表，每行保存一条记录的JSON。
+/// 回收站中的事件和它们的时间记录单独存放，不与正常数据混在一起
+const TABLES: [&str; 8] = [
+    "projects",
+    "events",
+    "time_records",
+    "weekly_reports",
+    "templates",
+    "deleted_events",
+    "deleted_time_records",
+    "pomodoros",
+];
+
+/// SQLite存储后端，可替代JSON文件存储
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// 打开（或创建）数据库文件
+    pub fn open(path: &str) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(io::Error::other)?;
+        Self::with_connection(conn)
+    }
+
+    /// 使用内存数据库，主要用于测试
+    pub fn open_in_memory() -> io::Result<Self> {
+        let conn = Connection::open_in_memory().map_err(io::Error::other)?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> io::Result<Self> {
+        for table in TABLES {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+                    table
+                ),
+                [],
+            )
+            .map_err(io::Error::other)?;
+        }
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (id INTEGER PRIMARY KEY CHECK (id = 1), data TEXT NOT NULL)",
+            [],
+        )
+        .map_err(io::Error::other)?;
+
+        Ok(Self { conn })
+    }
+
+    /// 保存应用数据
+    pub fn save_data(
+        &self,
+        project_manager: &ProjectManager,
+        event_manager: &EventManager,
+    ) -> io::Result<()> {
+        self.save_app_data(&AppData::from_managers(project_manager, event_manager))
+    }
+
+    /// 在一个事务中整体重写所有数据
+    pub fn save_app_data(&self, app_data: &AppData) -> io::Result<()> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(io::Error::other)?;
+
+        for table in TABLES {
+            tx.execute(&format!("DELETE FROM {}", table), [])
+                .map_err(io::Error::other)?;
+        }
+        for project in &app_data.projects {
+            upsert(&tx, "projects", project.id, project)?;
+        }
+        for event in &app_data.events {
+            upsert(&tx, event_table(event), event.id, event)?;
+        }
+        let trashed = app_data.trashed_event_ids();
+        for record in &app_data.time_records {
+            let table = if trashed.contains(&record.event_id) {
+                "deleted_time_records"
+            } else {
+                "time_records"
+            };
+            upsert(&tx, table, record.id, record)?;
+        }
+        for report in &app_data.weekly_reports {
+            upsert(&tx, "weekly_reports", report.id, report)?;
+        }
+        for template in &app_data.templates {
+            upsert(&tx, "templates", template.id, template)?;
+        }
+        for session in &app_data.pomodoros {
+            upsert(&tx, "pomodoros", session.event_id, session)?;
+        }
+
+        let settings = serde_json::to_string(&app_data.settings).map_err(io::Error::other)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (id, data) VALUES (1, ?1)",
+            params![settings],
+        )
+        .map_err(io::Error::other)?;
+
+        tx.commit().map_err(io::Error::other)
+    }
+
+    /// 加载应用数据，空数据库返回空数据
+    pub fn load_data(&self) -> io::Result<AppData> {
+        let mut app_data = AppData::new();
+        app_data.projects = self.load_table("projects")?;
+        app_data.events = self.load_table("events")?;
+        app_data
+            .events
+            .extend(self.load_table::<Event>("deleted_events")?);
+        app_data.time_records = self.load_table("time_records")?;
+        app_data
+            .time_records
+            .extend(self.load_table::<TimeRecord>("deleted_time_records")?);
+        app_data.weekly_reports = self.load_table("weekly_reports")?;
+        app_data.templates = self.load_table("templates")?;
+        app_data.pomodoros = self.load_table("pomodoros")?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM settings WHERE id = 1")
+            .map_err(io::Error::other)?;
+        let mut rows = stmt.query([]).map_err(io::Error::other)?;
+        if let Some(row) = rows.next().map_err(io::Error::other)? {
+            let data: String = row.get(0).map_err(io::Error::other)?;
+            app_data.settings = serde_json::from_str(&data).map_err(io::Error::other)?;
+        }
+
+        Ok(app_data)
+    }
+
+    /// 新增或更新单个项目
+    pub fn upsert_project(&self, project: &Project) -> io::Result<()> {
+        upsert(&self.conn, "projects", project.id, project)
+    }
+
+    /// 新增或更新单个事件，事件移入或移出回收站时从另一张表中删除，
+    /// 并把它的时间记录一起移到对应的表，与整体保存的结果一致
+    pub fn upsert_event(&self, event: &Event) -> io::Result<()> {
+        let trashed = event.deleted_at.is_some();
+        let (table, other) = tables_for(trashed, "events", "deleted_events");
+        let (record_table, other_record_table) =
+            tables_for(trashed, "time_records", "deleted_time_records");
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(io::Error::other)?;
+        let event_id = event.id.to_string();
+        tx.execute(
+            &format!("DELETE FROM {} WHERE id = ?1", other),
+            params![event_id],
+        )
+        .map_err(io::Error::other)?;
+        tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO {} (id, data) SELECT id, data FROM {} WHERE json_extract(data, '$.event_id') = ?1",
+                record_table, other_record_table
+            ),
+            params![event_id],
+        )
+        .map_err(io::Error::other)?;
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE json_extract(data, '$.event_id') = ?1",
+                other_record_table
+            ),
+            params![event_id],
+        )
+        .map_err(io::Error::other)?;
+        upsert(&tx, table, event.id, event)?;
+        tx.commit().map_err(io::Error::other)
+    }
+
+    /// 新增或更新单条时间记录，所属事件在回收站中时写入回收站的记录表
+    pub fn upsert_time_record(&self, record: &TimeRecord) -> io::Result<()> {
+        let trashed: bool = self
+            .conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM deleted_events WHERE id = ?1)",
+                params![record.event_id.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(io::Error::other)?;
+        let (table, other) = tables_for(trashed, "time_records", "deleted_time_records");
+        self.conn
+            .execute(
+                &format!("DELETE FROM {} WHERE id = ?1", other),
+                params![record.id.to_string()],
+            )
+            .map_err(io::Error::other)?;
+        upsert(&self.conn, table, record.id, record)
+    }
+
+    fn load_table<T: DeserializeOwned>(&self, table: &str) -> io::Result<Vec<T>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT data FROM {} ORDER BY id", table))
+            .map_err(io::Error::other)?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(io::Error::other)?;
+
+        let mut items = Vec::new();
+        for data in rows {
+            let data = data.map_err(io::Error::other)?;
+            items.push(serde_json::from_str(&data).map_err(io::Error::other)?);
+        }
+        Ok(items)
+    }
+}
+
+impl DataStore for SqliteStorage {
+    fn load_data(&self) -> io::Result<AppData> {
+        SqliteStorage::load_data(self)
+    }
+
+    fn save_app_data(&self, app_data: &AppData) -> io::Result<()> {
+        SqliteStorage::save_app_data(self, app_data)
+    }
+}
+
+/// 事件所在的数据表
+fn event_table(event: &Event) -> &'static str {
+    if event.deleted_at.is_some() {
+        "deleted_events"
+    } else {
+        "events"
+    }
+}
+
+/// 按是否在回收站返回要写入的表和另一张需要清除的表
+fn tables_for(
+    trashed: bool,
+    table: &'static str,
+    deleted_table: &'static str,
+) -> (&'static str, &'static str) {
+    if trashed {
+        (deleted_table, table)
+    } else {
+        (table, deleted_table)
+    }
+}
+
+fn upsert<T: Serialize>(conn: &Connection, table: &str, id: Uuid, item: &T) -> io::Result<()> {
+    let data = serde_json::to_string(item).map_err(io::Error::other)?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {} (id, data) VALUES (?1, ?2) ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            table
+        ),
+        params![id.to_string(), data],
+    )
+    .map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WeekStart;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let project_id = project_manager
+            .add_project("测试项目".to_string(), None)
+            .unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        let event_id = event_manager
+            .add_project_event("测试事件".to_string(), None, project_id, Some(start))
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+
+        let mut app_data = AppData::from_managers(&project_manager, &event_manager);
+        app_data.settings.week_start = WeekStart::Sunday;
+        storage.save_app_data(&app_data).unwrap();
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.projects.len(), 1);
+        assert_eq!(loaded.projects[0].id, project_id);
+        assert_eq!(loaded.events.len(), 1);
+        assert_eq!(loaded.events[0].id, event_id);
+        assert_eq!(loaded.time_records.len(), 1);
+        assert_eq!(loaded.time_records[0].event_id, event_id);
+        assert_eq!(loaded.settings.week_start, WeekStart::Sunday);
+    }
+
+    #[test]
+    fn test_running_pomodoro_is_saved() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let mut event_manager = EventManager::new();
+        let start = Utc::now() - Duration::minutes(10);
+        let event_id = event_manager
+            .add_non_project_event("专注".to_string(), None, Some(start))
+            .unwrap();
+        event_manager
+            .start_pomodoro_at(event_id, 25, 5, start)
+            .unwrap();
+
+        storage
+            .save_data(&ProjectManager::new(), &event_manager)
+            .unwrap();
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.pomodoros.len(), 1);
+        assert_eq!(loaded.pomodoros[0].event_id, event_id);
+        assert_eq!(loaded.pomodoros[0].started_at, start);
+    }
+
+    #[test]
+    fn test_load_empty_database() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let loaded = storage.load_data().unwrap();
+        assert!(loaded.projects.is_empty());
+        assert!(loaded.events.is_empty());
+        assert!(loaded.time_records.is_empty());
+    }
+
+    #[test]
+    fn test_upsert_and_full_rewrite() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let mut project_manager = ProjectManager::new();
+        let first = project_manager
+            .add_project("项目A".to_string(), None)
+            .unwrap();
+        let second = project_manager
+            .add_project("项目B".to_string(), None)
+            .unwrap();
+
+        // 增量写入，同一ID再次写入时更新而不是重复插入
+        storage
+            .upsert_project(project_manager.get_project(first).unwrap())
+            .unwrap();
+        project_manager
+            .update_project(first, Some("项目A2".to_string()), None)
+            .unwrap();
+        storage
+            .upsert_project(project_manager.get_project(first).unwrap())
+            .unwrap();
+        storage
+            .upsert_project(project_manager.get_project(second).unwrap())
+            .unwrap();
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.projects.len(), 2);
+        assert!(loaded.projects.iter().any(|p| p.name == "项目A2"));
+
+        // 整体重写会删除已不存在的项目
+        project_manager.delete_project(second).unwrap();
+        storage
+            .save_data(&project_manager, &EventManager::new())
+            .unwrap();
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.projects.len(), 1);
+        assert_eq!(loaded.projects[0].id, first);
+    }
+
+    #[test]
+    fn test_trash_is_stored_separately() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let mut event_manager = EventManager::new();
+        let kept = event_manager
+            .add_non_project_event("保留的事件".to_string(), None, None)
+            .unwrap();
+        let trashed = event_manager
+            .add_non_project_event("删除的事件".to_string(), None, None)
+            .unwrap();
+        event_manager.set_event_end_time(kept, None).unwrap();
+        event_manager.set_event_end_time(trashed, None).unwrap();
+        event_manager.delete_event(trashed).unwrap();
+        storage
+            .save_data(&ProjectManager::new(), &event_manager)
+            .unwrap();
+
+        let count = |table: &str| -> i64 {
+            storage
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(count("events"), 1);
+        assert_eq!(count("time_records"), 1);
+        assert_eq!(count("deleted_events"), 1);
+        assert_eq!(count("deleted_time_records"), 1);
+
+        // 加载后回收站仍然完整
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.events.len(), 2);
+        assert_eq!(loaded.time_records.len(), 2);
+        assert_eq!(loaded.trashed_event_ids().len(), 1);
+
+        // 从回收站恢复后单独写入，事件回到正常的表
+        event_manager.restore_event(trashed).unwrap();
+        storage
+            .upsert_event(event_manager.get_event(trashed).unwrap())
+            .unwrap();
+        assert_eq!(count("events"), 2);
+        assert_eq!(count("deleted_events"), 0);
+        assert_eq!(count("time_records"), 2);
+        assert_eq!(count("deleted_time_records"), 0);
+    }
+
+    #[test]
+    fn test_upsert_time_record_follows_trash() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        let mut event_manager = EventManager::new();
+        let event_id = event_manager
+            .add_non_project_event("删除的事件".to_string(), None, None)
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+        event_manager.delete_event(event_id).unwrap();
+        storage
+            .save_data(&ProjectManager::new(), &event_manager)
+            .unwrap();
+
+        let count = |table: &str| -> i64 {
+            storage
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+
+        // 单独写入回收站事件的记录，结果与整体保存相同
+        let record = event_manager.get_deleted_time_records()[0].clone();
+        storage.upsert_time_record(&record).unwrap();
+        assert_eq!(count("time_records"), 0);
+        assert_eq!(count("deleted_time_records"), 1);
+
+        event_manager.restore_event(event_id).unwrap();
+        storage
+            .upsert_event(event_manager.get_event(event_id).unwrap())
+            .unwrap();
+        storage.upsert_time_record(&record).unwrap();
+        assert_eq!(count("time_records"), 1);
+        assert_eq!(count("deleted_time_records"), 0);
+    }
+}
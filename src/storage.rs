@@ -1,47 +1,474 @@
+use crate::crypto;
+use crate::csv::{csv_row, parse_csv};
 use crate::event_manager::EventManager;
-use crate::models::{Event, Project, TimeRecord, WeeklyReport};
-use crate::project_manager::ProjectManager;
+use crate::models::{
+    AppSettings, Event, EventTemplate, EventType, PomodoroSession, Project, TimeRecord,
+    WeeklyReport,
+};
+use crate::project_manager::{normalize_project_name, ProjectManager};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
+use uuid::Uuid;
+
+/// 导出范围
+#[derive(Debug, Clone)]
+pub enum ExportScope {
+    All,                     // 导出全部数据
+    Projects(HashSet<Uuid>), // 仅导出指定项目的数据
+}
+
+impl ExportScope {
+    /// 检查项目是否在导出范围内
+    pub fn contains_project(&self, project_id: Uuid) -> bool {
+        match self {
+            ExportScope::All => true,
+            ExportScope::Projects(ids) => ids.contains(&project_id),
+        }
+    }
+
+    /// 检查可选的项目引用是否在导出范围内（项目外数据只属于全量导出）
+    fn contains(&self, project_id: Option<Uuid>) -> bool {
+        match project_id {
+            Some(id) => self.contains_project(id),
+            None => matches!(self, ExportScope::All),
+        }
+    }
+}
+
+/// 导入数据时遇到ID冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStrategy {
+    Merge,        // 为冲突的记录分配新ID，并同步更新内部引用
+    Replace,      // 用导入的记录覆盖已有记录
+    SkipExisting, // 保留已有记录，跳过冲突的导入记录
+}
+
+/// 导入结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    pub remapped: usize,
+}
+
+/// 备份文件信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    pub path: String,
+    pub created_at: DateTime<Utc>, // 从文件名解析出的创建时间
+    pub size_bytes: u64,
+}
+
+/// 数据文件损坏时的处理结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadRecovery {
+    pub corrupt_path: String,          // 损坏的数据文件被移到的位置
+    pub restored_from: Option<String>, // 恢复所用的备份，没有可用备份时为 None
+}
+
+/// 从 backup_%Y%m%d_%H%M%S.json(.gz) 格式的文件名解析备份时间
+fn parse_backup_timestamp(file_name: &str) -> Option<DateTime<Utc>> {
+    let timestamp = file_name.strip_prefix("backup_")?;
+    let timestamp = timestamp
+        .strip_suffix(".json.gz")
+        .or_else(|| timestamp.strip_suffix(".json"))?;
+    NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// 当前的数据格式版本
+pub const CURRENT_VERSION: u32 = 2;
+
+/// 没有版本字段的旧数据文件视为第1版
+fn legacy_version() -> u32 {
+    1
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppData {
+    #[serde(default = "legacy_version")]
+    pub version: u32,
     pub projects: Vec<Project>,
     pub events: Vec<Event>,
     pub time_records: Vec<TimeRecord>,
     pub weekly_reports: Vec<WeeklyReport>,
+    #[serde(default)]
+    pub settings: AppSettings,
+    #[serde(default)]
+    pub templates: Vec<EventTemplate>,
+    #[serde(default)]
+    pub pomodoros: Vec<PomodoroSession>, // 进行中的番茄钟，退出后重新打开时继续计时
 }
 
 impl AppData {
     pub fn new() -> Self {
         Self {
+            version: CURRENT_VERSION,
             projects: Vec::new(),
             events: Vec::new(),
             time_records: Vec::new(),
             weekly_reports: Vec::new(),
+            settings: AppSettings::default(),
+            templates: Vec::new(),
+            pomodoros: Vec::new(),
         }
     }
 
     pub fn from_managers(project_manager: &ProjectManager, event_manager: &EventManager) -> Self {
         Self {
+            version: CURRENT_VERSION,
             projects: project_manager
                 .get_all_projects()
                 .into_iter()
                 .cloned()
                 .collect(),
+            // 回收站中的事件和它们的时间记录也要保存，以便之后恢复
             events: event_manager
                 .get_all_events()
                 .into_iter()
+                .chain(event_manager.get_deleted_events())
                 .cloned()
                 .collect(),
             time_records: event_manager
                 .get_all_time_records()
                 .into_iter()
+                .chain(event_manager.get_deleted_time_records())
                 .cloned()
                 .collect(),
             weekly_reports: Vec::new(), // 暂时不保存报表，因为可以重新生成
+            settings: AppSettings::default(),
+            templates: event_manager
+                .get_all_templates()
+                .into_iter()
+                .cloned()
+                .collect(),
+            pomodoros: event_manager
+                .get_all_pomodoros()
+                .into_iter()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// 回收站中的事件ID
+    pub fn trashed_event_ids(&self) -> HashSet<Uuid> {
+        self.events
+            .iter()
+            .filter(|event| event.deleted_at.is_some())
+            .map(|event| event.id)
+            .collect()
+    }
+
+    /// 不在回收站中的事件
+    pub fn visible_events(&self) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(|event| event.deleted_at.is_none())
+    }
+
+    /// 不属于回收站中事件的时间记录
+    pub fn visible_time_records(&self) -> impl Iterator<Item = &TimeRecord> {
+        let trashed = self.trashed_event_ids();
+        self.time_records
+            .iter()
+            .filter(move |record| !trashed.contains(&record.event_id))
+    }
+
+    /// 去掉回收站中的事件和它们的时间记录，用于统计和导出
+    pub fn without_trash(mut self) -> Self {
+        let trashed = self.trashed_event_ids();
+        self.events.retain(|event| !trashed.contains(&event.id));
+        self.time_records
+            .retain(|record| !trashed.contains(&record.event_id));
+        self
+    }
+
+    /// 附带用户偏好设置
+    pub fn with_settings(mut self, settings: AppSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// 导入另一份数据，按策略处理ID冲突，合并时保持事件→项目、记录→事件的引用一致
+    pub fn import(&mut self, incoming: AppData, strategy: ImportStrategy) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+
+        // 导入的项目不改变当前项目
+        let mut projects = incoming.projects;
+        if !self.projects.is_empty() {
+            for project in &mut projects {
+                project.set_active(false);
+            }
+        }
+        let project_map = import_items(
+            &mut self.projects,
+            projects,
+            strategy,
+            |p| p.id,
+            |p, id| p.id = id,
+            &mut summary,
+        );
+
+        let mut events = incoming.events;
+        for event in &mut events {
+            if let EventType::ProjectRelated(project_id) = &mut event.event_type {
+                if let Some(new_id) = project_map.get(project_id) {
+                    *project_id = *new_id;
+                }
+            }
+        }
+        // 跳过的事件的时间记录也要跳过，否则会挂到已有的同ID事件上
+        let skipped_events: HashSet<Uuid> = if strategy == ImportStrategy::SkipExisting {
+            events
+                .iter()
+                .filter(|event| self.events.iter().any(|e| e.id == event.id))
+                .map(|event| event.id)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let event_map = import_items(
+            &mut self.events,
+            events,
+            strategy,
+            |e| e.id,
+            |e, id| e.id = id,
+            &mut summary,
+        );
+
+        let mut time_records = incoming.time_records;
+        let before = time_records.len();
+        time_records.retain(|record| !skipped_events.contains(&record.event_id));
+        summary.skipped += before - time_records.len();
+        for record in &mut time_records {
+            if let Some(new_id) = event_map.get(&record.event_id) {
+                record.event_id = *new_id;
+            }
+            if let Some(new_id) = record.project_id.and_then(|id| project_map.get(&id)) {
+                record.project_id = Some(*new_id);
+            }
+        }
+        import_items(
+            &mut self.time_records,
+            time_records,
+            strategy,
+            |r| r.id,
+            |r, id| r.id = id,
+            &mut summary,
+        );
+
+        let mut templates = incoming.templates;
+        for template in &mut templates {
+            if let EventType::ProjectRelated(project_id) = &mut template.event_type {
+                if let Some(new_id) = project_map.get(project_id) {
+                    *project_id = *new_id;
+                }
+            }
+        }
+        import_items(
+            &mut self.templates,
+            templates,
+            strategy,
+            |t| t.id,
+            |t, id| t.id = id,
+            &mut summary,
+        );
+
+        summary
+    }
+
+    /// 按导出范围筛选数据，时间记录跟随其所属事件
+    pub fn filter_by_scope(self, scope: &ExportScope) -> Self {
+        let projects: Vec<Project> = self
+            .projects
+            .into_iter()
+            .filter(|project| scope.contains_project(project.id))
+            .collect();
+        let events: Vec<Event> = self
+            .events
+            .into_iter()
+            .filter(|event| scope.contains(event_project_id(event)))
+            .collect();
+        let event_ids: HashSet<Uuid> = events.iter().map(|event| event.id).collect();
+        let time_records = self
+            .time_records
+            .into_iter()
+            .filter(|record| event_ids.contains(&record.event_id))
+            .collect();
+
+        let templates = self
+            .templates
+            .into_iter()
+            .filter(|template| match template.event_type {
+                EventType::ProjectRelated(id) => scope.contains(Some(id)),
+                EventType::NonProject => scope.contains(None),
+            })
+            .collect();
+        let pomodoros = self
+            .pomodoros
+            .into_iter()
+            .filter(|session| event_ids.contains(&session.event_id))
+            .collect();
+
+        Self {
+            version: self.version,
+            projects,
+            events,
+            time_records,
+            weekly_reports: self.weekly_reports,
+            settings: self.settings,
+            templates,
+            pomodoros,
+        }
+    }
+}
+
+/// 按策略把导入的记录合并到已有列表中，返回合并时被重新分配的ID映射（旧ID → 新ID）
+fn import_items<T>(
+    existing: &mut Vec<T>,
+    incoming: Vec<T>,
+    strategy: ImportStrategy,
+    id_of: impl Fn(&T) -> Uuid,
+    set_id: impl Fn(&mut T, Uuid),
+    summary: &mut ImportSummary,
+) -> HashMap<Uuid, Uuid> {
+    let mut remapped = HashMap::new();
+
+    for mut item in incoming {
+        let Some(index) = existing.iter().position(|e| id_of(e) == id_of(&item)) else {
+            existing.push(item);
+            summary.imported += 1;
+            continue;
+        };
+
+        match strategy {
+            ImportStrategy::Merge => {
+                let new_id = Uuid::new_v4();
+                remapped.insert(id_of(&item), new_id);
+                set_id(&mut item, new_id);
+                existing.push(item);
+                summary.imported += 1;
+                summary.remapped += 1;
+            }
+            ImportStrategy::Replace => {
+                existing[index] = item;
+                summary.imported += 1;
+            }
+            ImportStrategy::SkipExisting => summary.skipped += 1,
+        }
+    }
+
+    remapped
+}
+
+fn event_project_id(event: &Event) -> Option<Uuid> {
+    match event.event_type {
+        EventType::ProjectRelated(id) => Some(id),
+        EventType::NonProject => None,
+    }
+}
+
+/// JSONL 导出的一行，`kind` 字段标明数据种类
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JsonlLineRef<'a> {
+    Project(&'a Project),
+    Event(&'a Event),
+    TimeRecord(&'a TimeRecord),
+}
+
+/// 导入时解析的 JSONL 行
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JsonlLine {
+    Project(Project),
+    Event(Event),
+    TimeRecord(TimeRecord),
+}
+
+/// 解析 Toggl 的 "HH:MM:SS" 时长（小时可以超过24），返回秒数
+fn parse_toggl_duration(value: &str) -> Option<i64> {
+    let parts: Vec<i64> = value
+        .split(':')
+        .map(|part| part.parse().ok().filter(|n: &i64| *n >= 0))
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [hours, minutes, seconds] if *minutes < 60 && *seconds < 60 => {
+            Some(hours * 3600 + minutes * 60 + seconds)
+        }
+        _ => None,
+    }
+}
+
+/// 解析保存的JSON数据，旧版本的数据会先迁移到当前版本
+pub fn parse_app_data(contents: &str) -> io::Result<AppData> {
+    let data: serde_json::Value = serde_json::from_str(contents).map_err(io::Error::other)?;
+    let from_version = data
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .map_or(legacy_version(), |version| version as u32);
+
+    if from_version > CURRENT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "数据文件版本 {} 高于程序支持的版本 {}",
+                from_version, CURRENT_VERSION
+            ),
+        ));
+    }
+
+    let data = migrate(data, from_version);
+    serde_json::from_value(data).map_err(io::Error::other)
+}
+
+/// 把数据从指定版本逐步迁移到当前版本
+pub fn migrate(mut data: serde_json::Value, from_version: u32) -> serde_json::Value {
+    let mut version = from_version;
+    while version < CURRENT_VERSION {
+        if version == 1 {
+            migrate_v1_to_v2(&mut data);
+        }
+        version += 1;
+    }
+
+    if let Some(object) = data.as_object_mut() {
+        object.insert("version".to_string(), CURRENT_VERSION.into());
+    }
+    data
+}
+
+/// v1 → v2：补全v1之后新增的字段（设置、项目归档状态、事件标签和工作量点数）
+fn migrate_v1_to_v2(data: &mut serde_json::Value) {
+    let Some(object) = data.as_object_mut() else {
+        return;
+    };
+
+    object
+        .entry("settings")
+        .or_insert_with(|| serde_json::json!(AppSettings::default()));
+
+    if let Some(projects) = object.get_mut("projects").and_then(|p| p.as_array_mut()) {
+        for project in projects.iter_mut().filter_map(|p| p.as_object_mut()) {
+            project.entry("archived").or_insert(false.into());
+        }
+    }
+
+    if let Some(events) = object.get_mut("events").and_then(|e| e.as_array_mut()) {
+        for event in events.iter_mut().filter_map(|e| e.as_object_mut()) {
+            event.entry("tags").or_insert_with(|| serde_json::json!([]));
+            event
+                .entry("effort_points")
+                .or_insert(serde_json::Value::Null);
         }
     }
 }
@@ -52,10 +479,52 @@ impl Default for AppData {
     }
 }
 
+/// 数据存储后端（JSON文件或SQLite）的公共接口
+pub trait DataStore {
+    fn load_data(&self) -> io::Result<AppData>;
+    fn save_app_data(&self, app_data: &AppData) -> io::Result<()>;
+
+    /// 加载数据，数据文件损坏时尽量恢复，并返回恢复的经过
+    fn load_data_with_recovery(&self) -> io::Result<(AppData, Option<LoadRecovery>)> {
+        Ok((self.load_data()?, None))
+    }
+}
+
 pub struct Storage {
     data_dir: String,
+    session_exports: RefCell<HashSet<String>>, // 本次运行中创建的导出文件，清理时保留
+    passphrase: Option<String>,                // 设置后数据文件和备份加密保存
+    compact_json: bool,                        // 数据文件不缩进以减小体积，备份仍带缩进
+}
+
+/// 数据目录锁，防止多个程序实例同时写入同一数据目录，drop 时释放
+pub struct StorageLock {
+    file: fs::File,
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// gzip数据的文件头
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 把解密后的文件字节还原为JSON文本，gzip压缩的内容会先解压
+fn decode_json(mut bytes: Vec<u8>) -> io::Result<String> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+        bytes = decompressed;
+    }
+
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
 
+/// 导出文件支持的扩展名
+const EXPORT_EXTENSIONS: [&str; 3] = ["csv", "xlsx", "json"];
+
 impl Storage {
     pub fn new(data_dir: String) -> Self {
         // 确保数据目录存在
@@ -65,30 +534,128 @@ impl Storage {
             });
         }
 
-        Self { data_dir }
+        Self {
+            data_dir,
+            session_exports: RefCell::new(HashSet::new()),
+            passphrase: None,
+            compact_json: false,
+        }
+    }
+
+    /// 使用口令加密数据文件和备份（导出文件仍为明文）
+    pub fn with_passphrase(data_dir: String, passphrase: String) -> Self {
+        Self {
+            passphrase: Some(passphrase),
+            ..Self::new(data_dir)
+        }
+    }
+
+    /// 设置数据文件是否使用不带缩进的紧凑JSON（备份不受影响）
+    pub fn with_compact_json(mut self, compact: bool) -> Self {
+        self.compact_json = compact;
+        self
+    }
+
+    /// 按是否设置了口令把JSON编码为要写入文件的内容
+    fn encode(&self, json_data: &str) -> io::Result<Vec<u8>> {
+        self.encode_bytes(json_data.as_bytes())
+    }
+
+    fn encode_bytes(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match &self.passphrase {
+            Some(passphrase) => crypto::encrypt(passphrase, data),
+            None => Ok(data.to_vec()),
+        }
+    }
+
+    /// 读取数据文件内容，加密文件会先解密，gzip压缩的内容会自动解压；设置了口令时仍可读取旧的明文文件
+    fn read_json(&self, path: &str) -> io::Result<String> {
+        decode_json(self.read_decrypted(path)?)
+    }
+
+    /// 读取文件字节，加密文件会先解密。缺少口令或口令错误时返回错误
+    fn read_decrypted(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+
+        if crypto::is_encrypted(&bytes) {
+            let passphrase = self.passphrase.as_deref().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "数据文件已加密，需要提供口令",
+                )
+            })?;
+            bytes = crypto::decrypt(passphrase, &bytes)?;
+        }
+        Ok(bytes)
+    }
+
+    pub fn get_lock_file_path(&self) -> String {
+        format!("{}/.lock", self.data_dir)
+    }
+
+    /// 获取数据目录的独占锁，另一个实例已持有锁时返回错误
+    pub fn acquire_lock(&self) -> io::Result<StorageLock> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.get_lock_file_path())?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(StorageLock { file }),
+            Err(fs::TryLockError::WouldBlock) => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("数据目录 {} 正被另一个程序实例使用", self.data_dir),
+            )),
+            Err(fs::TryLockError::Error(e)) => Err(e),
+        }
     }
 
     pub fn get_data_file_path(&self) -> String {
         format!("{}/app_data.json", self.data_dir)
     }
 
+    /// 保存时使用的临时文件路径
+    pub fn get_temp_file_path(&self) -> String {
+        format!("{}.tmp", self.get_data_file_path())
+    }
+
     pub fn get_backup_file_path(&self, timestamp: &str) -> String {
         format!("{}/backup_{}.json", self.data_dir, timestamp)
     }
 
+    /// 压缩备份的文件路径
+    pub fn get_compressed_backup_file_path(&self, timestamp: &str) -> String {
+        format!("{}.gz", self.get_backup_file_path(timestamp))
+    }
+
     /// 保存应用数据到文件
     pub fn save_data(
         &self,
         project_manager: &ProjectManager,
         event_manager: &EventManager,
     ) -> io::Result<()> {
-        let app_data = AppData::from_managers(project_manager, event_manager);
-        let json_data = serde_json::to_string_pretty(&app_data)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.save_app_data(&AppData::from_managers(project_manager, event_manager))
+    }
+
+    /// 保存完整的应用数据（包括用户设置）到文件
+    pub fn save_app_data(&self, app_data: &AppData) -> io::Result<()> {
+        let json_data = if self.compact_json {
+            serde_json::to_string(app_data)
+        } else {
+            serde_json::to_string_pretty(app_data)
+        }
+        .map_err(io::Error::other)?;
 
+        // 先写入临时文件并落盘，再重命名覆盖正式文件，避免写到一半时崩溃导致数据丢失
         let file_path = self.get_data_file_path();
-        let mut file = fs::File::create(&file_path)?;
-        file.write_all(json_data.as_bytes())?;
+        let temp_path = self.get_temp_file_path();
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(&self.encode(&json_data)?)?;
+        file.flush()?;
+        file.sync_all()?;
+        fs::rename(&temp_path, &file_path)?;
 
         Ok(())
     }
@@ -101,14 +668,55 @@ impl Storage {
             return Ok(AppData::new());
         }
 
-        let mut file = fs::File::open(&file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+        parse_app_data(&self.read_json(&file_path)?)
+    }
 
-        let app_data: AppData =
-            serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    /// 加载数据；数据文件无法解压、不是UTF-8文本、不是有效的JSON或结构不对时，把它移到
+    /// app_data.corrupt.<时间>.json 保留下来，再从最新的有效备份恢复并写回数据文件。
+    /// 没有可用备份时返回空数据。读取失败（如口令错误）或版本过高时仍返回错误
+    pub fn load_data_with_recovery(&self) -> io::Result<(AppData, Option<LoadRecovery>)> {
+        let file_path = self.get_data_file_path();
+        if !Path::new(&file_path).exists() {
+            return Ok((AppData::new(), None));
+        }
 
-        Ok(app_data)
+        // 口令错误时无法判断文件是否损坏，直接返回错误；解密之后的解码失败才算损坏
+        if let Ok(contents) = decode_json(self.read_decrypted(&file_path)?) {
+            match parse_app_data(&contents) {
+                Ok(app_data) => return Ok((app_data, None)),
+                // 版本过高的文件是完好的，只是程序太旧，不能当作损坏处理
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let corrupt_path = format!("{}/app_data.corrupt.{}.json", self.data_dir, timestamp);
+        fs::rename(&file_path, &corrupt_path)?;
+
+        let restored = self
+            .list_backups_detailed()?
+            .into_iter()
+            .find_map(|backup| {
+                self.restore_from_backup(&backup.path)
+                    .ok()
+                    .map(|app_data| (app_data, backup.path))
+            });
+        let (app_data, restored_from) = match restored {
+            Some((app_data, path)) => {
+                self.save_app_data(&app_data)?;
+                (app_data, Some(path))
+            }
+            None => (AppData::new(), None),
+        };
+
+        Ok((
+            app_data,
+            Some(LoadRecovery {
+                corrupt_path,
+                restored_from,
+            }),
+        ))
     }
 
     /// 创建数据备份
@@ -118,35 +726,63 @@ impl Storage {
         event_manager: &EventManager,
     ) -> io::Result<String> {
         let app_data = AppData::from_managers(project_manager, event_manager);
-        let json_data = serde_json::to_string_pretty(&app_data)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let json_data = serde_json::to_string_pretty(&app_data).map_err(io::Error::other)?;
 
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
         let backup_path = self.get_backup_file_path(&timestamp);
 
         let mut file = fs::File::create(&backup_path)?;
-        file.write_all(json_data.as_bytes())?;
+        file.write_all(&self.encode(&json_data)?)?;
+
+        Ok(backup_path)
+    }
+
+    /// 创建gzip压缩的数据备份（设置了口令时先压缩再加密）
+    pub fn create_backup_compressed(
+        &self,
+        project_manager: &ProjectManager,
+        event_manager: &EventManager,
+    ) -> io::Result<String> {
+        let app_data = AppData::from_managers(project_manager, event_manager);
+        let json_data = serde_json::to_string(&app_data).map_err(io::Error::other)?;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json_data.as_bytes())?;
+        let compressed = encoder.finish()?;
+
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let backup_path = self.get_compressed_backup_file_path(&timestamp);
+
+        let mut file = fs::File::create(&backup_path)?;
+        file.write_all(&self.encode_bytes(&compressed)?)?;
 
         Ok(backup_path)
     }
 
+    /// 把另一份数据导入到已保存的数据中。
+    /// 运行中的程序持有数据目录锁时拒绝导入，否则它下次自动保存会覆盖导入的数据
+    pub fn import_app_data(
+        &self,
+        incoming: AppData,
+        strategy: ImportStrategy,
+    ) -> io::Result<ImportSummary> {
+        let _lock = self.acquire_lock()?;
+        let mut app_data = self.load_data()?;
+        let summary = app_data.import(incoming, strategy);
+        self.save_app_data(&app_data)?;
+        Ok(summary)
+    }
+
     /// 从备份恢复数据
     pub fn restore_from_backup(&self, backup_path: &str) -> io::Result<AppData> {
         if !Path::new(backup_path).exists() {
             return Err(io::Error::new(io::ErrorKind::NotFound, "备份文件不存在"));
         }
 
-        let mut file = fs::File::open(backup_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-
-        let app_data: AppData =
-            serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-        Ok(app_data)
+        parse_app_data(&self.read_json(backup_path)?)
     }
 
-    /// 列出所有备份文件
+    /// 列出所有备份文件（包括压缩备份）
     pub fn list_backups(&self) -> io::Result<Vec<String>> {
         let mut backups = Vec::new();
 
@@ -154,7 +790,8 @@ impl Storage {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.starts_with("backup_") && file_name.ends_with(".json") {
+                    let is_backup = file_name.ends_with(".json") || file_name.ends_with(".json.gz");
+                    if file_name.starts_with("backup_") && is_backup {
                         backups.push(path.to_string_lossy().to_string());
                     }
                 }
@@ -167,6 +804,33 @@ impl Storage {
         Ok(backups)
     }
 
+    /// 列出备份文件及其创建时间和大小（最新的在前），文件名不符合格式的文件会被忽略
+    pub fn list_backups_detailed(&self) -> io::Result<Vec<BackupInfo>> {
+        let mut backups = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(&self.data_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Some(created_at) = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(parse_backup_timestamp)
+                else {
+                    continue;
+                };
+                backups.push(BackupInfo {
+                    path: path.to_string_lossy().to_string(),
+                    created_at,
+                    size_bytes: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.path.cmp(&a.path)));
+
+        Ok(backups)
+    }
+
     /// 删除备份文件
     pub fn delete_backup(&self, backup_path: &str) -> io::Result<()> {
         fs::remove_file(backup_path)
@@ -185,11 +849,15 @@ impl Storage {
 
         // 导出项目
         for project in project_manager.get_all_projects() {
-            csv_content.push_str(&format!(
-                "项目,\"{}\",\"{}\",N/A,N/A,N/A,N/A\n",
-                project.name,
-                project.description.as_deref().unwrap_or("")
-            ));
+            csv_content.push_str(&csv_row(&[
+                "项目",
+                &project.name,
+                project.description.as_deref().unwrap_or(""),
+                "N/A",
+                "N/A",
+                "N/A",
+                "N/A",
+            ]));
         }
 
         // 导出事件
@@ -211,18 +879,18 @@ impl Storage {
                 "进行中".to_string()
             };
 
-            csv_content.push_str(&format!(
-                "事件,\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",{}\n",
-                event.title,
+            csv_content.push_str(&csv_row(&[
+                "事件",
+                &event.title,
                 event.description.as_deref().unwrap_or(""),
                 project_name,
-                event.start_time.format("%Y-%m-%d %H:%M:%S"),
-                event
+                &event.start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                &event
                     .end_time
                     .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
                     .unwrap_or_else(|| "N/A".to_string()),
-                duration
-            ));
+                &duration,
+            ]));
         }
 
         // 导出时间记录
@@ -233,13 +901,16 @@ impl Storage {
                 .map(|p| p.name.as_str())
                 .unwrap_or("项目外");
 
-            csv_content.push_str(&format!(
-                "时间记录,N/A,N/A,\"{}\",\"{}\",\"{}\",{}\n",
+            // 时间记录的描述列保存笔记
+            csv_content.push_str(&csv_row(&[
+                "时间记录",
+                "N/A",
+                record.note.as_deref().unwrap_or(""),
                 project_name,
-                record.start_time.format("%Y-%m-%d %H:%M:%S"),
-                record.end_time.format("%Y-%m-%d %H:%M:%S"),
-                record.duration_minutes
-            ));
+                &record.start_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                &record.end_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                &record.duration_minutes.to_string(),
+            ]));
         }
 
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
@@ -247,65 +918,471 @@ impl Storage {
 
         let mut file = fs::File::create(&csv_path)?;
         file.write_all(csv_content.as_bytes())?;
+        self.session_exports.borrow_mut().insert(csv_path.clone());
 
         Ok(csv_path)
     }
 
-    /// 获取数据目录大小
-    pub fn get_data_dir_size(&self) -> io::Result<u64> {
-        let mut total_size = 0;
+    /// 从 export_to_csv 导出的CSV文件重建数据，格式错误的行会被跳过并返回警告。
+    /// 与 existing 中已有项目名称相同（按 find_project_by_name_fuzzy 匹配）的项目不再重复创建，
+    /// 事件直接关联到已有项目
+    pub fn import_from_csv(
+        &self,
+        csv_path: &str,
+        existing: &ProjectManager,
+    ) -> io::Result<(AppData, Vec<String>)> {
+        let contents = fs::read_to_string(csv_path)?;
+        let mut app_data = AppData::new();
+        let mut warnings = Vec::new();
+        // 项目名称按 normalize_project_name 规范化后匹配，大小写或空白不同的名称视为同一项目
+        let mut project_ids: HashMap<String, Uuid> = HashMap::new();
+        let mut record_rows = Vec::new();
+
+        let parse_time = |value: &str| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|time| time.and_utc())
+        };
+
+        // 第一行是表头
+        for (index, row) in parse_csv(&contents).into_iter().enumerate().skip(1) {
+            let line = index + 1;
+            if row.len() != 7 {
+                warnings.push(format!("第{}行: 应有7列，实际为{}列", line, row.len()));
+                continue;
+            }
 
-        if let Ok(entries) = fs::read_dir(&self.data_dir) {
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.is_file() {
-                        total_size += metadata.len();
+            match row[0].as_str() {
+                "项目" => {
+                    if project_ids.contains_key(&normalize_project_name(&row[1])) {
+                        warnings.push(format!("第{}行: 项目名称重复: {}", line, row[1]));
+                        continue;
+                    }
+                    let key = normalize_project_name(&row[1]);
+                    if let Some(project_id) = existing.find_project_by_name_fuzzy(&row[1]) {
+                        project_ids.insert(key, project_id);
+                        continue;
+                    }
+                    let description = (!row[2].is_empty()).then(|| row[2].clone());
+                    let project = Project::new(row[1].clone(), description);
+                    project_ids.insert(key, project.id);
+                    app_data.projects.push(project);
+                }
+                "事件" => {
+                    let event_type = if row[3] == "项目外" {
+                        EventType::NonProject
+                    } else if let Some(project_id) =
+                        project_ids.get(&normalize_project_name(&row[3]))
+                    {
+                        EventType::ProjectRelated(*project_id)
+                    } else {
+                        warnings.push(format!("第{}行: 找不到项目: {}", line, row[3]));
+                        continue;
+                    };
+                    let Some(start_time) = parse_time(&row[4]) else {
+                        warnings.push(format!("第{}行: 开始时间格式错误: {}", line, row[4]));
+                        continue;
+                    };
+                    let end_time = match row[5].as_str() {
+                        "N/A" => None,
+                        value => match parse_time(value) {
+                            Some(end_time) if end_time > start_time => Some(end_time),
+                            _ => {
+                                warnings.push(format!("第{}行: 结束时间无效: {}", line, value));
+                                continue;
+                            }
+                        },
+                    };
+
+                    let description = (!row[2].is_empty()).then(|| row[2].clone());
+                    let mut event = Event::new(row[1].clone(), description, event_type, start_time);
+                    if let Some(end_time) = end_time {
+                        event.set_end_time(end_time);
+                        app_data.time_records.push(TimeRecord::new(
+                            event.id,
+                            event_project_id(&event),
+                            start_time,
+                            end_time,
+                        ));
                     }
+                    app_data.events.push(event);
                 }
+                "时间记录" => record_rows.push((line, row)),
+                other => warnings.push(format!("第{}行: 未知的类型: {}", line, other)),
             }
         }
 
-        Ok(total_size)
+        // 时间记录由已完成的事件重新生成，这里只检查每条记录都有对应的事件并恢复笔记
+        for (line, row) in record_rows {
+            let project_id = project_ids.get(&normalize_project_name(&row[3])).copied();
+            let matched = app_data.time_records.iter_mut().find(|record| {
+                record.project_id == project_id
+                    && Some(record.start_time) == parse_time(&row[4])
+                    && Some(record.end_time) == parse_time(&row[5])
+            });
+            match matched {
+                Some(record) => {
+                    record.note = (!row[2].is_empty() && row[2] != "N/A").then(|| row[2].clone())
+                }
+                None => warnings.push(format!("第{}行: 时间记录没有对应的事件，已忽略", line)),
+            }
+        }
+
+        Ok((app_data, warnings))
     }
 
-    /// 清理旧备份文件（保留最近N个）
-    pub fn cleanup_old_backups(&self, keep_count: usize) -> io::Result<usize> {
-        let mut backups = self.list_backups()?;
+    /// 导入 Toggl 导出的CSV，按项目名称创建项目，没有项目的记录作为项目外事件。
+    /// 名称与 existing 中已有项目相同的记录关联到已有项目，不重复创建。
+    /// 缺少必需的列时返回错误，格式错误的行会被跳过并返回警告
+    pub fn import_from_toggl_csv(
+        &self,
+        csv_path: &str,
+        existing: &ProjectManager,
+    ) -> io::Result<(AppData, Vec<String>)> {
+        let contents = fs::read_to_string(csv_path)?;
+        let mut rows = parse_csv(contents.trim_start_matches('\u{feff}')).into_iter();
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let header = rows
+            .next()
+            .ok_or_else(|| invalid("CSV文件为空".to_string()))?;
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or_else(|| invalid(format!("缺少 Toggl 列: {}", name)))
+        };
+        let project_col = column("Project")?;
+        let description_col = column("Description")?;
+        let date_col = column("Start date")?;
+        let time_col = column("Start time")?;
+        let duration_col = column("Duration")?;
+        let tags_col = column("Tags").ok();
 
-        if backups.len() > keep_count {
-            let to_delete = backups.split_off(keep_count);
-            let mut deleted_count = 0;
+        let mut app_data = AppData::new();
+        let mut warnings = Vec::new();
+        let mut project_ids: HashMap<String, Uuid> = HashMap::new();
+
+        for (index, row) in rows.enumerate() {
+            let line = index + 2;
+            let field = |col: usize| row.get(col).map(|value| value.trim()).unwrap_or("");
+
+            let Ok(start_time) = NaiveDateTime::parse_from_str(
+                &format!("{} {}", field(date_col), field(time_col)),
+                "%Y-%m-%d %H:%M:%S",
+            ) else {
+                warnings.push(format!(
+                    "第{}行: 开始时间格式错误: {} {}",
+                    line,
+                    field(date_col),
+                    field(time_col)
+                ));
+                continue;
+            };
+            let start_time = start_time.and_utc();
+            let Some(seconds) = parse_toggl_duration(field(duration_col)) else {
+                warnings.push(format!(
+                    "第{}行: 时长格式错误: {}",
+                    line,
+                    field(duration_col)
+                ));
+                continue;
+            };
 
-            for backup_path in to_delete {
-                if let Err(e) = self.delete_backup(&backup_path) {
-                    eprintln!("删除备份文件失败 {}: {}", backup_path, e);
-                } else {
-                    deleted_count += 1;
+            let event_type = match field(project_col) {
+                "" => EventType::NonProject,
+                name => {
+                    let key = normalize_project_name(name);
+                    let project_id = *project_ids.entry(key).or_insert_with(|| {
+                        existing
+                            .find_project_by_name_fuzzy(name)
+                            .unwrap_or_else(|| {
+                                let project = Project::new(name.to_string(), None);
+                                let project_id = project.id;
+                                app_data.projects.push(project);
+                                project_id
+                            })
+                    });
+                    EventType::ProjectRelated(project_id)
                 }
-            }
+            };
 
-            Ok(deleted_count)
-        } else {
-            Ok(0)
+            let title = match field(description_col) {
+                "" => "(无描述)".to_string(),
+                description => description.to_string(),
+            };
+            let mut event = Event::new(title, None, event_type, start_time);
+            if let Some(col) = tags_col {
+                event.tags = field(col)
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            if seconds > 0 {
+                let end_time = start_time + chrono::Duration::seconds(seconds);
+                event.set_end_time(end_time);
+                app_data.time_records.push(TimeRecord::new(
+                    event.id,
+                    event_project_id(&event),
+                    start_time,
+                    end_time,
+                ));
+            }
+            app_data.events.push(event);
         }
-    }
 
-    /// 检查数据完整性
-    pub fn check_data_integrity(&self, app_data: &AppData) -> Vec<String> {
-        let mut issues = Vec::new();
+        Ok((app_data, warnings))
+    }
 
-        // 检查项目ID重复
-        let mut project_ids = std::collections::HashSet::new();
-        for project in &app_data.projects {
-            if project_ids.contains(&project.id) {
-                issues.push(format!("项目ID重复: {}", project.id));
-            }
-            project_ids.insert(project.id);
+    /// 按范围导出数据到JSON文件，导出前确认没有范围外的数据
+    pub fn export_with_scope(
+        &self,
+        project_manager: &ProjectManager,
+        event_manager: &EventManager,
+        scope: ExportScope,
+    ) -> io::Result<String> {
+        let app_data = AppData::from_managers(project_manager, event_manager)
+            .without_trash()
+            .filter_by_scope(&scope);
+
+        let offenders = self.check_export_scope(&app_data, &scope);
+        if !offenders.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("导出数据包含范围外的记录:\n{}", offenders.join("\n")),
+            ));
         }
 
-        // 检查事件ID重复
-        let mut event_ids = std::collections::HashSet::new();
-        for event in &app_data.events {
+        let json_data = serde_json::to_string_pretty(&app_data).map_err(io::Error::other)?;
+
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let export_path = format!("{}/export_{}.json", self.data_dir, timestamp);
+
+        let mut file = fs::File::create(&export_path)?;
+        file.write_all(json_data.as_bytes())?;
+        self.session_exports
+            .borrow_mut()
+            .insert(export_path.clone());
+
+        Ok(export_path)
+    }
+
+    /// 逐行导出项目、事件和时间记录，每行一个JSON对象，不在内存中拼出整个文件。
+    /// 回收站中的事件和它们的时间记录不导出
+    pub fn export_jsonl(app_data: &AppData, writer: impl Write) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let lines = app_data
+            .projects
+            .iter()
+            .map(JsonlLineRef::Project)
+            .chain(app_data.visible_events().map(JsonlLineRef::Event))
+            .chain(
+                app_data
+                    .visible_time_records()
+                    .map(JsonlLineRef::TimeRecord),
+            );
+
+        for line in lines {
+            serde_json::to_writer(&mut writer, &line).map_err(io::Error::other)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+
+    /// 逐行导入 export_jsonl 导出的数据，空行会被跳过
+    pub fn import_jsonl(reader: impl Read) -> io::Result<AppData> {
+        let mut app_data = AppData::new();
+
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parsed: JsonlLine = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("第 {} 行格式错误: {}", index + 1, e),
+                )
+            })?;
+            match parsed {
+                JsonlLine::Project(project) => app_data.projects.push(project),
+                JsonlLine::Event(event) => app_data.events.push(event),
+                JsonlLine::TimeRecord(record) => app_data.time_records.push(record),
+            }
+        }
+
+        Ok(app_data)
+    }
+
+    /// 检查导出数据是否超出范围，返回所有越界记录的说明
+    pub fn check_export_scope(&self, app_data: &AppData, scope: &ExportScope) -> Vec<String> {
+        let mut offenders = Vec::new();
+
+        for project in &app_data.projects {
+            if !scope.contains_project(project.id) {
+                offenders.push(format!(
+                    "项目不在导出范围内: {} ({})",
+                    project.name, project.id
+                ));
+            }
+        }
+
+        for event in &app_data.events {
+            if !scope.contains(event_project_id(event)) {
+                offenders.push(format!(
+                    "事件不在导出范围内: {} ({})",
+                    event.title, event.id
+                ));
+            }
+        }
+
+        for record in &app_data.time_records {
+            if !scope.contains(record.project_id) {
+                offenders.push(format!(
+                    "时间记录不在导出范围内: 记录ID {}, 事件ID {}",
+                    record.id, record.event_id
+                ));
+            }
+        }
+
+        offenders
+    }
+
+    /// 获取数据目录大小
+    pub fn get_data_dir_size(&self) -> io::Result<u64> {
+        let mut total_size = 0;
+
+        if let Ok(entries) = fs::read_dir(&self.data_dir) {
+            for entry in entries.flatten() {
+                if let Ok(metadata) = entry.metadata() {
+                    if metadata.is_file() {
+                        total_size += metadata.len();
+                    }
+                }
+            }
+        }
+
+        Ok(total_size)
+    }
+
+    /// 清理旧备份文件（保留最近N个）
+    pub fn cleanup_old_backups(&self, keep_count: usize) -> io::Result<usize> {
+        let mut backups = self.list_backups()?;
+
+        if backups.len() > keep_count {
+            let to_delete = backups.split_off(keep_count);
+            let mut deleted_count = 0;
+
+            for backup_path in to_delete {
+                if let Err(e) = self.delete_backup(&backup_path) {
+                    eprintln!("删除备份文件失败 {}: {}", backup_path, e);
+                } else {
+                    deleted_count += 1;
+                }
+            }
+
+            Ok(deleted_count)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// 删除创建时间早于N天前的备份（按文件名中的时间判断，不论数量），返回删除的数量
+    pub fn cleanup_backups_older_than(&self, days: u32) -> io::Result<usize> {
+        let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+        let mut deleted_count = 0;
+
+        for backup in self.list_backups_detailed()? {
+            if backup.created_at >= cutoff {
+                continue;
+            }
+            if let Err(e) = self.delete_backup(&backup.path) {
+                eprintln!("删除备份文件失败 {}: {}", backup.path, e);
+            } else {
+                deleted_count += 1;
+            }
+        }
+
+        Ok(deleted_count)
+    }
+
+    /// 列出所有导出文件及其时间戳（最新的在前）
+    pub fn list_exports(&self) -> io::Result<Vec<(NaiveDateTime, String)>> {
+        let mut exports = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(&self.data_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let (Some(stem), Some(extension)) = (
+                    path.file_stem().and_then(|n| n.to_str()),
+                    path.extension().and_then(|n| n.to_str()),
+                ) else {
+                    continue;
+                };
+                if !EXPORT_EXTENSIONS.contains(&extension) {
+                    continue;
+                }
+                // 文件名无法解析出时间戳的不视为导出文件
+                if let Some(timestamp) = stem
+                    .strip_prefix("export_")
+                    .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y%m%d_%H%M%S").ok())
+                {
+                    exports.push((timestamp, path.to_string_lossy().to_string()));
+                }
+            }
+        }
+
+        exports.sort_by(|a, b| b.cmp(a));
+
+        Ok(exports)
+    }
+
+    /// 清理旧导出文件（保留最近N个，本次运行中创建的导出不会被删除）
+    pub fn cleanup_old_exports(&self, keep_count: usize) -> io::Result<usize> {
+        let exports = self.list_exports()?;
+        let session_exports = self.session_exports.borrow();
+        let mut deleted_count = 0;
+
+        for (_, export_path) in exports.into_iter().skip(keep_count) {
+            if session_exports.contains(&export_path) {
+                continue;
+            }
+            if let Err(e) = fs::remove_file(&export_path) {
+                eprintln!("删除导出文件失败 {}: {}", export_path, e);
+            } else {
+                deleted_count += 1;
+            }
+        }
+
+        Ok(deleted_count)
+    }
+
+    /// 数据目录维护：清理旧备份和旧导出文件，返回删除的文件总数
+    pub fn run_maintenance(&self, keep_backups: usize, keep_exports: usize) -> io::Result<usize> {
+        let deleted_backups = self.cleanup_old_backups(keep_backups)?;
+        let deleted_exports = self.cleanup_old_exports(keep_exports)?;
+        Ok(deleted_backups + deleted_exports)
+    }
+
+    /// 检查数据完整性，回收站中的事件和它们的时间记录不参与检查
+    pub fn check_data_integrity(&self, app_data: &AppData) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        // 检查项目ID重复
+        let mut project_ids = std::collections::HashSet::new();
+        for project in &app_data.projects {
+            if project_ids.contains(&project.id) {
+                issues.push(format!("项目ID重复: {}", project.id));
+            }
+            project_ids.insert(project.id);
+        }
+
+        // 检查事件ID重复
+        let mut event_ids = std::collections::HashSet::new();
+        for event in app_data.visible_events() {
             if event_ids.contains(&event.id) {
                 issues.push(format!("事件ID重复: {}", event.id));
             }
@@ -323,8 +1400,9 @@ impl Storage {
         }
 
         // 检查时间记录ID重复
+        let now = Utc::now();
         let mut record_ids = std::collections::HashSet::new();
-        for record in &app_data.time_records {
+        for record in app_data.visible_time_records() {
             if record_ids.contains(&record.id) {
                 issues.push(format!("时间记录ID重复: {}", record.id));
             }
@@ -347,12 +1425,48 @@ impl Storage {
                     ));
                 }
             }
+
+            // 检查系统时钟错误导致的异常时间
+            if record.end_time < record.start_time {
+                issues.push(format!(
+                    "时间记录的结束时间早于开始时间: 记录ID {}, 开始 {}, 结束 {}",
+                    record.id,
+                    record.start_time.format("%Y-%m-%d %H:%M:%S"),
+                    record.end_time.format("%Y-%m-%d %H:%M:%S")
+                ));
+            } else if record.duration_minutes < 0 {
+                issues.push(format!(
+                    "时间记录的时长为负数: 记录ID {}, 时长 {}分钟",
+                    record.id, record.duration_minutes
+                ));
+            }
+            if record.start_time > now {
+                issues.push(format!(
+                    "时间记录的开始时间在未来: 记录ID {}, 开始 {}",
+                    record.id,
+                    record.start_time.format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
         }
 
         issues
     }
 }
 
+impl DataStore for Storage {
+    fn load_data(&self) -> io::Result<AppData> {
+        Storage::load_data(self)
+    }
+
+    fn save_app_data(&self, app_data: &AppData) -> io::Result<()> {
+        Storage::save_app_data(self, app_data)
+    }
+
+    fn load_data_with_recovery(&self) -> io::Result<(AppData, Option<LoadRecovery>)> {
+        Storage::load_data_with_recovery(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,6 +1482,42 @@ mod tests {
         assert_eq!(storage.data_dir, data_dir);
     }
 
+    #[test]
+    fn test_compact_json_data_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage =
+            Storage::new(temp_dir.path().to_string_lossy().to_string()).with_compact_json(true);
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+        let project_id = project_manager
+            .add_project("紧凑项目".to_string(), None)
+            .unwrap();
+        let event_id = event_manager
+            .add_project_event("事件".to_string(), None, project_id, None)
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+        event_manager
+            .add_non_project_event("另一个事件".to_string(), None, None)
+            .unwrap();
+
+        let app_data = AppData::from_managers(&project_manager, &event_manager);
+        storage.save_app_data(&app_data).unwrap();
+        let contents = fs::read_to_string(storage.get_data_file_path()).unwrap();
+        assert!(!contents.contains('\n'));
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(
+            serde_json::to_string(&loaded).unwrap(),
+            serde_json::to_string(&app_data).unwrap()
+        );
+
+        // 备份仍然带缩进
+        let backup_path = storage
+            .create_backup(&project_manager, &event_manager)
+            .unwrap();
+        assert!(fs::read_to_string(backup_path).unwrap().contains('\n'));
+    }
+
     #[test]
     fn test_save_and_load_data() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -378,11 +1528,14 @@ mod tests {
         let mut event_manager = EventManager::new();
 
         // 添加测试数据
-        let project_id = project_manager.add_project("测试项目".to_string(), None);
+        let project_id = project_manager
+            .add_project("测试项目".to_string(), None)
+            .unwrap();
         project_manager.switch_to_project(project_id).unwrap();
 
-        let _event_id =
-            event_manager.add_project_event("测试事件".to_string(), None, project_id, None);
+        let _event_id = event_manager
+            .add_project_event("测试事件".to_string(), None, project_id, None)
+            .unwrap();
 
         // 保存数据
         storage.save_data(&project_manager, &event_manager).unwrap();
@@ -396,6 +1549,249 @@ mod tests {
         assert_eq!(loaded_data.events[0].title, "测试事件");
     }
 
+    #[test]
+    fn test_interrupted_save_keeps_previous_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        project_manager
+            .add_project("已保存项目".to_string(), None)
+            .unwrap();
+        storage
+            .save_data(&project_manager, &EventManager::new())
+            .unwrap();
+        assert!(!Path::new(&storage.get_temp_file_path()).exists());
+
+        // 模拟写临时文件时崩溃：只写了一半，没有重命名
+        fs::write(storage.get_temp_file_path(), "{\"projects\": [").unwrap();
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.projects.len(), 1);
+        assert_eq!(loaded.projects[0].name, "已保存项目");
+
+        // 下一次保存会覆盖残留的临时文件
+        project_manager
+            .add_project("新项目".to_string(), None)
+            .unwrap();
+        storage
+            .save_data(&project_manager, &EventManager::new())
+            .unwrap();
+        assert_eq!(storage.load_data().unwrap().projects.len(), 2);
+        assert!(!Path::new(&storage.get_temp_file_path()).exists());
+    }
+
+    #[test]
+    fn test_load_v1_data_migrates_to_current_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        // 第1版的数据没有版本号、设置、归档状态和标签等字段
+        let project_id = Uuid::new_v4();
+        let v1 = serde_json::json!({
+            "projects": [{
+                "id": project_id,
+                "name": "旧项目",
+                "description": null,
+                "created_at": "2024-01-01T08:00:00Z",
+                "is_active": true
+            }],
+            "events": [{
+                "id": Uuid::new_v4(),
+                "title": "旧事件",
+                "description": null,
+                "event_type": { "ProjectRelated": project_id },
+                "start_time": "2024-01-01T09:00:00Z",
+                "end_time": null,
+                "created_at": "2024-01-01T09:00:00Z"
+            }],
+            "time_records": [],
+            "weekly_reports": []
+        });
+        fs::write(storage.get_data_file_path(), v1.to_string()).unwrap();
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.version, CURRENT_VERSION);
+        assert_eq!(loaded.projects[0].name, "旧项目");
+        assert!(!loaded.projects[0].archived);
+        assert!(loaded.events[0].tags.is_empty());
+        assert_eq!(loaded.events[0].effort_points, None);
+
+        let migrated = migrate(v1, 1);
+        assert_eq!(migrated["version"], CURRENT_VERSION);
+        assert_eq!(migrated["projects"][0]["archived"], false);
+        assert!(migrated["settings"].is_object());
+    }
+
+    #[test]
+    fn test_load_rejects_newer_version() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut data = serde_json::to_value(AppData::new()).unwrap();
+        data["version"] = (CURRENT_VERSION + 1).into();
+        fs::write(storage.get_data_file_path(), data.to_string()).unwrap();
+
+        assert!(storage.load_data().is_err());
+    }
+
+    #[test]
+    fn test_load_recovers_from_corrupt_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        project_manager
+            .add_project("备份中的项目".to_string(), None)
+            .unwrap();
+        storage
+            .create_backup(&project_manager, &EventManager::new())
+            .unwrap();
+        // 更旧的备份同样损坏时应跳过，使用最新的有效备份
+        fs::write(temp_dir.path().join("backup_20200101_000000.json"), "{").unwrap();
+        fs::write(storage.get_data_file_path(), "{\"projects\": [").unwrap();
+
+        let (app_data, recovery) = storage.load_data_with_recovery().unwrap();
+        let recovery = recovery.unwrap();
+        assert_eq!(app_data.projects[0].name, "备份中的项目");
+        assert_eq!(
+            fs::read_to_string(&recovery.corrupt_path).unwrap(),
+            "{\"projects\": ["
+        );
+        assert!(recovery.restored_from.unwrap().contains("backup_"));
+        // 恢复的数据已写回数据文件
+        assert_eq!(storage.load_data().unwrap().projects.len(), 1);
+        assert_eq!(storage.load_data_with_recovery().unwrap().1, None);
+    }
+
+    #[test]
+    fn test_load_corrupt_file_without_backup() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        fs::write(storage.get_data_file_path(), "not json").unwrap();
+
+        let (app_data, recovery) = storage.load_data_with_recovery().unwrap();
+        let recovery = recovery.unwrap();
+        assert!(app_data.projects.is_empty());
+        assert_eq!(recovery.restored_from, None);
+        assert!(recovery.corrupt_path.contains("app_data.corrupt."));
+        assert!(Path::new(&recovery.corrupt_path).exists());
+        assert!(!Path::new(&storage.get_data_file_path()).exists());
+
+        // 版本过高的文件不会被当作损坏文件移走
+        let mut data = serde_json::to_value(AppData::new()).unwrap();
+        data["version"] = (CURRENT_VERSION + 1).into();
+        fs::write(storage.get_data_file_path(), data.to_string()).unwrap();
+        assert!(storage.load_data_with_recovery().is_err());
+        assert!(Path::new(&storage.get_data_file_path()).exists());
+    }
+
+    #[test]
+    fn test_load_recovers_from_undecodable_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        project_manager
+            .add_project("备份中的项目".to_string(), None)
+            .unwrap();
+        storage
+            .create_backup(&project_manager, &EventManager::new())
+            .unwrap();
+
+        // 非UTF-8内容
+        fs::write(storage.get_data_file_path(), [0xff, 0xfe, 0x00, 0x7b]).unwrap();
+        let (app_data, recovery) = storage.load_data_with_recovery().unwrap();
+        assert_eq!(app_data.projects[0].name, "备份中的项目");
+        assert!(recovery.unwrap().restored_from.is_some());
+
+        // 截断的gzip内容
+        fs::write(storage.get_data_file_path(), [0x1f, 0x8b, 0x08]).unwrap();
+        let (app_data, recovery) = storage.load_data_with_recovery().unwrap();
+        assert_eq!(app_data.projects.len(), 1);
+        assert!(Path::new(&recovery.unwrap().corrupt_path).exists());
+    }
+
+    #[test]
+    fn test_load_with_wrong_passphrase_is_not_treated_as_corrupt() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        Storage::with_passphrase(data_dir.clone(), "口令".to_string())
+            .save_app_data(&AppData::new())
+            .unwrap();
+
+        let wrong = Storage::with_passphrase(data_dir.clone(), "错误口令".to_string());
+        assert!(wrong.load_data_with_recovery().is_err());
+        assert!(Storage::new(data_dir).load_data_with_recovery().is_err());
+        assert!(Path::new(&wrong.get_data_file_path()).exists());
+    }
+
+    #[test]
+    fn test_encrypted_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let storage = Storage::with_passphrase(data_dir.clone(), "口令".to_string());
+
+        let mut project_manager = ProjectManager::new();
+        project_manager
+            .add_project("保密客户".to_string(), None)
+            .unwrap();
+        storage
+            .save_data(&project_manager, &EventManager::new())
+            .unwrap();
+
+        // 文件中不应出现明文
+        let raw = fs::read(storage.get_data_file_path()).unwrap();
+        assert!(crypto::is_encrypted(&raw));
+        assert!(!String::from_utf8_lossy(&raw).contains("保密客户"));
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.projects[0].name, "保密客户");
+
+        // 备份同样加密，并能恢复
+        let backup_path = storage
+            .create_backup(&project_manager, &EventManager::new())
+            .unwrap();
+        assert!(crypto::is_encrypted(&fs::read(&backup_path).unwrap()));
+        assert_eq!(
+            storage
+                .restore_from_backup(&backup_path)
+                .unwrap()
+                .projects
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_encrypted_load_with_wrong_passphrase() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        Storage::with_passphrase(data_dir.clone(), "正确口令".to_string())
+            .save_app_data(&AppData::new())
+            .unwrap();
+
+        let wrong = Storage::with_passphrase(data_dir.clone(), "错误口令".to_string());
+        let err = wrong.load_data().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // 没有口令时明确提示文件已加密
+        let err = Storage::new(data_dir).load_data().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_passphrase_storage_reads_plaintext_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let mut project_manager = ProjectManager::new();
+        project_manager
+            .add_project("明文项目".to_string(), None)
+            .unwrap();
+        Storage::new(data_dir.clone())
+            .save_data(&project_manager, &EventManager::new())
+            .unwrap();
+
+        let storage = Storage::with_passphrase(data_dir, "口令".to_string());
+        assert_eq!(storage.load_data().unwrap().projects[0].name, "明文项目");
+    }
+
     #[test]
     fn test_backup_and_restore() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -406,7 +1802,9 @@ mod tests {
         let event_manager = EventManager::new();
 
         // 添加测试数据
-        project_manager.add_project("测试项目".to_string(), None);
+        project_manager
+            .add_project("测试项目".to_string(), None)
+            .unwrap();
 
         // 创建备份
         let backup_path = storage
@@ -420,6 +1818,274 @@ mod tests {
         assert_eq!(restored_data.projects[0].name, "测试项目");
     }
 
+    #[test]
+    fn test_compressed_backup_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let project_id = project_manager
+            .add_project("压缩项目".to_string(), None)
+            .unwrap();
+        let event_id = event_manager
+            .add_project_event(
+                "事件".to_string(),
+                None,
+                project_id,
+                Some(chrono::Utc::now() - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+
+        let plain_path = storage
+            .create_backup(&project_manager, &event_manager)
+            .unwrap();
+        let compressed_path = storage
+            .create_backup_compressed(&project_manager, &event_manager)
+            .unwrap();
+        assert!(compressed_path.ends_with(".json.gz"));
+        assert!(fs::read(&compressed_path).unwrap().starts_with(&GZIP_MAGIC));
+
+        let backups = storage.list_backups().unwrap();
+        assert!(backups.contains(&plain_path));
+        assert!(backups.contains(&compressed_path));
+
+        // 压缩备份和明文备份恢复出完全相同的数据
+        let expected =
+            serde_json::to_value(AppData::from_managers(&project_manager, &event_manager)).unwrap();
+        let restored = storage.restore_from_backup(&compressed_path).unwrap();
+        assert_eq!(serde_json::to_value(restored).unwrap(), expected);
+        let restored = storage.restore_from_backup(&plain_path).unwrap();
+        assert_eq!(serde_json::to_value(restored).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_backup_timestamp() {
+        let expected = chrono::NaiveDate::from_ymd_opt(2024, 12, 31)
+            .unwrap()
+            .and_hms_opt(23, 59, 58)
+            .unwrap()
+            .and_utc();
+        assert_eq!(
+            parse_backup_timestamp("backup_20241231_235958.json"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_backup_timestamp("backup_20241231_235958.json.gz"),
+            Some(expected)
+        );
+        assert_eq!(parse_backup_timestamp("backup_latest.json"), None);
+        assert_eq!(parse_backup_timestamp("backup_20241331_000000.json"), None);
+        assert_eq!(parse_backup_timestamp("export_20241231_235958.json"), None);
+        assert_eq!(parse_backup_timestamp("backup_20241231_235958.txt"), None);
+    }
+
+    #[test]
+    fn test_list_backups_detailed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        fs::write(temp_dir.path().join("backup_20241231_235959.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("backup_20250101_000001.json.gz"), "").unwrap();
+        fs::write(
+            temp_dir.path().join("backup_20230615_120000.json"),
+            "{\"a\":1}",
+        )
+        .unwrap();
+        // 名称不符合格式的文件会被跳过
+        fs::write(temp_dir.path().join("backup_old.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("app_data.json"), "{}").unwrap();
+
+        let backups = storage.list_backups_detailed().unwrap();
+        let names: Vec<String> = backups
+            .iter()
+            .map(|b| {
+                Path::new(&b.path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "backup_20250101_000001.json.gz",
+                "backup_20241231_235959.json",
+                "backup_20230615_120000.json",
+            ]
+        );
+        assert_eq!(backups[1].size_bytes, 2);
+        assert_eq!(backups[2].size_bytes, 7);
+        assert_eq!(
+            backups[0].created_at.format("%Y-%m-%d").to_string(),
+            "2025-01-01"
+        );
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let website = project_manager
+            .add_project("网站".to_string(), Some("前端, 后端和部署".to_string()))
+            .unwrap();
+        project_manager
+            .add_project("空项目".to_string(), None)
+            .unwrap();
+
+        let start = chrono::Utc::now() - chrono::Duration::hours(2);
+        let done = event_manager
+            .add_project_event("写代码, 测试".to_string(), None, website, Some(start))
+            .unwrap();
+        event_manager
+            .set_event_end_time(done, Some(start + chrono::Duration::minutes(90)))
+            .unwrap();
+        let record_id = event_manager.get_event_time_record(done).unwrap().id;
+        event_manager
+            .set_record_note(record_id, Some("修好了登录页".to_string()))
+            .unwrap();
+        event_manager
+            .add_non_project_event("午饭".to_string(), None, Some(start))
+            .unwrap();
+
+        let csv_path = storage
+            .export_to_csv(&project_manager, &event_manager)
+            .unwrap();
+        let (imported, warnings) = storage
+            .import_from_csv(&csv_path, &ProjectManager::new())
+            .unwrap();
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(imported.projects.len(), 2);
+        assert_eq!(imported.events.len(), 2);
+        assert_eq!(imported.time_records.len(), 1);
+
+        let project = imported.projects.iter().find(|p| p.name == "网站").unwrap();
+        assert_eq!(project.description.as_deref(), Some("前端, 后端和部署"));
+        let event = imported
+            .events
+            .iter()
+            .find(|e| e.title == "写代码, 测试")
+            .unwrap();
+        assert!(matches!(event.event_type, EventType::ProjectRelated(id) if id == project.id));
+        assert_eq!(imported.time_records[0].event_id, event.id);
+        assert_eq!(imported.time_records[0].duration_minutes, 90);
+        assert_eq!(
+            imported.time_records[0].note.as_deref(),
+            Some("修好了登录页")
+        );
+    }
+
+    #[test]
+    fn test_record_note_survives_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let start = chrono::Utc::now() - chrono::Duration::hours(1);
+        let event_id = event_manager
+            .add_non_project_event("整理笔记".to_string(), None, Some(start))
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+        let record_id = event_manager.get_event_time_record(event_id).unwrap().id;
+        event_manager
+            .set_record_note(record_id, Some("归档了上周的会议记录".to_string()))
+            .unwrap();
+
+        storage.save_data(&project_manager, &event_manager).unwrap();
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(
+            loaded.time_records[0].note.as_deref(),
+            Some("归档了上周的会议记录")
+        );
+
+        // 没有笔记字段的旧记录仍能读取
+        let mut value = serde_json::to_value(&loaded.time_records[0]).unwrap();
+        value.as_object_mut().unwrap().remove("note");
+        let legacy: TimeRecord = serde_json::from_value(value).unwrap();
+        assert!(legacy.note.is_none());
+    }
+
+    #[test]
+    fn test_csv_export_escapes_special_characters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let name = "\"大\"客户, 二期";
+        let description = "第一行\n第二行 \"引用\"";
+        let project_id = project_manager
+            .add_project(name.to_string(), Some(description.to_string()))
+            .unwrap();
+        let start = chrono::Utc::now() - chrono::Duration::hours(1);
+        let event_id = event_manager
+            .add_project_event(
+                "修复 \"登录\" 问题".to_string(),
+                Some("a,b\nc".to_string()),
+                project_id,
+                Some(start),
+            )
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+
+        let csv_path = storage
+            .export_to_csv(&project_manager, &event_manager)
+            .unwrap();
+        let contents = fs::read_to_string(&csv_path).unwrap();
+        assert!(
+            contents.contains("项目,\"\"\"大\"\"客户, 二期\",\"第一行\n第二行 \"\"引用\"\"\",N/A")
+        );
+
+        // 每行都解析为7列，导入后内容不变
+        let rows = parse_csv(&contents);
+        assert!(rows.iter().all(|row| row.len() == 7));
+        let (imported, warnings) = storage
+            .import_from_csv(&csv_path, &ProjectManager::new())
+            .unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(imported.projects[0].name, name);
+        assert_eq!(
+            imported.projects[0].description.as_deref(),
+            Some(description)
+        );
+        assert_eq!(imported.events[0].title, "修复 \"登录\" 问题");
+        assert_eq!(imported.events[0].description.as_deref(), Some("a,b\nc"));
+        assert_eq!(imported.time_records.len(), 1);
+    }
+
+    #[test]
+    fn test_csv_import_skips_malformed_rows() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let csv_path = temp_dir.path().join("import.csv");
+        fs::write(
+            &csv_path,
+            "类型,名称,描述,项目,开始时间,结束时间,持续时间(分钟)\n\
+             项目,\"网站\",\"\",N/A,N/A,N/A,N/A\n\
+             事件,\"少了列\",\"\"\n\
+             事件,\"未知项目\",\"\",\"不存在\",\"2024-01-01 09:00:00\",\"N/A\",进行中\n\
+             事件,\"坏时间\",\"\",\"网站\",\"昨天\",\"N/A\",进行中\n\
+             事件,\"正常\",\"\",\"网站\",\"2024-01-01 09:00:00\",\"2024-01-01 10:00:00\",60\n",
+        )
+        .unwrap();
+
+        let (imported, warnings) = storage
+            .import_from_csv(&csv_path.to_string_lossy(), &ProjectManager::new())
+            .unwrap();
+        assert_eq!(imported.projects.len(), 1);
+        assert_eq!(imported.events.len(), 1);
+        assert_eq!(imported.time_records.len(), 1);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("第3行"));
+    }
+
     #[test]
     fn test_data_integrity_check() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -456,4 +2122,652 @@ mod tests {
         assert!(!issues.is_empty());
         assert!(issues.iter().any(|issue| issue.contains("项目ID重复")));
     }
+
+    #[test]
+    fn test_data_integrity_detects_clock_skew() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let now = Utc::now();
+
+        let mut app_data = AppData::new();
+        let event = Event::new(
+            "事件".to_string(),
+            None,
+            crate::models::EventType::NonProject,
+            now - chrono::Duration::hours(3),
+        );
+        let event_id = event.id;
+        app_data.events.push(event);
+        app_data.time_records.push(TimeRecord::new(
+            event_id,
+            None,
+            now - chrono::Duration::hours(3),
+            now - chrono::Duration::hours(2),
+        ));
+        assert!(storage.check_data_integrity(&app_data).is_empty());
+
+        // 结束时间早于开始时间
+        let reversed = TimeRecord::new(
+            event_id,
+            None,
+            now - chrono::Duration::hours(1),
+            now - chrono::Duration::hours(2),
+        );
+        assert!(reversed.duration_minutes < 0);
+        let reversed_id = reversed.id;
+        // 时间正常但保存的时长为负数
+        let mut negative = TimeRecord::new(event_id, None, now - chrono::Duration::hours(1), now);
+        negative.duration_minutes = -5;
+        let negative_id = negative.id;
+        // 开始时间在未来
+        let future = TimeRecord::new(
+            event_id,
+            None,
+            now + chrono::Duration::days(1),
+            now + chrono::Duration::days(1) + chrono::Duration::hours(1),
+        );
+        let future_id = future.id;
+        app_data.time_records.extend([reversed, negative, future]);
+
+        let issues = storage.check_data_integrity(&app_data);
+        assert_eq!(issues.len(), 3);
+        assert!(
+            issues[0].contains("结束时间早于开始时间")
+                && issues[0].contains(&reversed_id.to_string())
+        );
+        assert!(issues[1].contains("时长为负数") && issues[1].contains(&negative_id.to_string()));
+        assert!(issues[2].contains("开始时间在未来") && issues[2].contains(&future_id.to_string()));
+    }
+
+    #[test]
+    fn test_export_with_scope() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let storage = Storage::new(data_dir);
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let client_a = project_manager
+            .add_project("客户A".to_string(), None)
+            .unwrap();
+        let client_b = project_manager
+            .add_project("客户B".to_string(), None)
+            .unwrap();
+
+        let start = chrono::Utc::now();
+        let end = start + chrono::Duration::hours(1);
+        let event_a = event_manager
+            .add_project_event("A事件".to_string(), None, client_a, Some(start))
+            .unwrap();
+        let event_b = event_manager
+            .add_project_event("B事件".to_string(), None, client_b, Some(start))
+            .unwrap();
+        let personal = event_manager
+            .add_non_project_event("私事".to_string(), None, Some(start))
+            .unwrap();
+        for event_id in [event_a, event_b, personal] {
+            event_manager
+                .set_event_end_time(event_id, Some(end))
+                .unwrap();
+        }
+
+        let scope = ExportScope::Projects([client_a].into_iter().collect());
+        let export_path = storage
+            .export_with_scope(&project_manager, &event_manager, scope)
+            .unwrap();
+
+        let contents = fs::read_to_string(&export_path).unwrap();
+        let exported: AppData = serde_json::from_str(&contents).unwrap();
+        assert_eq!(exported.projects.len(), 1);
+        assert_eq!(exported.projects[0].id, client_a);
+        assert_eq!(exported.events.len(), 1);
+        assert_eq!(exported.events[0].id, event_a);
+        assert_eq!(exported.time_records.len(), 1);
+        assert_eq!(exported.time_records[0].project_id, Some(client_a));
+    }
+
+    #[test]
+    fn test_check_export_scope_reports_offenders() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let storage = Storage::new(data_dir);
+        let client_a = Uuid::new_v4();
+        let client_b = Uuid::new_v4();
+        let scope = ExportScope::Projects([client_a].into_iter().collect());
+
+        let start = chrono::Utc::now();
+        let event = Event::new(
+            "A事件".to_string(),
+            None,
+            EventType::ProjectRelated(client_a),
+            start,
+        );
+        let mut app_data = AppData::new();
+        app_data.time_records.push(TimeRecord::new(
+            event.id,
+            Some(client_a),
+            start,
+            start + chrono::Duration::hours(1),
+        ));
+        // 事件在范围内，但时间记录指向了其他客户的项目
+        let leaked = TimeRecord::new(
+            event.id,
+            Some(client_b),
+            start,
+            start + chrono::Duration::hours(1),
+        );
+        let leaked_id = leaked.id;
+        app_data.time_records.push(leaked);
+        app_data.events.push(event);
+
+        let filtered = app_data.filter_by_scope(&scope);
+        let offenders = storage.check_export_scope(&filtered, &scope);
+        assert_eq!(offenders.len(), 1);
+        assert!(offenders[0].contains(&leaked_id.to_string()));
+
+        assert!(storage
+            .check_export_scope(&filtered, &ExportScope::All)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cleanup_backups_older_than() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let backup_name = |days_ago: i64, extension: &str| {
+            let created_at = Utc::now() - chrono::Duration::days(days_ago);
+            format!(
+                "backup_{}.{}",
+                created_at.format("%Y%m%d_%H%M%S"),
+                extension
+            )
+        };
+        for (days_ago, extension) in [(1, "json"), (5, "json.gz"), (31, "json"), (90, "json.gz")] {
+            fs::write(temp_dir.path().join(backup_name(days_ago, extension)), "{}").unwrap();
+        }
+        fs::write(temp_dir.path().join("backup_old.json"), "{}").unwrap();
+
+        assert_eq!(storage.cleanup_backups_older_than(30).unwrap(), 2);
+        let remaining = storage.list_backups_detailed().unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[0].path.ends_with(".json"));
+        assert!(remaining[1].path.ends_with(".json.gz"));
+        // 文件名无法解析时间的文件不受影响
+        assert!(temp_dir.path().join("backup_old.json").exists());
+
+        assert_eq!(storage.cleanup_backups_older_than(30).unwrap(), 0);
+        assert_eq!(storage.cleanup_backups_older_than(0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_cleanup_old_exports() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let storage = Storage::new(data_dir.clone());
+
+        // 模拟之前运行留下的导出文件
+        for day in 1..=5 {
+            let path = format!("{}/export_202401{:02}_120000.csv", data_dir, day);
+            fs::write(path, "").unwrap();
+        }
+        fs::write(format!("{}/export_20231231_120000.xlsx", data_dir), "").unwrap();
+        fs::write(format!("{}/export_notes.csv", data_dir), "").unwrap();
+
+        let deleted = storage.cleanup_old_exports(2).unwrap();
+        assert_eq!(deleted, 4);
+
+        let remaining: Vec<String> = storage
+            .list_exports()
+            .unwrap()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[0].ends_with("export_20240105_120000.csv"));
+        assert!(remaining[1].ends_with("export_20240104_120000.csv"));
+        // 无法解析时间戳的文件不受影响
+        assert!(Path::new(&format!("{}/export_notes.csv", data_dir)).exists());
+    }
+
+    #[test]
+    fn test_cleanup_keeps_session_exports() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let storage = Storage::new(data_dir.clone());
+        let project_manager = ProjectManager::new();
+        let event_manager = EventManager::new();
+
+        fs::write(format!("{}/export_20240101_120000.csv", data_dir), "").unwrap();
+        let session_export = storage
+            .export_to_csv(&project_manager, &event_manager)
+            .unwrap();
+
+        let deleted = storage.cleanup_old_exports(0).unwrap();
+        assert_eq!(deleted, 1);
+        assert!(Path::new(&session_export).exists());
+    }
+
+    fn sample_app_data(project_name: &str, event_title: &str) -> AppData {
+        let project = Project::new(project_name.to_string(), None);
+        let start = chrono::Utc::now();
+        let mut event = Event::new(
+            event_title.to_string(),
+            None,
+            EventType::ProjectRelated(project.id),
+            start,
+        );
+        event.set_end_time(start + chrono::Duration::hours(1));
+        let record = TimeRecord::new(
+            event.id,
+            Some(project.id),
+            start,
+            start + chrono::Duration::hours(1),
+        );
+
+        let mut app_data = AppData::new();
+        app_data.projects.push(project);
+        app_data.events.push(event);
+        app_data.time_records.push(record);
+        app_data
+    }
+
+    fn duplicate_ids(source: &AppData, project_name: &str, event_title: &str) -> AppData {
+        let mut copy = sample_app_data(project_name, event_title);
+        copy.projects[0].id = source.projects[0].id;
+        copy.events[0].id = source.events[0].id;
+        copy.events[0].event_type = EventType::ProjectRelated(source.projects[0].id);
+        copy.time_records[0].id = source.time_records[0].id;
+        copy.time_records[0].event_id = source.events[0].id;
+        copy.time_records[0].project_id = Some(source.projects[0].id);
+        copy
+    }
+
+    #[test]
+    fn test_import_merge_remaps_colliding_ids() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let storage = Storage::new(data_dir);
+
+        let existing = sample_app_data("本地项目", "本地事件");
+        let incoming = duplicate_ids(&existing, "导入项目", "导入事件");
+        let original_project_id = existing.projects[0].id;
+        storage.save_app_data(&existing).unwrap();
+
+        let summary = storage
+            .import_app_data(incoming, ImportStrategy::Merge)
+            .unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 3,
+                skipped: 0,
+                remapped: 3,
+            }
+        );
+
+        let merged = storage.load_data().unwrap();
+        assert_eq!(merged.projects.len(), 2);
+        assert_eq!(merged.events.len(), 2);
+        assert_eq!(merged.time_records.len(), 2);
+        assert!(storage.check_data_integrity(&merged).is_empty());
+
+        // 导入的事件和记录应指向重新分配ID后的导入项目
+        let imported_project = merged
+            .projects
+            .iter()
+            .find(|p| p.name == "导入项目")
+            .unwrap();
+        assert_ne!(imported_project.id, original_project_id);
+        let imported_event = merged
+            .events
+            .iter()
+            .find(|e| e.title == "导入事件")
+            .unwrap();
+        assert!(matches!(
+            imported_event.event_type,
+            EventType::ProjectRelated(id) if id == imported_project.id
+        ));
+        let imported_record = merged
+            .time_records
+            .iter()
+            .find(|r| r.event_id == imported_event.id)
+            .unwrap();
+        assert_eq!(imported_record.project_id, Some(imported_project.id));
+    }
+
+    #[test]
+    fn test_import_skip_and_replace() {
+        let mut existing = sample_app_data("本地项目", "本地事件");
+        let incoming = duplicate_ids(&existing, "导入项目", "导入事件");
+        let summary = existing.import(incoming, ImportStrategy::SkipExisting);
+        assert_eq!(summary.skipped, 3);
+        assert_eq!(summary.imported, 0);
+        assert_eq!(existing.projects[0].name, "本地项目");
+
+        // 跳过的事件下ID不冲突的时间记录同样跳过，不挂到已有事件上
+        let mut incoming = duplicate_ids(&existing, "导入项目", "导入事件");
+        incoming.time_records[0].id = Uuid::new_v4();
+        let summary = existing.import(incoming, ImportStrategy::SkipExisting);
+        assert_eq!(summary.skipped, 3);
+        assert_eq!(summary.imported, 0);
+        assert_eq!(existing.time_records.len(), 1);
+
+        let incoming = duplicate_ids(&existing, "导入项目", "导入事件");
+        let summary = existing.import(incoming, ImportStrategy::Replace);
+        assert_eq!(summary.imported, 3);
+        assert_eq!(existing.projects.len(), 1);
+        assert_eq!(existing.projects[0].name, "导入项目");
+        assert_eq!(existing.events[0].title, "导入事件");
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let mut project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+        let project_id = project_manager
+            .add_project("流式导出".to_string(), None)
+            .unwrap();
+        let base = Utc::now() - chrono::Duration::days(30);
+
+        for i in 0..300 {
+            let start = base + chrono::Duration::minutes(i * 90);
+            let event_id = if i % 3 == 0 {
+                event_manager
+                    .add_non_project_event(format!("休息{}", i), None, Some(start))
+                    .unwrap()
+            } else {
+                event_manager
+                    .add_project_event(format!("任务{}", i), None, project_id, Some(start))
+                    .unwrap()
+            };
+            event_manager
+                .set_event_end_time(event_id, Some(start + chrono::Duration::minutes(45)))
+                .unwrap();
+        }
+
+        let app_data = AppData::from_managers(&project_manager, &event_manager);
+        let mut buffer: Vec<u8> = Vec::new();
+        Storage::export_jsonl(&app_data, &mut buffer).unwrap();
+
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert_eq!(text.lines().count(), 1 + 300 + 300);
+        assert!(text
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"kind\":\"project\""));
+
+        let imported = Storage::import_jsonl(buffer.as_slice()).unwrap();
+        assert_eq!(imported.projects.len(), 1);
+        assert_eq!(imported.projects[0].name, "流式导出");
+        assert_eq!(imported.events.len(), 300);
+        assert_eq!(imported.time_records.len(), 300);
+
+        let original: HashMap<Uuid, &TimeRecord> = app_data
+            .time_records
+            .iter()
+            .map(|record| (record.id, record))
+            .collect();
+        for record in &imported.time_records {
+            let expected = original[&record.id];
+            assert_eq!(record.project_id, expected.project_id);
+            assert_eq!(record.start_time, expected.start_time);
+            assert_eq!(record.duration_minutes, 45);
+        }
+
+        let bad = b"{\"kind\":\"project\",\"id\":1}\n";
+        let err = Storage::import_jsonl(&bad[..]).unwrap_err();
+        assert!(err.to_string().contains("第 1 行"));
+    }
+
+    #[test]
+    fn test_data_dir_lock() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+
+        let first = Storage::new(data_dir.clone());
+        let second = Storage::new(data_dir.clone());
+
+        let lock = first.acquire_lock().unwrap();
+        let err = second.acquire_lock().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(err.to_string().contains(&data_dir));
+
+        // 释放后另一个实例可以获取
+        drop(lock);
+        assert!(second.acquire_lock().is_ok());
+    }
+
+    #[test]
+    fn test_import_refused_while_locked() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let storage = Storage::new(data_dir.clone());
+        let existing = sample_app_data("本地项目", "本地事件");
+        storage.save_app_data(&existing).unwrap();
+
+        // 运行中的程序持有锁时导入失败，已保存的数据不变
+        let lock = Storage::new(data_dir).acquire_lock().unwrap();
+        let err = storage
+            .import_app_data(
+                sample_app_data("导入项目", "导入事件"),
+                ImportStrategy::Merge,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(storage.load_data().unwrap().projects.len(), 1);
+
+        drop(lock);
+        storage
+            .import_app_data(
+                sample_app_data("导入项目", "导入事件"),
+                ImportStrategy::Merge,
+            )
+            .unwrap();
+        assert_eq!(storage.load_data().unwrap().projects.len(), 2);
+    }
+
+    #[test]
+    fn test_trash_is_saved_and_loaded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let project_manager = ProjectManager::new();
+        let mut event_manager = EventManager::new();
+
+        let event_id = event_manager
+            .add_non_project_event("回收站中的事件".to_string(), None, None)
+            .unwrap();
+        event_manager.set_event_end_time(event_id, None).unwrap();
+        event_manager.delete_event(event_id).unwrap();
+        storage.save_data(&project_manager, &event_manager).unwrap();
+
+        let loaded = storage.load_data().unwrap();
+        assert_eq!(loaded.events.len(), 1);
+        assert!(loaded.events[0].deleted_at.is_some());
+        assert_eq!(loaded.time_records.len(), 1);
+
+        // 统计、完整性检查和导出都不包含回收站中的数据
+        assert_eq!(loaded.visible_events().count(), 0);
+        assert_eq!(loaded.visible_time_records().count(), 0);
+        assert!(storage
+            .load_data()
+            .unwrap()
+            .without_trash()
+            .time_records
+            .is_empty());
+
+        let mut dangling = storage.load_data().unwrap();
+        dangling.time_records[0].event_id = Uuid::new_v4();
+        dangling.time_records.push(loaded.time_records[0].clone());
+        assert_eq!(storage.check_data_integrity(&dangling).len(), 1);
+
+        let mut buffer = Vec::new();
+        Storage::export_jsonl(&loaded, &mut buffer).unwrap();
+        assert!(buffer.is_empty());
+
+        let export_path = storage
+            .export_with_scope(&project_manager, &event_manager, ExportScope::All)
+            .unwrap();
+        let exported: AppData =
+            serde_json::from_str(&fs::read_to_string(export_path).unwrap()).unwrap();
+        assert!(exported.events.is_empty());
+        assert!(exported.time_records.is_empty());
+    }
+
+    #[test]
+    fn test_import_from_toggl_csv() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let storage = Storage::new(data_dir.clone());
+
+        let csv_path = format!("{}/toggl.csv", data_dir);
+        fs::write(
+            &csv_path,
+            "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\n\
+             张三,z@example.com,,网站改版,,首页设计,Yes,2024-03-04,09:00:00,2024-03-04,10:30:00,01:30:00,\"设计, 前端\"\n\
+             张三,z@example.com,,网站改版,,接口联调,Yes,2024-03-04,11:00:00,2024-03-04,11:45:30,00:45:30,\n\
+             张三,z@example.com,,客户支持,,回复邮件,No,2024-03-04,14:00:00,2024-03-04,14:20:00,00:20:00,\n\
+             张三,z@example.com,,,,午休,No,2024-03-04,12:00:00,2024-03-04,13:00:00,01:00:00,\n\
+             张三,z@example.com,,,,,No,2024-03-05,22:00:00,2024-03-06,01:00:00,27:00:00,\n",
+        )
+        .unwrap();
+
+        let (app_data, warnings) = storage
+            .import_from_toggl_csv(&csv_path, &ProjectManager::new())
+            .unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let mut project_names: Vec<&str> =
+            app_data.projects.iter().map(|p| p.name.as_str()).collect();
+        project_names.sort();
+        assert_eq!(project_names, vec!["客户支持", "网站改版"]);
+        assert_eq!(app_data.events.len(), 5);
+        assert_eq!(app_data.time_records.len(), 5);
+
+        let website = app_data
+            .projects
+            .iter()
+            .find(|p| p.name == "网站改版")
+            .unwrap()
+            .id;
+        let durations: Vec<i64> = app_data
+            .time_records
+            .iter()
+            .map(|record| record.duration_minutes)
+            .collect();
+        assert_eq!(durations, vec![90, 45, 20, 60, 1620]);
+        assert_eq!(
+            app_data
+                .time_records
+                .iter()
+                .filter(|record| record.project_id == Some(website))
+                .count(),
+            2
+        );
+
+        let design = &app_data.events[0];
+        assert_eq!(design.title, "首页设计");
+        assert_eq!(design.tags, vec!["设计", "前端"]);
+        assert!(matches!(design.event_type, EventType::ProjectRelated(id) if id == website));
+        let lunch = &app_data.events[3];
+        assert_eq!(lunch.title, "午休");
+        assert!(matches!(lunch.event_type, EventType::NonProject));
+        assert_eq!(app_data.events[4].title, "(无描述)");
+
+        // 格式错误的行被跳过并给出警告，其余行照常导入
+        fs::write(
+            &csv_path,
+            "Project,Description,Start date,Start time,Duration\n\
+             网站改版,首页,2024-03-04,09:00:00,1:75:00\n\
+             网站改版,接口,2024-03-04,9点,01:00:00\n\
+             客户支持,回复邮件,2024-03-04,14:00:00,00:20:00\n",
+        )
+        .unwrap();
+        let (app_data, warnings) = storage
+            .import_from_toggl_csv(&csv_path, &ProjectManager::new())
+            .unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("第2行: 时长格式错误"));
+        assert!(warnings[1].starts_with("第3行: 开始时间格式错误"));
+        assert_eq!(app_data.events.len(), 1);
+        assert_eq!(app_data.events[0].title, "回复邮件");
+        assert_eq!(app_data.projects.len(), 1);
+        assert_eq!(app_data.projects[0].name, "客户支持");
+
+        fs::write(&csv_path, "Project,Description\n").unwrap();
+        assert!(storage
+            .import_from_toggl_csv(&csv_path, &ProjectManager::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_import_matches_project_names_fuzzily() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_string_lossy().to_string();
+        let storage = Storage::new(data_dir.clone());
+
+        let csv_path = format!("{}/toggl.csv", data_dir);
+        fs::write(
+            &csv_path,
+            "Project,Description,Start date,Start time,Duration\n\
+             Project X,设计,2024-03-04,09:00:00,01:00:00\n\
+             \"project  x \",评审,2024-03-04,11:00:00,00:30:00\n\
+             Project Y,测试,2024-03-04,14:00:00,00:20:00\n",
+        )
+        .unwrap();
+
+        let (app_data, _) = storage
+            .import_from_toggl_csv(&csv_path, &ProjectManager::new())
+            .unwrap();
+        let mut project_names: Vec<&str> =
+            app_data.projects.iter().map(|p| p.name.as_str()).collect();
+        project_names.sort();
+        assert_eq!(project_names, vec!["Project X", "Project Y"]);
+        let project_x = app_data.projects[0].id;
+        assert_eq!(
+            app_data
+                .time_records
+                .iter()
+                .filter(|record| record.project_id == Some(project_x))
+                .count(),
+            2
+        );
+
+        // 已有的项目不重复创建，记录直接关联到已有项目
+        let mut existing = ProjectManager::new();
+        let existing_x = existing.add_project("PROJECT X".to_string(), None).unwrap();
+        let (app_data, _) = storage.import_from_toggl_csv(&csv_path, &existing).unwrap();
+        assert_eq!(app_data.projects.len(), 1);
+        assert_eq!(app_data.projects[0].name, "Project Y");
+        assert_eq!(
+            app_data
+                .time_records
+                .iter()
+                .filter(|record| record.project_id == Some(existing_x))
+                .count(),
+            2
+        );
+
+        let csv_path = format!("{}/export.csv", data_dir);
+        fs::write(
+            &csv_path,
+            "类型,名称,描述,项目,开始时间,结束时间,时长(分钟)\n\
+             项目,project x ,,,,,\n\
+             事件,设计,,project x ,2024-03-04 09:00:00,2024-03-04 10:00:00,60\n",
+        )
+        .unwrap();
+        let (app_data, warnings) = storage.import_from_csv(&csv_path, &existing).unwrap();
+        assert!(warnings.is_empty());
+        assert!(app_data.projects.is_empty());
+        assert!(matches!(
+            app_data.events[0].event_type,
+            EventType::ProjectRelated(id) if id == existing_x
+        ));
+        assert_eq!(app_data.time_records[0].project_id, Some(existing_x));
+    }
 }
@@ -1,11 +1,51 @@
-use crate::models::{ProjectTimeBreakdown, TimeRecord};
-use chrono::{DateTime, Datelike, Utc};
+use crate::models::{
+    BreakdownOrder, ProjectTimeBreakdown, RangeStats, RoundingMode, Session, SessionConfig,
+    SessionProject, TimeRecord, TimelineBlock, WeekStart, WorkingDays,
+};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc};
 use std::collections::HashMap;
 use uuid::Uuid;
 
 pub struct TimeCalculator;
 
 impl TimeCalculator {
+    /// 计算时间记录落在指定范围内的分钟数（跨越边界的记录只计重叠部分），不重叠时返回None
+    pub fn clipped_minutes(
+        record: &TimeRecord,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Option<i64> {
+        if record.start_time >= start_time && record.end_time <= end_time {
+            return Some(record.duration_minutes);
+        }
+
+        // 范围结束时间通常是 23:59:59，按四舍五入换算成分钟避免少算最后一分钟
+        let seconds = Self::clipped_seconds(record, start_time, end_time)?;
+        Some((seconds + 30) / 60)
+    }
+
+    /// 时间记录与范围重叠的秒数。记录时长短于起止时间跨度时（如多个项目分摊的事件），
+    /// 重叠部分按时长占跨度的比例折算，避免每个项目都计入整段时间
+    fn clipped_seconds(
+        record: &TimeRecord,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Option<i64> {
+        let overlap_start = record.start_time.max(start_time);
+        let overlap_end = record.end_time.min(end_time);
+        if overlap_end <= overlap_start {
+            return None;
+        }
+
+        let seconds = (overlap_end - overlap_start).num_seconds();
+        let span_seconds = (record.end_time - record.start_time).num_seconds();
+        if record.duration_minutes < span_seconds / 60 {
+            Some(seconds * record.duration_minutes.max(0) * 60 / span_seconds)
+        } else {
+            Some(seconds)
+        }
+    }
+
     /// 计算指定时间范围内的项目内时间
     pub fn calculate_project_time(
         time_records: &[&TimeRecord],
@@ -14,12 +54,8 @@ impl TimeCalculator {
     ) -> i64 {
         time_records
             .iter()
-            .filter(|record| {
-                record.project_id.is_some()
-                    && record.start_time >= start_time
-                    && record.start_time <= end_time
-            })
-            .map(|record| record.duration_minutes)
+            .filter(|record| record.project_id.is_some())
+            .filter_map(|record| Self::clipped_minutes(record, start_time, end_time))
             .sum()
     }
 
@@ -31,12 +67,8 @@ impl TimeCalculator {
     ) -> i64 {
         time_records
             .iter()
-            .filter(|record| {
-                record.project_id.is_none()
-                    && record.start_time >= start_time
-                    && record.start_time <= end_time
-            })
-            .map(|record| record.duration_minutes)
+            .filter(|record| record.project_id.is_none())
+            .filter_map(|record| Self::clipped_minutes(record, start_time, end_time))
             .sum()
     }
 
@@ -47,17 +79,65 @@ impl TimeCalculator {
         start_time: Option<DateTime<Utc>>,
         end_time: Option<DateTime<Utc>>,
     ) -> i64 {
+        let start_time = start_time.unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let end_time = end_time.unwrap_or(DateTime::<Utc>::MAX_UTC);
+
         time_records
             .iter()
-            .filter(|record| {
-                record.project_id == Some(project_id)
-                    && start_time.map_or(true, |start| record.start_time >= start)
-                    && end_time.map_or(true, |end| record.start_time <= end)
+            .filter(|record| record.project_id == Some(project_id))
+            .filter_map(|record| Self::clipped_minutes(record, start_time, end_time))
+            .sum()
+    }
+
+    /// 计算指定项目的总时间，每条记录先按取整方式取整再求和
+    pub fn calculate_project_total_time_rounded(
+        time_records: &[&TimeRecord],
+        project_id: Uuid,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        rounding: RoundingMode,
+    ) -> i64 {
+        let start_time = start_time.unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let end_time = end_time.unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        time_records
+            .iter()
+            .filter(|record| record.project_id == Some(project_id))
+            .filter_map(|record| {
+                Self::rounded_record_minutes(record, start_time, end_time, rounding)
             })
-            .map(|record| record.duration_minutes)
             .sum()
     }
 
+    /// 单条记录落在范围内的分钟数，按取整方式取整
+    pub fn rounded_record_minutes(
+        record: &TimeRecord,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        rounding: RoundingMode,
+    ) -> Option<i64> {
+        let minutes = Self::clipped_minutes(record, start_time, end_time)?;
+        let seconds = Self::clipped_seconds(record, start_time, end_time).unwrap_or(0);
+        Some(match rounding {
+            RoundingMode::None => minutes,
+            RoundingMode::NearestMinute => (seconds + 30) / 60,
+            RoundingMode::Up15 => (seconds + 899) / 900 * 15,
+            RoundingMode::Nearest15 => (seconds + 450) / 900 * 15,
+        })
+    }
+
+    /// 去掉时长小于 min_duration_minutes 的记录（如误点产生的1分钟记录），0 表示不过滤
+    pub fn filter_min_duration<'a>(
+        time_records: &[&'a TimeRecord],
+        min_duration_minutes: i64,
+    ) -> Vec<&'a TimeRecord> {
+        time_records
+            .iter()
+            .copied()
+            .filter(|record| record.duration_minutes >= min_duration_minutes)
+            .collect()
+    }
+
     /// 生成项目时间分解
     pub fn generate_project_breakdown(
         time_records: &[&TimeRecord],
@@ -69,19 +149,18 @@ impl TimeCalculator {
 
         // 统计每个项目的总时间和事件数量
         for record in time_records {
-            if record.project_id.is_some()
-                && record.start_time >= start_time
-                && record.start_time <= end_time
-            {
-                let project_id = record.project_id.unwrap();
+            let Some(project_id) = record.project_id else {
+                continue;
+            };
+            if let Some(minutes) = Self::clipped_minutes(record, start_time, end_time) {
                 let entry = project_times.entry(project_id).or_insert((0, 0));
-                entry.0 += record.duration_minutes;
+                entry.0 += minutes;
                 entry.1 += 1;
             }
         }
 
         // 创建项目时间分解结构
-        project_times
+        let mut breakdown: Vec<ProjectTimeBreakdown> = project_times
             .into_iter()
             .map(
                 |(project_id, (total_time, event_count))| ProjectTimeBreakdown {
@@ -94,28 +173,95 @@ impl TimeCalculator {
                     event_count,
                 },
             )
-            .collect()
+            .collect();
+
+        // 默认按时间降序，保证每次生成的顺序一致
+        Self::sort_project_breakdown(&mut breakdown, &BreakdownOrder::default());
+        breakdown
+    }
+
+    /// 按指定方式排序项目时间分解，相同条件下按名称和ID保持稳定顺序
+    pub fn sort_project_breakdown(breakdown: &mut [ProjectTimeBreakdown], order: &BreakdownOrder) {
+        let by_time = |a: &ProjectTimeBreakdown, b: &ProjectTimeBreakdown| {
+            b.total_time_minutes
+                .cmp(&a.total_time_minutes)
+                .then_with(|| a.project_name.cmp(&b.project_name))
+                .then_with(|| a.project_id.cmp(&b.project_id))
+        };
+
+        match order {
+            BreakdownOrder::TimeDesc => breakdown.sort_by(by_time),
+            BreakdownOrder::Name => breakdown.sort_by(|a, b| {
+                a.project_name
+                    .cmp(&b.project_name)
+                    .then_with(|| a.project_id.cmp(&b.project_id))
+            }),
+            BreakdownOrder::Pinned(pinned) => {
+                let position = |id: &Uuid| pinned.iter().position(|p| p == id);
+                breakdown.sort_by(|a, b| {
+                    match (position(&a.project_id), position(&b.project_id)) {
+                        (Some(x), Some(y)) => x.cmp(&y),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => by_time(a, b),
+                    }
+                });
+            }
+        }
+    }
+
+    /// 获取一周的开始时间（周一或周日的 00:00:00，由first_day决定）
+    pub fn get_week_start(date: DateTime<Utc>, first_day: WeekStart) -> DateTime<Utc> {
+        Self::get_week_start_tz(date, first_day, utc_offset())
     }
 
-    /// 获取一周的开始时间（周一）
-    pub fn get_week_start(date: DateTime<Utc>) -> DateTime<Utc> {
-        let days_since_monday = date.weekday().num_days_from_monday();
-        date - chrono::Duration::days(days_since_monday as i64)
+    /// 获取一周的结束时间（一周最后一天的 23:59:59）
+    pub fn get_week_end(date: DateTime<Utc>, first_day: WeekStart) -> DateTime<Utc> {
+        Self::get_week_end_tz(date, first_day, utc_offset())
     }
 
-    /// 获取一周的结束时间（周日）
-    pub fn get_week_end(date: DateTime<Utc>) -> DateTime<Utc> {
-        let days_until_sunday = 6 - date.weekday().num_days_from_monday();
-        date + chrono::Duration::days(days_until_sunday as i64)
+    /// 按指定时区的日历获取一周的开始时间，返回对应的 UTC 时刻
+    pub fn get_week_start_tz(
+        date: DateTime<Utc>,
+        first_day: WeekStart,
+        tz: FixedOffset,
+    ) -> DateTime<Utc> {
+        let local_date = date.with_timezone(&tz).date_naive();
+        let days_since_start = first_day.days_from_start(local_date.weekday());
+        local_to_utc(
+            local_date - chrono::Duration::days(days_since_start as i64),
+            0,
+            0,
+            0,
+            tz,
+        )
+    }
+
+    /// 按指定时区的日历获取一周的结束时间，返回对应的 UTC 时刻
+    pub fn get_week_end_tz(
+        date: DateTime<Utc>,
+        first_day: WeekStart,
+        tz: FixedOffset,
+    ) -> DateTime<Utc> {
+        let local_date = date.with_timezone(&tz).date_naive();
+        let days_until_end = 6 - first_day.days_from_start(local_date.weekday());
+        local_to_utc(
+            local_date + chrono::Duration::days(days_until_end as i64),
+            23,
+            59,
+            59,
+            tz,
+        )
     }
 
     /// 获取指定日期所在周的所有时间记录
     pub fn get_week_time_records<'a>(
         time_records: &'a [&TimeRecord],
         date: DateTime<Utc>,
+        first_day: WeekStart,
     ) -> Vec<&'a TimeRecord> {
-        let week_start = Self::get_week_start(date);
-        let week_end = Self::get_week_end(date);
+        let week_start = Self::get_week_start(date, first_day);
+        let week_end = Self::get_week_end(date, first_day);
 
         time_records
             .iter()
@@ -126,8 +272,18 @@ impl TimeCalculator {
 
     /// 计算每日时间统计
     pub fn calculate_daily_stats(time_records: &[&TimeRecord], date: DateTime<Utc>) -> (i64, i64) {
-        let day_start = date.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
-        let day_end = date.date_naive().and_hms_opt(23, 59, 59).unwrap().and_utc();
+        Self::calculate_daily_stats_tz(time_records, date, utc_offset())
+    }
+
+    /// 按指定时区的日历计算每日时间统计
+    pub fn calculate_daily_stats_tz(
+        time_records: &[&TimeRecord],
+        date: DateTime<Utc>,
+        tz: FixedOffset,
+    ) -> (i64, i64) {
+        let local_date = date.with_timezone(&tz).date_naive();
+        let day_start = local_to_utc(local_date, 0, 0, 0, tz);
+        let day_end = local_to_utc(local_date, 23, 59, 59, tz);
 
         let project_time = Self::calculate_project_time(time_records, day_start, day_end);
         let non_project_time = Self::calculate_non_project_time(time_records, day_start, day_end);
@@ -135,41 +291,194 @@ impl TimeCalculator {
         (project_time, non_project_time)
     }
 
-    /// 计算每周时间统计
-    pub fn calculate_weekly_stats(time_records: &[&TimeRecord], date: DateTime<Utc>) -> (i64, i64) {
-        let week_start = Self::get_week_start(date);
-        let week_end = Self::get_week_end(date);
+    /// 一次遍历把时间记录按tz时区的日期分组，得到范围内每天的（项目内, 项目外）时间，
+    /// 结果与逐天调用 calculate_daily_stats_tz 相同，没有记录的日期不出现在结果中
+    pub fn daily_stats_tz(
+        time_records: &[&TimeRecord],
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        tz: FixedOffset,
+    ) -> HashMap<NaiveDate, (i64, i64)> {
+        let first_date = start_time.with_timezone(&tz).date_naive();
+        let last_date = end_time.with_timezone(&tz).date_naive();
+        // 每天的起止时间只计算一次
+        let days: Vec<(NaiveDate, DateTime<Utc>, DateTime<Utc>)> = first_date
+            .iter_days()
+            .take_while(|date| *date <= last_date)
+            .map(|date| {
+                (
+                    date,
+                    local_to_utc(date, 0, 0, 0, tz),
+                    local_to_utc(date, 23, 59, 59, tz),
+                )
+            })
+            .collect();
+        let mut totals = vec![(0, 0, false); days.len()];
+        let day_index =
+            |time: DateTime<Utc>| (time.with_timezone(&tz).date_naive() - first_date).num_days();
 
-        let project_time = Self::calculate_project_time(time_records, week_start, week_end);
-        let non_project_time = Self::calculate_non_project_time(time_records, week_start, week_end);
+        for record in time_records {
+            let start_index = day_index(record.start_time);
+            let end_index = day_index(record.end_time);
+            let first = start_index.min(end_index).max(0);
+            let last = start_index.max(end_index).min(days.len() as i64 - 1);
 
+            for index in first..=last {
+                let (_, day_start, day_end) = days[index as usize];
+                if let Some(minutes) = Self::clipped_minutes(record, day_start, day_end) {
+                    let entry = &mut totals[index as usize];
+                    if record.project_id.is_some() {
+                        entry.0 += minutes;
+                    } else {
+                        entry.1 += minutes;
+                    }
+                    entry.2 = true;
+                }
+            }
+        }
+
+        days.iter()
+            .zip(totals)
+            .filter(|(_, (_, _, has_records))| *has_records)
+            .map(|((date, _, _), (project, non_project, _))| (*date, (project, non_project)))
+            .collect()
+    }
+
+    /// 只统计指定时间范围内按tz时区划分的工作日的项目内和项目外时间
+    pub fn calculate_working_day_time_tz(
+        time_records: &[&TimeRecord],
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        working_days: WorkingDays,
+        tz: FixedOffset,
+    ) -> (i64, i64) {
+        if working_days.is_all() {
+            return (
+                Self::calculate_project_time(time_records, start_time, end_time),
+                Self::calculate_non_project_time(time_records, start_time, end_time),
+            );
+        }
+
+        let last_date = end_time.with_timezone(&tz).date_naive();
+        let mut project_time = 0;
+        let mut non_project_time = 0;
+        for date in start_time
+            .with_timezone(&tz)
+            .date_naive()
+            .iter_days()
+            .take_while(|date| *date <= last_date)
+            .filter(|date| working_days.contains(date.weekday()))
+        {
+            let day_start = local_to_utc(date, 0, 0, 0, tz).max(start_time);
+            let day_end = local_to_utc(date, 23, 59, 59, tz).min(end_time);
+            project_time += Self::calculate_project_time(time_records, day_start, day_end);
+            non_project_time += Self::calculate_non_project_time(time_records, day_start, day_end);
+        }
         (project_time, non_project_time)
     }
 
-    /// 计算每月时间统计
-    pub fn calculate_monthly_stats(
+    /// 一年中每一天的记录总时间（含项目外），没有记录的日期为0，跨天的记录按各天的重叠部分统计
+    pub fn daily_totals_for_year(time_records: &[&TimeRecord], year: i32) -> Vec<(NaiveDate, i64)> {
+        Self::daily_totals_for_year_tz(time_records, year, utc_offset())
+    }
+
+    /// 一年中每一天的记录总时间，按tz时区的日历划分每一天
+    pub fn daily_totals_for_year_tz(
         time_records: &[&TimeRecord],
         year: i32,
-        month: u32,
+        tz: FixedOffset,
+    ) -> Vec<(NaiveDate, i64)> {
+        let (Some(first_day), Some(next_year)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        ) else {
+            return Vec::new();
+        };
+        let mut totals: Vec<(NaiveDate, i64)> = first_day
+            .iter_days()
+            .take_while(|date| *date < next_year)
+            .map(|date| (date, 0))
+            .collect();
+
+        for record in time_records {
+            let first = record
+                .start_time
+                .with_timezone(&tz)
+                .date_naive()
+                .max(first_day);
+            let last = record
+                .end_time
+                .with_timezone(&tz)
+                .date_naive()
+                .min(next_year.pred_opt().unwrap());
+            for date in first.iter_days().take_while(|date| *date <= last) {
+                let day_start = local_to_utc(date, 0, 0, 0, tz);
+                let day_end = local_to_utc(date, 23, 59, 59, tz);
+                if let Some(minutes) = Self::clipped_minutes(record, day_start, day_end) {
+                    totals[date.ordinal0() as usize].1 += minutes;
+                }
+            }
+        }
+
+        totals
+    }
+
+    /// 计算每周时间统计
+    pub fn calculate_weekly_stats(
+        time_records: &[&TimeRecord],
+        date: DateTime<Utc>,
+        first_day: WeekStart,
     ) -> (i64, i64) {
-        let month_start = chrono::NaiveDate::from_ymd_opt(year, month, 1)
-            .unwrap()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_utc();
+        let week_start = Self::get_week_start(date, first_day);
+        let week_end = Self::get_week_end(date, first_day);
 
-        let next_month = if month == 12 {
-            (year + 1, 1)
-        } else {
-            (year, month + 1)
-        };
+        let project_time = Self::calculate_project_time(time_records, week_start, week_end);
+        let non_project_time = Self::calculate_non_project_time(time_records, week_start, week_end);
+
+        (project_time, non_project_time)
+    }
 
-        let month_end = chrono::NaiveDate::from_ymd_opt(next_month.0, next_month.1, 1)
+    /// 获取某月第一天的 00:00:00
+    pub fn get_month_start(year: i32, month: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(year, month, 1)
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap()
             .and_utc()
-            - chrono::Duration::seconds(1);
+    }
+
+    /// 获取从某月开始、连续若干个月的时间范围（结束于最后一天的 23:59:59）
+    pub fn get_month_range(year: i32, month: u32, months: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+        Self::get_month_range_tz(year, month, months, utc_offset())
+    }
+
+    /// 按指定时区的日历获取连续若干个月的时间范围，返回对应的 UTC 时刻
+    pub fn get_month_range_tz(
+        year: i32,
+        month: u32,
+        months: u32,
+        tz: FixedOffset,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let first_day = |year: i32, month: u32| NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        let start = local_to_utc(first_day(year, month), 0, 0, 0, tz);
+        let next = month - 1 + months;
+        let end = local_to_utc(
+            first_day(year + (next / 12) as i32, next % 12 + 1),
+            0,
+            0,
+            0,
+            tz,
+        ) - chrono::Duration::seconds(1);
+        (start, end)
+    }
+
+    /// 计算每月时间统计
+    pub fn calculate_monthly_stats(
+        time_records: &[&TimeRecord],
+        year: i32,
+        month: u32,
+    ) -> (i64, i64) {
+        let (month_start, month_end) = Self::get_month_range(year, month, 1);
 
         let project_time = Self::calculate_project_time(time_records, month_start, month_end);
         let non_project_time =
@@ -178,6 +487,206 @@ impl TimeCalculator {
         (project_time, non_project_time)
     }
 
+    /// 计算任意时间范围内的汇总统计（项目内外时间、效率和项目分解）
+    pub fn calculate_range_stats(
+        time_records: &[&TimeRecord],
+        project_names: &HashMap<Uuid, String>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> RangeStats {
+        RangeStats {
+            start_time,
+            end_time,
+            project_time_minutes: Self::calculate_project_time(time_records, start_time, end_time),
+            non_project_time_minutes: Self::calculate_non_project_time(
+                time_records,
+                start_time,
+                end_time,
+            ),
+            efficiency: Self::get_efficiency_stats(time_records, start_time, end_time),
+            project_breakdown: Self::generate_project_breakdown(
+                time_records,
+                project_names,
+                start_time,
+                end_time,
+            ),
+        }
+    }
+
+    /// 计算时间窗口内没有被任何时间记录覆盖的分钟数（重叠的记录合并计算）
+    pub fn calculate_idle_time(
+        time_records: &[&TimeRecord],
+        day_start: DateTime<Utc>,
+        day_end: DateTime<Utc>,
+    ) -> i64 {
+        Self::idle_gaps(time_records, day_start, day_end)
+            .iter()
+            .map(|(start, end)| Self::rounded_minutes(*start, *end))
+            .sum()
+    }
+
+    /// 查找时间窗口内最长的空档，返回开始时间和分钟数，时长相同时取较早的
+    pub fn find_largest_gap(
+        time_records: &[&TimeRecord],
+        day_start: DateTime<Utc>,
+        day_end: DateTime<Utc>,
+    ) -> Option<(DateTime<Utc>, i64)> {
+        Self::idle_gaps(time_records, day_start, day_end)
+            .into_iter()
+            .map(|(start, end)| (start, Self::rounded_minutes(start, end)))
+            .max_by_key(|(start, minutes)| (*minutes, std::cmp::Reverse(*start)))
+    }
+
+    /// 时间窗口内未被记录覆盖的区间（按时间顺序）
+    pub fn idle_gaps(
+        time_records: &[&TimeRecord],
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut covered: Vec<(DateTime<Utc>, DateTime<Utc>)> = time_records
+            .iter()
+            .map(|record| {
+                (
+                    record.start_time.max(window_start),
+                    record.end_time.min(window_end),
+                )
+            })
+            .filter(|(start, end)| end > start)
+            .collect();
+        covered.sort();
+
+        let mut gaps = Vec::new();
+        let mut cursor = window_start;
+        for (start, end) in covered {
+            if start > cursor {
+                gaps.push((cursor, start));
+            }
+            cursor = cursor.max(end);
+        }
+        if window_end > cursor {
+            gaps.push((cursor, window_end));
+        }
+        gaps
+    }
+
+    /// 两个时间点之间的分钟数，按四舍五入换算（窗口通常结束于 23:59:59）
+    fn rounded_minutes(start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
+        ((end - start).num_seconds() + 30) / 60
+    }
+
+    /// 按规则把时间记录合并为连续工作时段（按开始时间排序）
+    pub fn sessions(time_records: &[&TimeRecord], config: &SessionConfig) -> Vec<Session> {
+        let mut records: Vec<&TimeRecord> = time_records.to_vec();
+        records.sort_by_key(|record| (record.start_time, record.end_time));
+
+        let project_of = |record: &TimeRecord| match record.project_id {
+            Some(id) => SessionProject::Project(id),
+            None => SessionProject::NonProject,
+        };
+
+        let mut sessions: Vec<Session> = Vec::new();
+        for record in records {
+            let project = project_of(record);
+
+            if let Some(session) = sessions.last_mut() {
+                let gap = (record.start_time - session.end_time).num_minutes();
+                let project_matches = !config.same_project_only || session.project == project;
+                if gap <= config.max_gap_minutes && project_matches {
+                    // 只累计与当前时段不重叠的部分
+                    let new_start = record.start_time.max(session.end_time);
+                    if record.end_time > new_start {
+                        session.active_minutes += (record.end_time - new_start).num_minutes();
+                        session.end_time = record.end_time;
+                    }
+                    if session.project != project {
+                        session.project = SessionProject::Mixed;
+                    }
+                    if !session.event_ids.contains(&record.event_id) {
+                        session.event_ids.push(record.event_id);
+                    }
+                    continue;
+                }
+            }
+
+            sessions.push(Session {
+                start_time: record.start_time,
+                end_time: record.end_time,
+                active_minutes: record.duration_minutes,
+                project,
+                event_ids: vec![record.event_id],
+            });
+        }
+
+        sessions
+    }
+
+    /// 生成指定日期的时间线，跨越零点的记录会被裁剪到当天，重叠的记录并排分列
+    pub fn get_day_timeline(time_records: &[&TimeRecord], date: NaiveDate) -> Vec<TimelineBlock> {
+        Self::get_day_timeline_tz(time_records, date, utc_offset())
+    }
+
+    /// 某一天的时间线，按tz时区的当地日期和时刻计算
+    pub fn get_day_timeline_tz(
+        time_records: &[&TimeRecord],
+        date: NaiveDate,
+        tz: FixedOffset,
+    ) -> Vec<TimelineBlock> {
+        let day_start = local_to_utc(date, 0, 0, 0, tz);
+        let day_end = day_start + chrono::Duration::days(1);
+
+        let mut blocks: Vec<TimelineBlock> = time_records
+            .iter()
+            .filter(|record| record.start_time < day_end && record.end_time > day_start)
+            .map(|record| TimelineBlock {
+                record_id: record.id,
+                event_id: record.event_id,
+                project_id: record.project_id,
+                start_minute: (record.start_time.max(day_start) - day_start).num_minutes(),
+                end_minute: (record.end_time.min(day_end) - day_start).num_minutes(),
+                column: 0,
+                column_count: 1,
+            })
+            .collect();
+        blocks.sort_by_key(|block| (block.start_minute, block.end_minute));
+
+        // 按相互重叠的分组分配列
+        let mut group_start = 0;
+        let mut group_end = i64::MIN;
+        let mut column_ends: Vec<i64> = Vec::new();
+        for index in 0..blocks.len() {
+            if blocks[index].start_minute >= group_end {
+                Self::set_column_count(&mut blocks[group_start..index], column_ends.len());
+                group_start = index;
+                column_ends.clear();
+            }
+
+            let block = &mut blocks[index];
+            match column_ends
+                .iter()
+                .position(|end| *end <= block.start_minute)
+            {
+                Some(column) => {
+                    block.column = column;
+                    column_ends[column] = block.end_minute;
+                }
+                None => {
+                    block.column = column_ends.len();
+                    column_ends.push(block.end_minute);
+                }
+            }
+            group_end = group_end.max(block.end_minute);
+        }
+        Self::set_column_count(&mut blocks[group_start..], column_ends.len());
+
+        blocks
+    }
+
+    fn set_column_count(blocks: &mut [TimelineBlock], column_count: usize) {
+        for block in blocks {
+            block.column_count = column_count;
+        }
+    }
+
     /// 格式化分钟数为可读格式
     pub fn format_duration(minutes: i64) -> String {
         if minutes < 60 {
@@ -206,6 +715,20 @@ impl TimeCalculator {
         }
     }
 
+    /// 全部记录中项目内分钟数减去项目外分钟数，正数表示项目时间更多
+    pub fn net_project_balance(time_records: &[&TimeRecord]) -> i64 {
+        time_records
+            .iter()
+            .map(|record| {
+                if record.project_id.is_some() {
+                    record.duration_minutes
+                } else {
+                    -record.duration_minutes
+                }
+            })
+            .sum()
+    }
+
     /// 获取时间效率统计
     pub fn get_efficiency_stats(
         time_records: &[&TimeRecord],
@@ -230,23 +753,30 @@ impl TimeCalculator {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Vec<(String, i64)> {
-        let mut breakdown =
-            Self::generate_project_breakdown(time_records, project_names, start_time, end_time);
-
-        // 按时间降序排序
-        breakdown.sort_by(|a, b| b.total_time_minutes.cmp(&a.total_time_minutes));
-
-        breakdown
+        // 项目分解默认已按时间降序排序
+        Self::generate_project_breakdown(time_records, project_names, start_time, end_time)
             .into_iter()
             .map(|item| (item.project_name, item.total_time_minutes))
             .collect()
     }
 }
 
+/// UTC 时区，报表默认使用
+pub fn utc_offset() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+/// 把某时区的本地日期和时刻转换为 UTC 时刻
+fn local_to_utc(date: NaiveDate, hour: u32, min: u32, sec: u32, tz: FixedOffset) -> DateTime<Utc> {
+    tz.from_local_datetime(&date.and_hms_opt(hour, min, sec).unwrap())
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Duration;
+    use chrono::{Duration, Weekday};
 
     fn create_test_time_record(
         project_id: Option<Uuid>,
@@ -257,6 +787,112 @@ mod tests {
         TimeRecord::new(Uuid::new_v4(), project_id, start_time, end_time)
     }
 
+    #[test]
+    fn test_daily_stats_match_per_day_calculation() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let week_start = NaiveDate::from_ymd_opt(2024, 3, 3)
+            .unwrap()
+            .and_hms_opt(16, 0, 0)
+            .unwrap()
+            .and_utc();
+        let week_end = week_start + Duration::days(7) - Duration::seconds(1);
+        let project_id = Some(Uuid::new_v4());
+
+        // 伪随机生成大量记录，包含跨天、跨越范围边界和结束早于开始的记录
+        let mut seed: i64 = 42;
+        let mut next = |modulo: i64| {
+            seed = (seed * 1_103_515_245 + 12_345) % 2_147_483_648;
+            seed % modulo
+        };
+        let records: Vec<TimeRecord> = (0..50_000)
+            .map(|i| {
+                let start = week_start - Duration::days(1) + Duration::seconds(next(9 * 86_400));
+                let length = Duration::seconds(next(30 * 3600) - 600);
+                let project = if i % 3 == 0 { None } else { project_id };
+                TimeRecord::new(Uuid::new_v4(), project, start, start + length)
+            })
+            .collect();
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let stats = TimeCalculator::daily_stats_tz(&refs, week_start, week_end, tz);
+        for offset in 0..7 {
+            let day = week_start + Duration::days(offset);
+            let expected = TimeCalculator::calculate_daily_stats_tz(&refs, day, tz);
+            let date = day.with_timezone(&tz).date_naive();
+            assert_eq!(stats.get(&date).copied().unwrap_or_default(), expected);
+        }
+        assert_eq!(stats.len(), 7);
+    }
+
+    #[test]
+    fn test_working_day_time_skips_weekends() {
+        // 2024-03-04 是周一
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let project_id = Some(Uuid::new_v4());
+        let weekday_work = create_test_time_record(project_id, monday, 240);
+        let weekday_break = create_test_time_record(None, monday + Duration::hours(5), 60);
+        let saturday_chores = create_test_time_record(None, monday + Duration::days(5), 180);
+        let records = vec![&weekday_work, &weekday_break, &saturday_chores];
+        let week_start = monday - Duration::hours(9);
+        let week_end = week_start + Duration::days(7) - Duration::seconds(1);
+
+        let all = TimeCalculator::calculate_working_day_time_tz(
+            &records,
+            week_start,
+            week_end,
+            WorkingDays::all(),
+            utc_offset(),
+        );
+        assert_eq!(all, (240, 240));
+
+        let weekdays = TimeCalculator::calculate_working_day_time_tz(
+            &records,
+            week_start,
+            week_end,
+            WorkingDays::monday_to_friday(),
+            utc_offset(),
+        );
+        assert_eq!(weekdays, (240, 60));
+
+        // 只统计周六
+        let saturday = TimeCalculator::calculate_working_day_time_tz(
+            &records,
+            week_start,
+            week_end,
+            WorkingDays::from_weekdays(&[Weekday::Sat]),
+            utc_offset(),
+        );
+        assert_eq!(saturday, (0, 180));
+        assert!(WorkingDays::default().is_all());
+        assert!(!WorkingDays::monday_to_friday().contains(Weekday::Sun));
+    }
+
+    #[test]
+    fn test_net_project_balance() {
+        let base_time = Utc::now() - Duration::days(30);
+        let project_a = create_test_time_record(Some(Uuid::new_v4()), base_time, 120);
+        let project_b =
+            create_test_time_record(Some(Uuid::new_v4()), base_time + Duration::days(10), 45);
+        let meeting = create_test_time_record(None, base_time + Duration::days(20), 90);
+
+        assert_eq!(
+            TimeCalculator::net_project_balance(&[&project_a, &project_b, &meeting]),
+            75
+        );
+        assert_eq!(TimeCalculator::net_project_balance(&[&meeting]), -90);
+
+        let break_time = create_test_time_record(None, base_time, 75);
+        assert_eq!(
+            TimeCalculator::net_project_balance(&[&project_a, &meeting, &break_time, &project_b]),
+            0
+        );
+        assert_eq!(TimeCalculator::net_project_balance(&[]), 0);
+    }
+
     #[test]
     fn test_calculate_project_time() {
         let project_id = Uuid::new_v4();
@@ -312,16 +948,32 @@ mod tests {
             .unwrap()
             .and_utc();
 
-        let week_start = TimeCalculator::get_week_start(test_date);
-        let week_end = TimeCalculator::get_week_end(test_date);
+        let week_start = TimeCalculator::get_week_start(test_date, WeekStart::Monday);
+        let week_end = TimeCalculator::get_week_end(test_date, WeekStart::Monday);
 
-        // 周一应该是1月8日
+        // 周一应该是1月8日零点
         assert_eq!(week_start.date_naive().day(), 8);
         assert_eq!(week_start.weekday(), Weekday::Mon);
+        assert_eq!(week_start.time(), chrono::NaiveTime::MIN);
 
-        // 周日应该是1月14日
+        // 周日应该是1月14日且覆盖到当天结束
         assert_eq!(week_end.date_naive().day(), 14);
         assert_eq!(week_end.weekday(), Weekday::Sun);
+        assert_eq!(
+            week_end.time(),
+            chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+        );
+
+        // 周日晚上的记录应计入本周，即使查询时间是周三中午
+        let sunday_evening = week_end - Duration::hours(2);
+        let monday_morning = week_start + Duration::hours(1);
+        let late = create_test_time_record(None, sunday_evening, 60);
+        let early = create_test_time_record(Some(Uuid::new_v4()), monday_morning, 30);
+        let records = vec![&late, &early];
+        assert_eq!(
+            TimeCalculator::calculate_weekly_stats(&records, test_date, WeekStart::Monday),
+            (30, 60)
+        );
     }
 
     #[test]
@@ -342,4 +994,451 @@ mod tests {
         // 项目时间60分钟，总时间90分钟，效率应该是66.67%
         assert!((efficiency - 66.67).abs() < 0.01);
     }
+
+    #[test]
+    fn test_breakdown_ordering() {
+        let alpha = Uuid::new_v4();
+        let beta = Uuid::new_v4();
+        let gamma = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let records = [
+            create_test_time_record(Some(alpha), base_time, 30),
+            create_test_time_record(Some(beta), base_time, 90),
+            create_test_time_record(Some(gamma), base_time, 60),
+        ];
+        let record_refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let mut project_names = HashMap::new();
+        project_names.insert(alpha, "Alpha".to_string());
+        project_names.insert(beta, "Beta".to_string());
+        project_names.insert(gamma, "Gamma".to_string());
+
+        let names = |breakdown: &[ProjectTimeBreakdown]| -> Vec<String> {
+            breakdown.iter().map(|b| b.project_name.clone()).collect()
+        };
+
+        let mut breakdown = TimeCalculator::generate_project_breakdown(
+            &record_refs,
+            &project_names,
+            base_time - Duration::hours(1),
+            base_time + Duration::hours(1),
+        );
+        assert_eq!(names(&breakdown), vec!["Beta", "Gamma", "Alpha"]);
+
+        TimeCalculator::sort_project_breakdown(&mut breakdown, &BreakdownOrder::Name);
+        assert_eq!(names(&breakdown), vec!["Alpha", "Beta", "Gamma"]);
+
+        TimeCalculator::sort_project_breakdown(
+            &mut breakdown,
+            &BreakdownOrder::Pinned(vec![alpha]),
+        );
+        assert_eq!(names(&breakdown), vec!["Alpha", "Beta", "Gamma"]);
+
+        TimeCalculator::sort_project_breakdown(
+            &mut breakdown,
+            &BreakdownOrder::Pinned(vec![gamma, alpha]),
+        );
+        assert_eq!(names(&breakdown), vec!["Gamma", "Alpha", "Beta"]);
+    }
+
+    #[test]
+    fn test_day_timeline() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let at = |hour: u32, minute: u32| date.and_hms_opt(hour, minute, 0).unwrap().and_utc();
+
+        // 前一天晚上开始、跨越零点的记录
+        let overnight = create_test_time_record(None, at(0, 0) - Duration::hours(1), 120);
+        let first = create_test_time_record(None, at(9, 0), 60);
+        let overlapping = create_test_time_record(None, at(9, 30), 60);
+        let later = create_test_time_record(None, at(14, 0), 30);
+        let other_day = create_test_time_record(None, at(0, 0) + Duration::days(1), 30);
+        let records = vec![&overnight, &first, &overlapping, &later, &other_day];
+
+        let timeline = TimeCalculator::get_day_timeline(&records, date);
+        assert_eq!(timeline.len(), 4);
+
+        assert_eq!(timeline[0].record_id, overnight.id);
+        assert_eq!((timeline[0].start_minute, timeline[0].end_minute), (0, 60));
+        assert_eq!(timeline[0].column_count, 1);
+
+        assert_eq!(timeline[1].record_id, first.id);
+        assert_eq!(timeline[2].record_id, overlapping.id);
+        assert_eq!((timeline[1].column, timeline[2].column), (0, 1));
+        assert_eq!((timeline[1].column_count, timeline[2].column_count), (2, 2));
+
+        assert_eq!(timeline[3].record_id, later.id);
+        assert_eq!(
+            (timeline[3].start_minute, timeline[3].end_minute),
+            (840, 870)
+        );
+        assert_eq!(timeline[3].column_count, 1);
+    }
+
+    #[test]
+    fn test_sessions_gap_and_project_rules() {
+        let project_a = Uuid::new_v4();
+        let project_b = Uuid::new_v4();
+        let base_time = Utc::now();
+
+        let a1 = create_test_time_record(Some(project_a), base_time, 30);
+        // 间隔5分钟
+        let a2 = create_test_time_record(Some(project_a), base_time + Duration::minutes(35), 25);
+        // 紧接着切换到另一个项目
+        let b1 = create_test_time_record(Some(project_b), base_time + Duration::minutes(60), 30);
+        // 间隔1小时
+        let a3 = create_test_time_record(Some(project_a), base_time + Duration::minutes(150), 20);
+        let records = vec![&a3, &b1, &a1, &a2];
+
+        let strict = TimeCalculator::sessions(&records, &SessionConfig::default());
+        assert_eq!(strict.len(), 3);
+        assert_eq!(strict[0].active_minutes, 55);
+        assert_eq!(strict[0].project, SessionProject::Project(project_a));
+        assert_eq!(strict[0].event_ids, vec![a1.event_id, a2.event_id]);
+        assert_eq!(strict[1].project, SessionProject::Project(project_b));
+        assert_eq!(strict[2].active_minutes, 20);
+
+        let mixed = TimeCalculator::sessions(
+            &records,
+            &SessionConfig {
+                max_gap_minutes: 5,
+                same_project_only: false,
+            },
+        );
+        assert_eq!(mixed.len(), 2);
+        assert_eq!(mixed[0].active_minutes, 85);
+        assert_eq!(mixed[0].project, SessionProject::Mixed);
+        assert_eq!(mixed[0].end_time, b1.end_time);
+
+        let no_gap = TimeCalculator::sessions(
+            &records,
+            &SessionConfig {
+                max_gap_minutes: 0,
+                same_project_only: true,
+            },
+        );
+        assert_eq!(no_gap.len(), 4);
+
+        let generous = TimeCalculator::sessions(
+            &records,
+            &SessionConfig {
+                max_gap_minutes: 60,
+                same_project_only: false,
+            },
+        );
+        assert_eq!(generous.len(), 1);
+        assert_eq!(generous[0].active_minutes, 105);
+    }
+
+    #[test]
+    fn test_sessions_overlapping_records_counted_once() {
+        let base_time = Utc::now();
+        let first = create_test_time_record(None, base_time, 60);
+        let overlapping = create_test_time_record(None, base_time + Duration::minutes(30), 60);
+        let records = vec![&first, &overlapping];
+
+        let sessions = TimeCalculator::sessions(&records, &SessionConfig::default());
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].active_minutes, 90);
+        assert_eq!(sessions[0].project, SessionProject::NonProject);
+    }
+
+    #[test]
+    fn test_idle_time() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let at = |hour: u32, minute: u32| day.and_hms_opt(hour, minute, 0).unwrap().and_utc();
+        let day_start = at(9, 0);
+        let day_end = at(18, 0);
+
+        // 完全空闲的一天
+        assert_eq!(
+            TimeCalculator::calculate_idle_time(&[], day_start, day_end),
+            540
+        );
+        assert_eq!(
+            TimeCalculator::find_largest_gap(&[], day_start, day_end),
+            Some((day_start, 540))
+        );
+
+        // 首尾相接和相互重叠的记录覆盖整个窗口，没有空档
+        let back_to_back = [
+            create_test_time_record(None, at(8, 0), 180),
+            create_test_time_record(Some(Uuid::new_v4()), at(11, 0), 240),
+            create_test_time_record(None, at(14, 30), 240),
+        ];
+        let refs: Vec<&TimeRecord> = back_to_back.iter().collect();
+        assert_eq!(
+            TimeCalculator::calculate_idle_time(&refs, day_start, day_end),
+            0
+        );
+        assert_eq!(
+            TimeCalculator::find_largest_gap(&refs, day_start, day_end),
+            None
+        );
+
+        // 中间有一个2小时的空档
+        let with_gap = [
+            create_test_time_record(None, at(9, 0), 300),
+            create_test_time_record(None, at(16, 0), 120),
+        ];
+        let refs: Vec<&TimeRecord> = with_gap.iter().collect();
+        assert_eq!(
+            TimeCalculator::calculate_idle_time(&refs, day_start, day_end),
+            120
+        );
+        assert_eq!(
+            TimeCalculator::find_largest_gap(&refs, day_start, day_end),
+            Some((at(14, 0), 120))
+        );
+
+        // 整天窗口以 23:59:59 结束时按整天计算
+        let whole_day_end = day.and_hms_opt(23, 59, 59).unwrap().and_utc();
+        assert_eq!(
+            TimeCalculator::calculate_idle_time(&[], at(0, 0), whole_day_end),
+            1440
+        );
+    }
+
+    #[test]
+    fn test_month_range() {
+        let (start, end) = TimeCalculator::get_month_range(2024, 11, 3);
+        assert_eq!(start.to_rfc3339(), "2024-11-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2025-01-31T23:59:59+00:00");
+
+        let (start, end) = TimeCalculator::get_month_range(2024, 1, 12);
+        assert_eq!(start.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-12-31T23:59:59+00:00");
+    }
+
+    #[test]
+    fn test_week_start_day() {
+        // 2024年1月14日是周日
+        let sunday = chrono::NaiveDate::from_ymd_opt(2024, 1, 14)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let monday_start = TimeCalculator::get_week_start(sunday, WeekStart::Monday);
+        let monday_end = TimeCalculator::get_week_end(sunday, WeekStart::Monday);
+        assert_eq!(monday_start.date_naive().day(), 8);
+        assert_eq!(monday_end.date_naive().day(), 14);
+
+        let sunday_start = TimeCalculator::get_week_start(sunday, WeekStart::Sunday);
+        let sunday_end = TimeCalculator::get_week_end(sunday, WeekStart::Sunday);
+        assert_eq!(sunday_start.date_naive().day(), 14);
+        assert_eq!(sunday_start.weekday(), Weekday::Sun);
+        assert_eq!(sunday_end.date_naive().day(), 20);
+        assert_eq!(sunday_end.weekday(), Weekday::Sat);
+
+        // 周六的记录在两种设置下属于不同的周
+        let record = create_test_time_record(None, sunday - Duration::days(1), 60);
+        let records = vec![&record];
+        assert_eq!(
+            TimeCalculator::calculate_weekly_stats(&records, sunday, WeekStart::Monday),
+            (0, 60)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_weekly_stats(&records, sunday, WeekStart::Sunday),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_rounding_applies_per_record() {
+        let project_id = Uuid::new_v4();
+        let base_time = Utc::now() - Duration::hours(2);
+        let records = [
+            create_test_time_record(Some(project_id), base_time, 7),
+            create_test_time_record(Some(project_id), base_time + Duration::minutes(10), 7),
+            // 7分40秒：不取整时按记录的7分钟计算
+            TimeRecord::new(
+                Uuid::new_v4(),
+                Some(project_id),
+                base_time + Duration::minutes(20),
+                base_time + Duration::minutes(27) + Duration::seconds(40),
+            ),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+        let total = |rounding| {
+            TimeCalculator::calculate_project_total_time_rounded(
+                &refs, project_id, None, None, rounding,
+            )
+        };
+
+        let raw = TimeCalculator::calculate_project_total_time(&refs, project_id, None, None);
+        assert_eq!(raw, 21);
+        assert_eq!(total(RoundingMode::None), raw);
+        assert_eq!(total(RoundingMode::NearestMinute), 22);
+        // 每条记录分别向上取整到15分钟，而不是先求和再取整
+        assert_eq!(total(RoundingMode::Up15), 45);
+        assert_eq!(total(RoundingMode::Nearest15), 15);
+    }
+
+    #[test]
+    fn test_records_spanning_boundaries_are_clipped() {
+        let day = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let midnight = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let project_id = Uuid::new_v4();
+
+        // 23:00 开始、次日 01:00 结束的记录
+        let overnight =
+            create_test_time_record(Some(project_id), midnight - Duration::hours(1), 120);
+        let records = vec![&overnight];
+
+        let previous_day = midnight - Duration::hours(12);
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats(&records, previous_day),
+            (60, 0)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats(&records, midnight),
+            (60, 0)
+        );
+
+        // 跨越周边界（2024年1月8日周一零点）的项目外记录
+        let week_boundary = NaiveDate::from_ymd_opt(2024, 1, 8)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        let straddling = create_test_time_record(None, week_boundary - Duration::minutes(30), 90);
+        let records = vec![&straddling];
+
+        assert_eq!(
+            TimeCalculator::calculate_weekly_stats(&records, week_boundary, WeekStart::Monday),
+            (0, 60)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_weekly_stats(
+                &records,
+                week_boundary - Duration::days(1),
+                WeekStart::Monday
+            ),
+            (0, 30)
+        );
+
+        let mut project_names = HashMap::new();
+        project_names.insert(project_id, "测试项目".to_string());
+        let overnight_records = vec![&overnight];
+        let breakdown = TimeCalculator::generate_project_breakdown(
+            &overnight_records,
+            &project_names,
+            midnight,
+            midnight + Duration::hours(12),
+        );
+        assert_eq!(breakdown[0].total_time_minutes, 60);
+    }
+
+    #[test]
+    fn test_timezone_shifts_evening_record_to_next_day() {
+        let utc8 = FixedOffset::east_opt(8 * 3600).unwrap();
+        let project_id = Uuid::new_v4();
+
+        // UTC 1月10日 18:00 开始，即 UTC+8 的 1月11日 02:00
+        let evening = NaiveDate::from_ymd_opt(2024, 1, 10)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap()
+            .and_utc();
+        let record = create_test_time_record(Some(project_id), evening, 60);
+        let records = vec![&record];
+
+        let jan_10_morning = evening - Duration::hours(10);
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats(&records, jan_10_morning),
+            (60, 0)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats_tz(&records, jan_10_morning, utc8),
+            (0, 0)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats_tz(&records, evening, utc8),
+            (60, 0)
+        );
+        assert_eq!(
+            TimeCalculator::calculate_daily_stats_tz(&records, evening, utc_offset()),
+            TimeCalculator::calculate_daily_stats(&records, evening)
+        );
+
+        // UTC 1月7日（周日）20:00 在 UTC+8 已是 1月8日（周一）
+        let sunday_evening = NaiveDate::from_ymd_opt(2024, 1, 7)
+            .unwrap()
+            .and_hms_opt(20, 0, 0)
+            .unwrap()
+            .and_utc();
+        assert_eq!(
+            TimeCalculator::get_week_start(sunday_evening, WeekStart::Monday)
+                .date_naive()
+                .to_string(),
+            "2024-01-01"
+        );
+        let local_start =
+            TimeCalculator::get_week_start_tz(sunday_evening, WeekStart::Monday, utc8);
+        assert_eq!(
+            local_start.with_timezone(&utc8).to_rfc3339(),
+            "2024-01-08T00:00:00+08:00"
+        );
+        let local_end = TimeCalculator::get_week_end_tz(sunday_evening, WeekStart::Monday, utc8);
+        assert_eq!(
+            local_end.with_timezone(&utc8).to_rfc3339(),
+            "2024-01-14T23:59:59+08:00"
+        );
+    }
+
+    #[test]
+    fn test_daily_totals_for_leap_year() {
+        let at = |month: u32, day: u32, hour: u32| {
+            NaiveDate::from_ymd_opt(2024, month, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let project_id = Uuid::new_v4();
+        let records = [
+            create_test_time_record(Some(project_id), at(2, 29, 9), 60),
+            create_test_time_record(None, at(2, 29, 12), 30),
+            create_test_time_record(Some(project_id), at(2, 29, 14), 45),
+            // 跨年的记录只统计当年的部分
+            create_test_time_record(Some(project_id), at(12, 31, 23), 120),
+            create_test_time_record(None, at(1, 1, 0) - Duration::hours(2), 180),
+        ];
+        let refs: Vec<&TimeRecord> = records.iter().collect();
+
+        let totals = TimeCalculator::daily_totals_for_year(&refs, 2024);
+        assert_eq!(totals.len(), 366);
+        assert_eq!(
+            totals[0],
+            (NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), 60)
+        );
+        let leap_day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(totals[leap_day.ordinal0() as usize], (leap_day, 135));
+        assert_eq!(totals[365].1, 60);
+        assert_eq!(totals.iter().map(|(_, minutes)| minutes).sum::<i64>(), 255);
+        assert!(totals
+            .windows(2)
+            .all(|pair| pair[0].0.succ_opt() == Some(pair[1].0)));
+
+        assert_eq!(
+            TimeCalculator::daily_totals_for_year(&refs, 2023).len(),
+            365
+        );
+        assert_eq!(
+            TimeCalculator::daily_totals_for_year(&refs, 2023)[364].1,
+            120
+        );
+
+        // UTC+8 时，跨年的两条记录都整段落在当地的1月1日
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let local = TimeCalculator::daily_totals_for_year_tz(&refs, 2024, tz);
+        assert_eq!(local[0].1, 180);
+        assert_eq!(local[365].1, 0);
+        assert_eq!(local[leap_day.ordinal0() as usize].1, 135);
+        assert_eq!(
+            TimeCalculator::daily_totals_for_year_tz(&refs, 2023, tz)[364].1,
+            0
+        );
+    }
 }
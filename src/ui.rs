@@ -1,29 +1,302 @@
+use crate::error::{AppError, EventError, ProjectError};
 use crate::event_manager::EventManager;
-use crate::models::{Event, EventType, Project, TimeRecord};
-use crate::project_manager::ProjectManager;
+use crate::models::{
+    validate_name, AppSettings, BreakdownOrder, Event, EventTemplate, EventType, PomodoroPhase,
+    PomodoroSession, Priority, Project, ProjectStatus, Recurrence, ReportFormat, RoundingMode,
+    TimeRecord, WeekStart, WeeklyReport,
+};
+use crate::project_manager::{parse_hex_color, ProjectManager};
 use crate::report_generator::ReportGenerator;
 use crate::storage;
-use chrono::Utc;
+use crate::time_calculator::TimeCalculator;
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
 use eframe::egui;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
+    Dashboard,
     ProjectList,
     EventList,
     AddProject,
     AddEvent,
     Reports,
+    RecentCompletions,
+    SplitEvent,
+    EditEvent,
+    EditProject,
+    ConfirmDelete { target: DeleteTarget },
+    Timeline,
+    Stats,
+    Trash,
     Help,
+    DateRangeSearch,
 }
 
+/// 报表界面按周还是按月显示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    Week,
+    Month,
+}
+
+/// 等待确认删除的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteTarget {
+    Project(Uuid),
+    Event(Uuid),
+}
+
+/// 番茄钟默认的工作和休息分钟数
+const POMODORO_WORK_MINUTES: i64 = 25;
+const POMODORO_BREAK_MINUTES: i64 = 5;
+
+/// 补齐未记录时间时使用的工作时间（UTC小时）
+const WORK_DAY_START_HOUR: u32 = 9;
+const WORK_DAY_END_HOUR: u32 = 18;
+
+/// 周报中趋势显示的周数
+const TREND_WEEKS: usize = 8;
+
+/// 回收站中的事件默认保留的天数
+const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// 撤销栈最多保留的操作数
+const UNDO_LIMIT: usize = 20;
+
+/// 删除项目时如何处理它的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectDeletion {
+    #[default]
+    DeleteEvents, // 一并删除事件和时间记录
+    MoveToNonProject, // 保留事件，改为项目外
+}
+
+/// 列表的滚动位置，用键盘移动选中项时让可见区域跟随选中项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListScroll {
+    pub offset: usize,       // 可见区域第一行的序号
+    pub visible_rows: usize, // 上一帧可见的行数
+    scroll_pending: bool,
+    first_visible: Option<usize>,
+    counted_rows: usize,
+}
+
+impl Default for ListScroll {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            visible_rows: 1,
+            scroll_pending: false,
+            first_visible: None,
+            counted_rows: 0,
+        }
+    }
+}
+
+impl ListScroll {
+    /// 调整偏移量使选中项落在可见区域内，下一帧滚动到新的位置
+    pub fn follow(&mut self, selected: usize) {
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + self.visible_rows {
+            self.offset = selected + 1 - self.visible_rows;
+        }
+        self.scroll_pending = true;
+    }
+
+    /// 绘制每一行后调用，统计可见行并在需要时滚动
+    fn track_row(&mut self, ui: &egui::Ui, index: usize, row: &egui::Response) {
+        if index == 0 {
+            self.first_visible = None;
+            self.counted_rows = 0;
+        }
+        if self.scroll_pending && index == self.offset {
+            row.scroll_to_me(Some(egui::Align::Min));
+        }
+        if ui.is_rect_visible(row.rect) {
+            self.first_visible.get_or_insert(index);
+            self.counted_rows += 1;
+        }
+    }
+
+    /// 列表绘制完成后调用，用户用鼠标滚动时同步偏移量
+    fn finish_frame(&mut self) {
+        self.visible_rows = self.counted_rows.max(1);
+        if !self.scroll_pending {
+            if let Some(first) = self.first_visible {
+                self.offset = first;
+            }
+        }
+        self.scroll_pending = false;
+    }
+}
+
+fn project_status_label(status: ProjectStatus) -> &'static str {
+    match status {
+        ProjectStatus::Planned => "计划中",
+        ProjectStatus::Active => "进行中",
+        ProjectStatus::Done => "已完成",
+        ProjectStatus::Cancelled => "已取消",
+    }
+}
+
+fn priority_label(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "低",
+        Priority::Medium => "中",
+        Priority::High => "高",
+    }
+}
+
+/// 序列化后数据内容的哈希，用于判断数据自上次保存后是否变化
+fn content_hash(app_data: &storage::AppData) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+
+    let json = serde_json::to_string(app_data).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    json.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// 在长度为 len 的列表中上下移动选中项
+fn move_selection(selected: usize, len: usize, down: bool) -> usize {
+    if len == 0 {
+        0
+    } else if down {
+        (selected + 1).min(len - 1)
+    } else {
+        selected.saturating_sub(1).min(len - 1)
+    }
+}
+
+/// 解析 YYYY-MM-DD 格式的起止日期，返回包含两端整天的 UTC 时间范围
+pub fn parse_date_range(start: &str, end: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let parse = |input: &str, label: &str| {
+        NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("{}“{}”无效，格式应为 YYYY-MM-DD", label, input.trim()))
+    };
+    let start_date = parse(start, "开始日期")?;
+    let end_date = parse(end, "结束日期")?;
+    if start_date > end_date {
+        return Err("开始日期不能晚于结束日期".to_string());
+    }
+
+    let start_time = start_date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let end_time = end_date.and_hms_opt(23, 59, 59).unwrap().and_utc();
+    Ok((start_time, end_time))
+}
+
+/// 可撤销的操作，保存执行前的数据以便恢复
+#[derive(Debug, Clone)]
+pub enum UndoAction {
+    DeleteProject {
+        project: Project,
+        was_current: bool,
+        events: Vec<Event>, // 受影响的事件（含回收站中的）、时间记录和项目的模板
+        time_records: Vec<TimeRecord>,
+        templates: Vec<EventTemplate>,
+    },
+    DeleteEvent {
+        event: Event,
+        time_records: Vec<TimeRecord>,
+    },
+    CompleteEvent {
+        event_id: Uuid,
+        pomodoro: Option<PomodoroSession>, // 完成时正在进行的番茄钟
+    },
+    CompleteEvents {
+        event_ids: Vec<Uuid>,
+        pomodoros: Vec<PomodoroSession>,
+    },
+}
+
+impl UndoAction {
+    /// 执行反向操作，恢复到操作之前的状态
+    pub fn apply_inverse(self, app: &mut App) -> Result<(), AppError> {
+        match self {
+            UndoAction::DeleteProject {
+                mut project,
+                was_current,
+                events,
+                time_records,
+                templates,
+            } => {
+                let project_id = project.id;
+                project.set_active(false);
+                app.project_manager.insert_project(project);
+                for event in events {
+                    app.event_manager.insert_event(event);
+                }
+                for record in time_records {
+                    app.event_manager.insert_time_record(record);
+                }
+                for template in templates {
+                    app.event_manager.add_template(template);
+                }
+                if was_current && app.project_manager.get_current_project().is_none() {
+                    app.project_manager.switch_to_project(project_id)?;
+                }
+                Ok(())
+            }
+            UndoAction::DeleteEvent {
+                event,
+                time_records,
+            } => {
+                app.event_manager.insert_event(event);
+                for record in time_records {
+                    app.event_manager.insert_time_record(record);
+                }
+                Ok(())
+            }
+            UndoAction::CompleteEvent { event_id, pomodoro } => {
+                app.event_manager.reopen_event(event_id)?;
+                if let Some(session) = pomodoro {
+                    app.event_manager.insert_pomodoro(session);
+                }
+                Ok(())
+            }
+            UndoAction::CompleteEvents {
+                event_ids,
+                pomodoros,
+            } => {
+                event_ids
+                    .into_iter()
+                    .try_for_each(|event_id| app.event_manager.reopen_event(event_id))?;
+                for session in pomodoros {
+                    app.event_manager.insert_pomodoro(session);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 概览界面显示的汇总数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DashboardStats {
+    pub project_count: usize,
+    pub active_events: usize,
+    pub completed_events: usize,
+    pub total_minutes: i64,
+    pub busiest_project: Option<(String, i64)>, // 记录时间最长的项目及其分钟数
+}
+
+/// 最近完成列表的最大条数
+const RECENT_COMPLETIONS_LIMIT: usize = 200;
+/// 最近完成列表每页条数
+const RECENT_COMPLETIONS_PAGE_SIZE: usize = 20;
+
 pub struct App {
     pub project_manager: ProjectManager,
     pub event_manager: EventManager,
     pub mode: AppMode,
     pub selected_project_index: usize,
     pub selected_event_index: usize,
+    pub project_scroll: ListScroll,
+    pub event_scroll: ListScroll,
     pub input: String,
     pub message: String,
     pub selected_project_id: Option<Uuid>,
@@ -33,6 +306,36 @@ pub struct App {
     pub new_event_title: String,
     pub new_event_description: String,
     pub show_completed_events: bool,
+    pub sort_by_priority: bool,
+    pub note_input: String,
+    pub recent_completions_page: usize,
+    pub split_event_id: Option<Uuid>,
+    pub split_time_input: String,
+    pub split_title_input: String,
+    pub settings: AppSettings,
+    pub timeline_date: NaiveDate,
+    pub discard_prompt: bool, // 正在询问是否放弃输入
+    pub show_archived_projects: bool,
+    pub tag_filter: String,         // 事件列表的标签筛选
+    pub new_tag_input: String,      // 待添加到事件上的标签
+    pub new_subtask_input: String,  // 待添加到事件上的检查项
+    pub merge_source: Option<Uuid>, // 正在选择合并目标的项目
+    pub undo_stack: Vec<UndoAction>,
+    pub dirty: bool,                              // 有尚未保存的修改
+    pub new_event_recurrence: Option<Recurrence>, // 新事件的重复规则，None 表示不重复
+    pub edit_event_id: Option<Uuid>,
+    pub edit_project_id: Option<Uuid>, // 正在编辑的项目，名称和描述使用添加项目的输入框
+    pub data_dir: String,              // 数据目录，保存的报表写入其中的 reports 子目录
+    pub report_format: ReportFormat,   // 保存报表时使用的格式
+    pub selected_report_date: DateTime<Utc>, // 报表界面显示这一天所在的周或月
+    pub report_period: ReportPeriod,
+    pub project_deletion: ProjectDeletion, // 确认删除项目时选择的事件处理方式
+    pub exit_prompt: bool,                 // 退出时还有进行中的事件，等待用户确认
+    pub exit_confirmed: bool,              // 用户已确认退出
+    pub last_saved_hash: Option<u64>,      // 上次保存（或加载）的数据内容的哈希，内容未变时跳过保存
+    pub add_event_return_mode: AppMode,    // 添加事件完成或取消后返回的界面
+    pub date_range_start: String,          // 按日期搜索事件的开始日期
+    pub date_range_end: String,            // 按日期搜索事件的结束日期
 }
 
 impl App {
@@ -43,6 +346,8 @@ impl App {
             mode: AppMode::ProjectList,
             selected_project_index: 0,
             selected_event_index: 0,
+            project_scroll: ListScroll::default(),
+            event_scroll: ListScroll::default(),
             input: String::new(),
             message: "欢迎使用项目管理系统".to_string(),
             selected_project_id: None,
@@ -52,57 +357,85 @@ impl App {
             new_event_title: String::new(),
             new_event_description: String::new(),
             show_completed_events: false,
+            sort_by_priority: false,
+            note_input: String::new(),
+            recent_completions_page: 0,
+            split_event_id: None,
+            split_time_input: String::new(),
+            split_title_input: String::new(),
+            settings: AppSettings::default(),
+            timeline_date: Utc::now().date_naive(),
+            discard_prompt: false,
+            show_archived_projects: false,
+            tag_filter: String::new(),
+            new_tag_input: String::new(),
+            new_subtask_input: String::new(),
+            merge_source: None,
+            undo_stack: Vec::new(),
+            dirty: false,
+            new_event_recurrence: None,
+            edit_event_id: None,
+            edit_project_id: None,
+            data_dir: "./data".to_string(),
+            report_format: ReportFormat::default(),
+            selected_report_date: Utc::now(),
+            report_period: ReportPeriod::Week,
+            project_deletion: ProjectDeletion::default(),
+            exit_prompt: false,
+            exit_confirmed: false,
+            last_saved_hash: None,
+            add_event_return_mode: AppMode::EventList,
+            date_range_start: String::new(),
+            date_range_end: String::new(),
         }
     }
 
     pub fn from_data(data: storage::AppData) -> Self {
-        let mut app = Self {
-            project_manager: ProjectManager::new(),
-            event_manager: EventManager::new(),
-            mode: AppMode::ProjectList,
-            selected_project_index: 0,
-            selected_event_index: 0,
-            input: String::new(),
-            message: "已加载保存的数据".to_string(),
-            selected_project_id: None,
-            event_type_selection: false,
-            new_project_name: String::new(),
-            new_project_description: String::new(),
-            new_event_title: String::new(),
-            new_event_description: String::new(),
-            show_completed_events: false,
-        };
+        let mut app = Self::new();
+        app.message = "已加载保存的数据".to_string();
+        app.last_saved_hash = content_hash(&data);
+        app.settings = data.settings;
 
-        // 恢复项目数据
+        // 恢复项目数据（保留原ID，事件才能关联到正确的项目）
+        let active_project_id = data
+            .projects
+            .iter()
+            .find(|p| p.is_active && !p.archived && !p.status.is_closed())
+            .map(|p| p.id);
         for project in data.projects {
-            let project_id = app
-                .project_manager
-                .add_project(project.name, project.description);
-            if project.is_active {
-                app.project_manager.switch_to_project(project_id).unwrap();
+            app.project_manager.insert_project(project);
+        }
+        // 以保存时激活的项目为当前项目（有多个时取第一个），并清除其他项目的激活状态
+        match active_project_id {
+            Some(project_id) => {
+                if app.project_manager.switch_to_project(project_id).is_ok() {
+                    app.selected_project_id = Some(project_id);
+                }
             }
+            None => app.project_manager.clear_current_project(),
         }
 
-        // 恢复事件数据
+        // 恢复事件数据（保留原ID和结束时间，时间记录才能关联到对应事件）
         for event in data.events {
-            match event.event_type {
-                EventType::ProjectRelated(project_id) => {
-                    app.event_manager.add_project_event(
-                        event.title,
-                        event.description,
-                        project_id,
-                        Some(event.start_time),
-                    );
-                }
-                EventType::NonProject => {
-                    app.event_manager.add_non_project_event(
-                        event.title,
-                        event.description,
-                        Some(event.start_time),
-                    );
-                }
-            }
+            app.event_manager.insert_event(event);
+        }
+
+        // 恢复时间记录
+        for record in data.time_records {
+            app.event_manager.insert_time_record(record);
+        }
+
+        // 恢复进行中的番茄钟
+        for session in data.pomodoros {
+            app.event_manager.insert_pomodoro(session);
+        }
+
+        // 恢复重复事件模板，并生成今天的事件
+        for template in data.templates {
+            app.event_manager.add_template(template);
         }
+        let today = Utc::now().date_naive();
+        app.event_manager.materialize_templates(today, today);
 
         app
     }
@@ -111,11 +444,164 @@ impl App {
         self.project_manager.get_all_projects()
     }
 
+    /// 项目列表中显示的项目（默认不含已归档项目）
+    pub fn get_listed_projects(&self) -> Vec<&Project> {
+        if self.show_archived_projects {
+            self.project_manager.get_all_projects()
+        } else {
+            self.project_manager.get_open_projects()
+        }
+    }
+
     pub fn get_events(&self) -> Vec<&Event> {
-        if self.show_completed_events {
+        let mut events = if self.show_completed_events {
             self.event_manager.get_all_events()
         } else {
             self.event_manager.get_active_events()
+        };
+        if self.sort_by_priority {
+            events.sort_by_key(|event| std::cmp::Reverse(event.priority));
+        }
+
+        // 输入标签时按标签筛选（不区分大小写，部分匹配即可）
+        let filter = self.tag_filter.trim().to_lowercase();
+        if filter.is_empty() {
+            return events;
+        }
+        events
+            .into_iter()
+            .filter(|event| {
+                event
+                    .tags
+                    .iter()
+                    .any(|tag| tag.to_lowercase().contains(&filter))
+            })
+            .collect()
+    }
+
+    /// 开始时间落在输入的日期范围内（含两端）的事件，按开始时间排序
+    pub fn events_in_date_range(&self) -> Result<Vec<&Event>, String> {
+        let (start_time, end_time) =
+            parse_date_range(&self.date_range_start, &self.date_range_end)?;
+        let mut events = self.event_manager.get_events_in_range(start_time, end_time);
+        events.sort_by_key(|event| event.start_time);
+        Ok(events)
+    }
+
+    pub fn set_event_priority(&mut self, event_id: Uuid, priority: Priority) {
+        match self.event_manager.set_priority(event_id, priority) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = format!("优先级已设置为{}", priority_label(priority));
+            }
+            Err(e) => self.message = format!("设置优先级失败: {}", e),
+        }
+    }
+
+    /// 把笔记输入框的内容保存到事件的时间记录
+    pub fn set_event_note(&mut self, event_id: Uuid) {
+        let record_id = self
+            .event_manager
+            .get_event_time_record(event_id)
+            .map(|r| r.id);
+        let Some(record_id) = record_id else {
+            self.message = "事件还没有时间记录，完成后才能添加笔记".to_string();
+            return;
+        };
+        let note = Some(self.note_input.clone());
+        match self.event_manager.set_record_note(record_id, note) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "笔记已保存".to_string();
+                self.note_input.clear();
+            }
+            Err(e) => self.message = format!("保存笔记失败: {}", e),
+        }
+    }
+
+    pub fn add_tag_to_event(&mut self, event_id: Uuid) {
+        match self.event_manager.add_tag(event_id, &self.new_tag_input) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = format!("已添加标签: {}", self.new_tag_input.trim());
+                self.new_tag_input.clear();
+            }
+            Err(e) => self.message = format!("添加标签失败: {}", e),
+        }
+    }
+
+    /// 复制事件为一个从现在开始的新事件
+    pub fn duplicate_event(&mut self, event_id: Uuid) {
+        match self.event_manager.duplicate_event(event_id, None) {
+            Ok(_) => {
+                self.dirty = true;
+                self.message = "已复制事件".to_string();
+            }
+            Err(e) => self.message = format!("复制事件失败: {}", e),
+        }
+    }
+
+    pub fn add_subtask_to_event(&mut self, event_id: Uuid) {
+        match self
+            .event_manager
+            .add_subtask(event_id, &self.new_subtask_input)
+        {
+            Ok(_) => {
+                self.dirty = true;
+                self.message = format!("已添加检查项: {}", self.new_subtask_input.trim());
+                self.new_subtask_input.clear();
+            }
+            Err(e) => self.message = format!("添加检查项失败: {}", e),
+        }
+    }
+
+    pub fn toggle_subtask(&mut self, event_id: Uuid, index: usize) {
+        match self.event_manager.toggle_subtask(event_id, index) {
+            Ok(_) => self.dirty = true,
+            Err(e) => self.message = format!("更新检查项失败: {}", e),
+        }
+    }
+
+    /// 给当前项目的所有事件加上新标签输入框中的标签
+    pub fn tag_current_project_events(&mut self) {
+        let Some(project_id) = self.selected_project_id else {
+            self.message = "请先选择项目".to_string();
+            return;
+        };
+        match self
+            .event_manager
+            .tag_project_events(project_id, &self.new_tag_input)
+        {
+            Ok(count) => {
+                if count > 0 {
+                    self.dirty = true;
+                }
+                self.message =
+                    format!("已给{}个事件添加标签: {}", count, self.new_tag_input.trim());
+                self.new_tag_input.clear();
+            }
+            Err(e) => self.message = format!("添加标签失败: {}", e),
+        }
+    }
+
+    /// 设置前置事件：event_id 要等 dependency_id 完成后才能开始
+    pub fn add_event_dependency(&mut self, event_id: Uuid, dependency_id: Uuid) {
+        match self.event_manager.add_dependency(event_id, dependency_id) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "已添加前置事件".to_string();
+            }
+            Err(e) => self.message = format!("添加前置事件失败: {}", e),
+        }
+    }
+
+    pub fn remove_tag_from_event(&mut self, event_id: Uuid, tag: &str) {
+        match self.event_manager.remove_tag(event_id, tag) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = format!("已移除标签: {}", tag);
+            }
+            Err(e) => self.message = format!("移除标签失败: {}", e),
         }
     }
 
@@ -123,358 +609,4191 @@ impl App {
         self.project_manager.get_current_project()
     }
 
-    pub fn add_project(&mut self, name: String, description: Option<String>) {
-        let project_id = self.project_manager.add_project(name, description);
+    pub fn add_project(
+        &mut self,
+        name: String,
+        description: Option<String>,
+    ) -> Result<Uuid, ProjectError> {
+        let project_id = match self.project_manager.add_project(name, description) {
+            Ok(project_id) => project_id,
+            Err(e) => {
+                self.message = format!("添加项目失败: {}", e);
+                return Err(e);
+            }
+        };
+        self.dirty = true;
         self.message = format!("项目添加成功: ID {}", project_id);
         self.new_project_name.clear();
         self.new_project_description.clear();
+        Ok(project_id)
     }
 
     pub fn switch_to_project(&mut self, project_id: Uuid) {
         if let Err(e) = self.project_manager.switch_to_project(project_id) {
             self.message = format!("切换项目失败: {}", e);
         } else {
+            self.dirty = true;
             self.message = "项目切换成功".to_string();
             self.selected_project_id = Some(project_id);
         }
     }
 
+    pub fn set_project_archived(&mut self, project_id: Uuid, archived: bool) {
+        let result = if archived {
+            self.project_manager.archive_project(project_id)
+        } else {
+            self.project_manager.unarchive_project(project_id)
+        };
+        if result.is_ok() {
+            self.dirty = true;
+        }
+        match result {
+            Ok(()) if archived => self.message = "项目已归档".to_string(),
+            Ok(()) => self.message = "项目已取消归档".to_string(),
+            Err(e) => self.message = format!("操作失败: {}", e),
+        }
+    }
+
+    pub fn set_project_status(&mut self, project_id: Uuid, status: ProjectStatus) {
+        match self.project_manager.set_status(project_id, status) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = format!("项目状态已改为{}", project_status_label(status));
+            }
+            Err(e) => self.message = format!("修改项目状态失败: {}", e),
+        }
+    }
+
+    /// 合并项目：删除源项目，并把它的事件和时间记录迁移到目标项目
+    pub fn merge_projects(&mut self, source_id: Uuid, target_id: Uuid) {
+        match self.project_manager.merge_projects(source_id, target_id) {
+            Ok(()) => {
+                let moved = self.event_manager.reassign_project(source_id, target_id);
+                self.dirty = true;
+                self.message = format!("项目已合并，迁移了{}个事件", moved);
+            }
+            Err(e) => self.message = format!("合并项目失败: {}", e),
+        }
+        self.merge_source = None;
+    }
+
     pub fn add_event(
         &mut self,
         title: String,
         description: Option<String>,
         is_project_event: bool,
-    ) {
-        if is_project_event {
-            if let Some(current_project) = self.get_current_project() {
-                let event_id = self.event_manager.add_project_event(
-                    title,
-                    description,
-                    current_project.id,
-                    None,
-                );
-                self.message = format!("项目事件添加成功: ID {}", event_id);
-            } else {
-                self.message = "没有当前活动项目，请先选择项目".to_string();
+    ) -> Result<Uuid, AppError> {
+        let project_id = if is_project_event {
+            match self.get_current_project() {
+                Some(current_project) => Some(current_project.id),
+                None => {
+                    self.message = "没有当前活动项目，请先选择项目".to_string();
+                    return Err(ProjectError::NoCurrentProject.into());
+                }
             }
         } else {
-            let event_id = self
+            None
+        };
+        let event_id = self.add_event_validated(title, description, project_id)?;
+        self.new_event_title.clear();
+        self.new_event_description.clear();
+        Ok(event_id)
+    }
+
+    /// 添加事件，项目事件要求关联的项目存在，避免产生引用不存在项目的事件
+    pub fn add_event_validated(
+        &mut self,
+        title: String,
+        description: Option<String>,
+        project_id: Option<Uuid>,
+    ) -> Result<Uuid, AppError> {
+        let result = match project_id {
+            Some(project_id) => {
+                if !self.project_manager.project_exists(project_id) {
+                    Err(ProjectError::NotFound.into())
+                } else {
+                    self.event_manager
+                        .add_project_event(title, description, project_id, None)
+                        .map(|event_id| (event_id, "项目事件"))
+                        .map_err(AppError::from)
+                }
+            }
+            None => self
                 .event_manager
-                .add_non_project_event(title, description, None);
-            self.message = format!("项目外事件添加成功: ID {}", event_id);
-        }
+                .add_non_project_event(title, description, None)
+                .map(|event_id| (event_id, "项目外事件"))
+                .map_err(AppError::from),
+        };
+        let event_id = match result {
+            Ok((event_id, kind)) => {
+                self.message = format!("{}添加成功: ID {}", kind, event_id);
+                event_id
+            }
+            Err(e) => {
+                self.message = format!("添加事件失败: {}", e);
+                return Err(e);
+            }
+        };
+        self.dirty = true;
+        Ok(event_id)
+    }
+
+    /// 添加重复事件：保存模板并生成今天的事件
+    pub fn add_recurring_event(
+        &mut self,
+        title: String,
+        description: Option<String>,
+        is_project_event: bool,
+        recurrence: Recurrence,
+    ) -> Result<Vec<Uuid>, AppError> {
+        let title = match validate_name(&title) {
+            Ok(title) => title,
+            Err(e) => {
+                self.message = format!("添加重复事件失败: {}", e);
+                return Err(EventError::Invalid(e).into());
+            }
+        };
+        let event_type = if is_project_event {
+            match self.get_current_project() {
+                Some(project) => EventType::ProjectRelated(project.id),
+                None => {
+                    self.message = "没有当前活动项目，请先选择项目".to_string();
+                    return Err(ProjectError::NoCurrentProject.into());
+                }
+            }
+        } else {
+            EventType::NonProject
+        };
+
+        let now = Utc::now();
+        self.event_manager.add_template(EventTemplate::new(
+            title,
+            description,
+            event_type,
+            recurrence,
+            now.time(),
+        ));
+        let today = now.date_naive();
+        let created = self.event_manager.materialize_templates(today, today);
+        self.dirty = true;
+        self.message = format!("重复事件已添加，今天生成了{}个事件", created.len());
         self.new_event_title.clear();
         self.new_event_description.clear();
+        Ok(created)
     }
 
     pub fn complete_event(&mut self, event_id: Uuid) {
+        let pomodoro = self.event_manager.get_pomodoro(event_id).cloned();
         if let Err(e) = self.event_manager.set_event_end_time(event_id, None) {
             self.message = format!("完成事件失败: {}", e);
         } else {
+            self.push_undo(UndoAction::CompleteEvent { event_id, pomodoro });
             self.message = "事件已完成".to_string();
         }
     }
 
-    pub fn get_weekly_report(&self) -> String {
-        let time_records = self.event_manager.get_all_time_records();
-        let time_records_refs: Vec<&TimeRecord> = time_records.iter().map(|&r| r).collect();
-
-        let mut project_names = HashMap::new();
-        for project in self.get_projects() {
-            project_names.insert(project.id, project.name.clone());
+    pub fn set_project_weekly_goal(&mut self, project_id: Uuid, goal_minutes: Option<i64>) {
+        match self
+            .project_manager
+            .set_weekly_goal(project_id, goal_minutes)
+        {
+            Ok(()) => self.dirty = true,
+            Err(e) => self.message = format!("设置每周目标失败: {}", e),
         }
-
-        let now = Utc::now();
-        let weekly_report =
-            ReportGenerator::generate_weekly_report(&time_records_refs, &project_names, now);
-        ReportGenerator::generate_report_summary(&weekly_report)
     }
 
-    pub fn update(&mut self, ctx: &egui::Context) {
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.heading("项目管理系统");
-                
-                if let Some(current_project) = self.get_current_project() {
-                    ui.label(format!("当前项目: {}", current_project.name));
-                } else {
-                    ui.label("没有当前项目");
-                }
-
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.button("帮助").clicked() {
-                        self.mode = AppMode::Help;
-                    }
-                    if ui.button("报表").clicked() {
-                        self.mode = AppMode::Reports;
-                    }
-                });
-            });
-        });
+    pub fn set_project_color(&mut self, project_id: Uuid, color: &str) {
+        match self.project_manager.set_color(project_id, Some(color)) {
+            Ok(()) => self.dirty = true,
+            Err(e) => self.message = format!("设置项目颜色失败: {}", e),
+        }
+    }
 
-        egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                let mode_text = match self.mode {
-                    AppMode::ProjectList => "项目列表",
-                    AppMode::EventList => "事件列表",
-                    AppMode::AddProject => "添加项目",
-                    AppMode::AddEvent => "添加事件",
-                    AppMode::Reports => "报表",
-                    AppMode::Help => "帮助",
-                };
-                ui.label(format!("模式: {}", mode_text));
-                ui.label(&self.message);
-            });
-        });
+    pub fn start_pomodoro(&mut self, event_id: Uuid) {
+        match self.event_manager.start_pomodoro(
+            event_id,
+            POMODORO_WORK_MINUTES,
+            POMODORO_BREAK_MINUTES,
+        ) {
+            Ok(()) => {
+                self.message = format!(
+                    "番茄钟已开始: 工作{}分钟，休息{}分钟",
+                    POMODORO_WORK_MINUTES, POMODORO_BREAK_MINUTES
+                )
+            }
+            Err(e) => self.message = format!("开始番茄钟失败: {}", e),
+        }
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            match self.mode {
-                AppMode::ProjectList => self.show_project_list(ui),
-                AppMode::EventList => self.show_event_list(ui),
-                AppMode::AddProject => self.show_add_project(ui),
-                AppMode::AddEvent => self.show_add_event(ui),
-                AppMode::Reports => self.show_reports(ui),
-                AppMode::Help => self.show_help(ui),
+    /// 结束番茄钟并完成事件，只记录工作时间
+    pub fn finish_pomodoro(&mut self, event_id: Uuid) {
+        let pomodoro = self.event_manager.get_pomodoro(event_id).cloned();
+        match self.event_manager.finish_pomodoro(event_id, None) {
+            Ok(cycles) => {
+                self.push_undo(UndoAction::CompleteEvent { event_id, pomodoro });
+                self.message = format!("事件已完成，共完成{}个番茄钟", cycles);
             }
-        });
+            Err(e) => self.message = format!("结束番茄钟失败: {}", e),
+        }
     }
 
-    fn show_project_list(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            if ui.button("添加项目").clicked() {
-                self.mode = AppMode::AddProject;
+    pub fn cancel_pomodoro(&mut self, event_id: Uuid) {
+        match self.event_manager.cancel_pomodoro(event_id) {
+            Ok(()) => self.message = "已放弃番茄钟".to_string(),
+            Err(e) => self.message = format!("放弃番茄钟失败: {}", e),
+        }
+    }
+
+    /// 用“未记录时间”事件填满时间线当天工作时间内的空档
+    pub fn fill_timeline_gaps(&mut self) {
+        match self.event_manager.fill_day_gaps_tz(
+            self.timeline_date,
+            WORK_DAY_START_HOUR,
+            WORK_DAY_END_HOUR,
+            self.settings.timezone(),
+        ) {
+            Ok(created) if created.is_empty() => {
+                self.message = "工作时间内没有未记录的空档".to_string()
             }
-            if ui.button("查看事件").clicked() {
-                self.mode = AppMode::EventList;
+            Ok(created) => {
+                self.dirty = true;
+                self.message = format!("已补齐{}段未记录时间", created.len());
             }
-        });
+            Err(e) => self.message = format!("补齐未记录时间失败: {}", e),
+        }
+    }
 
-        ui.separator();
+    /// 结束所有进行中的事件
+    pub fn complete_all_active(&mut self) {
+        let running: Vec<PomodoroSession> = self
+            .event_manager
+            .get_all_pomodoros()
+            .into_iter()
+            .cloned()
+            .collect();
+        let event_ids = self.event_manager.complete_all_active(None);
+        if event_ids.is_empty() {
+            self.message = "没有可以完成的进行中事件".to_string();
+            return;
+        }
 
-        let projects: Vec<_> = self.get_projects().into_iter().cloned().collect();
-        if projects.is_empty() {
-            ui.label("没有项目，点击\"添加项目\"创建新项目");
-        } else {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let mut project_to_switch = None;
-                
-                for (index, project) in projects.iter().enumerate() {
-                    ui.horizontal(|ui| {
-                        let mut selected = self.selected_project_index == index;
-                        if ui.checkbox(&mut selected, "").clicked() {
-                            project_to_switch = Some((index, project.id));
-                        }
-                        
-                        ui.vertical(|ui| {
-                            ui.heading(&project.name);
-                            if let Some(desc) = &project.description {
-                                ui.label(desc);
-                            }
-                            ui.label(format!("创建时间: {}", project.created_at.format("%Y-%m-%d %H:%M")));
-                            if project.is_active {
-                                ui.label("（当前项目）");
-                            }
-                        });
-                    });
-                    ui.separator();
-                }
-                
-                // 在闭包外切换项目
-                if let Some((index, project_id)) = project_to_switch {
-                    self.selected_project_index = index;
-                    self.switch_to_project(project_id);
+        let pomodoros = running
+            .into_iter()
+            .filter(|session| event_ids.contains(&session.event_id))
+            .collect();
+        self.message = format!("已完成{}个进行中的事件（按U撤销）", event_ids.len());
+        self.push_undo(UndoAction::CompleteEvents {
+            event_ids,
+            pomodoros,
+        });
+    }
+
+    /// 仍在进行中的事件标题，这些事件还没有时间记录
+    pub fn unclosed_event_titles(&self) -> Vec<String> {
+        self.event_manager
+            .get_active_events()
+            .iter()
+            .map(|event| event.title.clone())
+            .collect()
+    }
+
+    /// 窗口请求关闭时调用，有进行中的事件且用户还没确认时显示提示并返回 false
+    pub fn request_exit(&mut self) -> bool {
+        if self.exit_confirmed || self.unclosed_event_titles().is_empty() {
+            return true;
+        }
+        self.exit_prompt = true;
+        false
+    }
+
+    /// 退出前提示仍在进行中的事件，可以全部完成后退出
+    fn show_exit_prompt(&mut self, ctx: &egui::Context) {
+        let titles = self.unclosed_event_titles();
+        let mut exit = false;
+        egui::Window::new("退出确认")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("以下事件仍在进行中，退出后不会产生时间记录:");
+                for title in &titles {
+                    ui.label(format!("  - {}", title));
                 }
+                ui.horizontal(|ui| {
+                    if ui.button("全部完成后退出").clicked() {
+                        self.complete_all_active();
+                        exit = true;
+                    }
+                    if ui.button("直接退出").clicked() {
+                        exit = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        self.exit_prompt = false;
+                    }
+                });
             });
+
+        if exit {
+            self.exit_prompt = false;
+            self.exit_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
         }
     }
 
-    fn show_event_list(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal(|ui| {
-            if ui.button("返回项目").clicked() {
-                self.mode = AppMode::ProjectList;
+    /// 删除项目，事件按确认对话框中选择的方式处理
+    pub fn delete_project(&mut self, project_id: Uuid) {
+        let _ = self.delete_project_cascade(project_id, self.project_deletion);
+    }
+
+    /// 删除项目并按方式处理它的事件，避免留下引用不存在项目的事件，返回受影响的事件数量
+    pub fn delete_project_cascade(
+        &mut self,
+        project_id: Uuid,
+        deletion: ProjectDeletion,
+    ) -> Result<usize, ProjectError> {
+        let Some(project) = self.project_manager.get_project(project_id).cloned() else {
+            self.message = "删除项目失败: 项目不存在".to_string();
+            return Err(ProjectError::NotFound);
+        };
+        let was_current =
+            self.project_manager.get_current_project().map(|p| p.id) == Some(project_id);
+        let belongs = |event_type: &EventType| matches!(event_type, EventType::ProjectRelated(id) if *id == project_id);
+        // 撤销时要恢复所有受影响的事件和记录，包括回收站中的事件和分摊给该项目的其他事件
+        let events: Vec<Event> = self
+            .event_manager
+            .get_all_events()
+            .into_iter()
+            .chain(self.event_manager.get_deleted_events())
+            .filter(|event| {
+                belongs(&event.event_type)
+                    || event.project_shares.iter().any(|(id, _)| *id == project_id)
+            })
+            .cloned()
+            .collect();
+        let event_ids: HashSet<Uuid> = events.iter().map(|event| event.id).collect();
+        let time_records: Vec<TimeRecord> = self
+            .event_manager
+            .get_all_time_records()
+            .into_iter()
+            .chain(self.event_manager.get_deleted_time_records())
+            .filter(|record| {
+                event_ids.contains(&record.event_id) || record.project_id == Some(project_id)
+            })
+            .cloned()
+            .collect();
+        let templates: Vec<EventTemplate> = self
+            .event_manager
+            .get_all_templates()
+            .into_iter()
+            .filter(|template| belongs(&template.event_type))
+            .cloned()
+            .collect();
+
+        if let Err(e) = self.project_manager.delete_project(project_id) {
+            self.message = format!("删除项目失败: {}", e);
+            return Err(e);
+        }
+        let affected = match deletion {
+            ProjectDeletion::DeleteEvents => self.event_manager.delete_project_events(project_id),
+            ProjectDeletion::MoveToNonProject => self.event_manager.detach_project(project_id),
+        };
+
+        self.push_undo(UndoAction::DeleteProject {
+            project,
+            was_current,
+            events,
+            time_records,
+            templates,
+        });
+        self.message = match deletion {
+            ProjectDeletion::DeleteEvents => {
+                format!("项目已删除，同时删除了{}个事件（按U撤销）", affected)
             }
-            if ui.button("添加项目事件").clicked() {
-                self.mode = AppMode::AddEvent;
-                self.event_type_selection = true;
+            ProjectDeletion::MoveToNonProject => {
+                format!("项目已删除，{}个事件改为项目外（按U撤销）", affected)
             }
-            if ui.button("添加非项目事件").clicked() {
-                self.mode = AppMode::AddEvent;
-                self.event_type_selection = false;
+        };
+        Ok(affected)
+    }
+
+    pub fn delete_event(&mut self, event_id: Uuid) {
+        let Some(event) = self.event_manager.get_event(event_id).cloned() else {
+            self.message = "删除事件失败: 事件不存在".to_string();
+            return;
+        };
+        let time_records: Vec<TimeRecord> = self
+            .event_manager
+            .get_all_time_records()
+            .into_iter()
+            .filter(|record| record.event_id == event_id)
+            .cloned()
+            .collect();
+
+        match self.event_manager.delete_event(event_id) {
+            Ok(()) => {
+                self.push_undo(UndoAction::DeleteEvent {
+                    event,
+                    time_records,
+                });
+                self.message = "事件已删除（按U撤销）".to_string();
             }
-            
-            ui.checkbox(&mut self.show_completed_events, "显示已完成事件");
-        });
+            Err(e) => self.message = format!("删除事件失败: {}", e),
+        }
+    }
 
-        ui.separator();
+    /// 从回收站恢复事件
+    pub fn restore_event(&mut self, event_id: Uuid) {
+        match self.event_manager.restore_event(event_id) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "事件已恢复".to_string();
+            }
+            Err(e) => self.message = format!("恢复事件失败: {}", e),
+        }
+    }
 
-        let events: Vec<_> = self.get_events().into_iter().cloned().collect();
-        if events.is_empty() {
-            ui.label("没有事件");
+    /// 永久删除移入回收站超过keep_days天的事件
+    pub fn purge_trash(&mut self, keep_days: i64) {
+        let before = Utc::now() - chrono::Duration::days(keep_days);
+        let purged = self.event_manager.purge_deleted(before);
+        if purged > 0 {
+            self.dirty = true;
+        }
+        self.message = format!("已永久删除{}个事件", purged);
+    }
+
+    /// 记录可撤销的操作，超过上限时丢弃最早的（可撤销的操作都会修改数据）
+    fn push_undo(&mut self, action: UndoAction) {
+        self.dirty = true;
+        if self.undo_stack.len() >= UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(action);
+    }
+
+    /// 撤销最近一次删除或完成操作
+    pub fn undo(&mut self) {
+        let Some(action) = self.undo_stack.pop() else {
+            self.message = "没有可以撤销的操作".to_string();
+            return;
+        };
+        match action.apply_inverse(self) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "已撤销".to_string();
+            }
+            Err(e) => self.message = format!("撤销失败: {}", e),
+        }
+    }
+
+    pub fn reopen_event(&mut self, event_id: Uuid) {
+        if let Err(e) = self.event_manager.reopen_event(event_id) {
+            self.message = format!("重新打开事件失败: {}", e);
         } else {
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let mut events_to_complete = Vec::new();
-                
-                for event in events.iter() {
-                    ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            ui.heading(&event.title);
-                            if let Some(desc) = &event.description {
-                                ui.label(desc);
-                            }
-                            
-                            let event_type = match &event.event_type {
-                                EventType::ProjectRelated(project_id) => {
-                                    if let Some(project) = self.project_manager.get_project(*project_id) {
-                                        format!("项目: {}", project.name)
-                                    } else {
-                                        "项目: (未知)".to_string()
-                                    }
-                                }
-                                EventType::NonProject => "非项目事件".to_string(),
-                            };
-                            ui.label(event_type);
-                            
-                            ui.label(format!("开始时间: {}", event.start_time.format("%Y-%m-%d %H:%M")));
-                            
-                            if let Some(end_time) = event.end_time {
-                                ui.label(format!("结束时间: {}", end_time.format("%Y-%m-%d %H:%M")));
-                                if let Some(duration) = event.duration() {
-                                    ui.label(format!("持续时间: {}分钟", duration.num_minutes()));
-                                }
-                            } else {
-                                if ui.button("完成").clicked() {
-                                    events_to_complete.push(event.id);
-                                }
-                            }
-                        });
-                    });
-                    ui.separator();
-                }
-                
-                // 在闭包外完成事件
-                for event_id in events_to_complete {
-                    self.complete_event(event_id);
-                }
-            });
+            self.dirty = true;
+            self.message = "事件已重新打开".to_string();
         }
     }
 
-    fn show_add_project(&mut self, ui: &mut egui::Ui) {
-        ui.heading("添加新项目");
-        
-        ui.horizontal(|ui| {
-            ui.label("项目名称:");
-            ui.text_edit_singleline(&mut self.new_project_name);
-        });
-        
-        ui.horizontal(|ui| {
-            ui.label("项目描述:");
-            ui.text_edit_multiline(&mut self.new_project_description);
-        });
-        
-        ui.horizontal(|ui| {
-            if ui.button("添加").clicked() {
-                if !self.new_project_name.is_empty() {
-                    self.add_project(
-                        self.new_project_name.clone(),
-                        if self.new_project_description.is_empty() {
-                            None
-                        } else {
-                            Some(self.new_project_description.clone())
-                        },
-                    );
-                    self.mode = AppMode::ProjectList;
-                } else {
-                    self.message = "项目名称不能为空".to_string();
-                }
+    /// 进入编辑事件模式，输入框预先填入当前标题
+    pub fn begin_edit_event(&mut self, event_id: Uuid) {
+        if let Some(event) = self.event_manager.get_event(event_id) {
+            self.input = event.title.clone();
+            self.edit_event_id = Some(event_id);
+            self.mode = AppMode::EditEvent;
+        }
+    }
+
+    /// 保存编辑后的事件标题
+    pub fn confirm_edit_event(&mut self) {
+        let Some(event_id) = self.edit_event_id else {
+            return;
+        };
+        let title = self.input.trim().to_string();
+        if title.is_empty() {
+            self.message = "事件标题不能为空".to_string();
+            return;
+        }
+
+        match self.event_manager.update_event(event_id, Some(title), None) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "事件已更新".to_string();
+                self.edit_event_id = None;
+                self.input.clear();
+                self.mode = AppMode::EventList;
             }
-            
-            if ui.button("取消").clicked() {
+            Err(e) => self.message = format!("更新事件失败: {}", e),
+        }
+    }
+
+    /// 进入编辑项目模式，输入框预先填入当前名称和描述
+    pub fn begin_edit_project(&mut self, project_id: Uuid) {
+        if let Some(project) = self.project_manager.get_project(project_id) {
+            self.new_project_name = project.name.clone();
+            self.new_project_description = project.description.clone().unwrap_or_default();
+            self.edit_project_id = Some(project_id);
+            self.mode = AppMode::EditProject;
+        }
+    }
+
+    /// 保存编辑后的项目名称和描述
+    pub fn confirm_edit_project(&mut self) {
+        let Some(project_id) = self.edit_project_id else {
+            return;
+        };
+
+        match self.project_manager.update_project(
+            project_id,
+            Some(self.new_project_name.clone()),
+            Some(self.new_project_description.clone()),
+        ) {
+            Ok(()) => {
+                self.dirty = true;
+                self.message = "项目已更新".to_string();
+                self.edit_project_id = None;
                 self.new_project_name.clear();
                 self.new_project_description.clear();
                 self.mode = AppMode::ProjectList;
             }
-        });
+            Err(e) => self.message = format!("更新项目失败: {}", e),
+        }
     }
 
-    fn show_add_event(&mut self, ui: &mut egui::Ui) {
-        ui.heading("添加新事件");
-        
-        ui.horizontal(|ui| {
-            ui.label("事件标题:");
-            ui.text_edit_singleline(&mut self.new_event_title);
-        });
-        
-        ui.horizontal(|ui| {
-            ui.label("事件描述:");
-            ui.text_edit_multiline(&mut self.new_event_description);
-        });
-        
-        ui.horizontal(|ui| {
-            ui.label("事件类型:");
-            ui.radio_value(&mut self.event_type_selection, true, "项目事件");
-            ui.radio_value(&mut self.event_type_selection, false, "非项目事件");
-        });
-        
-        ui.horizontal(|ui| {
-            if ui.button("添加").clicked() {
-                if !self.new_event_title.is_empty() {
-                    self.add_event(
-                        self.new_event_title.clone(),
-                        if self.new_event_description.is_empty() {
-                            None
-                        } else {
-                            Some(self.new_event_description.clone())
-                        },
-                        self.event_type_selection,
-                    );
-                    self.mode = AppMode::EventList;
-                } else {
-                    self.message = "事件标题不能为空".to_string();
+    /// 请求删除项目或事件，需要确认后才会真正删除
+    pub fn request_delete(&mut self, target: DeleteTarget) {
+        self.project_deletion = ProjectDeletion::default();
+        self.mode = AppMode::ConfirmDelete { target };
+    }
+
+    /// 确认删除，完成后回到对应的列表
+    pub fn confirm_delete(&mut self) {
+        let AppMode::ConfirmDelete { target } = self.mode else {
+            return;
+        };
+        match target {
+            DeleteTarget::Project(project_id) => {
+                self.delete_project(project_id);
+                self.mode = AppMode::ProjectList;
+            }
+            DeleteTarget::Event(event_id) => {
+                self.delete_event(event_id);
+                self.mode = AppMode::EventList;
+            }
+        }
+    }
+
+    /// 取消删除，不做任何修改
+    pub fn cancel_delete(&mut self) {
+        if let AppMode::ConfirmDelete { target } = self.mode {
+            self.mode = match target {
+                DeleteTarget::Project(_) => AppMode::ProjectList,
+                DeleteTarget::Event(_) => AppMode::EventList,
+            };
+        }
+    }
+
+    /// 处理不在文本框中输入时的快捷键
+    pub fn handle_key(&mut self, key: egui::Key) {
+        match (self.mode, key) {
+            (AppMode::ConfirmDelete { .. }, egui::Key::Y) => self.confirm_delete(),
+            (AppMode::ConfirmDelete { .. }, egui::Key::N | egui::Key::Escape) => {
+                self.cancel_delete()
+            }
+            (AppMode::ConfirmDelete { .. }, _) => {}
+            (_, egui::Key::U) => self.undo(),
+            (
+                AppMode::AddProject
+                | AppMode::AddEvent
+                | AppMode::SplitEvent
+                | AppMode::EditEvent
+                | AppMode::EditProject
+                | AppMode::DateRangeSearch,
+                _,
+            ) => {}
+            // 在任意界面按N直接记录一个项目外事件
+            (_, egui::Key::N) => self.begin_add_event(false),
+            (AppMode::ProjectList, egui::Key::A) => self.mode = AppMode::AddProject,
+            (AppMode::EventList, egui::Key::A) => self.begin_add_event(true),
+            (AppMode::ProjectList, egui::Key::ArrowUp | egui::Key::ArrowDown) => {
+                let len = self.get_listed_projects().len();
+                self.selected_project_index = move_selection(
+                    self.selected_project_index,
+                    len,
+                    key == egui::Key::ArrowDown,
+                );
+                self.project_scroll.follow(self.selected_project_index);
+            }
+            (AppMode::EventList, egui::Key::ArrowUp | egui::Key::ArrowDown) => {
+                let len = self.get_events().len();
+                self.selected_event_index =
+                    move_selection(self.selected_event_index, len, key == egui::Key::ArrowDown);
+                self.event_scroll.follow(self.selected_event_index);
+            }
+            (AppMode::ProjectList, egui::Key::D) => {
+                let selected = self
+                    .get_listed_projects()
+                    .get(self.selected_project_index)
+                    .map(|project| project.id);
+                match selected {
+                    Some(project_id) => self.request_delete(DeleteTarget::Project(project_id)),
+                    None => self.message = "请先选择要删除的项目".to_string(),
                 }
             }
-            
-            if ui.button("取消").clicked() {
-                self.new_event_title.clear();
-                self.new_event_description.clear();
+            (AppMode::EventList, egui::Key::D) => {
+                let selected = self
+                    .get_events()
+                    .get(self.selected_event_index)
+                    .map(|event| event.id);
+                match selected {
+                    Some(event_id) => self.request_delete(DeleteTarget::Event(event_id)),
+                    None => self.message = "请先选择要删除的事件".to_string(),
+                }
+            }
+            (AppMode::ProjectList, egui::Key::I) => {
+                let selected = self
+                    .get_listed_projects()
+                    .get(self.selected_project_index)
+                    .map(|project| project.id);
+                match selected {
+                    Some(project_id) => self.begin_edit_project(project_id),
+                    None => self.message = "请先选择要编辑的项目".to_string(),
+                }
+            }
+            (AppMode::Reports, egui::Key::S) => {
+                self.save_weekly_report();
+            }
+            (AppMode::Reports, egui::Key::ArrowLeft | egui::Key::ArrowRight) => {
+                self.step_report_date(key == egui::Key::ArrowRight);
+            }
+            (AppMode::EventList, egui::Key::C) => self.complete_all_active(),
+            (AppMode::EventList, egui::Key::R) => {
+                let selected = self
+                    .get_events()
+                    .get(self.selected_event_index)
+                    .map(|event| event.id);
+                match selected {
+                    Some(event_id) => self.duplicate_event(event_id),
+                    None => self.message = "请先选择要复制的事件".to_string(),
+                }
+            }
+            (AppMode::EventList, egui::Key::I) => {
+                let selected = self
+                    .get_events()
+                    .get(self.selected_event_index)
+                    .map(|event| event.id);
+                match selected {
+                    Some(event_id) => self.begin_edit_event(event_id),
+                    None => self.message = "请先选择要编辑的事件".to_string(),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 进入添加事件界面，添加或取消后回到当前界面
+    pub fn begin_add_event(&mut self, project_event: bool) {
+        self.add_event_return_mode = self.mode;
+        self.event_type_selection = project_event;
+        self.mode = AppMode::AddEvent;
+    }
+
+    /// 进入拆分事件模式，默认拆分时间为事件的中点
+    pub fn begin_split_event(&mut self, event_id: Uuid) {
+        if let Some(event) = self.event_manager.get_event(event_id) {
+            let end_time = event.end_time.unwrap_or_else(Utc::now);
+            let midpoint = event.start_time + (end_time - event.start_time) / 2;
+            self.split_event_id = Some(event_id);
+            self.split_time_input = midpoint.format("%Y-%m-%d %H:%M").to_string();
+            self.split_title_input.clear();
+            self.mode = AppMode::SplitEvent;
+        }
+    }
+
+    pub fn split_event(&mut self) {
+        let Some(event_id) = self.split_event_id else {
+            return;
+        };
+        let split_time =
+            match NaiveDateTime::parse_from_str(self.split_time_input.trim(), "%Y-%m-%d %H:%M") {
+                Ok(time) => time.and_utc(),
+                Err(_) => {
+                    self.message = "拆分时间格式应为 YYYY-MM-DD HH:MM".to_string();
+                    return;
+                }
+            };
+        if self.split_title_input.trim().is_empty() {
+            self.message = "新事件标题不能为空".to_string();
+            return;
+        }
+
+        match self.event_manager.split_event(
+            event_id,
+            split_time,
+            self.split_title_input.trim().to_string(),
+        ) {
+            Ok(_) => {
+                self.dirty = true;
+                self.message = "事件已拆分".to_string();
+                self.split_event_id = None;
                 self.mode = AppMode::EventList;
             }
-        });
+            Err(e) => self.message = format!("拆分事件失败: {}", e),
+        }
     }
 
-    fn show_reports(&mut self, ui: &mut egui::Ui) {
-        ui.heading("周报");
-        
-        if ui.button("返回").clicked() {
-            self.mode = AppMode::ProjectList;
+    /// 本周的报表，项目时间分解按设置排序
+    fn current_weekly_report(&self, now: DateTime<Utc>) -> WeeklyReport {
+        let mut project_names = HashMap::new();
+        for project in self.get_projects() {
+            project_names.insert(project.id, project.name.clone());
         }
-        
-        ui.separator();
-        
-        let report = self.get_weekly_report();
-        ui.label(&report);
+
+        let mut weekly_report = ReportGenerator::generate_weekly_report_tz(
+            &self.event_manager.get_all_time_records(),
+            &project_names,
+            now,
+            self.settings.week_start,
+            self.settings.min_report_duration_minutes,
+            self.settings.timezone(),
+        );
+        TimeCalculator::sort_project_breakdown(
+            &mut weekly_report.project_breakdown,
+            &self.settings.breakdown_order,
+        );
+        weekly_report
     }
 
-    fn show_help(&mut self, ui: &mut egui::Ui) {
-        ui.heading("帮助");
-        
-        if ui.button("返回").clicked() {
-            self.mode = AppMode::ProjectList;
+    /// 报表界面向前或向后翻一周（按月显示时翻一个月）
+    pub fn step_report_date(&mut self, forward: bool) {
+        let date = self.selected_report_date;
+        self.selected_report_date = match (self.report_period, forward) {
+            (ReportPeriod::Week, true) => date + chrono::Duration::days(7),
+            (ReportPeriod::Week, false) => date - chrono::Duration::days(7),
+            (ReportPeriod::Month, true) => date.checked_add_months(Months::new(1)).unwrap_or(date),
+            (ReportPeriod::Month, false) => date.checked_sub_months(Months::new(1)).unwrap_or(date),
+        };
+    }
+
+    /// 报表界面当前显示的时间范围，按设置的时区划分周和月
+    pub fn report_range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let date = self.selected_report_date;
+        let tz = self.settings.timezone();
+        match self.report_period {
+            ReportPeriod::Week => (
+                TimeCalculator::get_week_start_tz(date, self.settings.week_start, tz),
+                TimeCalculator::get_week_end_tz(date, self.settings.week_start, tz),
+            ),
+            ReportPeriod::Month => {
+                let local_date = date.with_timezone(&tz).date_naive();
+                TimeCalculator::get_month_range_tz(local_date.year(), local_date.month(), 1, tz)
+            }
         }
-        
-        ui.separator();
-        
-        ui.label("项目管理系统使用说明：");
-        ui.label("");
-        ui.label("1. 项目列表：查看所有项目，选择当前项目");
-        ui.label("2. 事件列表：查看所有事件，完成进行中的事件");
-        ui.label("3. 添加项目：创建新项目");
-        ui.label("4. 添加事件：创建新事件（项目事件或非项目事件）");
-        ui.label("5. 报表：查看周报统计");
-        ui.label("");
-        ui.label("操作说明：");
-        ui.label("- 点击项目名称切换当前项目");
-        ui.label("- 点击\"完成\"按钮结束事件");
-        ui.label("- 使用复选框选择项目或事件");
+    }
+
+    /// 报表界面的标题，包含显示的时间范围
+    pub fn report_title(&self) -> String {
+        let tz = self.settings.timezone();
+        let (start, end) = self.report_range();
+        let (start, end) = (start.with_timezone(&tz), end.with_timezone(&tz));
+        match self.report_period {
+            ReportPeriod::Week => format!(
+                "周报 {} 至 {}",
+                start.format("%Y-%m-%d"),
+                end.format("%Y-%m-%d")
+            ),
+            ReportPeriod::Month => format!("月报 {}年{}月", start.year(), start.month()),
+        }
+    }
+
+    /// 报表界面所选月份的月度报表
+    pub fn get_monthly_report(&self) -> String {
+        let mut project_names = HashMap::new();
+        for project in self.get_projects() {
+            project_names.insert(project.id, project.name.clone());
+        }
+        let tz = self.settings.timezone();
+        let date = self.selected_report_date.with_timezone(&tz).date_naive();
+        ReportGenerator::generate_monthly_summary_tz(
+            &self.event_manager.get_all_time_records(),
+            &project_names,
+            date.year(),
+            date.month(),
+            tz,
+        )
+    }
+
+    /// 把报表界面所选周的报表按选择的格式保存到数据目录的 reports 子目录，返回保存的路径
+    pub fn save_weekly_report(&mut self) -> Option<PathBuf> {
+        let report = self.current_weekly_report(self.selected_report_date);
+        let tz = self.settings.timezone();
+        let path = Path::new(&self.data_dir).join("reports").join(format!(
+            "weekly_{}",
+            report.week_start.with_timezone(&tz).format("%Y-%m-%d")
+        ));
+        match ReportGenerator::save_report_tz(&report, &path, self.report_format, tz) {
+            Ok(path) => {
+                self.message = format!("报表已保存到 {}", path.display());
+                Some(path)
+            }
+            Err(e) => {
+                self.message = format!("保存报表失败: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 把项目已完成的事件导出为数据目录 reports 子目录中的ICS日历文件，返回文件路径
+    pub fn export_project_calendar(&mut self, project_id: Uuid) -> Option<PathBuf> {
+        let Some(project) = self.project_manager.get_project(project_id) else {
+            self.message = "项目不存在".to_string();
+            return None;
+        };
+        let project_names = HashMap::from([(project.id, project.name.clone())]);
+        let ics = ReportGenerator::export_events_to_ics(
+            &self.event_manager.get_project_events(project_id),
+            &project_names,
+        );
+
+        let dir = Path::new(&self.data_dir).join("reports");
+        let path = dir.join(format!("calendar_{}.ics", project_id));
+        match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, ics)) {
+            Ok(()) => {
+                self.message = format!("日历已导出到 {}", path.display());
+                Some(path)
+            }
+            Err(e) => {
+                self.message = format!("导出日历失败: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn get_weekly_report(&self) -> String {
+        let time_records_refs: Vec<&TimeRecord> = self.event_manager.get_all_time_records();
+
+        let mut project_names = HashMap::new();
+        for project in self.get_projects() {
+            project_names.insert(project.id, project.name.clone());
+        }
+
+        let now = self.selected_report_date;
+        let tz = self.settings.timezone();
+        let weekly_report = self.current_weekly_report(now);
+        let mut summary = ReportGenerator::generate_report_summary_tz(&weekly_report, tz);
+
+        let goals = ReportGenerator::generate_goal_progress_tz(
+            &time_records_refs,
+            &self.get_projects(),
+            now,
+            self.settings.week_start,
+            tz,
+        );
+        if !goals.is_empty() {
+            summary.push('\n');
+            summary.push_str(&ReportGenerator::format_goal_progress(&goals));
+        }
+
+        let trend = ReportGenerator::generate_trend_tz(
+            &time_records_refs,
+            &project_names,
+            now,
+            TREND_WEEKS,
+            self.settings.week_start,
+            tz,
+        );
+        summary.push('\n');
+        summary.push_str(&ReportGenerator::format_trend_tz(&trend, tz));
+        summary
+    }
+
+    /// 报表界面所选周的详细周报，每日统计只列出设置中的工作日
+    pub fn get_detailed_weekly_report(&self) -> String {
+        let mut project_names = HashMap::new();
+        for project in self.project_manager.get_all_projects() {
+            project_names.insert(project.id, project.name.clone());
+        }
+
+        ReportGenerator::generate_detailed_weekly_report(
+            &self.event_manager.get_all_time_records(),
+            &project_names,
+            self.selected_report_date,
+            self.settings.week_start,
+            self.settings.timezone(),
+            self.settings.working_days,
+        )
+    }
+
+    /// 报表界面所选周的效率分析，按月显示时为所选月份
+    pub fn get_efficiency_analysis(&self) -> String {
+        let mut project_names = HashMap::new();
+        for project in self.project_manager.get_all_projects() {
+            project_names.insert(project.id, project.name.clone());
+        }
+
+        let (start, end) = self.report_range();
+        ReportGenerator::generate_efficiency_analysis_tz(
+            &self.event_manager.get_all_time_records(),
+            &project_names,
+            start,
+            end,
+            self.settings.efficiency_thresholds,
+            self.settings.working_days,
+            self.settings.timezone(),
+        )
+        .unwrap_or_else(|e| format!("无法生成效率分析: {}", e))
+    }
+
+    /// 报表界面所选周的计费报表，按月显示时为所选月份
+    pub fn get_billing_report(&self) -> String {
+        let (start, end) = self.report_range();
+        ReportGenerator::generate_billing_report(
+            &self.event_manager.get_all_time_records(),
+            &self.project_manager.get_all_projects(),
+            start,
+            end,
+            self.settings.billing_rounding,
+            &self.settings.currency_symbol,
+        )
+    }
+
+    /// 报表界面所选周与前一周的对比报表
+    pub fn get_week_comparison_report(&self) -> String {
+        let mut project_names = HashMap::new();
+        for project in self.project_manager.get_all_projects() {
+            project_names.insert(project.id, project.name.clone());
+        }
+
+        let now = self.selected_report_date;
+        let last_week = now - chrono::Duration::days(7);
+        let tz = self.settings.timezone();
+        let week = |date| {
+            (
+                TimeCalculator::get_week_start_tz(date, self.settings.week_start, tz),
+                TimeCalculator::get_week_end_tz(date, self.settings.week_start, tz),
+            )
+        };
+        ReportGenerator::generate_comparison_tz(
+            &self.event_manager.get_all_time_records(),
+            &project_names,
+            week(last_week),
+            week(now),
+            tz,
+        )
+    }
+
+    pub fn get_all_time_stats(&self) -> String {
+        ReportGenerator::generate_all_time_stats(
+            &self.event_manager.get_all_time_records(),
+            &self.event_manager.get_all_events(),
+            &self.get_projects(),
+        )
+    }
+
+    /// 今天（按设置的时区）记录的项目内时间（分钟）
+    pub fn today_project_minutes(&self) -> i64 {
+        let (project_minutes, _) = TimeCalculator::calculate_daily_stats_tz(
+            &self.event_manager.get_all_time_records(),
+            Utc::now(),
+            self.settings.timezone(),
+        );
+        project_minutes
+    }
+
+    /// 汇总所有项目的项目数、事件数和记录时间，记录时间相同时取较早创建的项目为最忙项目
+    pub fn dashboard_stats(&self) -> DashboardStats {
+        let records = self.event_manager.get_all_time_records();
+        let mut project_minutes: HashMap<Uuid, i64> = HashMap::new();
+        for record in &records {
+            if let Some(project_id) = record.project_id {
+                *project_minutes.entry(project_id).or_default() += record.duration_minutes;
+            }
+        }
+        let busiest_project = self
+            .get_projects()
+            .into_iter()
+            .filter_map(|p| project_minutes.get(&p.id).map(|minutes| (p, *minutes)))
+            .filter(|(_, minutes)| *minutes > 0)
+            .max_by_key(|(p, minutes)| (*minutes, std::cmp::Reverse((p.created_at, p.id))))
+            .map(|(p, minutes)| (p.name.clone(), minutes));
+
+        DashboardStats {
+            project_count: self.project_manager.get_project_count(),
+            active_events: self.event_manager.get_active_events().len(),
+            completed_events: self.event_manager.get_completed_events().len(),
+            total_minutes: records.iter().map(|record| record.duration_minutes).sum(),
+            busiest_project,
+        }
+    }
+
+    /// 有未保存的修改时保存数据，返回是否执行了保存
+    pub fn save_if_dirty(&mut self, store: &dyn storage::DataStore) -> std::io::Result<bool> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        self.save_if_changed(store)
+    }
+
+    /// 数据内容与上次保存时不同才写入，返回是否执行了保存
+    pub fn save_if_changed(&mut self, store: &dyn storage::DataStore) -> std::io::Result<bool> {
+        let app_data = self.to_app_data();
+        let hash = content_hash(&app_data);
+        if hash.is_some() && hash == self.last_saved_hash {
+            self.dirty = false;
+            return Ok(false);
+        }
+        store.save_app_data(&app_data)?;
+        self.last_saved_hash = hash;
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// 导出当前状态用于保存（包括用户设置）
+    pub fn to_app_data(&self) -> storage::AppData {
+        storage::AppData::from_managers(&self.project_manager, &self.event_manager)
+            .with_settings(self.settings.clone())
+    }
+
+    /// 生成最近完成事件的一行描述，例如 "14:32 完成 '写报告' (项目A) 用时1小时"
+    pub fn format_completion_line(&self, event: &Event) -> String {
+        let project_name = match &event.event_type {
+            EventType::ProjectRelated(project_id) => self
+                .project_manager
+                .get_project(*project_id)
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| "未知项目".to_string()),
+            EventType::NonProject => "项目外".to_string(),
+        };
+        let end_time = event
+            .end_time
+            .map(|t| t.format("%H:%M").to_string())
+            .unwrap_or_default();
+        let minutes = event.duration().map(|d| d.num_minutes()).unwrap_or(0);
+
+        format!(
+            "{} 完成 '{}' ({}) 用时{}",
+            end_time,
+            event.title,
+            project_name,
+            TimeCalculator::format_duration(minutes)
+        )
+    }
+
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let previous_mode = self.mode;
+        let previous_settings = self.settings.clone();
+
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("项目管理系统");
+
+                if let Some(current_project) = self.get_current_project() {
+                    ui.label(format!("当前项目: {}", current_project.name));
+                } else {
+                    ui.label("没有当前项目");
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("帮助").clicked() {
+                        self.mode = AppMode::Help;
+                    }
+                    if ui.button("概览").clicked() {
+                        self.mode = AppMode::Dashboard;
+                    }
+                    if ui
+                        .add_enabled(!self.undo_stack.is_empty(), egui::Button::new("撤销"))
+                        .clicked()
+                    {
+                        self.undo();
+                    }
+                    if ui.button("报表").clicked() {
+                        self.selected_report_date = Utc::now();
+                        self.mode = AppMode::Reports;
+                    }
+                    if ui.button("统计").clicked() {
+                        self.mode = AppMode::Stats;
+                    }
+                    if ui.button("时间线").clicked() {
+                        self.timeline_date = Utc::now()
+                            .with_timezone(&self.settings.timezone())
+                            .date_naive();
+                        self.mode = AppMode::Timeline;
+                    }
+                    if ui.button("回收站").clicked() {
+                        self.mode = AppMode::Trash;
+                    }
+                    if ui.button("最近完成").clicked() {
+                        self.recent_completions_page = 0;
+                        self.mode = AppMode::RecentCompletions;
+                    }
+                });
+            });
+        });
+
+        egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let mode_text = match self.mode {
+                    AppMode::Dashboard => "概览",
+                    AppMode::ProjectList => "项目列表",
+                    AppMode::EventList => "事件列表",
+                    AppMode::AddProject => "添加项目",
+                    AppMode::AddEvent => "添加事件",
+                    AppMode::Reports => "报表",
+                    AppMode::RecentCompletions => "最近完成",
+                    AppMode::SplitEvent => "拆分事件",
+                    AppMode::EditEvent => "编辑事件",
+                    AppMode::EditProject => "编辑项目",
+                    AppMode::ConfirmDelete { .. } => "确认删除",
+                    AppMode::Timeline => "时间线",
+                    AppMode::Stats => "统计",
+                    AppMode::Trash => "回收站",
+                    AppMode::Help => "帮助",
+                    AppMode::DateRangeSearch => "按日期搜索",
+                };
+                ui.label(format!("模式: {}", mode_text));
+                ui.label(format!(
+                    "今日项目时间: {}",
+                    TimeCalculator::format_duration(self.today_project_minutes())
+                ));
+                ui.label(&self.message);
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| match self.mode {
+            AppMode::Dashboard => self.show_dashboard(ui),
+            AppMode::ProjectList => self.show_project_list(ui),
+            AppMode::EventList => self.show_event_list(ui),
+            AppMode::AddProject => self.show_add_project(ui),
+            AppMode::AddEvent => self.show_add_event(ui),
+            AppMode::Reports => self.show_reports(ui),
+            AppMode::RecentCompletions => self.show_recent_completions(ui),
+            AppMode::SplitEvent => self.show_split_event(ui),
+            AppMode::EditEvent => self.show_edit_event(ui),
+            AppMode::EditProject => self.show_edit_project(ui),
+            AppMode::ConfirmDelete { target } => self.show_confirm_delete(ui, target),
+            AppMode::Timeline => self.show_timeline(ui),
+            AppMode::Stats => self.show_stats(ui),
+            AppMode::Trash => self.show_trash(ui),
+            AppMode::Help => self.show_help(ui),
+            AppMode::DateRangeSearch => self.show_date_range_search(ui),
+        });
+
+        // 没有在输入文字时处理快捷键
+        if !ctx.wants_keyboard_input() {
+            let keys: Vec<egui::Key> = ctx.input(|i| {
+                [
+                    egui::Key::U,
+                    egui::Key::A,
+                    egui::Key::I,
+                    egui::Key::C,
+                    egui::Key::D,
+                    egui::Key::S,
+                    egui::Key::R,
+                    egui::Key::Y,
+                    egui::Key::N,
+                    egui::Key::Escape,
+                    egui::Key::ArrowUp,
+                    egui::Key::ArrowDown,
+                    egui::Key::ArrowLeft,
+                    egui::Key::ArrowRight,
+                ]
+                .into_iter()
+                .filter(|key| i.key_pressed(*key))
+                .collect()
+            });
+            for key in keys {
+                self.handle_key(key);
+            }
+        }
+
+        if self.settings != previous_settings {
+            self.dirty = true;
+        }
+
+        // 切换界面后不再保留上一个表单的放弃确认
+        if self.mode != previous_mode {
+            self.discard_prompt = false;
+        }
+
+        // 有番茄钟在进行时每秒刷新倒计时，其他进行中的事件按分钟刷新已进行时间
+        let active_events = self.event_manager.get_active_events();
+        let pomodoro_running = active_events
+            .iter()
+            .any(|event| self.event_manager.get_pomodoro(event.id).is_some());
+        if pomodoro_running {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        } else if !active_events.is_empty() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(30));
+        }
+
+        if self.exit_prompt {
+            self.show_exit_prompt(ctx);
+        }
+    }
+
+    fn show_project_list(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("添加项目").clicked() {
+                self.mode = AppMode::AddProject;
+            }
+            if ui.button("查看事件").clicked() {
+                self.mode = AppMode::EventList;
+            }
+
+            ui.checkbox(&mut self.show_archived_projects, "显示已归档和已结束的项目");
+        });
+
+        if let Some(source_id) = self.merge_source {
+            ui.horizontal(|ui| {
+                let name = self
+                    .project_manager
+                    .get_project(source_id)
+                    .map(|p| p.name.clone())
+                    .unwrap_or_default();
+                ui.label(format!("选择要把\"{}\"合并到哪个项目", name));
+                if ui.button("取消合并").clicked() {
+                    self.merge_source = None;
+                }
+            });
+        }
+
+        ui.separator();
+
+        let projects: Vec<_> = self.get_listed_projects().into_iter().cloned().collect();
+        if projects.is_empty() {
+            ui.label("没有项目，点击\"添加项目\"创建新项目");
+        } else {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut project_to_switch = None;
+                let mut project_to_archive = None;
+                let mut merge_action = None;
+                let mut project_to_delete = None;
+                let mut project_to_edit = None;
+                let mut project_to_export = None;
+                let mut color_change = None;
+                let mut goal_change = None;
+                let mut status_change = None;
+
+                for (index, project) in projects.iter().enumerate() {
+                    let row = ui
+                        .horizontal(|ui| {
+                            let mut selected = self.selected_project_index == index;
+                            if ui.checkbox(&mut selected, "").clicked() {
+                                project_to_switch = Some((index, project.id));
+                            }
+
+                            ui.vertical(|ui| {
+                                let rgb = project
+                                    .color
+                                    .as_deref()
+                                    .and_then(|hex| parse_hex_color(hex).ok());
+                                ui.horizontal(|ui| {
+                                    let mut name = egui::RichText::new(&project.name).heading();
+                                    if let Some([r, g, b]) = rgb {
+                                        name = name.color(egui::Color32::from_rgb(r, g, b));
+                                    }
+                                    ui.label(name);
+
+                                    let mut picked = rgb.unwrap_or([70, 130, 180]);
+                                    if ui.color_edit_button_srgb(&mut picked).changed() {
+                                        let [r, g, b] = picked;
+                                        let hex = format!("#{:02x}{:02x}{:02x}", r, g, b);
+                                        color_change = Some((project.id, hex));
+                                    }
+                                });
+                                if let Some(desc) = &project.description {
+                                    ui.label(desc);
+                                }
+                                ui.label(format!(
+                                    "创建时间: {}",
+                                    project.created_at.format("%Y-%m-%d %H:%M")
+                                ));
+                                ui.horizontal(|ui| {
+                                    ui.label("每周目标:");
+                                    let mut hours =
+                                        project.weekly_goal_minutes.unwrap_or(0) as f64 / 60.0;
+                                    let drag = egui::DragValue::new(&mut hours)
+                                        .speed(0.5)
+                                        .clamp_range(0.0..=168.0)
+                                        .suffix(" 小时");
+                                    if ui.add(drag).changed() {
+                                        let minutes = (hours * 60.0).round() as i64;
+                                        goal_change =
+                                            Some((project.id, (minutes > 0).then_some(minutes)));
+                                    }
+                                });
+                                if project.is_active {
+                                    ui.label("（当前项目）");
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label("状态:");
+                                    egui::ComboBox::from_id_source(("project_status", project.id))
+                                        .selected_text(project_status_label(project.status))
+                                        .show_ui(ui, |ui| {
+                                            for status in [
+                                                ProjectStatus::Planned,
+                                                ProjectStatus::Active,
+                                                ProjectStatus::Done,
+                                                ProjectStatus::Cancelled,
+                                            ] {
+                                                let label = project_status_label(status);
+                                                if ui
+                                                    .selectable_label(
+                                                        project.status == status,
+                                                        label,
+                                                    )
+                                                    .clicked()
+                                                    && project.status != status
+                                                {
+                                                    status_change = Some((project.id, status));
+                                                }
+                                            }
+                                        });
+                                });
+                                if project.archived {
+                                    ui.label("（已归档）");
+                                    if ui.button("取消归档").clicked() {
+                                        project_to_archive = Some((project.id, false));
+                                    }
+                                } else if ui.button("归档").clicked() {
+                                    project_to_archive = Some((project.id, true));
+                                }
+                                match self.merge_source {
+                                    None => {
+                                        if ui.button("合并到...").clicked() {
+                                            merge_action = Some((project.id, None));
+                                        }
+                                    }
+                                    Some(source_id) if source_id != project.id => {
+                                        if ui.button("合并到此项目").clicked() {
+                                            merge_action = Some((source_id, Some(project.id)));
+                                        }
+                                    }
+                                    Some(_) => {}
+                                }
+                                if ui.button("编辑").clicked() {
+                                    project_to_edit = Some(project.id);
+                                }
+                                if ui.button("导出日历").clicked() {
+                                    project_to_export = Some(project.id);
+                                }
+                                if ui.button("删除").clicked() {
+                                    project_to_delete = Some(project.id);
+                                }
+                            });
+                        })
+                        .response;
+                    self.project_scroll.track_row(ui, index, &row);
+                    ui.separator();
+                }
+                self.project_scroll.finish_frame();
+
+                // 在闭包外切换项目
+                if let Some((index, project_id)) = project_to_switch {
+                    self.selected_project_index = index;
+                    self.switch_to_project(project_id);
+                }
+                if let Some((project_id, archived)) = project_to_archive {
+                    self.set_project_archived(project_id, archived);
+                }
+                match merge_action {
+                    Some((source_id, None)) => self.merge_source = Some(source_id),
+                    Some((source_id, Some(target_id))) => self.merge_projects(source_id, target_id),
+                    None => {}
+                }
+                if let Some((project_id, status)) = status_change {
+                    self.set_project_status(project_id, status);
+                }
+                if let Some((project_id, goal_minutes)) = goal_change {
+                    self.set_project_weekly_goal(project_id, goal_minutes);
+                }
+                if let Some((project_id, color)) = color_change {
+                    self.set_project_color(project_id, &color);
+                }
+                if let Some(project_id) = project_to_edit {
+                    self.begin_edit_project(project_id);
+                }
+                if let Some(project_id) = project_to_export {
+                    self.export_project_calendar(project_id);
+                }
+                if let Some(project_id) = project_to_delete {
+                    self.request_delete(DeleteTarget::Project(project_id));
+                }
+            });
+        }
+    }
+
+    fn show_event_list(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("返回项目").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            if ui.button("添加项目事件").clicked() {
+                self.begin_add_event(true);
+            }
+            if ui.button("添加非项目事件").clicked() {
+                self.begin_add_event(false);
+            }
+
+            if ui.button("全部完成").clicked() {
+                self.complete_all_active();
+            }
+            if ui.button("按日期搜索").clicked() {
+                self.mode = AppMode::DateRangeSearch;
+            }
+
+            ui.checkbox(&mut self.show_completed_events, "显示已完成事件");
+            ui.checkbox(&mut self.sort_by_priority, "按优先级排序");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("按标签筛选:");
+            ui.text_edit_singleline(&mut self.tag_filter);
+            if !self.tag_filter.is_empty() && ui.button("清除").clicked() {
+                self.tag_filter.clear();
+            }
+            ui.label("新标签:");
+            ui.text_edit_singleline(&mut self.new_tag_input);
+            if self.selected_project_id.is_some() && ui.button("加到当前项目的全部事件").clicked()
+            {
+                self.tag_current_project_events();
+            }
+            ui.label("笔记:");
+            ui.text_edit_singleline(&mut self.note_input);
+            ui.label("新检查项:");
+            ui.text_edit_singleline(&mut self.new_subtask_input);
+        });
+
+        let overlap_count = self.event_manager.find_overlapping_events().len();
+        if overlap_count > 0 {
+            ui.colored_label(
+                egui::Color32::from_rgb(200, 120, 0),
+                format!(
+                    "注意：有{}组事件的时间相互重叠，可能重复计时",
+                    overlap_count
+                ),
+            );
+        }
+
+        ui.separator();
+
+        let events: Vec<_> = self.get_events().into_iter().cloned().collect();
+        let blocked: HashSet<Uuid> = self
+            .event_manager
+            .get_blocked_events(Utc::now())
+            .iter()
+            .map(|event| event.id)
+            .collect();
+        if events.is_empty() {
+            ui.label("没有事件");
+        } else {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut events_to_complete = Vec::new();
+                let mut event_to_reopen = None;
+                let mut event_to_split = None;
+                let mut event_to_tag = None;
+                let mut event_to_add_subtask = None;
+                let mut subtask_to_toggle = None;
+                let mut event_to_select = None;
+                let mut event_to_edit = None;
+                let mut event_to_delete = None;
+                let mut event_to_duplicate = None;
+                let mut priority_change = None;
+                let mut pomodoro_to_start = None;
+                let mut event_to_note = None;
+                let mut pomodoro_action = None;
+                let mut tag_to_remove = None;
+                let mut dependency_to_add = None;
+
+                for (index, event) in events.iter().enumerate() {
+                    let row = ui
+                        .horizontal(|ui| {
+                            ui.vertical(|ui| {
+                                let selected = self.selected_event_index == index;
+                                // 高优先级且进行中的事件加上标记
+                                let title =
+                                    if event.priority == Priority::High && !event.is_completed() {
+                                        format!("⚠ {}", event.title)
+                                    } else {
+                                        event.title.clone()
+                                    };
+                                if ui
+                                    .selectable_label(
+                                        selected,
+                                        egui::RichText::new(title).heading(),
+                                    )
+                                    .clicked()
+                                {
+                                    event_to_select = Some(index);
+                                }
+                                if let Some(desc) = &event.description {
+                                    ui.label(desc);
+                                }
+
+                                let event_type = match &event.event_type {
+                                    EventType::ProjectRelated(project_id) => {
+                                        if let Some(project) =
+                                            self.project_manager.get_project(*project_id)
+                                        {
+                                            format!("项目: {}", project.name)
+                                        } else {
+                                            "项目: (未知)".to_string()
+                                        }
+                                    }
+                                    EventType::NonProject => "非项目事件".to_string(),
+                                };
+                                ui.label(event_type);
+
+                                if blocked.contains(&event.id) {
+                                    let waiting: Vec<&str> = event
+                                        .depends_on
+                                        .iter()
+                                        .filter_map(|id| self.event_manager.get_event(*id))
+                                        .filter(|dependency| !dependency.is_completed())
+                                        .map(|dependency| dependency.title.as_str())
+                                        .collect();
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(200, 120, 0),
+                                        format!("等待前置事件: {}", waiting.join("、")),
+                                    );
+                                }
+
+                                ui.horizontal(|ui| {
+                                    ui.label("优先级:");
+                                    for priority in
+                                        [Priority::Low, Priority::Medium, Priority::High]
+                                    {
+                                        let label = priority_label(priority);
+                                        if ui
+                                            .selectable_label(event.priority == priority, label)
+                                            .clicked()
+                                            && event.priority != priority
+                                        {
+                                            priority_change = Some((event.id, priority));
+                                        }
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    ui.label("标签:");
+                                    for tag in &event.tags {
+                                        if ui.small_button(format!("{} ×", tag)).clicked() {
+                                            tag_to_remove = Some((event.id, tag.clone()));
+                                        }
+                                    }
+                                    if ui.small_button("添加标签").clicked() {
+                                        event_to_tag = Some(event.id);
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    let (done, total) = event.subtask_progress();
+                                    if total > 0 {
+                                        ui.label(format!("检查项 {}/{} 完成", done, total));
+                                    }
+                                    if ui.small_button("添加检查项").clicked() {
+                                        event_to_add_subtask = Some(event.id);
+                                    }
+                                });
+                                for (subtask_index, subtask) in event.subtasks.iter().enumerate() {
+                                    let mut done = subtask.done;
+                                    if ui.checkbox(&mut done, &subtask.title).clicked() {
+                                        subtask_to_toggle = Some((event.id, subtask_index));
+                                    }
+                                }
+
+                                ui.label(format!(
+                                    "开始时间: {}",
+                                    event.start_time.format("%Y-%m-%d %H:%M")
+                                ));
+
+                                if let Some(end_time) = event.end_time {
+                                    ui.label(format!(
+                                        "结束时间: {}",
+                                        end_time.format("%Y-%m-%d %H:%M")
+                                    ));
+                                    if let Some(duration) = event.duration() {
+                                        ui.label(format!(
+                                            "持续时间: {}分钟",
+                                            duration.num_minutes()
+                                        ));
+                                    }
+                                    if let Some(note) = self
+                                        .event_manager
+                                        .get_event_time_record(event.id)
+                                        .and_then(|record| record.note.as_deref())
+                                    {
+                                        ui.label(format!("笔记: {}", note));
+                                    }
+                                    ui.horizontal(|ui| {
+                                        if ui.button("重新打开").clicked() {
+                                            event_to_reopen = Some(event.id);
+                                        }
+                                        if ui.button("保存笔记").clicked() {
+                                            event_to_note = Some(event.id);
+                                        }
+                                    });
+                                } else if let Some(session) =
+                                    self.event_manager.get_pomodoro(event.id)
+                                {
+                                    let now = Utc::now();
+                                    ui.label(format!(
+                                        "[进行中] 已进行 {}",
+                                        TimeCalculator::format_duration(
+                                            event.elapsed(now).num_minutes()
+                                        )
+                                    ));
+                                    let phase = match session.phase(now) {
+                                        PomodoroPhase::Work => "工作中",
+                                        PomodoroPhase::Break => "休息中",
+                                    };
+                                    let remaining = session.remaining(now).num_seconds();
+                                    ui.label(format!(
+                                        "番茄钟{}，剩余 {:02}:{:02}（已完成{}个）",
+                                        phase,
+                                        remaining / 60,
+                                        remaining % 60,
+                                        session.completed_cycles(now)
+                                    ));
+                                    ui.horizontal(|ui| {
+                                        if ui.button("结束番茄钟").clicked() {
+                                            pomodoro_action = Some((event.id, true));
+                                        }
+                                        if ui.button("放弃番茄钟").clicked() {
+                                            pomodoro_action = Some((event.id, false));
+                                        }
+                                    });
+                                } else {
+                                    ui.label(format!(
+                                        "[进行中] 已进行 {}",
+                                        TimeCalculator::format_duration(
+                                            event.elapsed(Utc::now()).num_minutes()
+                                        )
+                                    ));
+                                    ui.horizontal(|ui| {
+                                        if ui.button("完成").clicked() {
+                                            events_to_complete.push(event.id);
+                                        }
+                                        if ui.button("开始番茄钟").clicked() {
+                                            pomodoro_to_start = Some(event.id);
+                                        }
+                                    });
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.button("编辑").clicked() {
+                                        event_to_edit = Some(event.id);
+                                    }
+                                    if ui.button("拆分").clicked() {
+                                        event_to_split = Some(event.id);
+                                    }
+                                    if ui.button("复制").clicked() {
+                                        event_to_duplicate = Some(event.id);
+                                    }
+                                    if ui.button("删除").clicked() {
+                                        event_to_delete = Some(event.id);
+                                    }
+                                    if index != self.selected_event_index
+                                        && ui.button("依赖选中事件").clicked()
+                                    {
+                                        dependency_to_add = Some(event.id);
+                                    }
+                                });
+                            });
+                        })
+                        .response;
+                    self.event_scroll.track_row(ui, index, &row);
+                    ui.separator();
+                }
+                self.event_scroll.finish_frame();
+
+                // 在闭包外完成事件
+                for event_id in events_to_complete {
+                    self.complete_event(event_id);
+                }
+                if let Some(event_id) = event_to_reopen {
+                    self.reopen_event(event_id);
+                }
+                if let Some(index) = event_to_select {
+                    self.selected_event_index = index;
+                }
+                if let Some(event_id) = event_to_edit {
+                    self.begin_edit_event(event_id);
+                }
+                if let Some(event_id) = event_to_split {
+                    self.begin_split_event(event_id);
+                }
+                if let Some(event_id) = event_to_note {
+                    self.set_event_note(event_id);
+                }
+                if let Some(event_id) = pomodoro_to_start {
+                    self.start_pomodoro(event_id);
+                }
+                match pomodoro_action {
+                    Some((event_id, true)) => self.finish_pomodoro(event_id),
+                    Some((event_id, false)) => self.cancel_pomodoro(event_id),
+                    None => {}
+                }
+                if let Some((event_id, priority)) = priority_change {
+                    self.set_event_priority(event_id, priority);
+                }
+                if let Some(event_id) = event_to_delete {
+                    self.request_delete(DeleteTarget::Event(event_id));
+                }
+                if let Some(event_id) = event_to_duplicate {
+                    self.duplicate_event(event_id);
+                }
+                if let Some(event_id) = event_to_tag {
+                    self.add_tag_to_event(event_id);
+                }
+                if let Some(event_id) = event_to_add_subtask {
+                    self.add_subtask_to_event(event_id);
+                }
+                if let Some((event_id, index)) = subtask_to_toggle {
+                    self.toggle_subtask(event_id, index);
+                }
+                if let Some((event_id, tag)) = tag_to_remove {
+                    self.remove_tag_from_event(event_id, &tag);
+                }
+                if let (Some(event_id), Some(selected)) =
+                    (dependency_to_add, events.get(self.selected_event_index))
+                {
+                    self.add_event_dependency(event_id, selected.id);
+                }
+            });
+        }
+    }
+
+    fn show_add_project(&mut self, ui: &mut egui::Ui) {
+        ui.heading("添加新项目");
+
+        ui.horizontal(|ui| {
+            ui.label("项目名称:");
+            let name = ui.text_edit_singleline(&mut self.new_project_name);
+            // 打开表单时直接输入项目名称
+            if ui.memory(|m| m.focused().is_none()) {
+                name.request_focus();
+            }
+        });
+
+        let description = ui
+            .horizontal(|ui| {
+                ui.label("项目描述:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.new_project_description)
+                        .id(egui::Id::new("new_project_description")),
+                )
+            })
+            .inner;
+
+        // 在描述中按Enter是换行，不提交表单
+        let confirm = !self.discard_prompt
+            && !description.has_focus()
+            && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let mut cancel_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("添加").clicked() || confirm {
+                let description = if self.new_project_description.is_empty() {
+                    None
+                } else {
+                    Some(self.new_project_description.clone())
+                };
+                if self
+                    .add_project(self.new_project_name.clone(), description)
+                    .is_ok()
+                {
+                    self.mode = AppMode::ProjectList;
+                }
+            }
+
+            cancel_clicked = ui.button("取消").clicked();
+        });
+
+        self.handle_input_cancel(ui, cancel_clicked);
+    }
+
+    fn show_add_event(&mut self, ui: &mut egui::Ui) {
+        ui.heading("添加新事件");
+
+        ui.horizontal(|ui| {
+            ui.label("事件标题:");
+            ui.text_edit_singleline(&mut self.new_event_title);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("事件描述:");
+            ui.text_edit_multiline(&mut self.new_event_description);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("事件类型:");
+            ui.radio_value(&mut self.event_type_selection, true, "项目事件");
+            ui.radio_value(&mut self.event_type_selection, false, "非项目事件");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("重复:");
+            let weekday = Utc::now().weekday();
+            ui.radio_value(&mut self.new_event_recurrence, None, "不重复");
+            ui.radio_value(
+                &mut self.new_event_recurrence,
+                Some(Recurrence::Daily),
+                "每天",
+            );
+            ui.radio_value(
+                &mut self.new_event_recurrence,
+                Some(Recurrence::Weekdays),
+                "工作日",
+            );
+            ui.radio_value(
+                &mut self.new_event_recurrence,
+                Some(Recurrence::Weekly(weekday)),
+                "每周",
+            );
+        });
+
+        let mut cancel_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("添加").clicked() {
+                let description = if self.new_event_description.is_empty() {
+                    None
+                } else {
+                    Some(self.new_event_description.clone())
+                };
+                let added = match self.new_event_recurrence {
+                    Some(recurrence) => self
+                        .add_recurring_event(
+                            self.new_event_title.clone(),
+                            description,
+                            self.event_type_selection,
+                            recurrence,
+                        )
+                        .is_ok(),
+                    None => self
+                        .add_event(
+                            self.new_event_title.clone(),
+                            description,
+                            self.event_type_selection,
+                        )
+                        .is_ok(),
+                };
+                if added {
+                    self.mode = self.add_event_return_mode;
+                }
+            }
+
+            cancel_clicked = ui.button("取消").clicked();
+        });
+
+        self.handle_input_cancel(ui, cancel_clicked);
+    }
+
+    fn show_split_event(&mut self, ui: &mut egui::Ui) {
+        ui.heading("拆分事件");
+
+        if let Some(event) = self
+            .split_event_id
+            .and_then(|id| self.event_manager.get_event(id))
+        {
+            ui.label(format!("原事件: {}", event.title));
+            ui.label(format!(
+                "时间: {} - {}",
+                event.start_time.format("%Y-%m-%d %H:%M"),
+                event
+                    .end_time
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "进行中".to_string())
+            ));
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("拆分时间:");
+            ui.text_edit_singleline(&mut self.split_time_input);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("新事件标题:");
+            ui.text_edit_singleline(&mut self.split_title_input);
+        });
+
+        ui.label("Enter 确认拆分，Esc 取消");
+
+        let confirm = !self.discard_prompt && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        let mut cancel_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("拆分").clicked() || confirm {
+                self.split_event();
+            }
+            cancel_clicked = ui.button("取消").clicked();
+        });
+
+        self.handle_input_cancel(ui, cancel_clicked);
+    }
+
+    fn show_confirm_delete(&mut self, ui: &mut egui::Ui, target: DeleteTarget) {
+        ui.heading("确认删除? y/n");
+
+        match target {
+            DeleteTarget::Project(project_id) => {
+                if let Some(project) = self.project_manager.get_project(project_id) {
+                    ui.label(format!("项目: {}", project.name));
+                }
+                ui.radio_value(
+                    &mut self.project_deletion,
+                    ProjectDeletion::DeleteEvents,
+                    "一并删除项目的事件和时间记录",
+                );
+                ui.radio_value(
+                    &mut self.project_deletion,
+                    ProjectDeletion::MoveToNonProject,
+                    "保留事件，改为项目外事件",
+                );
+            }
+            DeleteTarget::Event(event_id) => {
+                if let Some(event) = self.event_manager.get_event(event_id) {
+                    ui.label(format!("事件: {}", event.title));
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("删除").clicked() {
+                self.confirm_delete();
+            }
+            if ui.button("取消").clicked() {
+                self.cancel_delete();
+            }
+        });
+    }
+
+    fn show_edit_event(&mut self, ui: &mut egui::Ui) {
+        ui.heading("编辑事件");
+
+        ui.horizontal(|ui| {
+            ui.label("事件标题:");
+            ui.text_edit_singleline(&mut self.input);
+        });
+
+        ui.label("Enter 保存，Esc 取消");
+
+        let confirm = ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        let mut cancel_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("保存").clicked() || confirm {
+                self.confirm_edit_event();
+            }
+            cancel_clicked = ui.button("取消").clicked();
+        });
+
+        self.handle_input_cancel(ui, cancel_clicked);
+    }
+
+    fn show_edit_project(&mut self, ui: &mut egui::Ui) {
+        ui.heading("编辑项目");
+
+        ui.horizontal(|ui| {
+            ui.label("项目名称:");
+            ui.text_edit_singleline(&mut self.new_project_name);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("项目描述:");
+            ui.text_edit_multiline(&mut self.new_project_description);
+        });
+
+        ui.label("描述留空表示清除描述，Esc 取消");
+
+        let mut cancel_clicked = false;
+        ui.horizontal(|ui| {
+            if ui.button("保存").clicked() {
+                self.confirm_edit_project();
+            }
+            cancel_clicked = ui.button("取消").clicked();
+        });
+
+        self.handle_input_cancel(ui, cancel_clicked);
+    }
+
+    /// 当前表单是否有尚未保存的输入
+    fn has_unsaved_input(&self) -> bool {
+        match self.mode {
+            AppMode::AddProject => {
+                !self.new_project_name.is_empty() || !self.new_project_description.is_empty()
+            }
+            AppMode::AddEvent => {
+                !self.new_event_title.is_empty() || !self.new_event_description.is_empty()
+            }
+            AppMode::SplitEvent => !self.split_title_input.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// 放弃当前表单的输入并返回上一级
+    fn discard_input(&mut self) {
+        match self.mode {
+            AppMode::AddProject => {
+                self.new_project_name.clear();
+                self.new_project_description.clear();
+                self.mode = AppMode::ProjectList;
+            }
+            AppMode::AddEvent => {
+                self.new_event_title.clear();
+                self.new_event_description.clear();
+                self.mode = self.add_event_return_mode;
+            }
+            AppMode::SplitEvent => {
+                self.split_event_id = None;
+                self.split_title_input.clear();
+                self.mode = AppMode::EventList;
+            }
+            AppMode::EditEvent => {
+                self.edit_event_id = None;
+                self.input.clear();
+                self.mode = AppMode::EventList;
+            }
+            AppMode::EditProject => {
+                self.edit_project_id = None;
+                self.new_project_name.clear();
+                self.new_project_description.clear();
+                self.mode = AppMode::ProjectList;
+            }
+            _ => {}
+        }
+    }
+
+    /// 处理表单的取消操作（取消按钮或Esc），有未保存输入时按设置先询问
+    fn handle_input_cancel(&mut self, ui: &mut egui::Ui, cancel_clicked: bool) {
+        let (escape, yes, no) = ui.input(|i| {
+            (
+                i.key_pressed(egui::Key::Escape),
+                i.key_pressed(egui::Key::Y),
+                i.key_pressed(egui::Key::N),
+            )
+        });
+
+        if self.discard_prompt {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("放弃输入？(y/n)");
+                if ui.button("是").clicked() || yes {
+                    self.discard_prompt = false;
+                    self.discard_input();
+                } else if ui.button("否").clicked() || no || escape {
+                    // 取消确认，继续编辑
+                    self.discard_prompt = false;
+                }
+            });
+        } else if cancel_clicked || escape {
+            if self.settings.confirm_discard_input && self.has_unsaved_input() {
+                self.discard_prompt = true;
+                // 避免确认时按下的 y/n 被输入到文本框中
+                ui.ctx().memory_mut(|m| m.stop_text_input());
+            } else {
+                self.discard_input();
+            }
+        }
+    }
+
+    fn show_reports(&mut self, ui: &mut egui::Ui) {
+        ui.heading(self.report_title());
+
+        ui.horizontal(|ui| {
+            if ui.button("返回").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            let step = match self.report_period {
+                ReportPeriod::Week => "周",
+                ReportPeriod::Month => "月",
+            };
+            if ui.button(format!("◀ 上一{}", step)).clicked() {
+                self.step_report_date(false);
+            }
+            if ui.button(format!("下一{} ▶", step)).clicked() {
+                self.step_report_date(true);
+            }
+            if ui.button("回到现在").clicked() {
+                self.selected_report_date = Utc::now();
+            }
+            ui.radio_value(&mut self.report_period, ReportPeriod::Week, "按周");
+            ui.radio_value(&mut self.report_period, ReportPeriod::Month, "按月");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("每周开始:");
+            ui.radio_value(&mut self.settings.week_start, WeekStart::Monday, "周一");
+            ui.radio_value(&mut self.settings.week_start, WeekStart::Sunday, "周日");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("项目排序:");
+            let order = &mut self.settings.breakdown_order;
+            if ui
+                .radio(*order == BreakdownOrder::TimeDesc, "按时间")
+                .clicked()
+            {
+                *order = BreakdownOrder::TimeDesc;
+            }
+            if ui.radio(*order == BreakdownOrder::Name, "按名称").clicked() {
+                *order = BreakdownOrder::Name;
+            }
+            let is_pinned = matches!(order, BreakdownOrder::Pinned(_));
+            if ui.radio(is_pinned, "自定义").clicked() && !is_pinned {
+                let mut projects = self.project_manager.get_all_projects();
+                projects.sort_by(|a, b| a.name.cmp(&b.name));
+                *order = BreakdownOrder::Pinned(projects.iter().map(|p| p.id).collect());
+            }
+        });
+
+        if let BreakdownOrder::Pinned(pinned) = &mut self.settings.breakdown_order {
+            let mut move_up = None;
+            for (index, project_id) in pinned.iter().enumerate() {
+                let name = self
+                    .project_manager
+                    .get_project(*project_id)
+                    .map(|p| p.name.as_str())
+                    .unwrap_or("未知项目");
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(index > 0, egui::Button::new("↑")).clicked() {
+                        move_up = Some(index);
+                    }
+                    ui.label(format!("{}. {}", index + 1, name));
+                });
+            }
+            if let Some(index) = move_up {
+                pinned.swap(index - 1, index);
+            }
+        }
+
+        ui.separator();
+
+        if self.report_period == ReportPeriod::Month {
+            ui.label(self.get_monthly_report());
+            ui.separator();
+            ui.label(self.get_billing_report());
+            ui.separator();
+            ui.label(self.get_efficiency_analysis());
+            return;
+        }
+
+        let report = self.get_weekly_report();
+        ui.label(&report);
+
+        ui.horizontal(|ui| {
+            ui.label("保存格式:");
+            for (format, label) in [
+                (ReportFormat::Markdown, "Markdown"),
+                (ReportFormat::Html, "HTML"),
+                (ReportFormat::Csv, "CSV"),
+                (ReportFormat::Json, "JSON"),
+            ] {
+                ui.radio_value(&mut self.report_format, format, label);
+            }
+            if ui.button("保存到文件 (S)").clicked() {
+                self.save_weekly_report();
+            }
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("计费时间取整:");
+            for rounding in [
+                RoundingMode::None,
+                RoundingMode::NearestMinute,
+                RoundingMode::Up15,
+                RoundingMode::Nearest15,
+            ] {
+                ui.radio_value(
+                    &mut self.settings.billing_rounding,
+                    rounding,
+                    ReportGenerator::rounding_mode_label(rounding),
+                );
+            }
+        });
+        ui.label(self.get_billing_report());
+
+        ui.separator();
+        ui.label(self.get_detailed_weekly_report());
+
+        ui.separator();
+        ui.label(self.get_efficiency_analysis());
+
+        ui.separator();
+        ui.label(self.get_week_comparison_report());
+    }
+
+    fn show_dashboard(&mut self, ui: &mut egui::Ui) {
+        ui.heading("概览");
+
+        let stats = self.dashboard_stats();
+        ui.label(format!("项目数: {}", stats.project_count));
+        ui.label(format!("进行中的事件: {}", stats.active_events));
+        ui.label(format!("已完成的事件: {}", stats.completed_events));
+        ui.label(format!(
+            "累计记录时间: {}",
+            TimeCalculator::format_duration(stats.total_minutes)
+        ));
+        match &stats.busiest_project {
+            Some((name, minutes)) => ui.label(format!(
+                "最忙的项目: {}（{}）",
+                name,
+                TimeCalculator::format_duration(*minutes)
+            )),
+            None => ui.label("最忙的项目: 暂无"),
+        };
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("项目列表").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            if ui.button("事件列表").clicked() {
+                self.mode = AppMode::EventList;
+            }
+        });
+    }
+
+    fn show_stats(&mut self, ui: &mut egui::Ui) {
+        ui.heading("累计统计");
+
+        if ui.button("返回").clicked() {
+            self.mode = AppMode::ProjectList;
+        }
+
+        ui.separator();
+
+        ui.group(|ui| {
+            ui.strong("总览");
+            let balance =
+                TimeCalculator::net_project_balance(&self.event_manager.get_all_time_records());
+            let (text, color) = match balance.cmp(&0) {
+                std::cmp::Ordering::Greater => (
+                    format!("项目时间领先 {}", TimeCalculator::format_duration(balance)),
+                    egui::Color32::from_rgb(40, 160, 70),
+                ),
+                std::cmp::Ordering::Less => (
+                    format!("项目时间落后 {}", TimeCalculator::format_duration(-balance)),
+                    egui::Color32::from_rgb(200, 60, 60),
+                ),
+                std::cmp::Ordering::Equal => {
+                    ("项目内外时间持平".to_string(), ui.visuals().text_color())
+                }
+            };
+            ui.label("累计净项目时间（项目内减项目外）:");
+            ui.colored_label(color, text);
+        });
+
+        ui.separator();
+
+        let stats = self.get_all_time_stats();
+        ui.label(&stats);
+
+        ui.separator();
+        ui.label(format!(
+            "{}年每日记录时间",
+            Utc::now().with_timezone(&self.settings.timezone()).year()
+        ));
+        self.show_year_heatmap(ui);
+    }
+
+    /// 今年每天记录时间的日历热力图，每列一周，颜色越深时间越长
+    fn show_year_heatmap(&self, ui: &mut egui::Ui) {
+        const CELL: f32 = 12.0;
+
+        let records = self.event_manager.get_all_time_records();
+        let tz = self.settings.timezone();
+        let totals = TimeCalculator::daily_totals_for_year_tz(
+            &records,
+            Utc::now().with_timezone(&tz).year(),
+            tz,
+        );
+        let Some(&(first_day, _)) = totals.first() else {
+            return;
+        };
+        let max_minutes = totals
+            .iter()
+            .map(|(_, minutes)| *minutes)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let first_slot = self
+            .settings
+            .week_start
+            .days_from_start(first_day.weekday()) as usize;
+        let columns = (first_slot + totals.len()).div_ceil(7);
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(columns as f32 * CELL, 7.0 * CELL),
+            egui::Sense::hover(),
+        );
+        let cell_rect = |index: usize| {
+            let slot = first_slot + index;
+            let min =
+                rect.left_top() + egui::vec2((slot / 7) as f32 * CELL, (slot % 7) as f32 * CELL);
+            egui::Rect::from_min_size(min, egui::vec2(CELL - 2.0, CELL - 2.0))
+        };
+
+        let painter = ui.painter_at(rect);
+        let empty = ui.visuals().widgets.inactive.bg_fill;
+        for (index, (_, minutes)) in totals.iter().enumerate() {
+            let fill = if *minutes == 0 {
+                empty
+            } else {
+                let strength = 0.25 + 0.75 * (*minutes as f32 / max_minutes as f32);
+                egui::Color32::from_rgb(
+                    (200.0 - 170.0 * strength) as u8,
+                    (230.0 - 100.0 * strength) as u8,
+                    (200.0 - 170.0 * strength) as u8,
+                )
+            };
+            painter.rect_filled(cell_rect(index), 2.0, fill);
+        }
+
+        let hovered = response
+            .hover_pos()
+            .and_then(|pos| (0..totals.len()).find(|index| cell_rect(*index).contains(pos)));
+        if let Some(index) = hovered {
+            let (date, minutes) = totals[index];
+            response.on_hover_text(format!(
+                "{}: {}",
+                date.format("%Y-%m-%d"),
+                TimeCalculator::format_duration(minutes)
+            ));
+        }
+    }
+
+    fn show_recent_completions(&mut self, ui: &mut egui::Ui) {
+        ui.heading("最近完成");
+
+        let lines: Vec<String> = self
+            .event_manager
+            .recent_completions(RECENT_COMPLETIONS_LIMIT)
+            .into_iter()
+            .map(|event| self.format_completion_line(event))
+            .collect();
+        let page_count = lines.len().div_ceil(RECENT_COMPLETIONS_PAGE_SIZE).max(1);
+        if self.recent_completions_page >= page_count {
+            self.recent_completions_page = page_count - 1;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("返回").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            if ui.button("上一页").clicked() && self.recent_completions_page > 0 {
+                self.recent_completions_page -= 1;
+            }
+            if ui.button("下一页").clicked() && self.recent_completions_page + 1 < page_count {
+                self.recent_completions_page += 1;
+            }
+            ui.label(format!(
+                "第 {}/{} 页",
+                self.recent_completions_page + 1,
+                page_count
+            ));
+        });
+
+        ui.separator();
+
+        if lines.is_empty() {
+            ui.label("还没有已完成的事件");
+        } else {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for line in lines
+                    .iter()
+                    .skip(self.recent_completions_page * RECENT_COMPLETIONS_PAGE_SIZE)
+                    .take(RECENT_COMPLETIONS_PAGE_SIZE)
+                {
+                    ui.label(line);
+                }
+            });
+        }
+    }
+
+    fn show_date_range_search(&mut self, ui: &mut egui::Ui) {
+        ui.heading("按日期搜索事件");
+
+        ui.horizontal(|ui| {
+            if ui.button("返回").clicked() {
+                self.mode = AppMode::EventList;
+            }
+            ui.label("开始日期:");
+            ui.add(egui::TextEdit::singleline(&mut self.date_range_start).hint_text("YYYY-MM-DD"));
+            ui.label("结束日期:");
+            ui.add(egui::TextEdit::singleline(&mut self.date_range_end).hint_text("YYYY-MM-DD"));
+        });
+
+        ui.separator();
+
+        if self.date_range_start.trim().is_empty() || self.date_range_end.trim().is_empty() {
+            ui.label("请输入开始和结束日期");
+            return;
+        }
+        match self.events_in_date_range() {
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            Ok(events) if events.is_empty() => {
+                ui.label("该日期范围内没有事件");
+            }
+            Ok(events) => {
+                ui.label(format!("共 {} 个事件", events.len()));
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for event in events {
+                        let status = if event.is_completed() {
+                            "已完成"
+                        } else {
+                            "进行中"
+                        };
+                        ui.label(format!(
+                            "{} {} （{}）",
+                            event.start_time.format("%Y-%m-%d %H:%M"),
+                            event.title,
+                            status
+                        ));
+                    }
+                });
+            }
+        }
+    }
+
+    fn show_trash(&mut self, ui: &mut egui::Ui) {
+        ui.heading("回收站");
+
+        let mut purge_days = None;
+        ui.horizontal(|ui| {
+            if ui.button("返回").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            if ui
+                .button(format!("永久删除{}天前删除的事件", TRASH_RETENTION_DAYS))
+                .clicked()
+            {
+                purge_days = Some(TRASH_RETENTION_DAYS);
+            }
+            if ui.button("清空回收站").clicked() {
+                purge_days = Some(0);
+            }
+        });
+
+        ui.separator();
+
+        let mut event_to_restore = None;
+        let deleted = self.event_manager.get_deleted_events();
+        if deleted.is_empty() {
+            ui.label("回收站是空的");
+        } else {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for event in deleted {
+                    ui.horizontal(|ui| {
+                        let deleted_at = event
+                            .deleted_at
+                            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_default();
+                        ui.label(format!("{} （删除于 {}）", event.title, deleted_at));
+                        if ui.button("恢复").clicked() {
+                            event_to_restore = Some(event.id);
+                        }
+                    });
+                }
+            });
+        }
+
+        if let Some(event_id) = event_to_restore {
+            self.restore_event(event_id);
+        }
+        if let Some(days) = purge_days {
+            self.purge_trash(days);
+        }
+    }
+
+    fn show_timeline(&mut self, ui: &mut egui::Ui) {
+        ui.heading("时间线");
+        let tz = self.settings.timezone();
+
+        let (previous_day, next_day) = ui.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowLeft) || i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowRight) || i.key_pressed(egui::Key::ArrowDown),
+            )
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("返回").clicked() {
+                self.mode = AppMode::ProjectList;
+            }
+            if ui.button("◀ 前一天").clicked() || previous_day {
+                self.timeline_date -= chrono::Duration::days(1);
+            }
+            ui.label(self.timeline_date.format("%Y-%m-%d (%a)").to_string());
+            if ui.button("后一天 ▶").clicked() || next_day {
+                self.timeline_date += chrono::Duration::days(1);
+            }
+            if ui.button("今天").clicked() {
+                self.timeline_date = Utc::now().with_timezone(&tz).date_naive();
+            }
+            let fill_label = format!(
+                "补齐未记录时间（{}:00-{}:00）",
+                WORK_DAY_START_HOUR, WORK_DAY_END_HOUR
+            );
+            if ui.button(fill_label).clicked() {
+                self.fill_timeline_gaps();
+            }
+        });
+
+        ui.separator();
+
+        let time_records = self.event_manager.get_all_time_records();
+        let blocks = TimeCalculator::get_day_timeline_tz(&time_records, self.timeline_date, tz);
+        if blocks.is_empty() {
+            ui.label("这一天没有时间记录");
+        } else {
+            // 只统计当天第一条记录开始到最后一条记录结束之间的空档
+            let day_start = self
+                .timeline_date
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_local_timezone(tz)
+                .unwrap()
+                .with_timezone(&Utc);
+            let first_minute = blocks.iter().map(|b| b.start_minute).min().unwrap_or(0);
+            let last_minute = blocks.iter().map(|b| b.end_minute).max().unwrap_or(0);
+            let window_start = day_start + chrono::Duration::minutes(first_minute);
+            let window_end = day_start + chrono::Duration::minutes(last_minute);
+            if let Some((gap_start, minutes)) =
+                TimeCalculator::find_largest_gap(&time_records, window_start, window_end)
+            {
+                ui.label(format!(
+                    "空闲共{}，最长的空档从 {} 开始，持续{}",
+                    TimeCalculator::format_duration(TimeCalculator::calculate_idle_time(
+                        &time_records,
+                        window_start,
+                        window_end
+                    )),
+                    gap_start.with_timezone(&tz).format("%H:%M"),
+                    TimeCalculator::format_duration(minutes)
+                ));
+            }
+        }
+
+        let rect = ui.available_rect_before_wrap();
+        ui.allocate_rect(rect, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        // 左侧留出小时刻度
+        let gutter = 48.0;
+        let minute_height = rect.height() / 1440.0;
+        let content_left = rect.left() + gutter;
+        let content_width = (rect.width() - gutter).max(1.0);
+        let grid_color = ui.visuals().weak_text_color();
+
+        for hour in 0..=24 {
+            let y = rect.top() + hour as f32 * 60.0 * minute_height;
+            painter.line_segment(
+                [egui::pos2(content_left, y), egui::pos2(rect.right(), y)],
+                egui::Stroke::new(0.5, grid_color),
+            );
+            if hour < 24 {
+                painter.text(
+                    egui::pos2(rect.left() + 4.0, y),
+                    egui::Align2::LEFT_TOP,
+                    format!("{:02}:00", hour),
+                    egui::FontId::proportional(11.0),
+                    grid_color,
+                );
+            }
+        }
+
+        for block in &blocks {
+            let column_width = content_width / block.column_count as f32;
+            let left = content_left + block.column as f32 * column_width;
+            let top = rect.top() + block.start_minute as f32 * minute_height;
+            let bottom = rect.top() + block.end_minute as f32 * minute_height;
+            let block_rect = egui::Rect::from_min_max(
+                egui::pos2(left + 1.0, top),
+                egui::pos2(left + column_width - 1.0, bottom.max(top + 2.0)),
+            );
+
+            // 项目设置了颜色时使用项目颜色
+            let project_rgb = block
+                .project_id
+                .and_then(|id| self.project_manager.get_project(id))
+                .and_then(|project| project.color.as_deref())
+                .and_then(|hex| parse_hex_color(hex).ok());
+            let fill = match (block.project_id, project_rgb) {
+                (Some(_), Some([r, g, b])) => egui::Color32::from_rgb(r, g, b),
+                (Some(_), None) => egui::Color32::from_rgb(70, 130, 180),
+                (None, _) => egui::Color32::from_rgb(160, 160, 160),
+            };
+            painter.rect_filled(block_rect, 2.0, fill);
+
+            let title = self
+                .event_manager
+                .get_event(block.event_id)
+                .map(|e| e.title.as_str())
+                .unwrap_or("未知事件");
+            let label = format!(
+                "{:02}:{:02}-{:02}:{:02} {}",
+                block.start_minute / 60,
+                block.start_minute % 60,
+                block.end_minute / 60,
+                block.end_minute % 60,
+                title
+            );
+            painter.with_clip_rect(block_rect).text(
+                block_rect.left_top() + egui::vec2(3.0, 1.0),
+                egui::Align2::LEFT_TOP,
+                label,
+                egui::FontId::proportional(11.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    fn show_help(&mut self, ui: &mut egui::Ui) {
+        ui.heading("帮助");
+
+        if ui.button("返回").clicked() {
+            self.mode = AppMode::ProjectList;
+        }
+
+        ui.separator();
+
+        ui.label("项目管理系统使用说明：");
+        ui.label("");
+        ui.label("1. 项目列表：查看所有项目，选择当前项目");
+        ui.label("2. 事件列表：查看所有事件，完成进行中的事件");
+        ui.label("3. 添加项目：创建新项目");
+        ui.label("4. 添加事件：创建新事件（项目事件或非项目事件）");
+        ui.label("5. 报表：查看周报或月报统计，按←/→切换到上一周/下一周（按月显示时为上一月/下一月），按S把周报保存到数据目录的 reports 子目录");
+        ui.label("6. 最近完成：按完成时间倒序查看已完成的事件");
+        ui.label("7. 时间线：按天查看时间记录分布，方向键切换日期");
+        ui.label("8. 统计：查看全部数据的累计统计");
+        ui.label("");
+        ui.label("操作说明：");
+        ui.label("- 点击项目名称切换当前项目");
+        ui.label("- 在项目列表按I或点击\"编辑\"修改选中项目的名称和描述");
+        ui.label("- 在项目列表按A添加项目，在事件列表按A添加项目事件，输入名称后按Enter确认");
+        ui.label("- 在任意界面按N直接添加项目外事件，添加或按Esc取消后回到原来的界面");
+        ui.label("- 点击\"完成\"按钮结束事件，误操作时可点击\"重新打开\"");
+        ui.label("- 点击\"拆分\"按钮在指定时间把事件拆成两个");
+        ui.label(
+            "- 点击项目的\"导出日历\"把已完成的事件导出为ICS文件，保存在数据目录的 reports 子目录",
+        );
+        ui.label("- 按R或点击\"复制\"以选中事件的标题、描述和类型新建一个从现在开始的事件");
+        ui.label("- 点击事件标题或按↑/↓选中事件，按I或点击\"编辑\"修改标题");
+        ui.label("- 在项目或事件列表按D删除选中的项目或事件，按Y确认、N取消");
+        ui.label("- 在事件列表按C或点击\"全部完成\"结束所有进行中的事件");
+        ui.label("- 按U或点击\"撤销\"恢复最近删除或完成的项目/事件");
+        ui.label("- 在事件列表输入标签进行筛选，点击标签可将其移除");
+        ui.label("- 在\"新检查项\"中输入内容后点击事件的\"添加检查项\"，勾选检查项标记完成");
+        ui.label("- 使用复选框选择项目或事件");
+        ui.label("");
+        ui.label("设置：");
+        ui.checkbox(
+            &mut self.settings.confirm_discard_input,
+            "取消有未保存输入的表单前先确认",
+        );
+        ui.horizontal(|ui| {
+            ui.label("自动保存间隔（秒，0 表示只在退出时保存）:");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.autosave_interval_secs)
+                    .clamp_range(0..=3600),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("周报忽略短于此时长的记录（分钟，0 表示不忽略）:");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.min_report_duration_minutes)
+                    .clamp_range(0..=120),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("计费报表的货币符号:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.settings.currency_symbol).desired_width(40.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("工作日（详细周报和效率分析只统计这些日期）:");
+            for (weekday, label) in [
+                (Weekday::Mon, "一"),
+                (Weekday::Tue, "二"),
+                (Weekday::Wed, "三"),
+                (Weekday::Thu, "四"),
+                (Weekday::Fri, "五"),
+                (Weekday::Sat, "六"),
+                (Weekday::Sun, "日"),
+            ] {
+                let mut working = self.settings.working_days.contains(weekday);
+                if ui.checkbox(&mut working, label).changed() {
+                    self.settings.working_days.set(weekday, working);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("效率分析阈值（项目内时间占比%）: 低于");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.efficiency_thresholds.low)
+                    .clamp_range(0.0..=100.0),
+            );
+            ui.label("时建议增加项目内时间，高于");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.efficiency_thresholds.high)
+                    .clamp_range(0.0..=100.0),
+            );
+            ui.label("时提醒注意休息");
+        });
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_button_handlers_smoke() {
+        let mut app = App::new();
+        app.add_project("界面项目".to_string(), None).unwrap();
+        let project_id = app.project_manager.get_all_projects()[0].id;
+        app.switch_to_project(project_id);
+        assert_eq!(app.get_current_project().map(|p| p.id), Some(project_id));
+
+        app.add_event("项目事件".to_string(), None, true).unwrap();
+        app.add_event("项目外事件".to_string(), None, false)
+            .unwrap();
+        assert_eq!(app.get_events().len(), 2);
+
+        let event_id = app.get_events()[0].id;
+        app.complete_event(event_id);
+        assert!(app
+            .event_manager
+            .get_event(event_id)
+            .unwrap()
+            .is_completed());
+        assert!(app.get_weekly_report().contains("周报"));
+    }
+
+    #[test]
+    fn test_today_project_minutes() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let today_start = Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        for (start, minutes, project) in [
+            (
+                today_start + chrono::Duration::hours(1),
+                60,
+                Some(project_id),
+            ),
+            (
+                today_start + chrono::Duration::hours(3),
+                30,
+                Some(project_id),
+            ),
+            (today_start + chrono::Duration::hours(4), 45, None),
+            (
+                today_start - chrono::Duration::hours(3),
+                90,
+                Some(project_id),
+            ),
+        ] {
+            let end = start + chrono::Duration::minutes(minutes);
+            let event_id = match project {
+                Some(id) => {
+                    app.event_manager
+                        .add_project_event("工作".to_string(), None, id, Some(start))
+                }
+                None => {
+                    app.event_manager
+                        .add_non_project_event("休息".to_string(), None, Some(start))
+                }
+            }
+            .unwrap();
+            app.event_manager
+                .set_event_end_time(event_id, Some(end))
+                .unwrap();
+        }
+
+        assert_eq!(app.today_project_minutes(), 90);
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        let (start, end) = parse_date_range("2024-03-01", " 2024-03-03 ").unwrap();
+        assert_eq!(
+            start.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2024-03-01 00:00:00"
+        );
+        assert_eq!(
+            end.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2024-03-03 23:59:59"
+        );
+
+        // 同一天也是有效范围
+        assert!(parse_date_range("2024-03-01", "2024-03-01").is_ok());
+
+        let err = parse_date_range("2024-02-30", "2024-03-03").unwrap_err();
+        assert!(err.contains("开始日期"));
+        assert!(err.contains("2024-02-30"));
+        let err = parse_date_range("2024-03-01", "03/03/2024").unwrap_err();
+        assert!(err.contains("结束日期"));
+        assert!(parse_date_range("", "2024-03-03").is_err());
+        assert_eq!(
+            parse_date_range("2024-03-05", "2024-03-01").unwrap_err(),
+            "开始日期不能晚于结束日期"
+        );
+    }
+
+    #[test]
+    fn test_events_in_date_range() {
+        let mut app = App::new();
+        let day = |d: u32, h: u32, m: u32| {
+            NaiveDate::from_ymd_opt(2024, 3, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let mut add = |title: &str, start| {
+            app.event_manager
+                .add_non_project_event(title.to_string(), None, Some(start))
+                .unwrap()
+        };
+        add("前一天", day(1, 23, 59));
+        let last = add("最后一天结束", day(4, 23, 59));
+        let first = add("第一天开始", day(2, 0, 0));
+        add("后一天", day(5, 0, 0));
+
+        app.date_range_start = "2024-03-02".to_string();
+        app.date_range_end = "2024-03-04".to_string();
+        let ids: Vec<Uuid> = app
+            .events_in_date_range()
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![first, last]);
+
+        app.date_range_end = "2024-13-01".to_string();
+        assert!(app.events_in_date_range().is_err());
+    }
+
+    #[test]
+    fn test_dashboard_stats() {
+        let mut app = App::new();
+        assert_eq!(app.dashboard_stats().busiest_project, None);
+
+        let start = Utc::now() - chrono::Duration::hours(3);
+        let project_a = app
+            .project_manager
+            .add_project("项目A".to_string(), None)
+            .unwrap();
+        let project_b = app
+            .project_manager
+            .add_project("项目B".to_string(), None)
+            .unwrap();
+        let event_a = app
+            .event_manager
+            .add_project_event("设计".to_string(), None, project_a, Some(start))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_a, Some(start + chrono::Duration::minutes(30)))
+            .unwrap();
+        let event_b = app
+            .event_manager
+            .add_project_event("开发".to_string(), None, project_b, Some(start))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_b, Some(start + chrono::Duration::minutes(90)))
+            .unwrap();
+        app.event_manager
+            .add_non_project_event("午休".to_string(), None, Some(start))
+            .unwrap();
+
+        let stats = app.dashboard_stats();
+        assert_eq!(stats.project_count, 2);
+        assert_eq!(stats.active_events, 1);
+        assert_eq!(stats.completed_events, 2);
+        assert_eq!(stats.total_minutes, 120);
+        assert_eq!(stats.busiest_project, Some(("项目B".to_string(), 90)));
+    }
+
+    #[test]
+    fn test_every_screen_renders() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        app.switch_to_project(project_id);
+        let event_id = app
+            .event_manager
+            .add_project_event("事件".to_string(), None, project_id, None)
+            .unwrap();
+        app.start_pomodoro(event_id);
+        app.event_manager.add_subtask(event_id, "检查项").unwrap();
+
+        let ctx = egui::Context::default();
+        let modes = [
+            AppMode::Dashboard,
+            AppMode::ProjectList,
+            AppMode::EventList,
+            AppMode::AddProject,
+            AppMode::AddEvent,
+            AppMode::Reports,
+            AppMode::RecentCompletions,
+            AppMode::Timeline,
+            AppMode::Stats,
+            AppMode::Trash,
+            AppMode::Help,
+            AppMode::DateRangeSearch,
+            AppMode::ConfirmDelete {
+                target: DeleteTarget::Event(event_id),
+            },
+        ];
+        for mode in modes {
+            app.mode = mode;
+            let _ = ctx.run(egui::RawInput::default(), |ctx| app.update(ctx));
+            assert_eq!(app.mode, mode);
+        }
+
+        app.begin_edit_event(event_id);
+        let _ = ctx.run(egui::RawInput::default(), |ctx| app.update(ctx));
+        app.discard_input();
+        app.begin_edit_project(project_id);
+        let _ = ctx.run(egui::RawInput::default(), |ctx| app.update(ctx));
+        app.discard_input();
+        app.begin_split_event(event_id);
+        let _ = ctx.run(egui::RawInput::default(), |ctx| app.update(ctx));
+    }
+
+    #[test]
+    fn test_project_event_survives_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let mut app = App::new();
+        app.add_project("客户项目".to_string(), None).unwrap();
+        app.add_event("写报告".to_string(), None, true).unwrap();
+        storage.save_app_data(&app.to_app_data()).unwrap();
+
+        let reloaded = App::from_data(storage.load_data().unwrap());
+        let events = reloaded.event_manager.get_all_events();
+        assert_eq!(events.len(), 1);
+
+        let EventType::ProjectRelated(project_id) = events[0].event_type else {
+            panic!("事件应关联到项目");
+        };
+        let project = reloaded.project_manager.get_project(project_id).unwrap();
+        assert_eq!(project.name, "客户项目");
+    }
+
+    #[test]
+    fn test_current_project_survives_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let mut app = App::new();
+        app.add_project("项目一".to_string(), None).unwrap();
+        app.add_project("项目二".to_string(), None).unwrap();
+        let third = app.add_project("项目三".to_string(), None).unwrap();
+        app.switch_to_project(third);
+        storage.save_app_data(&app.to_app_data()).unwrap();
+
+        let reloaded = App::from_data(storage.load_data().unwrap());
+        assert_eq!(reloaded.get_current_project().map(|p| p.id), Some(third));
+        assert_eq!(reloaded.selected_project_id, Some(third));
+        let active: Vec<Uuid> = reloaded
+            .get_projects()
+            .iter()
+            .filter(|p| p.is_active)
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(active, vec![third]);
+
+        // 保存的激活项目已归档时不恢复当前项目，也不保留激活标记
+        let mut data = app.to_app_data();
+        for project in &mut data.projects {
+            project.archived = project.id == third;
+        }
+        let reloaded = App::from_data(data);
+        assert!(reloaded.get_current_project().is_none());
+        assert!(reloaded.get_projects().iter().all(|p| !p.is_active));
+    }
+
+    #[test]
+    fn test_dirty_flag() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let mut app = App::new();
+        assert!(!app.dirty);
+        assert!(!app.save_if_dirty(&storage).unwrap());
+
+        app.add_project("项目".to_string(), None).unwrap();
+        assert!(app.dirty);
+        assert!(app.save_if_dirty(&storage).unwrap());
+        assert!(!app.dirty);
+        assert_eq!(storage.load_data().unwrap().projects.len(), 1);
+
+        // 失败的操作不会标记为需要保存
+        app.complete_event(Uuid::new_v4());
+        assert!(!app.dirty);
+        assert!(!app.save_if_dirty(&storage).unwrap());
+    }
+
+    #[test]
+    fn test_save_skipped_when_unchanged() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+        let data_file = storage.get_data_file_path();
+
+        let mut app = App::new();
+        app.add_project("项目".to_string(), None).unwrap();
+        assert!(app.save_if_changed(&storage).unwrap());
+        assert!(Path::new(&data_file).exists());
+
+        // 内容没有变化时不再写入文件
+        std::fs::remove_file(&data_file).unwrap();
+        assert!(!app.save_if_changed(&storage).unwrap());
+        assert!(!Path::new(&data_file).exists());
+
+        app.add_project("另一个项目".to_string(), None).unwrap();
+        assert!(app.save_if_changed(&storage).unwrap());
+        assert_eq!(storage.load_data().unwrap().projects.len(), 2);
+
+        // 刚加载的数据没有修改时退出也不需要保存
+        let mut reloaded = App::from_data(storage.load_data().unwrap());
+        assert!(!reloaded.save_if_changed(&storage).unwrap());
+    }
+
+    #[test]
+    fn test_undo_delete_event_restores_identical_event() {
+        let mut app = App::new();
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let event_id = app
+            .event_manager
+            .add_non_project_event("开会".to_string(), Some("周会".to_string()), Some(start))
+            .unwrap();
+        app.event_manager.add_tag(event_id, "meeting").unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, None)
+            .unwrap();
+
+        let before = serde_json::to_value(app.event_manager.get_event(event_id).unwrap()).unwrap();
+        let record_before =
+            serde_json::to_value(app.event_manager.get_event_time_record(event_id).unwrap())
+                .unwrap();
+
+        app.delete_event(event_id);
+        assert!(app.event_manager.get_event(event_id).is_none());
+        assert!(app.event_manager.get_all_time_records().is_empty());
+
+        app.undo();
+        let after = serde_json::to_value(app.event_manager.get_event(event_id).unwrap()).unwrap();
+        assert_eq!(before, after);
+        let record_after =
+            serde_json::to_value(app.event_manager.get_event_time_record(event_id).unwrap())
+                .unwrap();
+        assert_eq!(record_before, record_after);
+        assert!(app.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_undo_complete_and_delete_project() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let event_id = app
+            .event_manager
+            .add_project_event(
+                "写代码".to_string(),
+                None,
+                project_id,
+                Some(Utc::now() - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+
+        app.complete_event(event_id);
+        app.delete_project(project_id);
+        assert!(app.get_current_project().is_none());
+
+        // 按相反顺序撤销
+        app.undo();
+        assert_eq!(app.get_current_project().unwrap().id, project_id);
+        app.undo();
+        assert!(app
+            .event_manager
+            .get_event(event_id)
+            .unwrap()
+            .end_time
+            .is_none());
+        assert!(app.event_manager.get_event_time_record(event_id).is_none());
+
+        app.undo();
+        assert_eq!(app.message, "没有可以撤销的操作");
+    }
+
+    #[test]
+    fn test_delete_project_cascade() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        for deletion in [
+            ProjectDeletion::DeleteEvents,
+            ProjectDeletion::MoveToNonProject,
+        ] {
+            let mut app = App::new();
+            let project_id = app
+                .project_manager
+                .add_project("项目".to_string(), None)
+                .unwrap();
+            let other_id = app
+                .project_manager
+                .add_project("其他".to_string(), None)
+                .unwrap();
+            let start = Utc::now() - chrono::Duration::hours(2);
+            for project in [project_id, project_id, other_id] {
+                let event_id = app
+                    .event_manager
+                    .add_project_event("工作".to_string(), None, project, Some(start))
+                    .unwrap();
+                app.event_manager
+                    .set_event_end_time(event_id, None)
+                    .unwrap();
+            }
+            app.event_manager
+                .add_project_event("进行中".to_string(), None, project_id, None)
+                .unwrap();
+
+            assert_eq!(app.delete_project_cascade(project_id, deletion), Ok(3));
+            assert!(storage.check_data_integrity(&app.to_app_data()).is_empty());
+            assert_eq!(app.event_manager.get_project_events(other_id).len(), 1);
+
+            match deletion {
+                ProjectDeletion::DeleteEvents => {
+                    assert_eq!(app.event_manager.get_event_count(), 1);
+                    assert_eq!(app.event_manager.get_all_time_records().len(), 1);
+                }
+                ProjectDeletion::MoveToNonProject => {
+                    assert_eq!(app.event_manager.get_event_count(), 4);
+                    assert_eq!(app.event_manager.get_non_project_events().len(), 3);
+                    assert_eq!(app.event_manager.get_non_project_time_records().len(), 2);
+                }
+            }
+
+            // 撤销后项目和事件恢复原样
+            app.undo();
+            assert_eq!(app.event_manager.get_project_events(project_id).len(), 3);
+            assert_eq!(
+                app.event_manager.get_project_time_records(project_id).len(),
+                2
+            );
+            assert!(storage.check_data_integrity(&app.to_app_data()).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_undo_delete_project_restores_trashed_events() {
+        for deletion in [
+            ProjectDeletion::DeleteEvents,
+            ProjectDeletion::MoveToNonProject,
+        ] {
+            let mut app = App::new();
+            let project_id = app
+                .project_manager
+                .add_project("项目".to_string(), None)
+                .unwrap();
+            let other_id = app
+                .project_manager
+                .add_project("其他".to_string(), None)
+                .unwrap();
+            let start = Utc::now() - chrono::Duration::hours(2);
+            let trashed = app
+                .event_manager
+                .add_project_event("回收站中".to_string(), None, project_id, Some(start))
+                .unwrap();
+            app.event_manager.set_event_end_time(trashed, None).unwrap();
+            app.event_manager.delete_event(trashed).unwrap();
+            let shared = app
+                .event_manager
+                .add_project_event("分摊".to_string(), None, other_id, Some(start))
+                .unwrap();
+            app.event_manager
+                .set_project_shares(shared, vec![(project_id, 50), (other_id, 50)])
+                .unwrap();
+            app.event_manager.set_event_end_time(shared, None).unwrap();
+
+            app.delete_project_cascade(project_id, deletion).unwrap();
+            app.undo();
+
+            // 回收站中的事件和它的记录原样恢复，仍在回收站中
+            let restored = app
+                .event_manager
+                .get_deleted_events()
+                .into_iter()
+                .find(|event| event.id == trashed)
+                .unwrap();
+            assert!(
+                matches!(restored.event_type, EventType::ProjectRelated(id) if id == project_id)
+            );
+            let trashed_records = app.event_manager.get_deleted_time_records();
+            assert_eq!(trashed_records.len(), 1);
+            assert_eq!(trashed_records[0].project_id, Some(project_id));
+
+            // 其他项目事件的分摊和记录也恢复原样
+            let shared_event = app.event_manager.get_event(shared).unwrap();
+            assert_eq!(
+                shared_event.project_shares,
+                vec![(project_id, 50), (other_id, 50)]
+            );
+            assert_eq!(
+                app.event_manager.get_project_time_records(project_id).len(),
+                1
+            );
+            assert_eq!(
+                app.event_manager.get_project_time_records(other_id).len(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_edit_event_via_shortcut() {
+        let mut app = App::new();
+        app.event_manager
+            .add_non_project_event("旧标题".to_string(), None, None)
+            .unwrap();
+        let event_id = app
+            .event_manager
+            .add_non_project_event("要改的".to_string(), None, None)
+            .unwrap();
+        app.mode = AppMode::EventList;
+        app.selected_event_index = 1;
+
+        app.handle_key(egui::Key::I);
+        assert_eq!(app.mode, AppMode::EditEvent);
+        assert_eq!(app.input, "要改的");
+
+        app.input = "新标题".to_string();
+        app.confirm_edit_event();
+        assert_eq!(app.mode, AppMode::EventList);
+        assert_eq!(
+            app.event_manager.get_event(event_id).unwrap().title,
+            "新标题"
+        );
+        assert!(app.input.is_empty());
+    }
+
+    #[test]
+    fn test_edit_event_cancel_keeps_title() {
+        let mut app = App::new();
+        let event_id = app
+            .event_manager
+            .add_non_project_event("原标题".to_string(), None, None)
+            .unwrap();
+        app.mode = AppMode::EventList;
+
+        app.begin_edit_event(event_id);
+        app.input = "改了一半".to_string();
+        app.discard_input();
+        assert_eq!(app.mode, AppMode::EventList);
+        assert_eq!(
+            app.event_manager.get_event(event_id).unwrap().title,
+            "原标题"
+        );
+        assert!(app.edit_event_id.is_none());
+
+        // 空标题不会保存
+        app.begin_edit_event(event_id);
+        app.input = "  ".to_string();
+        app.confirm_edit_event();
+        assert_eq!(app.mode, AppMode::EditEvent);
+        assert_eq!(
+            app.event_manager.get_event(event_id).unwrap().title,
+            "原标题"
+        );
+
+        // 没有选中的事件时给出提示
+        app.mode = AppMode::EventList;
+        app.selected_event_index = 5;
+        app.handle_key(egui::Key::I);
+        assert_eq!(app.mode, AppMode::EventList);
+    }
+
+    #[test]
+    fn test_confirm_delete_event() {
+        let mut app = App::new();
+        let event_id = app
+            .event_manager
+            .add_non_project_event("要删除的".to_string(), None, None)
+            .unwrap();
+        app.mode = AppMode::EventList;
+
+        app.handle_key(egui::Key::D);
+        let target = DeleteTarget::Event(event_id);
+        assert_eq!(app.mode, AppMode::ConfirmDelete { target });
+        // 确认期间其他快捷键无效
+        app.handle_key(egui::Key::U);
+        assert_eq!(app.mode, AppMode::ConfirmDelete { target });
+
+        app.handle_key(egui::Key::Y);
+        assert_eq!(app.mode, AppMode::EventList);
+        assert!(app.event_manager.get_event(event_id).is_none());
+    }
+
+    #[test]
+    fn test_quick_non_project_event_shortcut() {
+        let mut app = App::new();
+        app.mode = AppMode::Reports;
+
+        app.handle_key(egui::Key::N);
+        assert_eq!(app.mode, AppMode::AddEvent);
+        assert!(!app.event_type_selection);
+        // 表单中的按键不会再次触发快捷键
+        app.handle_key(egui::Key::N);
+        assert_eq!(app.mode, AppMode::AddEvent);
+
+        app.discard_input();
+        assert_eq!(app.mode, AppMode::Reports);
+
+        app.mode = AppMode::Timeline;
+        app.handle_key(egui::Key::N);
+        app.new_event_title = "临时任务".to_string();
+        let ctx = egui::Context::default();
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Escape)]);
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Y)]);
+        assert_eq!(app.mode, AppMode::Timeline);
+        assert!(app.new_event_title.is_empty());
+
+        app.mode = AppMode::EventList;
+        app.handle_key(egui::Key::A);
+        assert!(app.event_type_selection);
+        app.discard_input();
+        assert_eq!(app.mode, AppMode::EventList);
+    }
+
+    #[test]
+    fn test_cancel_delete_project() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("保留的项目".to_string(), None)
+            .unwrap();
+        app.mode = AppMode::ProjectList;
+
+        app.handle_key(egui::Key::D);
+        let target = DeleteTarget::Project(project_id);
+        assert_eq!(app.mode, AppMode::ConfirmDelete { target });
+        app.handle_key(egui::Key::N);
+        assert_eq!(app.mode, AppMode::ProjectList);
+        assert!(app.project_manager.get_project(project_id).is_some());
+
+        app.handle_key(egui::Key::D);
+        app.handle_key(egui::Key::Escape);
+        assert_eq!(app.mode, AppMode::ProjectList);
+        assert!(app.project_manager.get_project(project_id).is_some());
+
+        app.handle_key(egui::Key::D);
+        app.handle_key(egui::Key::Y);
+        assert_eq!(app.mode, AppMode::ProjectList);
+        assert!(app.project_manager.get_project(project_id).is_none());
+    }
+
+    #[test]
+    fn test_confirm_delete_project_keeps_events() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("要删除的项目".to_string(), None)
+            .unwrap();
+        let event_id = app
+            .event_manager
+            .add_project_event("保留的事件".to_string(), None, project_id, None)
+            .unwrap();
+        app.mode = AppMode::ProjectList;
+
+        app.request_delete(DeleteTarget::Project(project_id));
+        assert_eq!(app.project_deletion, ProjectDeletion::DeleteEvents);
+        app.project_deletion = ProjectDeletion::MoveToNonProject;
+        app.handle_key(egui::Key::Y);
+
+        assert!(app.project_manager.get_project(project_id).is_none());
+        let event = app.event_manager.get_event(event_id).unwrap();
+        assert!(matches!(event.event_type, EventType::NonProject));
+
+        // 每次请求删除都重新从默认方式开始
+        let other_id = app
+            .project_manager
+            .add_project("另一个项目".to_string(), None)
+            .unwrap();
+        app.request_delete(DeleteTarget::Project(other_id));
+        assert_eq!(app.project_deletion, ProjectDeletion::DeleteEvents);
+    }
+
+    #[test]
+    fn test_running_pomodoro_survives_reload_and_undo() {
+        let mut app = App::new();
+        let start = Utc::now() - chrono::Duration::minutes(40);
+        let event_id = app
+            .event_manager
+            .add_non_project_event("专注".to_string(), None, Some(start))
+            .unwrap();
+        app.event_manager
+            .start_pomodoro_at(event_id, 25, 5, start)
+            .unwrap();
+
+        // 保存后重新加载，番茄钟从原来的开始时间继续计时
+        let json = serde_json::to_string(&app.to_app_data()).unwrap();
+        let mut app = App::from_data(serde_json::from_str(&json).unwrap());
+        assert_eq!(
+            app.event_manager.get_pomodoro(event_id).unwrap().started_at,
+            start
+        );
+
+        // 撤销完成后番茄钟恢复原状
+        app.finish_pomodoro(event_id);
+        assert!(app.event_manager.get_pomodoro(event_id).is_none());
+        app.undo();
+        assert!(!app
+            .event_manager
+            .get_event(event_id)
+            .unwrap()
+            .is_completed());
+        assert_eq!(
+            app.event_manager.get_pomodoro(event_id).unwrap().started_at,
+            start
+        );
+
+        app.complete_all_active();
+        assert!(app.event_manager.get_pomodoro(event_id).is_none());
+        app.undo();
+        assert!(app.event_manager.get_pomodoro(event_id).is_some());
+    }
+
+    #[test]
+    fn test_list_scroll_follows_selection() {
+        let mut scroll = ListScroll {
+            visible_rows: 3,
+            ..ListScroll::default()
+        };
+
+        // 在可见区域内移动不需要滚动
+        scroll.follow(2);
+        assert_eq!(scroll.offset, 0);
+        // 超出底部时刚好让选中项成为最后一个可见行
+        scroll.follow(3);
+        assert_eq!(scroll.offset, 1);
+        scroll.follow(9);
+        assert_eq!(scroll.offset, 7);
+        scroll.follow(8);
+        assert_eq!(scroll.offset, 7);
+        // 超出顶部时让选中项成为第一个可见行
+        scroll.follow(4);
+        assert_eq!(scroll.offset, 4);
+
+        assert_eq!(move_selection(0, 3, false), 0);
+        assert_eq!(move_selection(2, 3, true), 2);
+        assert_eq!(move_selection(5, 3, false), 2);
+        assert_eq!(move_selection(0, 0, true), 0);
+    }
+
+    #[test]
+    fn test_arrow_keys_move_event_selection() {
+        let mut app = App::new();
+        for i in 0..5 {
+            app.event_manager
+                .add_non_project_event(format!("事件{}", i), None, None)
+                .unwrap();
+        }
+        app.mode = AppMode::EventList;
+        app.event_scroll.visible_rows = 2;
+
+        for _ in 0..3 {
+            app.handle_key(egui::Key::ArrowDown);
+        }
+        assert_eq!(app.selected_event_index, 3);
+        assert_eq!(app.event_scroll.offset, 2);
+
+        app.handle_key(egui::Key::ArrowUp);
+        app.handle_key(egui::Key::ArrowUp);
+        assert_eq!(app.selected_event_index, 1);
+        assert_eq!(app.event_scroll.offset, 1);
+    }
+
+    #[test]
+    fn test_complete_all_active_via_shortcut() {
+        let mut app = App::new();
+        let start = Utc::now() - chrono::Duration::hours(1);
+        for i in 0..2 {
+            app.event_manager
+                .add_non_project_event(format!("事件{}", i), None, Some(start))
+                .unwrap();
+        }
+        app.mode = AppMode::EventList;
+
+        app.handle_key(egui::Key::C);
+        assert!(app.event_manager.get_active_events().is_empty());
+        assert_eq!(app.message, "已完成2个进行中的事件（按U撤销）");
+
+        // 一次撤销恢复全部事件
+        app.undo();
+        assert_eq!(app.event_manager.get_active_events().len(), 2);
+        assert!(app.event_manager.get_all_time_records().is_empty());
+
+        app.event_manager.complete_all_active(None);
+        app.handle_key(egui::Key::C);
+        assert_eq!(app.message, "没有可以完成的进行中事件");
+    }
+
+    #[test]
+    fn test_undo_stack_is_bounded() {
+        let mut app = App::new();
+        for i in 0..(UNDO_LIMIT + 5) {
+            let event_id = app
+                .event_manager
+                .add_non_project_event(format!("事件{}", i), None, None)
+                .unwrap();
+            app.delete_event(event_id);
+        }
+        assert_eq!(app.undo_stack.len(), UNDO_LIMIT);
+    }
+
+    #[test]
+    fn test_merge_projects_moves_events_and_time() {
+        let mut app = App::new();
+        let source = app
+            .project_manager
+            .add_project("重复项目".to_string(), None)
+            .unwrap();
+        let target = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let start = Utc::now() - chrono::Duration::hours(3);
+        for (project_id, minutes) in [(source, 30), (target, 60), (source, 45)] {
+            let event_id = app
+                .event_manager
+                .add_project_event("工作".to_string(), None, project_id, Some(start))
+                .unwrap();
+            app.event_manager
+                .set_event_end_time(event_id, Some(start + chrono::Duration::minutes(minutes)))
+                .unwrap();
+        }
+
+        app.merge_projects(source, target);
+
+        assert!(!app.project_manager.project_exists(source));
+        assert_eq!(app.get_current_project().unwrap().id, target);
+        assert_eq!(app.event_manager.get_project_events(target).len(), 3);
+        assert!(app.event_manager.get_project_events(source).is_empty());
+        let records = app.event_manager.get_all_time_records();
+        assert_eq!(
+            TimeCalculator::calculate_project_total_time(&records, target, None, None),
+            135
+        );
+        assert!(app
+            .event_manager
+            .get_project_time_records(source)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_add_event_with_unknown_project_is_refused() {
+        let mut app = App::new();
+        let result = app.add_event_validated("写报告".to_string(), None, Some(Uuid::new_v4()));
+
+        assert_eq!(result, Err(AppError::Project(ProjectError::NotFound)));
+        assert!(app.message.starts_with("添加事件失败"));
+        assert_eq!(app.event_manager.get_event_count(), 0);
+        assert!(!app.dirty);
+
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let event_id = app
+            .add_event_validated("写报告".to_string(), None, Some(project_id))
+            .unwrap();
+        assert_eq!(
+            app.event_manager.get_project_events(project_id)[0].id,
+            event_id
+        );
+    }
+
+    #[test]
+    fn test_recurring_event_survives_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let mut app = App::new();
+        app.add_recurring_event("站会".to_string(), None, false, Recurrence::Daily)
+            .unwrap();
+        assert_eq!(app.event_manager.get_event_count(), 1);
+        storage.save_app_data(&app.to_app_data()).unwrap();
+
+        // 重新加载时模板保留，且今天已有实例不会重复生成
+        let reloaded = App::from_data(storage.load_data().unwrap());
+        assert_eq!(reloaded.event_manager.get_all_templates().len(), 1);
+        assert_eq!(reloaded.event_manager.get_event_count(), 1);
+    }
+
+    #[test]
+    fn test_time_records_survive_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("客户项目".to_string(), None)
+            .unwrap();
+        // 固定在周三上午，记录不会跨过周的边界
+        let start = NaiveDate::from_ymd_opt(2024, 3, 13)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = app
+            .event_manager
+            .add_project_event("写报告".to_string(), None, project_id, Some(start))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, Some(start + chrono::Duration::minutes(90)))
+            .unwrap();
+        storage.save_app_data(&app.to_app_data()).unwrap();
+
+        let mut reloaded = App::from_data(storage.load_data().unwrap());
+        reloaded.selected_report_date = start;
+        assert!(reloaded
+            .event_manager
+            .get_event(event_id)
+            .unwrap()
+            .is_completed());
+        let record = reloaded
+            .event_manager
+            .get_event_time_record(event_id)
+            .unwrap();
+        assert_eq!(record.duration_minutes, 90);
+        assert!(reloaded
+            .get_weekly_report()
+            .contains("项目内时间: 1小时30分钟"));
+    }
+
+    /// 用合成的输入事件驱动一帧界面
+    fn run_frame(app: &mut App, ctx: &egui::Context, events: Vec<egui::Event>) {
+        let input = egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| app.update(ctx));
+    }
+
+    fn key_press(key: egui::Key) -> egui::Event {
+        egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn test_add_project_with_keyboard() {
+        let mut app = App::new();
+        let ctx = egui::Context::default();
+
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::A)]);
+        assert_eq!(app.mode, AppMode::AddProject);
+
+        // 表单显示后名称输入框获得焦点，输入的文字进入名称
+        run_frame(&mut app, &ctx, Vec::new());
+        run_frame(
+            &mut app,
+            &ctx,
+            vec![egui::Event::Text("键盘项目".to_string())],
+        );
+        assert_eq!(app.new_project_name, "键盘项目");
+
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Enter)]);
+        assert_eq!(app.mode, AppMode::ProjectList);
+        let projects = app.project_manager.get_all_projects();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "键盘项目");
+        assert!(app.new_project_name.is_empty());
+
+        // 名称为空时按Enter留在表单中，先空转一帧让已关闭表单的焦点释放
+        run_frame(&mut app, &ctx, Vec::new());
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::A)]);
+        assert_eq!(app.mode, AppMode::AddProject);
+        run_frame(&mut app, &ctx, Vec::new());
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Enter)]);
+        assert_eq!(app.mode, AppMode::AddProject);
+        assert_eq!(app.project_manager.get_all_projects().len(), 1);
+    }
+
+    #[test]
+    fn test_enter_in_project_description_inserts_newline() {
+        let mut app = App::new();
+        let ctx = egui::Context::default();
+
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::A)]);
+        app.new_project_name = "项目".to_string();
+        ctx.memory_mut(|m| m.request_focus(egui::Id::new("new_project_description")));
+        run_frame(
+            &mut app,
+            &ctx,
+            vec![egui::Event::Text("第一行".to_string())],
+        );
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Enter)]);
+        run_frame(
+            &mut app,
+            &ctx,
+            vec![egui::Event::Text("第二行".to_string())],
+        );
+
+        assert_eq!(app.mode, AppMode::AddProject);
+        assert!(app.project_manager.get_all_projects().is_empty());
+        assert_eq!(app.new_project_description, "第一行\n第二行");
+    }
+
+    #[test]
+    fn test_backspace_after_multibyte_input() {
+        let mut app = App::new();
+        let ctx = egui::Context::default();
+
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::A)]);
+        run_frame(&mut app, &ctx, Vec::new());
+        run_frame(
+            &mut app,
+            &ctx,
+            vec![
+                egui::Event::Text("项".to_string()),
+                egui::Event::Text("目".to_string()),
+            ],
+        );
+        assert_eq!(app.new_project_name, "项目");
+
+        // 光标按字符移动，退格删除整个汉字而不是半个字节序列
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Backspace)]);
+        assert_eq!(app.new_project_name, "项");
+        run_frame(&mut app, &ctx, vec![egui::Event::Text("a".to_string())]);
+        assert_eq!(app.new_project_name, "项a");
+        run_frame(
+            &mut app,
+            &ctx,
+            vec![
+                key_press(egui::Key::Backspace),
+                key_press(egui::Key::Backspace),
+            ],
+        );
+        assert!(app.new_project_name.is_empty());
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Backspace)]);
+        assert!(app.new_project_name.is_empty());
+    }
+
+    #[test]
+    fn test_add_and_edit_project_description() {
+        let mut app = App::new();
+        let ctx = egui::Context::default();
+
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::A)]);
+        app.new_project_name = "带描述的项目".to_string();
+        app.new_project_description = "项目说明".to_string();
+        run_frame(&mut app, &ctx, vec![key_press(egui::Key::Enter)]);
+        assert_eq!(app.mode, AppMode::ProjectList);
+        let project = &app.project_manager.get_all_projects()[0];
+        assert_eq!(project.name, "带描述的项目");
+        assert_eq!(project.description.as_deref(), Some("项目说明"));
+        let project_id = project.id;
+
+        app.dirty = false;
+        app.handle_key(egui::Key::I);
+        assert_eq!(app.mode, AppMode::EditProject);
+        assert_eq!(app.new_project_name, "带描述的项目");
+        assert_eq!(app.new_project_description, "项目说明");
+
+        app.new_project_description = "新的说明".to_string();
+        app.confirm_edit_project();
+        assert_eq!(app.mode, AppMode::ProjectList);
+        assert!(app.dirty);
+        let project = app.project_manager.get_project(project_id).unwrap();
+        assert_eq!(project.description.as_deref(), Some("新的说明"));
+        assert!(app.new_project_description.is_empty());
+
+        // 名称为空时留在编辑界面，取消后不修改
+        app.begin_edit_project(project_id);
+        app.new_project_name.clear();
+        app.confirm_edit_project();
+        assert_eq!(app.mode, AppMode::EditProject);
+        app.discard_input();
+        assert_eq!(app.mode, AppMode::ProjectList);
+        assert_eq!(
+            app.project_manager.get_project(project_id).unwrap().name,
+            "带描述的项目"
+        );
+    }
+
+    #[test]
+    fn test_save_weekly_report_from_reports_screen() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::new();
+        app.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let event_id = app
+            .event_manager
+            .add_project_event("事件".to_string(), None, project_id, None)
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, Some(Utc::now()))
+            .unwrap();
+        app.mode = AppMode::Reports;
+
+        app.report_format = ReportFormat::Csv;
+        app.handle_key(egui::Key::S);
+        let reports_dir = temp_dir.path().join("reports");
+        let saved: Vec<_> = std::fs::read_dir(&reports_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].extension().unwrap(), "csv");
+        assert!(app.message.starts_with("报表已保存到"));
+
+        app.report_format = ReportFormat::Html;
+        let path = app.save_weekly_report().unwrap();
+        assert!(std::fs::read_to_string(path).unwrap().contains("项目"));
+    }
+
+    #[test]
+    fn test_saved_report_header_matches_file_name() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::new();
+        app.data_dir = temp_dir.path().to_string_lossy().to_string();
+        app.settings.utc_offset_minutes = 8 * 60;
+        // UTC 周日 18:00，即 UTC+8 的周一 02:00
+        app.selected_report_date = NaiveDate::from_ymd_opt(2024, 3, 10)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        for (format, header) in [
+            (
+                ReportFormat::Markdown,
+                "# 每周报表 2024-03-11 至 2024-03-17",
+            ),
+            (
+                ReportFormat::Html,
+                "<h1>每周报表 2024-03-11 至 2024-03-17</h1>",
+            ),
+        ] {
+            app.report_format = format;
+            let path = app.save_weekly_report().unwrap();
+            assert!(path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("weekly_2024-03-11."));
+            assert!(std::fs::read_to_string(path).unwrap().contains(header));
+        }
+    }
+
+    #[test]
+    fn test_duplicate_event_via_shortcut() {
+        let mut app = App::new();
+        let event_id = app
+            .event_manager
+            .add_non_project_event("整理邮件".to_string(), None, None)
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, None)
+            .unwrap();
+        app.show_completed_events = true;
+        app.mode = AppMode::EventList;
+
+        app.handle_key(egui::Key::R);
+        assert!(app.dirty);
+        let copies: Vec<&Event> = app
+            .event_manager
+            .get_all_events()
+            .into_iter()
+            .filter(|event| event.id != event_id)
+            .collect();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].title, "整理邮件");
+        assert!(!copies[0].is_completed());
+
+        app.selected_event_index = 10;
+        app.handle_key(egui::Key::R);
+        assert_eq!(app.message, "请先选择要复制的事件");
+    }
+
+    #[test]
+    fn test_report_navigation() {
+        let mut app = App::new();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 31)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+        app.selected_report_date = start;
+        app.mode = AppMode::Reports;
+
+        app.handle_key(egui::Key::ArrowLeft);
+        app.handle_key(egui::Key::ArrowLeft);
+        assert_eq!(start - app.selected_report_date, chrono::Duration::days(14));
+        assert_eq!(app.report_title(), "周报 2024-03-11 至 2024-03-17");
+        app.handle_key(egui::Key::ArrowRight);
+        assert_eq!(start - app.selected_report_date, chrono::Duration::days(7));
+
+        // 按月显示时翻一个月，月末日期落到较短月份的最后一天
+        app.selected_report_date = start;
+        app.report_period = ReportPeriod::Month;
+        app.handle_key(egui::Key::ArrowLeft);
+        assert_eq!(
+            app.selected_report_date.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(app.report_title(), "月报 2024年2月");
+        app.handle_key(egui::Key::ArrowRight);
+        assert_eq!(
+            app.selected_report_date.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 3, 29).unwrap()
+        );
+
+        // 其他界面的左右键不影响报表日期
+        app.mode = AppMode::ProjectList;
+        let date = app.selected_report_date;
+        app.handle_key(egui::Key::ArrowLeft);
+        assert_eq!(app.selected_report_date, date);
+    }
+
+    #[test]
+    fn test_weekly_report_follows_selected_week() {
+        let mut app = App::new();
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        let two_weeks_ago = NaiveDate::from_ymd_opt(2024, 3, 6)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc();
+        app.selected_report_date = two_weeks_ago + chrono::Duration::days(14);
+        let event_id = app
+            .event_manager
+            .add_project_event("旧事件".to_string(), None, project_id, Some(two_weeks_ago))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(
+                event_id,
+                Some(two_weeks_ago + chrono::Duration::minutes(45)),
+            )
+            .unwrap();
+        app.mode = AppMode::Reports;
+
+        assert!(app.get_weekly_report().contains("项目内时间: 0分钟"));
+        app.step_report_date(false);
+        app.step_report_date(false);
+        assert!(app.get_weekly_report().contains("项目内时间: 45分钟"));
+
+        app.report_period = ReportPeriod::Month;
+        let ctx = egui::Context::default();
+        run_frame(&mut app, &ctx, Vec::new());
+    }
+
+    #[test]
+    fn test_reports_use_settings_timezone() {
+        let mut app = App::new();
+        app.settings.utc_offset_minutes = 8 * 60;
+        let project_id = app
+            .project_manager
+            .add_project("项目".to_string(), None)
+            .unwrap();
+        app.project_manager
+            .set_project_hourly_rate(project_id, Some(60.0))
+            .unwrap();
+        app.project_manager
+            .set_weekly_goal(project_id, Some(60))
+            .unwrap();
+        // UTC 周日 18:00，即 UTC+8 的周一 02:00
+        let start = NaiveDate::from_ymd_opt(2024, 3, 10)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = app
+            .event_manager
+            .add_project_event("周一凌晨".to_string(), None, project_id, Some(start))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, Some(start + chrono::Duration::minutes(30)))
+            .unwrap();
+        app.selected_report_date = start + chrono::Duration::days(1);
+
+        // 周报、目标、计费和对比报表都使用同一个按UTC+8划分的周
+        assert_eq!(app.report_title(), "周报 2024-03-11 至 2024-03-17");
+        let weekly = app.get_weekly_report();
+        assert!(weekly.contains("时间范围: 2024-03-11 至 2024-03-17"));
+        assert!(weekly.contains("项目内时间: 30分钟"));
+        assert!(weekly.contains("项目: 30分钟 / 1小时"));
+        assert!(app.get_billing_report().contains("= 30.00"));
+        assert!(app
+            .get_week_comparison_report()
+            .contains("时段B: 2024-03-11 至 2024-03-17"));
+
+        // 月报按UTC+8的日历划分月份
+        app.selected_report_date = NaiveDate::from_ymd_opt(2024, 2, 29)
+            .unwrap()
+            .and_hms_opt(17, 0, 0)
+            .unwrap()
+            .and_utc();
+        app.report_period = ReportPeriod::Month;
+        assert_eq!(app.report_title(), "月报 2024年3月");
+        let (month_start, _) = app.report_range();
+        assert_eq!(
+            month_start,
+            NaiveDate::from_ymd_opt(2024, 2, 29)
+                .unwrap()
+                .and_hms_opt(16, 0, 0)
+                .unwrap()
+                .and_utc()
+        );
+    }
+
+    #[test]
+    fn test_detailed_weekly_report_uses_working_days_setting() {
+        let mut app = App::new();
+        app.selected_report_date = NaiveDate::from_ymd_opt(2024, 3, 13)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_utc();
+        let report = app.get_detailed_weekly_report();
+        assert!(report.contains("2024-03-16 (Sat)"));
+
+        app.settings.working_days = crate::models::WorkingDays::monday_to_friday();
+        let report = app.get_detailed_weekly_report();
+        assert!(report.contains("2024-03-15 (Fri)"));
+        assert!(!report.contains("2024-03-16 (Sat)"));
+        assert!(!report.contains("2024-03-17 (Sun)"));
+    }
+
+    #[test]
+    fn test_efficiency_analysis_uses_settings_thresholds() {
+        let mut app = App::new();
+        let project_id = app.add_project("效率项目".to_string(), None).unwrap();
+        let start = NaiveDate::from_ymd_opt(2024, 3, 12)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let event_id = app
+            .event_manager
+            .add_project_event("工作".to_string(), None, project_id, Some(start))
+            .unwrap();
+        app.event_manager
+            .set_event_end_time(event_id, Some(start + chrono::Duration::minutes(60)))
+            .unwrap();
+        app.selected_report_date = start;
+
+        // 全部是项目内时间，默认阈值下提醒注意平衡
+        assert!(app
+            .get_efficiency_analysis()
+            .contains("注意保持工作生活平衡"));
+
+        app.settings.efficiency_thresholds.high = 100.0;
+        assert!(app.get_efficiency_analysis().contains("工作效率良好"));
+
+        app.settings.efficiency_thresholds.low = 100.0;
+        assert_eq!(
+            app.get_efficiency_analysis(),
+            "无法生成效率分析: 效率下限必须小于上限"
+        );
+    }
+
+    #[test]
+    fn test_export_project_calendar() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = App::new();
+        app.data_dir = temp_dir.path().to_string_lossy().to_string();
+        let project_id = app
+            .project_manager
+            .add_project("网站".to_string(), None)
+            .unwrap();
+        let done = app
+            .event_manager
+            .add_project_event("改版".to_string(), None, project_id, None)
+            .unwrap();
+        app.event_manager.set_event_end_time(done, None).unwrap();
+        app.event_manager
+            .add_project_event("进行中".to_string(), None, project_id, None)
+            .unwrap();
+        app.event_manager
+            .add_non_project_event("午饭".to_string(), None, None)
+            .unwrap();
+
+        let path = app.export_project_calendar(project_id).unwrap();
+        let ics = std::fs::read_to_string(path).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:[网站] 改版"));
+        assert!(app.export_project_calendar(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn test_unclosed_events_block_exit() {
+        let mut app = App::new();
+        assert!(app.request_exit());
+
+        let done = app
+            .event_manager
+            .add_non_project_event("已完成".to_string(), None, None)
+            .unwrap();
+        app.event_manager.set_event_end_time(done, None).unwrap();
+        app.event_manager
+            .add_non_project_event("写周报".to_string(), None, None)
+            .unwrap();
+        app.event_manager
+            .add_non_project_event("代码评审".to_string(), None, None)
+            .unwrap();
+
+        let mut titles = app.unclosed_event_titles();
+        titles.sort();
+        assert_eq!(titles, vec!["代码评审", "写周报"]);
+
+        assert!(!app.request_exit());
+        assert!(app.exit_prompt);
+        let ctx = egui::Context::default();
+        run_frame(&mut app, &ctx, Vec::new());
+
+        // 全部完成后不再提示
+        app.complete_all_active();
+        assert!(app.unclosed_event_titles().is_empty());
+        app.exit_prompt = false;
+        assert!(app.request_exit());
+        assert!(!app.exit_prompt);
     }
 }
@@ -0,0 +1,65 @@
+use chrono::{Duration, NaiveDate};
+use project_manager::{
+    AppData, EventManager, ProjectManager, ReportGenerator, Storage, TimeCalculator, WeekStart,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_track_report_and_persist_through_public_api() {
+    let mut project_manager = ProjectManager::new();
+    let mut event_manager = EventManager::new();
+
+    let project_id = project_manager
+        .add_project("公开接口".to_string(), None)
+        .unwrap();
+
+    // 2024年5月6日是周一
+    let monday = NaiveDate::from_ymd_opt(2024, 5, 6)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap()
+        .and_utc();
+    let work = event_manager
+        .add_project_event("编写文档".to_string(), None, project_id, Some(monday))
+        .unwrap();
+    event_manager
+        .set_event_end_time(work, Some(monday + Duration::minutes(90)))
+        .unwrap();
+    let lunch = event_manager
+        .add_non_project_event("午饭".to_string(), None, Some(monday + Duration::hours(3)))
+        .unwrap();
+    event_manager
+        .set_event_end_time(lunch, Some(monday + Duration::hours(4)))
+        .unwrap();
+
+    let records = event_manager.get_all_time_records();
+    let week_start = TimeCalculator::get_week_start(monday, WeekStart::Monday);
+    let week_end = TimeCalculator::get_week_end(monday, WeekStart::Monday);
+    assert_eq!(
+        TimeCalculator::calculate_project_time(&records, week_start, week_end),
+        90
+    );
+
+    let mut project_names = HashMap::new();
+    project_names.insert(project_id, "公开接口".to_string());
+    let report = ReportGenerator::generate_weekly_report(
+        &records,
+        &project_names,
+        monday,
+        WeekStart::Monday,
+        0,
+    );
+    assert_eq!(report.total_project_time_minutes, 90);
+    assert_eq!(report.total_non_project_time_minutes, 60);
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let storage = Storage::new(temp_dir.path().to_string_lossy().to_string());
+    storage
+        .save_app_data(&AppData::from_managers(&project_manager, &event_manager))
+        .unwrap();
+
+    let loaded = storage.load_data().unwrap();
+    assert_eq!(loaded.projects.len(), 1);
+    assert_eq!(loaded.events.len(), 2);
+    assert_eq!(loaded.time_records.len(), 2);
+}